@@ -0,0 +1,11 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Print the field reference of `Enarx.toml` as Markdown
+//!
+//! `cargo run --example reference > reference.md`
+
+use enarx_config::EnarxConfig;
+
+fn main() {
+    print!("{}", EnarxConfig::reference_markdown());
+}
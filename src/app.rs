@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Multiple applications sharing a single configuration
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Digest, EnarxConfig, Merge, ValidationError};
+
+/// An `[apps.NAME]` section, one application of a multi-application configuration
+///
+/// The section contains the settings of the application, e.g. its own `files`, `env`
+/// and `args`, layered on top of the top-level settings shared by all applications,
+/// see [`EnarxConfig::app`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct App {
+    /// SHA-256 digest the WASM binary of the application has to match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<Digest>,
+
+    /// the settings of the application, overriding the top-level ones
+    #[serde(flatten)]
+    pub config: EnarxConfig,
+}
+
+impl EnarxConfig {
+    /// get the configuration of the application `name` of the `[apps.NAME]` sections
+    ///
+    /// The settings of the application are [merged](Merge) on top of the top-level settings,
+    /// so e.g. `files` of the application replace top-level `files` of the same name,
+    /// and further `files` are appended. The returned configuration has no `apps`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::EnarxConfig;
+    /// const CONFIG: &str = r#"
+    /// env = { "LOG" = "info" }
+    ///
+    /// [apps.api]
+    /// args = [ "serve" ]
+    ///
+    /// [apps.proxy]
+    /// env = { "LOG" = "debug" }
+    /// "#;
+    ///
+    /// let config: EnarxConfig = toml::from_str(CONFIG).unwrap();
+    ///
+    /// let api = config.app("api").unwrap();
+    /// assert_eq!(api.args, [ "serve" ]);
+    /// assert_eq!(api.env["LOG"], "info");
+    /// assert_eq!(config.app("proxy").unwrap().env["LOG"], "debug");
+    /// assert!(config.app("db").is_none());
+    /// ```
+    pub fn app(&self, name: &str) -> Option<EnarxConfig> {
+        let app = self.apps.get(name)?;
+
+        let mut config = Self {
+            apps: Default::default(),
+            ..self.clone()
+        };
+        config.merge(Self {
+            apps: Default::default(),
+            ..app.config.clone()
+        });
+        Some(config)
+    }
+
+    pub(crate) fn validate_apps(&self) -> Result<(), ValidationError> {
+        for (name, app) in &self.apps {
+            let path = format!("apps.{}", name);
+
+            if name.is_empty() {
+                return Err(ValidationError::new(path, "empty application name"));
+            }
+            if !app.config.apps.is_empty() {
+                return Err(ValidationError::new(
+                    format!("{}.apps", path),
+                    "nested applications are not supported",
+                ));
+            }
+
+            // the paths are the ones of the merged configuration
+            let config = self.app(name).expect("application exists");
+            config
+                .validate()
+                .map_err(|e| ValidationError::new(format!("{}.{}", path, e.path()), e.message()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+    env = { "LOG" = "info", "REGION" = "eu" }
+
+    [[files]]
+    kind = "stdin"
+
+    [[files]]
+    kind = "stdout"
+
+    [apps.api]
+    sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    args = [ "serve" ]
+    env = { "LOG" = "debug" }
+
+    [[apps.api.files]]
+    name = "API"
+    kind = "listen"
+    port = 8443
+
+    [apps.worker]
+    args = [ "work" ]
+
+    [[apps.worker.files]]
+    kind = "stdout"
+    path = "/var/log/worker.log"
+    "#;
+
+    #[test]
+    fn apps() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.apps["api"].sha256, Some(Digest::of("")));
+
+        let api = cfg.app("api").unwrap();
+        assert_eq!(api.args, ["serve"]);
+        assert_eq!(api.env["LOG"], "debug");
+        assert_eq!(api.env["REGION"], "eu");
+        assert_eq!(
+            api.files.iter().map(|f| f.name()).collect::<Vec<_>>(),
+            ["stdin", "stdout", "API"]
+        );
+        assert!(api.apps.is_empty());
+
+        let worker = cfg.app("worker").unwrap();
+        assert_eq!(worker.files.len(), 2);
+        assert!(worker.files[1].path().is_some());
+
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), cfg);
+    }
+
+    #[test]
+    fn invalid() {
+        let validate = |config: &str| {
+            toml::from_str::<EnarxConfig>(config)
+                .unwrap()
+                .validate()
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            validate("[apps.api]\n[[apps.api.files]]\nname = \"API\"\nkind = \"listen\"\nport = 0"),
+            "apps.api.files[0].port: port 0 selects a random port, set `ephemeral = true` to request this explicitly"
+        );
+        assert_eq!(
+            validate("[apps.api.apps.nested]\nargs = [ \"serve\" ]"),
+            "apps.api.apps: nested applications are not supported"
+        );
+    }
+}
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The command line arguments of the application
+
+use std::fmt;
+
+use serde::{
+    de::{Error, SeqAccess, Visitor},
+    Deserializer,
+};
+
+/// split a command line into words with the quoting rules of a POSIX shell
+///
+/// Words are separated by whitespace. Single quotes preserve all characters literally,
+/// double quotes all but `\`, which escapes `"`, `\`, `$` and `` ` ``. Outside of quotes,
+/// `\` escapes any character. No expansions are performed.
+pub(crate) fn split(line: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("missing closing `'`".into()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("missing closing `\"`".into()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("missing closing `\"`".into()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                // a line continuation
+                Some('\n') => {}
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err("trailing `\\`".into()),
+            },
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+
+    Ok(words)
+}
+
+/// deserialize the `args` from an array of strings or from a command line
+pub(crate) fn deserialize_args<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct ArgsVisitor;
+
+    impl<'de> Visitor<'de> for ArgsVisitor {
+        type Value = Vec<String>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("an array of strings or a command line string")
+        }
+
+        fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+            split(v).map_err(|e| E::custom(format!("invalid command line: {}", e)))
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut args = vec![];
+            while let Some(arg) = seq.next_element()? {
+                args.push(arg);
+            }
+            Ok(args)
+        }
+    }
+
+    deserializer.deserialize_any(ArgsVisitor)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "toml")]
+    use crate::EnarxConfig;
+
+    #[test]
+    fn split_words() {
+        assert_eq!(
+            split("serve --port 8080  --verbose").unwrap(),
+            ["serve", "--port", "8080", "--verbose"]
+        );
+        assert_eq!(
+            split(
+                r#"echo 'a "b"' "c \"d\" \n" e\ f '' x\
+y"#
+            )
+            .unwrap(),
+            ["echo", "a \"b\"", "c \"d\" \\n", "e f", "", "xy"]
+        );
+        assert!(split("echo 'a").is_err());
+        assert!(split("echo \"a").is_err());
+        assert!(split("echo a\\").is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn args_string() {
+        let cfg: EnarxConfig = toml::from_str("args = \"serve --name 'my app'\"").unwrap();
+        assert_eq!(cfg.args, ["serve", "--name", "my app"]);
+
+        let cfg: EnarxConfig = toml::from_str("args = [ \"serve\", \"--verbose\" ]").unwrap();
+        assert_eq!(cfg.args, ["serve", "--verbose"]);
+
+        let err = EnarxConfig::from_toml_str("args = \"serve 'x\"").unwrap_err();
+        assert_eq!(err.path(), Some("args"));
+    }
+}
@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Attestation policy of the `[attestation]` section
+
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use url::Url;
+
+use crate::Error;
+
+/// How the keep obtains the collateral to produce its attestation evidence
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AttestationMode {
+    /// the collateral, like the VCEK certificate and CRLs, is fetched from the vendor at startup
+    #[serde(rename = "online")]
+    Online,
+
+    /// pre-fetched collateral is used, for air-gapped deployments
+    #[serde(rename = "cached")]
+    Cached,
+
+    /// the keep is not attested
+    #[serde(rename = "none")]
+    None,
+}
+
+// `#[default]` on enum variants is not available on the MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for AttestationMode {
+    fn default() -> Self {
+        AttestationMode::Online
+    }
+}
+
+impl fmt::Display for AttestationMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Online => f.write_str("online"),
+            Self::Cached => f.write_str("cached"),
+            Self::None => f.write_str("none"),
+        }
+    }
+}
+
+/// Pre-fetched attestation collateral, written as a `http(s)://` URL or a path on the host
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Collateral {
+    /// a file or directory of the host
+    Path(PathBuf),
+
+    /// a mirror reachable from the keep
+    Url(Url),
+}
+
+impl FromStr for Collateral {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.contains("://") {
+            if s.is_empty() {
+                return Err(Error::parse("empty collateral path"));
+            }
+            return Ok(Self::Path(s.into()));
+        }
+
+        let url = Url::parse(s)
+            .map_err(|e| Error::parse(format!("invalid collateral URL `{}`: {}", s, e)))?;
+        match url.scheme() {
+            "http" | "https" => Ok(Self::Url(url)),
+            scheme => Err(Error::parse(format!(
+                "unsupported scheme `{}` of collateral URL `{}`, expected `http` or `https`",
+                scheme, s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Collateral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Path(path) => path.display().fmt(f),
+            Self::Url(url) => url.fmt(f),
+        }
+    }
+}
+
+impl Serialize for Collateral {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Collateral {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let collateral = String::deserialize(deserializer)?;
+        collateral.parse().map_err(D::Error::custom)
+    }
+}
+
+/// The `[attestation]` section
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Attestation {
+    /// how the collateral of the attestation evidence is obtained
+    #[serde(default)]
+    pub mode: AttestationMode,
+
+    /// pre-fetched collateral, like the VCEK certificate and CRLs, required for `mode = "cached"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collateral: Option<Collateral>,
+
+    /// names of the trust anchors of the `[trust]` section the certificate chain of the
+    /// attestation evidence is verified against, e.g. a `builtin = "amd-milan"` anchor
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trust: Vec<String>,
+}
+
+impl Attestation {
+    pub(crate) fn validate(&self) -> Result<(), (&'static str, String)> {
+        match (self.mode, &self.collateral) {
+            (AttestationMode::Cached, None) => Err((
+                "collateral",
+                "`mode = \"cached\"` requires `collateral`".into(),
+            )),
+            (AttestationMode::Online | AttestationMode::None, Some(_)) => Err((
+                "collateral",
+                "`collateral` requires `mode = \"cached\"`".into(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    #[test]
+    fn cached() {
+        let cfg: EnarxConfig = toml::from_str(
+            "[attestation]\nmode = \"cached\"\ncollateral = \"/var/lib/enarx/collateral\"",
+        )
+        .unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let attestation = cfg.attestation.as_ref().unwrap();
+        assert_eq!(attestation.mode, AttestationMode::Cached);
+        assert_eq!(
+            attestation.collateral,
+            Some(Collateral::Path("/var/lib/enarx/collateral".into()))
+        );
+
+        let cfg: EnarxConfig = toml::from_str("[attestation]\nmode = \"cached\"").unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "attestation.collateral: `mode = \"cached\"` requires `collateral`"
+        );
+
+        let cfg: EnarxConfig =
+            toml::from_str("[attestation]\ncollateral = \"https://kds.example.com/\"").unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "attestation.collateral: `collateral` requires `mode = \"cached\"`"
+        );
+    }
+
+    #[test]
+    fn none() {
+        let mut cfg: EnarxConfig = toml::from_str("[attestation]\nmode = \"none\"").unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        cfg.steward = Some("https://steward.example.com".parse().unwrap());
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "attestation.mode: a `steward` requires attestation"
+        );
+    }
+
+    #[test]
+    fn collateral_scheme() {
+        assert!("ftp://kds.example.com/".parse::<Collateral>().is_err());
+        assert!("".parse::<Collateral>().is_err());
+    }
+}
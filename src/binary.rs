@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compact binary encodings of a configuration for transfer into the keep
+
+#[cfg(feature = "postcard")]
+use serde::{Deserialize, Serialize};
+
+use crate::{EnarxConfig, Error};
+
+#[cfg(feature = "cbor")]
+impl EnarxConfig {
+    /// encode the configuration as CBOR (RFC 8949)
+    ///
+    /// Requires the `cbor` feature.
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        // writing into a `Vec` cannot fail, all map keys are strings
+        ciborium::ser::into_writer(self, &mut out).expect("config is serializable");
+        out
+    }
+
+    /// decode a configuration encoded with [`to_cbor`](Self::to_cbor)
+    ///
+    /// Requires the `cbor` feature.
+    pub fn from_cbor_slice(bytes: &[u8]) -> Result<Self, Error> {
+        ciborium::de::from_reader(bytes).map_err(|e| Error::parse(e.to_string()))
+    }
+}
+
+/// A self-describing value tree, as the config types need a self-describing format
+/// and postcard is not one
+#[cfg(feature = "postcard")]
+#[derive(Deserialize, Serialize)]
+enum Node {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(String),
+    Seq(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+#[cfg(feature = "postcard")]
+impl From<serde_json::Value> for Node {
+    fn from(value: serde_json::Value) -> Self {
+        use serde_json::Value;
+
+        match value {
+            Value::Null => Self::Null,
+            Value::Bool(b) => Self::Bool(b),
+            Value::Number(n) => match (n.as_u64(), n.as_i64(), n.as_f64()) {
+                (Some(u), ..) => Self::UInt(u),
+                (_, Some(i), _) => Self::Int(i),
+                (.., f) => Self::Float(f.unwrap_or_default()),
+            },
+            Value::String(s) => Self::Str(s),
+            Value::Array(values) => Self::Seq(values.into_iter().map(Self::from).collect()),
+            Value::Object(map) => Self::Map(map.into_iter().map(|(k, v)| (k, v.into())).collect()),
+        }
+    }
+}
+
+#[cfg(feature = "postcard")]
+impl From<Node> for serde_json::Value {
+    fn from(node: Node) -> Self {
+        match node {
+            Node::Null => Self::Null,
+            Node::Bool(b) => b.into(),
+            Node::Int(i) => i.into(),
+            Node::UInt(u) => u.into(),
+            Node::Float(f) => f.into(),
+            Node::Str(s) => s.into(),
+            Node::Seq(nodes) => Self::Array(nodes.into_iter().map(Self::from).collect()),
+            Node::Map(entries) => {
+                Self::Object(entries.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "postcard")]
+impl EnarxConfig {
+    /// encode the configuration with [postcard](https://postcard.jamesmunns.com)
+    ///
+    /// Requires the `postcard` feature.
+    pub fn to_postcard(&self) -> Vec<u8> {
+        // serializing the config types into a `Value` cannot fail, all map keys are strings
+        let value = serde_json::to_value(self).expect("config is serializable");
+        postcard::to_allocvec(&Node::from(value)).expect("value tree is serializable")
+    }
+
+    /// decode a configuration encoded with [`to_postcard`](Self::to_postcard)
+    ///
+    /// Requires the `postcard` feature.
+    pub fn from_postcard_slice(bytes: &[u8]) -> Result<Self, Error> {
+        let node: Node = postcard::from_bytes(bytes).map_err(|e| Error::parse(e.to_string()))?;
+        serde_path_to_error::deserialize(serde_json::Value::from(node)).map_err(|e| {
+            let path = e.path().to_string();
+            Error::Parse {
+                path: Some(path).filter(|path| path != "."),
+                location: None,
+                message: e.into_inner().to_string(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+    args = "serve --name 'my app'"
+    env = { "LOG" = "debug", "RETRIES" = 3 }
+
+    [secrets]
+    token = { inline = "1234" }
+
+    [limits]
+    max_fds = 64
+
+    [[kv]]
+    name = "cache"
+    backend = "memory"
+
+    [[files]]
+    kind = "stdin"
+    content_base64 = "aGVsbG8="
+
+    [[files]]
+    name = "API"
+    kind = "listen"
+    prot = "tcp"
+    port = 8080
+
+    [[files]]
+    kind = "connect"
+    host = "db.example.com"
+    port = 5432
+    timeout = "5s"
+    "#;
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor() {
+        let config: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let cbor = config.to_cbor();
+        assert_eq!(EnarxConfig::from_cbor_slice(&cbor).unwrap(), config);
+
+        let err = EnarxConfig::from_cbor_slice(&cbor[..cbor.len() - 1]).unwrap_err();
+        assert!(matches!(err, Error::Parse { .. }));
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn postcard() {
+        let config: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let postcard = config.to_postcard();
+        assert_eq!(EnarxConfig::from_postcard_slice(&postcard).unwrap(), config);
+        assert!(EnarxConfig::from_postcard_slice(&postcard[..postcard.len() - 1]).is_err());
+    }
+}
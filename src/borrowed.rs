@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! A configuration borrowing its strings from the input buffer
+
+use std::{borrow::Cow, collections::BTreeMap};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::EnarxConfig;
+
+/// An [`EnarxConfig`] borrowing the `env` and the `args` from the input buffer
+///
+/// The environment and the arguments make up the bulk of the strings of large
+/// configurations. Deserializers supporting borrowed strings, like `serde_json::from_slice`,
+/// leave them in the input buffer, e.g. an attested and immutable memory region,
+/// instead of allocating each of them. Strings with escape sequences are still allocated.
+/// All other sections are deserialized into `rest`, as owned values.
+///
+/// Unlike [`EnarxConfig`], the `env` values must be strings and the `args` an array.
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+/// use enarx_config::EnarxConfigRef;
+///
+/// let input = br#"{"env":{"LOG":"debug"},"args":["--verbose"],"files":[{"kind":"stdin"}]}"#;
+/// let config: EnarxConfigRef<'_> = serde_json::from_slice(input).unwrap();
+///
+/// assert!(matches!(config.env["LOG"], Cow::Borrowed("debug")));
+/// assert_eq!(config.rest.files[0].kind(), "stdin");
+///
+/// let config = config.into_owned();
+/// assert_eq!(config.args, ["--verbose"]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct EnarxConfigRef<'a> {
+    /// the environment variables, see [`EnarxConfig::env`]
+    #[serde(default, borrow, deserialize_with = "deserialize_env")]
+    pub env: BTreeMap<Cow<'a, str>, Cow<'a, str>>,
+
+    /// the arguments, see [`EnarxConfig::args`]
+    #[serde(default, borrow, deserialize_with = "deserialize_args")]
+    pub args: Vec<Cow<'a, str>>,
+
+    /// all other fields, with an empty `env` and `args`
+    #[serde(flatten)]
+    pub rest: EnarxConfig,
+}
+
+/// a string borrowed from the input, if the deserializer supports it
+///
+/// `Cow<str>` itself always deserializes to an owned string inside of containers.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+struct CowStr<'a>(#[serde(borrow)] Cow<'a, str>);
+
+fn deserialize_env<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<Cow<'de, str>, Cow<'de, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let env = BTreeMap::<CowStr<'de>, CowStr<'de>>::deserialize(deserializer)?;
+    Ok(env.into_iter().map(|(k, v)| (k.0, v.0)).collect())
+}
+
+fn deserialize_args<'de, D>(deserializer: D) -> Result<Vec<Cow<'de, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let args = Vec::<CowStr<'de>>::deserialize(deserializer)?;
+    Ok(args.into_iter().map(|arg| arg.0).collect())
+}
+
+impl EnarxConfigRef<'_> {
+    /// copy the borrowed strings into an [`EnarxConfig`]
+    pub fn into_owned(self) -> EnarxConfig {
+        EnarxConfig {
+            env: self
+                .env
+                .into_iter()
+                .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                .collect(),
+            args: self.args.into_iter().map(Cow::into_owned).collect(),
+            ..self.rest
+        }
+    }
+}
+
+impl<'a> From<&'a EnarxConfig> for EnarxConfigRef<'a> {
+    fn from(config: &'a EnarxConfig) -> Self {
+        Self {
+            env: config
+                .env
+                .iter()
+                .map(|(k, v)| (Cow::Borrowed(&**k), Cow::Borrowed(&**v)))
+                .collect(),
+            args: config
+                .args
+                .iter()
+                .map(|arg| Cow::Borrowed(&**arg))
+                .collect(),
+            rest: EnarxConfig {
+                env: BTreeMap::new(),
+                args: vec![],
+                ..config.clone()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn borrowed() {
+        let input = r#"{
+            "env": { "A": "a", "ESCAPED": "\"b\"" },
+            "args": [ "serve", "--verbose" ],
+            "files": [ { "kind": "listen", "name": "API", "port": 8443 } ]
+        }"#;
+
+        let config: EnarxConfigRef<'_> = serde_json::from_str(input).unwrap();
+        assert!(matches!(config.env["A"], Cow::Borrowed("a")));
+        assert!(matches!(config.env["ESCAPED"], Cow::Owned(_)));
+        assert!(config
+            .args
+            .iter()
+            .all(|arg| matches!(arg, Cow::Borrowed(_))));
+        assert!(config.rest.env.is_empty());
+
+        let owned: EnarxConfig = serde_json::from_str(input).unwrap();
+        assert_eq!(config.clone().into_owned(), owned);
+        assert_eq!(EnarxConfigRef::from(&owned), config);
+    }
+}
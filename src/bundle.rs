@@ -0,0 +1,315 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Deployment bundles pinning a configuration with the files it references
+//!
+//! Requires the `std` feature.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Digest, EnarxConfig, File, PemSource, TrustAnchor};
+
+/// file name of the lock file of a [`Bundle`]
+pub const LOCK_FILE_NAME: &str = "Enarx.lock";
+
+/// version of the lock file format written by [`Bundle::new`]
+const LOCK_VERSION: u32 = 1;
+
+/// Error creating or verifying a [`Bundle`]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum BundleError {
+    /// a file of the bundle could not be read
+    #[error("failed to read `{}`: {source}", path.display())]
+    Io {
+        /// path of the file
+        path: PathBuf,
+
+        /// the underlying error
+        #[source]
+        source: io::Error,
+    },
+
+    /// the configuration differs from the pinned one
+    #[error("the configuration does not match the bundle")]
+    Config,
+
+    /// the content of a file differs from the pinned one
+    #[error("`{}` does not match the bundle", path.display())]
+    Mismatch {
+        /// path of the file
+        path: PathBuf,
+    },
+
+    /// the lock file has an unsupported format version
+    #[error("unsupported bundle version {0}")]
+    Version(u32),
+}
+
+/// A configuration pinned together with the WASM binary and the host files it references,
+/// the content of an `Enarx.lock`
+///
+/// The referenced files are the CA bundles and `path` trust anchors, the `wasi_nn` models,
+/// the `path` of `stdin` and the certificates and keys of listeners, including the ones of
+/// templates, of files with a `when` condition and of all `apps`. Revocation lists and
+/// attestation collateral are refreshed independently of a deployment and are not pinned.
+///
+/// # Examples
+///
+/// ```no_run
+/// use enarx_config::{Bundle, EnarxConfig};
+///
+/// let config = EnarxConfig::from_toml_file("Enarx.toml").unwrap();
+/// let bundle = Bundle::new(&config, "app.wasm").unwrap();
+///
+/// // later, before the deployment
+/// bundle.verify(&config, "app.wasm").unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Bundle {
+    /// version of the lock file format
+    pub version: u32,
+
+    /// digest of the canonical form of the configuration
+    pub config: Digest,
+
+    /// digest of the WASM binary
+    pub wasm: Digest,
+
+    /// digests of the host files referenced by the configuration, by their path
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub files: BTreeMap<PathBuf, Digest>,
+}
+
+/// calculate the digest of the file at `path`
+fn digest_file(path: &Path) -> Result<Digest, BundleError> {
+    std::fs::read(path)
+        .map(Digest::of)
+        .map_err(|source| BundleError::Io {
+            path: path.into(),
+            source,
+        })
+}
+
+impl Bundle {
+    /// pin `config` with the WASM binary at `wasm` and the content of all referenced files
+    ///
+    /// Relative paths are resolved against the current directory, like the runtime does.
+    pub fn new(config: &EnarxConfig, wasm: impl AsRef<Path>) -> Result<Self, BundleError> {
+        let files = config
+            .host_files()
+            .into_iter()
+            .map(|path| {
+                let digest = digest_file(&path)?;
+                Ok((path, digest))
+            })
+            .collect::<Result<_, BundleError>>()?;
+
+        Ok(Self {
+            version: LOCK_VERSION,
+            config: config.digest(),
+            wasm: digest_file(wasm.as_ref())?,
+            files,
+        })
+    }
+
+    /// check that `config`, the WASM binary at `wasm` and all files referenced by `config`
+    /// are the pinned ones
+    pub fn verify(&self, config: &EnarxConfig, wasm: impl AsRef<Path>) -> Result<(), BundleError> {
+        if self.version != LOCK_VERSION {
+            return Err(BundleError::Version(self.version));
+        }
+        if config.digest() != self.config {
+            return Err(BundleError::Config);
+        }
+
+        let wasm = wasm.as_ref();
+        if digest_file(wasm)? != self.wasm {
+            return Err(BundleError::Mismatch { path: wasm.into() });
+        }
+
+        // the configuration matches, so it references exactly the pinned files
+        for (path, digest) in &self.files {
+            if digest_file(path)? != *digest {
+                return Err(BundleError::Mismatch { path: path.clone() });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "toml")]
+impl Bundle {
+    /// parse the content of an `Enarx.lock`
+    ///
+    /// Requires the `toml` feature.
+    pub fn from_lock_str(s: &str) -> Result<Self, crate::Error> {
+        Ok(toml::from_str(s)?)
+    }
+
+    /// write the bundle in the format of an `Enarx.lock`
+    ///
+    /// Requires the `toml` feature.
+    pub fn to_lock_string(&self) -> String {
+        toml::to_string(self).expect("a bundle is representable as TOML")
+    }
+}
+
+impl EnarxConfig {
+    /// get the paths of the host files whose content is part of a [`Bundle`]
+    ///
+    /// Like the validation, this covers the files based on templates, the files with a
+    /// `when` condition and the files of all `apps`.
+    pub fn host_files(&self) -> BTreeSet<PathBuf> {
+        let mut paths = BTreeSet::new();
+        self.collect_host_files(&mut paths);
+        for name in self.apps.keys() {
+            let app = self.app(name).expect("application exists");
+            app.collect_host_files(&mut paths);
+        }
+        paths
+    }
+
+    fn collect_host_files(&self, paths: &mut BTreeSet<PathBuf>) {
+        let files = self
+            .resolve_templates_lossy()
+            .into_iter()
+            .map(|mut file| {
+                while let File::Conditional { file: inner, .. } = file {
+                    file = *inner;
+                }
+                file
+            })
+            .collect::<Vec<_>>();
+
+        let tls = self
+            .tls
+            .iter()
+            .chain(self.services.iter().filter_map(|s| s.tls.as_ref()))
+            .chain(files.iter().filter_map(File::tls));
+        paths.extend(tls.filter_map(|tls| tls.ca.clone()));
+
+        if let Some(ca) = self.steward.as_ref().and_then(|s| s.ca.as_ref()) {
+            paths.insert(ca.clone());
+        }
+
+        paths.extend(self.trust.values().filter_map(|anchor| match anchor {
+            TrustAnchor::Path(path) => Some(path.clone()),
+            _ => None,
+        }));
+
+        if let Some(wasi_nn) = self.wasm.as_ref().and_then(|w| w.wasi_nn.as_ref()) {
+            paths.extend(wasi_nn.models.iter().map(|model| model.path.clone()));
+        }
+
+        for file in files {
+            match file {
+                File::Stdin {
+                    path: Some(path), ..
+                } => {
+                    paths.insert(path);
+                }
+                File::Listen { cert, key, .. } => {
+                    paths.extend([cert, key].into_iter().filter_map(|source| match source {
+                        Some(PemSource::Path(path)) => Some(path),
+                        _ => None,
+                    }));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bundle() {
+        let dir = std::env::temp_dir().join(format!("enarx-config-bundle-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let wasm = dir.join("app.wasm");
+        let ca = dir.join("ca.pem");
+        std::fs::write(&wasm, "\0asm").unwrap();
+        std::fs::write(&ca, "-----BEGIN CERTIFICATE-----").unwrap();
+
+        let mut config = EnarxConfig {
+            tls: Some(crate::TlsOptions {
+                ca: Some(ca.clone()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.host_files().into_iter().collect::<Vec<_>>(),
+            [ca.as_path()]
+        );
+
+        let bundle = Bundle::new(&config, &wasm).unwrap();
+        assert_eq!(bundle.files[&ca], Digest::of("-----BEGIN CERTIFICATE-----"));
+        bundle.verify(&config, &wasm).unwrap();
+
+        #[cfg(feature = "toml")]
+        {
+            let lock = bundle.to_lock_string();
+            assert_eq!(Bundle::from_lock_str(&lock).unwrap(), bundle);
+        }
+
+        std::fs::write(&ca, "-----BEGIN CERTIFICATE-----\n").unwrap();
+        assert!(matches!(
+            bundle.verify(&config, &wasm),
+            Err(BundleError::Mismatch { path }) if path == ca
+        ));
+
+        config.args.push("--verbose".into());
+        assert!(matches!(
+            bundle.verify(&config, &wasm),
+            Err(BundleError::Config)
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn nested_host_files() {
+        const CONFIG: &str = r#"
+        [templates.web]
+        kind = "listen"
+        prot = "tls"
+        port = 8443
+        cert = { path = "/etc/ssl/web.pem" }
+
+        [[files]]
+        template = "web"
+        name = "WEB"
+
+        [[files]]
+        kind = "stdin"
+        path = "/srv/dev-input"
+        when = { profile = "dev" }
+
+        [apps.api]
+        [[apps.api.files]]
+        kind = "stdin"
+        path = "/srv/api-input"
+        "#;
+
+        let config = EnarxConfig::from_toml_str(CONFIG).unwrap();
+        assert_eq!(
+            config.host_files().into_iter().collect::<Vec<_>>(),
+            [
+                Path::new("/etc/ssl/web.pem"),
+                Path::new("/srv/api-input"),
+                Path::new("/srv/dev-input"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,381 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Canonical encoding of an [`EnarxConfig`]
+
+use std::hash::{Hash, Hasher};
+
+use serde_json::Value;
+
+use crate::{EnarxConfig, File, WriteMode};
+
+impl EnarxConfig {
+    /// get a deterministic encoding of the configuration
+    ///
+    /// The configuration is encoded as compact JSON with all object keys sorted
+    /// and all implicit values (like the default file descriptor names) filled in.
+    /// Two semantically equal configurations produce the same bytes, which makes
+    /// the result suitable for hashing and signing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::EnarxConfig;
+    ///
+    /// let a: EnarxConfig = toml::from_str(r#"
+    /// [[files]]
+    /// kind = "stdin"
+    /// "#).unwrap();
+    ///
+    /// let b: EnarxConfig = toml::from_str(r#"
+    /// [[files]]
+    /// name = "stdin"
+    /// kind = "stdin"
+    /// "#).unwrap();
+    ///
+    /// assert_eq!(a.to_canonical_bytes(), b.to_canonical_bytes());
+    /// ```
+    pub fn to_canonical_bytes(&self) -> Vec<u8> {
+        // serializing the config types into a `Value` cannot fail, all map keys are strings
+        let value = serde_json::to_value(self.normalize()).expect("config is serializable");
+
+        let mut out = Vec::new();
+        write_value(&value, &mut out);
+        out
+    }
+
+    /// check if two configurations are semantically equal
+    ///
+    /// Unlike `==`, this compares the [normalized](EnarxConfig::normalize) forms, so
+    /// configurations differing only in the order of fields, in values spelled out which
+    /// default to the same, or in the spelling of addresses, like `::` and `0:0::0`,
+    /// are equal. The [`Hash`] of a configuration is calculated from the normalized form
+    /// as well, semantically equal configurations have the same hash.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::EnarxConfig;
+    ///
+    /// let a: EnarxConfig = toml::from_str(r#"
+    /// [[files]]
+    /// name = "API"
+    /// kind = "listen"
+    /// addr = "0:0::0"
+    /// "#).unwrap();
+    ///
+    /// let b: EnarxConfig = toml::from_str(r#"
+    /// [[files]]
+    /// kind = "listen"
+    /// name = "API"
+    /// port = 443
+    /// "#).unwrap();
+    ///
+    /// assert!(a.semantic_eq(&b));
+    /// ```
+    pub fn semantic_eq(&self, other: &Self) -> bool {
+        self.to_canonical_bytes() == other.to_canonical_bytes()
+    }
+
+    /// get the configuration with all implicit values filled in
+    ///
+    /// The files based on one of the `templates` are resolved, dropping the `templates`
+    /// if all of them resolve. Listeners on multiple `ports` are expanded into one listener per
+    /// port. The files of the [`stdio`](crate::StdioPolicy) policy are added
+    /// for the missing standard streams. The implicit names and access modes of the files, and the
+    /// `mode` of `stdout` and `stderr` files with a `path` are made explicit, and later
+    /// files equal to an earlier one are removed. [`EnarxConfig::validate`] rejects such
+    /// duplicates, so the files of a valid configuration keep their
+    /// [file descriptors](EnarxConfig::fd_layout). The `env` is sorted by name anyway, and defaults
+    /// like the `addr`, `port` and `prot` of sockets are filled in on parsing already.
+    /// An empty `files` stays empty, it means no file descriptors at all.
+    ///
+    /// This is the form the [canonical encoding](EnarxConfig::to_canonical_bytes) is
+    /// calculated from, normalizing a normalized configuration does not change it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::{Access, EnarxConfig};
+    ///
+    /// let config: EnarxConfig = toml::from_str(r#"
+    /// [[files]]
+    /// kind = "stdin"
+    /// "#).unwrap();
+    ///
+    /// let normalized = config.normalize();
+    /// assert_eq!(normalized.files[0].name(), "stdin");
+    /// assert_eq!(normalized.files[0].access(), Access::ReadOnly);
+    /// assert_eq!(normalized.normalize(), normalized);
+    /// ```
+    pub fn normalize(&self) -> Self {
+        let mut config = self.clone();
+
+        let mut resolved = crate::ports::expand_ports(config.resolve_templates_lossy());
+        crate::stdio::inject(&mut resolved, config.stdio);
+
+        let mut files: Vec<File> = Vec::with_capacity(resolved.len());
+        for mut file in resolved {
+            fill_implicit(&mut file);
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+        config.files = files;
+        if config.files.iter().all(|file| file.template().is_none()) {
+            config.templates.clear();
+        }
+
+        config
+    }
+}
+
+pub(crate) fn fill_implicit(file: &mut File) {
+    let filled_name = file.name().into();
+    let filled_access = file.access();
+
+    match file {
+        File::Null { name, access, .. } | File::Stdin { name, access, .. } => {
+            *name = Some(filled_name);
+            *access = Some(filled_access);
+        }
+        File::Stdout {
+            name,
+            access,
+            path,
+            mode,
+            ..
+        }
+        | File::Stderr {
+            name,
+            access,
+            path,
+            mode,
+            ..
+        } => {
+            *name = Some(filled_name);
+            *access = Some(filled_access);
+            if path.is_some() {
+                *mode = Some(mode.unwrap_or(WriteMode::Truncate));
+            }
+        }
+        File::Connect { name, .. } => *name = Some(filled_name),
+        File::Listen { .. }
+        | File::Fifo { .. }
+        | File::Pipe { .. }
+        | File::Alias { .. }
+        | File::Custom { .. }
+        | File::Templated { .. } => {}
+        File::Conditional { file, .. } => fill_implicit(file),
+    }
+}
+
+/// Hashes the [canonical encoding](EnarxConfig::to_canonical_bytes), consistent with `==`
+/// and [`EnarxConfig::semantic_eq`].
+impl Hash for EnarxConfig {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_canonical_bytes().hash(state);
+    }
+}
+
+pub(crate) fn write_value(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Array(values) => {
+            out.push(b'[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_value(value, out);
+            }
+            out.push(b']');
+        }
+
+        // sort explicitly, `serde_json::Map` might preserve the insertion order
+        Value::Object(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+
+            out.push(b'{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_scalar(&Value::String(key.clone()), out);
+                out.push(b':');
+                write_value(value, out);
+            }
+            out.push(b'}');
+        }
+
+        scalar => write_scalar(scalar, out),
+    }
+}
+
+fn write_scalar(value: &Value, out: &mut Vec<u8>) {
+    // serializing a scalar `Value` cannot fail
+    let scalar = serde_json::to_string(value).expect("scalar is serializable");
+    out.extend_from_slice(scalar.as_bytes());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sorted_and_filled() {
+        const CONFIG: &str = r#"
+        env = { "B" = "b", "A" = "a" }
+
+        [[files]]
+        kind = "null"
+
+        [[files]]
+        kind = "connect"
+        host = "example.com"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+
+        assert_eq!(
+            String::from_utf8(cfg.to_canonical_bytes()).unwrap(),
+            r#"{"args":[],"env":{"A":"a","B":"b"},"files":[{"access":"rw","kind":"null","name":"null"},{"host":"example.com","kind":"connect","name":"example.com","port":443,"prot":"tls"}]}"#
+        );
+    }
+
+    #[test]
+    fn defaults_are_equal() {
+        let explicit: EnarxConfig = toml::from_str(
+            r#"
+            [[files]]
+            name = "X"
+            kind = "listen"
+            addr = "::"
+            port = 443
+            prot = "tls"
+            "#,
+        )
+        .unwrap();
+
+        let implicit: EnarxConfig = toml::from_str(
+            r#"
+            [[files]]
+            name = "X"
+            kind = "listen"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(explicit.to_canonical_bytes(), implicit.to_canonical_bytes());
+    }
+
+    #[test]
+    fn semantic_eq() {
+        use std::collections::{hash_map::DefaultHasher, HashSet};
+
+        let hash = |cfg: &EnarxConfig| {
+            let mut hasher = DefaultHasher::new();
+            cfg.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let a: EnarxConfig = toml::from_str(
+            r#"
+            env = { "B" = "b", "A" = "a" }
+
+            [[files]]
+            kind = "stdin"
+
+            [[files]]
+            name = "API"
+            kind = "listen"
+            addr = "0:0::0"
+            "#,
+        )
+        .unwrap();
+        let b: EnarxConfig = toml::from_str(
+            r#"
+            env = { "A" = "a", "B" = "b" }
+
+            [[files]]
+            kind = "stdin"
+            name = "stdin"
+            access = "ro"
+
+            [[files]]
+            port = 443
+            kind = "listen"
+            name = "API"
+            addr = "::"
+            "#,
+        )
+        .unwrap();
+
+        assert_ne!(a, b);
+        assert!(a.semantic_eq(&b));
+        assert_eq!(hash(&a), hash(&b));
+        assert_eq!(hash(&a), hash(&a.normalize()));
+        assert_eq!(
+            [a.clone(), b.normalize()]
+                .iter()
+                .collect::<HashSet<_>>()
+                .len(),
+            2
+        );
+
+        let mut c = b.clone();
+        c.env.insert("C".into(), "c".into());
+        assert!(!a.semantic_eq(&c));
+        assert_ne!(hash(&a), hash(&c));
+
+        // the order of the files is the order of the file descriptors
+        let mut d = b;
+        d.files.reverse();
+        assert!(!a.semantic_eq(&d));
+    }
+
+    #[test]
+    fn normalize() {
+        let cfg: EnarxConfig = toml::from_str(
+            r#"
+            [[files]]
+            kind = "stdin"
+
+            [[files]]
+            kind = "stdout"
+            path = "/var/log/app.log"
+
+            [[files]]
+            name = "stdin"
+            kind = "stdin"
+            access = "ro"
+
+            [[files]]
+            kind = "connect"
+            host = "example.com"
+            "#,
+        )
+        .unwrap();
+
+        let normalized = cfg.normalize();
+        assert_eq!(normalized.normalize(), normalized);
+        assert_eq!(normalized.to_canonical_bytes(), cfg.to_canonical_bytes());
+
+        let names = normalized.files.iter().map(File::name).collect::<Vec<_>>();
+        assert_eq!(names, ["stdin", "stdout", "example.com"]);
+        assert!(matches!(
+            normalized.files[1],
+            File::Stdout {
+                mode: Some(WriteMode::Truncate),
+                ..
+            }
+        ));
+
+        assert_eq!(
+            EnarxConfig::default().normalize().normalize(),
+            EnarxConfig::default().normalize()
+        );
+    }
+}
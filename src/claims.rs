@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Claims of a configuration for attestation evidence
+
+use serde_json::{json, Value};
+
+use crate::{canonical::write_value, EnarxConfig};
+
+impl EnarxConfig {
+    /// get the security-relevant subset of the configuration as a claims set
+    ///
+    /// The claims are a JSON object, encoded like the
+    /// [canonical form](EnarxConfig::to_canonical_bytes), so the bytes are deterministic
+    /// and suitable for embedding into attestation reports and for verification
+    /// on the Steward side:
+    ///
+    /// | Claim         | Value                                                   |
+    /// |---------------|---------------------------------------------------------|
+    /// | `config`      | the [digest](EnarxConfig::digest) of the whole configuration |
+    /// | `network`     | the effective `[network]` section, e.g. the egress policy |
+    /// | `attestation` | the effective `[attestation]` section                   |
+    ///
+    /// Absent sections are claimed with their default values, so a verifier does not
+    /// need to know the defaults.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enarx_config::EnarxConfig;
+    ///
+    /// let claims = EnarxConfig::default().to_attestation_claims();
+    /// assert!(claims.starts_with(br#"{"attestation":{"mode":"online"}"#));
+    /// ```
+    pub fn to_attestation_claims(&self) -> Vec<u8> {
+        let claims = json!({
+            "config": self.digest(),
+            "network": self.network.clone().unwrap_or_default(),
+            "attestation": self.attestation.clone().unwrap_or_default(),
+        });
+
+        let mut out = Vec::new();
+        write_value(&claims, &mut out);
+        out
+    }
+
+    /// get the [attestation claims](EnarxConfig::to_attestation_claims) as JSON value
+    pub fn attestation_claims(&self) -> Value {
+        // the claims are valid JSON by construction
+        serde_json::from_slice(&self.to_attestation_claims()).expect("claims are valid JSON")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn claims() {
+        let cfg: EnarxConfig = toml::from_str(
+            r#"
+            env = { "LOG" = "debug" }
+
+            [network.egress]
+            hosts = [ "api.example.com" ]
+
+            [attestation]
+            mode = "cached"
+            "#,
+        )
+        .unwrap();
+
+        let claims = cfg.attestation_claims();
+        assert_eq!(claims["config"], cfg.digest().to_string());
+        assert_eq!(claims["network"]["egress"]["hosts"][0], "api.example.com");
+        assert_eq!(claims["attestation"]["mode"], "cached");
+
+        // the environment is not part of the claims, but of the config digest
+        let mut other = cfg.clone();
+        other.env.clear();
+        let other_claims = other.attestation_claims();
+        assert_eq!(other_claims["network"], claims["network"]);
+        assert_ne!(other_claims["config"], claims["config"]);
+
+        assert_eq!(
+            cfg.to_attestation_claims(),
+            cfg.clone().to_attestation_claims()
+        );
+    }
+}
@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`clap`] integration
+//!
+//! Requires the `clap` feature. An [`EnarxConfig`] argument is parsed from the path of
+//! a TOML configuration file, which is loaded and validated:
+//!
+//! ```
+//! # extern crate dep_clap as clap;
+//! use clap::{value_parser, Arg, ArgAction, Command};
+//! use enarx_config::{EnarxConfig, File};
+//!
+//! let cmd = Command::new("run")
+//!     .arg(Arg::new("config").long("config").value_parser(value_parser!(EnarxConfig)))
+//!     .arg(
+//!         Arg::new("file")
+//!             .long("file")
+//!             .action(ArgAction::Append)
+//!             .value_parser(value_parser!(File)),
+//!     );
+//!
+//! let matches = cmd
+//!     .try_get_matches_from(["run", "--file", "stdin", "--file", "tls-listen://[::]:8443/API"])
+//!     .unwrap();
+//! assert!(matches.get_one::<EnarxConfig>("config").is_none());
+//! let files = matches.get_many::<File>("file").unwrap().collect::<Vec<_>>();
+//! assert_eq!(files[0].kind(), "stdin");
+//! assert_eq!(files[1].name(), "API");
+//! ```
+
+use std::ffi::OsStr;
+
+use clap::{
+    builder::{TypedValueParser, ValueParserFactory},
+    error::{ContextKind, ContextValue, ErrorKind},
+    Arg, Command,
+};
+
+use crate::EnarxConfig;
+
+/// [`TypedValueParser`] loading an [`EnarxConfig`] from the path given as argument
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConfigParser;
+
+impl TypedValueParser for ConfigParser {
+    type Value = EnarxConfig;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        EnarxConfig::from_toml_file(value).map_err(|e| {
+            let mut err = clap::Error::new(ErrorKind::ValueValidation).with_cmd(cmd);
+            if let Some(arg) = arg {
+                err.insert(
+                    ContextKind::InvalidArg,
+                    ContextValue::String(arg.to_string()),
+                );
+            }
+            err.insert(
+                ContextKind::InvalidValue,
+                ContextValue::String(value.to_string_lossy().into_owned()),
+            );
+            err.insert(ContextKind::Custom, ContextValue::String(e.to_string()));
+            err
+        })
+    }
+}
+
+impl ValueParserFactory for EnarxConfig {
+    type Parser = ConfigParser;
+
+    fn value_parser() -> Self::Parser {
+        ConfigParser
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Protocol;
+
+    fn command() -> Command {
+        Command::new("run")
+            .arg(
+                Arg::new("config")
+                    .long("config")
+                    .value_parser(clap::value_parser!(EnarxConfig)),
+            )
+            .arg(
+                Arg::new("prot")
+                    .long("prot")
+                    .value_parser(clap::value_parser!(Protocol)),
+            )
+    }
+
+    #[test]
+    fn config() {
+        let path =
+            std::env::temp_dir().join(format!("enarx-config-cli-{}.toml", std::process::id()));
+        std::fs::write(&path, "args = [ \"--verbose\" ]\n").unwrap();
+
+        let matches = command()
+            .try_get_matches_from(["run", "--config", path.to_str().unwrap(), "--prot", "tcp"])
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            matches.get_one::<EnarxConfig>("config").unwrap().args,
+            ["--verbose"]
+        );
+        assert_eq!(matches.get_one::<Protocol>("prot"), Some(&Protocol::Tcp));
+    }
+
+    #[test]
+    fn invalid() {
+        let err = command()
+            .try_get_matches_from(["run", "--config", "/nonexistent/Enarx.toml"])
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+
+        let err = command()
+            .try_get_matches_from(["run", "--prot", "udp"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid protocol `udp`, expected `tls` or `tcp`"));
+    }
+}
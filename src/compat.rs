@@ -0,0 +1,391 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compatibility of a configuration with the capabilities of a runtime
+
+use std::{collections::BTreeSet, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{EnarxConfig, File, ListenMode, NnBackend, Protocol, Resolver};
+
+/// A feature of a runtime a configuration may require
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum Feature {
+    /// `kind = "listen"` files
+    #[serde(rename = "listen")]
+    Listen,
+
+    /// `kind = "connect"` files
+    #[serde(rename = "connect")]
+    Connect,
+
+    /// sockets with `prot = "tls"`
+    #[serde(rename = "tls")]
+    Tls,
+
+    /// TLS connections authenticated with a pre-shared key
+    #[serde(rename = "psk")]
+    Psk,
+
+    /// `kind = "fifo"` files
+    #[serde(rename = "fifo")]
+    Fifo,
+
+    /// `kind = "pipe"` files
+    #[serde(rename = "pipe")]
+    Pipe,
+
+    /// listeners with `mode = "http"` or `mode = "h2"`
+    #[serde(rename = "http-listener")]
+    HttpListener,
+
+    /// certificates issued by a Steward
+    #[serde(rename = "steward")]
+    Steward,
+
+    /// the stores of the `[[kv]]` section
+    #[serde(rename = "kv")]
+    Kv,
+
+    /// the `wasi-crypto` proposal
+    #[serde(rename = "wasi-crypto")]
+    WasiCrypto,
+
+    /// the `wasi-nn` proposal
+    #[serde(rename = "wasi-nn")]
+    WasiNn,
+
+    /// the outbound requests of the `wasi-http` proposal
+    #[serde(rename = "wasi-http")]
+    WasiHttp,
+
+    /// outbound connections through a proxy
+    #[serde(rename = "proxy")]
+    Proxy,
+
+    /// enforcing the `[network.egress]` policy
+    #[serde(rename = "egress")]
+    Egress,
+
+    /// name resolution inside the keep, with the `in-keep` or `dns-over-tls` resolver
+    #[serde(rename = "in-keep-dns")]
+    InKeepDns,
+
+    /// exporting metrics
+    #[serde(rename = "telemetry")]
+    Telemetry,
+
+    /// serving health checks
+    #[serde(rename = "health")]
+    Health,
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Listen => "listen",
+            Self::Connect => "connect",
+            Self::Tls => "tls",
+            Self::Psk => "psk",
+            Self::Fifo => "fifo",
+            Self::Pipe => "pipe",
+            Self::HttpListener => "http-listener",
+            Self::Steward => "steward",
+            Self::Kv => "kv",
+            Self::WasiCrypto => "wasi-crypto",
+            Self::WasiNn => "wasi-nn",
+            Self::WasiHttp => "wasi-http",
+            Self::Proxy => "proxy",
+            Self::Egress => "egress",
+            Self::InKeepDns => "in-keep-dns",
+            Self::Telemetry => "telemetry",
+            Self::Health => "health",
+        })
+    }
+}
+
+/// What a runtime supports, e.g. published by the runtime as JSON
+///
+/// A configuration is checked against it with [`EnarxConfig::check_compat`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RuntimeCapabilities {
+    /// the supported features
+    #[serde(default)]
+    pub features: BTreeSet<Feature>,
+
+    /// the supported backends of `wasi-nn`
+    #[serde(default)]
+    pub nn_backends: Vec<NnBackend>,
+}
+
+impl RuntimeCapabilities {
+    /// does the runtime support `requirement`
+    pub fn supports(&self, requirement: &Requirement) -> bool {
+        match requirement {
+            Requirement::Feature(feature) => self.features.contains(feature),
+            Requirement::NnBackend(backend) => {
+                self.features.contains(&Feature::WasiNn) && self.nn_backends.contains(backend)
+            }
+        }
+    }
+}
+
+/// Something a configuration requires from the runtime
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Requirement {
+    /// a feature
+    Feature(Feature),
+
+    /// a backend of `wasi-nn`
+    NnBackend(NnBackend),
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Feature(feature) => write!(f, "`{}`", feature),
+            Self::NnBackend(backend) => write!(f, "the `{}` backend of `wasi-nn`", backend),
+        }
+    }
+}
+
+/// A setting of a configuration the runtime does not support
+///
+/// Contains the path of the setting, e.g. `files[2].prot`, and what it requires.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Incompatibility {
+    path: String,
+    requirement: Requirement,
+}
+
+impl Incompatibility {
+    /// the path of the setting
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// what the setting requires from the runtime
+    pub fn requirement(&self) -> Requirement {
+        self.requirement
+    }
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: requires {}, which the runtime does not support",
+            self.path, self.requirement
+        )
+    }
+}
+
+/// the requirements of a file, with the field requiring them
+fn file_requirements(file: &File) -> Vec<(&'static str, Requirement)> {
+    use Requirement::Feature as F;
+
+    let mut requirements = vec![];
+    match file {
+        File::Conditional { file, .. } => return file_requirements(file),
+        File::Listen { mode, .. } => {
+            requirements.push(("kind", F(Feature::Listen)));
+            if *mode != ListenMode::Raw {
+                requirements.push(("mode", F(Feature::HttpListener)));
+            }
+        }
+        File::Connect { .. } => requirements.push(("kind", F(Feature::Connect))),
+        File::Fifo { .. } => requirements.push(("kind", F(Feature::Fifo))),
+        File::Pipe { .. } => requirements.push(("kind", F(Feature::Pipe))),
+        _ => {}
+    }
+    if file.prot() == Some(Protocol::Tls) {
+        requirements.push(("prot", F(Feature::Tls)));
+    }
+    if file.psk().is_some() {
+        requirements.push(("psk", F(Feature::Psk)));
+    }
+    requirements
+}
+
+impl EnarxConfig {
+    /// get the requirements of the configuration, with the path of the setting requiring them
+    fn requirements(&self) -> Vec<(String, Requirement)> {
+        use Requirement::Feature as F;
+
+        let mut requirements = vec![];
+        let mut require = |path: &str, requirement| requirements.push((path.into(), requirement));
+
+        for (i, file) in self.resolve_templates_lossy().iter().enumerate() {
+            for (field, requirement) in file_requirements(file) {
+                require(&format!("files[{}].{}", i, field), requirement);
+            }
+        }
+
+        if self.steward.is_some() {
+            require("steward", F(Feature::Steward));
+        }
+        if !self.kv.is_empty() {
+            require("kv", F(Feature::Kv));
+        }
+        if let Some(wasm) = &self.wasm {
+            if matches!(&wasm.wasi_crypto, Some(crypto) if crypto.enable) {
+                require("wasm.wasi_crypto", F(Feature::WasiCrypto));
+            }
+            if let Some(nn) = wasm.wasi_nn.as_ref().filter(|nn| nn.enable) {
+                require("wasm.wasi_nn", F(Feature::WasiNn));
+                for (i, backend) in nn.backends.iter().flatten().enumerate() {
+                    require(
+                        &format!("wasm.wasi_nn.backends[{}]", i),
+                        Requirement::NnBackend(*backend),
+                    );
+                }
+                for (i, model) in nn.models.iter().enumerate() {
+                    require(
+                        &format!("wasm.wasi_nn.models[{}].backend", i),
+                        Requirement::NnBackend(model.backend),
+                    );
+                }
+            }
+            if matches!(&wasm.http, Some(http) if http.enable) {
+                require("wasm.http", F(Feature::WasiHttp));
+            }
+        }
+        if self.proxy.is_some() {
+            require("proxy", F(Feature::Proxy));
+        }
+        if let Some(network) = &self.network {
+            if network.egress.is_some() {
+                require("network.egress", F(Feature::Egress));
+            }
+            if matches!(&network.dns, Some(dns) if dns.resolver != Resolver::Host) {
+                require("network.dns.resolver", F(Feature::InKeepDns));
+            }
+        }
+        if self.telemetry.is_some() {
+            require("telemetry", F(Feature::Telemetry));
+        }
+        if self.health.is_some() {
+            require("health", F(Feature::Health));
+        }
+
+        requirements
+    }
+
+    /// check the configuration against the capabilities of a runtime
+    ///
+    /// Returns the settings requiring something the runtime does not support, empty if the
+    /// configuration can run on it. Files with a `when` condition are checked as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::{EnarxConfig, Feature, RuntimeCapabilities};
+    ///
+    /// let config: EnarxConfig = toml::from_str(r#"
+    /// [[files]]
+    /// name = "API"
+    /// kind = "listen"
+    /// prot = "tls"
+    /// "#).unwrap();
+    ///
+    /// let mut runtime = RuntimeCapabilities::default();
+    /// runtime.features.insert(Feature::Listen);
+    ///
+    /// let incompatibilities = config.check_compat(&runtime);
+    /// assert_eq!(incompatibilities.len(), 1);
+    /// assert_eq!(
+    ///     incompatibilities[0].to_string(),
+    ///     "files[0].prot: requires `tls`, which the runtime does not support"
+    /// );
+    /// ```
+    pub fn check_compat(&self, runtime: &RuntimeCapabilities) -> Vec<Incompatibility> {
+        self.requirements()
+            .into_iter()
+            .filter(|(_, requirement)| !runtime.supports(requirement))
+            .map(|(path, requirement)| Incompatibility { path, requirement })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+    steward = "https://steward.example.com"
+
+    [[files]]
+    name = "API"
+    kind = "listen"
+    prot = "tls"
+    mode = "http"
+
+    [[files]]
+    kind = "connect"
+    host = "db.example.com"
+    prot = "tcp"
+    when = { backend = "sgx" }
+
+    [wasm.wasi_nn]
+    enable = true
+    models = [ { name = "classifier", backend = "onnx", path = "/models/classifier.onnx" } ]
+
+    [network.dns]
+    resolver = "in-keep"
+    nameservers = [ "10.0.0.53" ]
+    "#;
+
+    #[test]
+    fn check_compat() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let runtime: RuntimeCapabilities = serde_json::from_str(
+            r#"{ "features": [ "listen", "tls", "wasi-nn" ], "nn_backends": [ "openvino" ] }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.check_compat(&runtime)
+                .iter()
+                .map(|i| (i.path(), i.requirement()))
+                .collect::<Vec<_>>(),
+            [
+                ("files[0].mode", Requirement::Feature(Feature::HttpListener)),
+                ("files[1].kind", Requirement::Feature(Feature::Connect)),
+                ("steward", Requirement::Feature(Feature::Steward)),
+                (
+                    "wasm.wasi_nn.models[0].backend",
+                    Requirement::NnBackend(NnBackend::Onnx)
+                ),
+                (
+                    "network.dns.resolver",
+                    Requirement::Feature(Feature::InKeepDns)
+                ),
+            ]
+        );
+        assert_eq!(
+            cfg.check_compat(&runtime)[3].to_string(),
+            "wasm.wasi_nn.models[0].backend: requires the `onnx` backend of `wasi-nn`, which the runtime does not support"
+        );
+
+        let runtime = RuntimeCapabilities {
+            features: [
+                Feature::Listen,
+                Feature::Connect,
+                Feature::Tls,
+                Feature::HttpListener,
+                Feature::Steward,
+                Feature::WasiNn,
+                Feature::InKeepDns,
+            ]
+            .into_iter()
+            .collect(),
+            nn_backends: vec![NnBackend::Onnx],
+        };
+        assert!(cfg.check_compat(&runtime).is_empty());
+        assert!(EnarxConfig::default()
+            .check_compat(&RuntimeCapabilities::default())
+            .is_empty());
+    }
+}
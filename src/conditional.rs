@@ -0,0 +1,295 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conditions on the keep backend and the profile, evaluated by [`EnarxConfig::for_target`]
+
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{env, EnarxConfig, File, ValidationError};
+
+/// Backend of the keep executing the application
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Backend {
+    /// no isolation, for development
+    #[serde(rename = "nil")]
+    Nil,
+
+    /// KVM virtual machine
+    #[serde(rename = "kvm")]
+    Kvm,
+
+    /// Intel SGX enclave
+    #[serde(rename = "sgx")]
+    Sgx,
+
+    /// AMD SEV-SNP virtual machine
+    #[serde(rename = "sev")]
+    Sev,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Nil => "nil",
+            Self::Kvm => "kvm",
+            Self::Sgx => "sgx",
+            Self::Sev => "sev",
+        })
+    }
+}
+
+/// A `when` condition of a file or a `[[conditional]]` section
+///
+/// All of the given fields have to match.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct When {
+    /// the backend of the keep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<Backend>,
+
+    /// the profile selected for the deployment, like `dev` or `prod`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+}
+
+impl When {
+    /// check if the condition holds for `backend` and `profile`
+    pub fn matches(&self, backend: Backend, profile: Option<&str>) -> bool {
+        let backend_matches = match self.backend {
+            Some(expected) => expected == backend,
+            None => true,
+        };
+        let profile_matches = match &self.profile {
+            Some(expected) => profile == Some(expected.as_str()),
+            None => true,
+        };
+        backend_matches && profile_matches
+    }
+
+    fn validate(&self) -> Result<(), (&'static str, String)> {
+        if self.backend.is_none() && self.profile.is_none() {
+            return Err(("", "requires `backend` or `profile`".into()));
+        }
+        if matches!(&self.profile, Some(profile) if profile.is_empty()) {
+            return Err(("profile", "empty profile name".into()));
+        }
+        Ok(())
+    }
+}
+
+/// A `[[conditional]]` section, settings only applying if its condition holds
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Conditional {
+    /// the condition
+    pub when: When,
+
+    /// environment variables added to, or overriding, the top-level `env`
+    #[serde(
+        default,
+        deserialize_with = "env::deserialize_env",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub env: BTreeMap<String, String>,
+}
+
+impl File {
+    /// get the condition of a file only opened if it holds
+    pub fn when(&self) -> Option<&When> {
+        match self {
+            Self::Conditional { when, .. } => Some(when),
+            _ => None,
+        }
+    }
+}
+
+impl EnarxConfig {
+    /// get the configuration for a keep with `backend`, and `profile` if any
+    ///
+    /// Files with a `when` condition are kept without it if it holds and dropped otherwise.
+    /// The `env` of the `[[conditional]]` sections whose condition holds is added in order,
+    /// overriding earlier values. The returned configuration has no conditions left.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::{Backend, EnarxConfig};
+    /// const CONFIG: &str = r#"
+    /// env = { "LOG" = "info" }
+    ///
+    /// [[files]]
+    /// kind = "stdout"
+    ///
+    /// [[files]]
+    /// kind = "stderr"
+    /// when = { profile = "dev" }
+    ///
+    /// [[conditional]]
+    /// when = { backend = "sgx", profile = "dev" }
+    /// env = { "LOG" = "debug" }
+    /// "#;
+    ///
+    /// let config: EnarxConfig = toml::from_str(CONFIG).unwrap();
+    ///
+    /// let dev = config.for_target(Backend::Sgx, Some("dev"));
+    /// assert_eq!(dev.files.len(), 2);
+    /// assert_eq!(dev.env["LOG"], "debug");
+    ///
+    /// let prod = config.for_target(Backend::Kvm, Some("prod"));
+    /// assert_eq!(prod.files.len(), 1);
+    /// assert_eq!(prod.env["LOG"], "info");
+    /// ```
+    pub fn for_target(&self, backend: Backend, profile: Option<&str>) -> Self {
+        let mut config = self.clone();
+
+        config.files = self
+            .files
+            .iter()
+            .filter_map(|file| match file {
+                File::Conditional { when, file } => {
+                    if when.matches(backend, profile) {
+                        Some((**file).clone())
+                    } else {
+                        None
+                    }
+                }
+                file => Some(file.clone()),
+            })
+            .collect();
+
+        for conditional in std::mem::take(&mut config.conditional) {
+            if conditional.when.matches(backend, profile) {
+                config.env.extend(conditional.env);
+            }
+        }
+
+        config
+    }
+
+    pub(crate) fn validate_conditions(&self) -> Result<(), ValidationError> {
+        let conditions =
+            self.files
+                .iter()
+                .enumerate()
+                .filter_map(|(i, file)| Some((format!("files[{}].when", i), file.when()?)))
+                .chain(self.conditional.iter().enumerate().map(|(i, conditional)| {
+                    (format!("conditional[{}].when", i), &conditional.when)
+                }));
+
+        for (path, when) in conditions {
+            when.validate().map_err(|(field, message)| match field {
+                "" => ValidationError::new(path.clone(), message),
+                field => ValidationError::new(format!("{}.{}", path, field), message),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+    env = { "LOG" = "info", "REGION" = "eu" }
+
+    [[files]]
+    kind = "stdin"
+
+    [[files]]
+    kind = "stdout"
+    path = "/var/log/app.log"
+    when = { backend = "kvm" }
+
+    [[files]]
+    kind = "stdout"
+    when = { backend = "sgx" }
+
+    [[files]]
+    name = "DEBUG"
+    kind = "listen"
+    port = 9000
+    when = { profile = "dev" }
+
+    [[conditional]]
+    when = { profile = "dev" }
+    env = { "LOG" = "debug" }
+
+    [[conditional]]
+    when = { backend = "sev", profile = "dev" }
+    env = { "LOG" = "trace", "SEV" = 1 }
+    "#;
+
+    #[test]
+    fn conditions() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.files[1].name(), "stdout");
+        assert_eq!(cfg.files[1].when().unwrap().backend, Some(Backend::Kvm));
+        assert!(cfg.files[0].when().is_none());
+
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), cfg);
+
+        let kvm = cfg.for_target(Backend::Kvm, None);
+        assert_eq!(kvm.validate(), Ok(()));
+        assert_eq!(kvm.files.len(), 2);
+        assert!(kvm.files[1].path().is_some());
+        assert_eq!(kvm.env["LOG"], "info");
+        assert!(kvm.conditional.is_empty());
+
+        let sgx = cfg.for_target(Backend::Sgx, Some("dev"));
+        assert_eq!(sgx.validate(), Ok(()));
+        assert_eq!(
+            sgx.files.iter().map(File::name).collect::<Vec<_>>(),
+            ["stdin", "stdout", "DEBUG"]
+        );
+        assert!(sgx.files[1].path().is_none());
+        assert_eq!(sgx.env["LOG"], "debug");
+
+        let sev = cfg.for_target(Backend::Sev, Some("dev"));
+        assert_eq!(sev.files.len(), 2);
+        assert_eq!(sev.env["LOG"], "trace");
+        assert_eq!(sev.env["SEV"], "1");
+        assert_eq!(sev.env["REGION"], "eu");
+    }
+
+    #[test]
+    fn invalid() {
+        let validate = |config: &str| {
+            toml::from_str::<EnarxConfig>(config)
+                .unwrap()
+                .validate()
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            validate("[[files]]\nkind = \"stdout\"\nwhen = {}"),
+            "files[0].when: requires `backend` or `profile`"
+        );
+        assert_eq!(
+            validate("[[conditional]]\nwhen = { profile = \"\" }"),
+            "conditional[0].when.profile: empty profile name"
+        );
+        assert_eq!(
+            validate("[[files]]\nname = \"API\"\nkind = \"listen\"\nport = 0\nwhen = { backend = \"nil\" }"),
+            "files[0].port: port 0 selects a random port, set `ephemeral = true` to request this explicitly"
+        );
+        assert_eq!(
+            validate("[[files]]\nkind = \"stdout\"\n[[files]]\nkind = \"stdout\"\nwhen = { backend = \"nil\" }\n[[files]]\nkind = \"stdout\""),
+            "files[2].name: duplicate file name `stdout`"
+        );
+        assert_eq!(
+            validate("[[files]]\nkind = \"stdin\"\n[[files]]\nkind = \"stdout\"\nwhen = { backend = \"nil\" }\n[[files]]\nname = \"stdout\"\nkind = \"stdout\"\nwhen = { backend = \"nil\" }"),
+            "files[2]: duplicate of `files[1]`"
+        );
+
+        assert!(toml::from_str::<EnarxConfig>(
+            "[[files]]\nkind = \"null\"\nwhen = { backend = \"tdx\" }"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,401 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Content-addressed references to remote configurations and workloads
+
+use std::{fmt, str::FromStr};
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use url::Url;
+
+use crate::{Digest, Host};
+
+/// A content-addressed reference to a remote configuration or workload
+///
+/// Parsed from, and displayed as, one of:
+///
+/// | reference                                    | value                 |
+/// |----------------------------------------------|-----------------------|
+/// | `oci://<registry>/<repository>@sha256:<hex>` | [`ConfigRef::Oci`]    |
+/// | `https://<url>#sha256=<hex>`                 | [`ConfigRef::Https`]  |
+///
+/// The content is fetched by a [`RefResolver`] of the caller and checked against the
+/// digest by [`ConfigRef::resolve`].
+///
+/// # Examples
+///
+/// ```
+/// use enarx_config::ConfigRef;
+///
+/// let digest = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+/// let reference: ConfigRef = format!("oci://ghcr.io/enarx/api@sha256:{}", digest).parse().unwrap();
+/// assert_eq!(reference.digest().to_string(), digest);
+///
+/// assert!("https://example.com/Enarx.toml".parse::<ConfigRef>().is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ConfigRef {
+    /// a manifest in an OCI registry
+    Oci {
+        /// host of the registry, with an optional port, e.g. `ghcr.io` or `localhost:5000`
+        registry: String,
+
+        /// path of the repository in the registry, e.g. `enarx/api`
+        repository: String,
+
+        /// digest of the manifest
+        digest: Digest,
+    },
+
+    /// a file served over HTTPS
+    Https {
+        /// the URL of the file, without the fragment
+        url: Url,
+
+        /// digest of the file
+        digest: Digest,
+    },
+}
+
+/// Error parsing a [`ConfigRef`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseConfigRefError(String);
+
+impl fmt::Display for ParseConfigRefError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseConfigRefError {}
+
+/// Error resolving a [`ConfigRef`]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ResolveError {
+    /// the [`RefResolver`] failed to fetch the content
+    #[error("failed to resolve `{reference}`: {source}")]
+    Resolver {
+        /// the reference
+        reference: Box<ConfigRef>,
+
+        /// the underlying error
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// the digest of the content does not match the reference
+    #[error("`{reference}` resolved to content with digest `{actual}`")]
+    DigestMismatch {
+        /// the reference
+        reference: Box<ConfigRef>,
+
+        /// the digest of the content
+        actual: Digest,
+    },
+
+    /// the content is not a valid configuration
+    #[error(transparent)]
+    Config(#[from] crate::Error),
+}
+
+/// Fetches the content of a [`ConfigRef`], e.g. with the OCI or HTTP client of the caller
+pub trait RefResolver {
+    /// get the content `reference` points to, it is checked against the digest afterwards
+    fn resolve(
+        &self,
+        reference: &ConfigRef,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// is `s` a valid repository path component of the OCI distribution specification,
+/// lowercase alphanumerics separated by `.`, `_`, `__` or any number of `-`
+fn valid_component(s: &str) -> bool {
+    let separators = |c: char| matches!(c, '.' | '_' | '-');
+    let alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+
+    s.starts_with(alphanumeric)
+        && s.ends_with(alphanumeric)
+        && s.chars().all(|c| alphanumeric(c) || separators(c))
+        && !s.contains("..")
+        && !s.contains("._")
+        && !s.contains("_.")
+        && !s.contains("___")
+        && !s.contains("-.")
+        && !s.contains(".-")
+        && !s.contains("-_")
+        && !s.contains("_-")
+}
+
+fn parse_digest(digest: &str) -> Result<Digest, ParseConfigRefError> {
+    digest
+        .parse()
+        .map_err(|e| ParseConfigRefError(format!("invalid digest: {}", e)))
+}
+
+fn parse_oci(s: &str, rest: &str) -> Result<ConfigRef, ParseConfigRefError> {
+    let invalid =
+        |reason: &str| ParseConfigRefError(format!("invalid reference `{}`: {}", s, reason));
+
+    let (name, digest) = rest
+        .split_once("@sha256:")
+        .ok_or_else(|| invalid("expected a `@sha256:` digest"))?;
+    let (registry, repository) = name
+        .split_once('/')
+        .ok_or_else(|| invalid("expected `<registry>/<repository>`"))?;
+
+    let split = match registry.starts_with('[') {
+        true => registry
+            .rsplit_once("]:")
+            .map(|(host, port)| (&registry[..host.len() + 1], port)),
+        false => registry.rsplit_once(':'),
+    };
+    let host = match split {
+        Some((host, port)) => {
+            port.parse::<u16>()
+                .map_err(|_| invalid("invalid registry port"))?;
+            host
+        }
+        None => registry,
+    };
+    host.parse::<Host>().map_err(|e| invalid(&e.to_string()))?;
+
+    if !repository.split('/').all(valid_component) {
+        return Err(invalid(
+            "repository path components must be lowercase alphanumerics separated by `.`, `_` or `-`",
+        ));
+    }
+
+    Ok(ConfigRef::Oci {
+        registry: registry.into(),
+        repository: repository.into(),
+        digest: parse_digest(digest)?,
+    })
+}
+
+fn parse_https(s: &str) -> Result<ConfigRef, ParseConfigRefError> {
+    let invalid =
+        |reason: &str| ParseConfigRefError(format!("invalid reference `{}`: {}", s, reason));
+
+    let mut url = Url::parse(s).map_err(|e| invalid(&e.to_string()))?;
+    if url.host().is_none() {
+        return Err(invalid("expected a host"));
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err(invalid("must not contain credentials"));
+    }
+
+    let digest = url
+        .fragment()
+        .and_then(|fragment| fragment.strip_prefix("sha256="))
+        .ok_or_else(|| invalid("expected a `#sha256=` digest"))?;
+    let digest = parse_digest(digest)?;
+    url.set_fragment(None);
+
+    Ok(ConfigRef::Https { url, digest })
+}
+
+impl FromStr for ConfigRef {
+    type Err = ParseConfigRefError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(rest) = s.strip_prefix("oci://") {
+            parse_oci(s, rest)
+        } else if s.starts_with("https://") {
+            parse_https(s)
+        } else {
+            Err(ParseConfigRefError(format!(
+                "invalid reference `{}`: expected `oci://` or `https://`",
+                s
+            )))
+        }
+    }
+}
+
+impl fmt::Display for ConfigRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Oci {
+                registry,
+                repository,
+                digest,
+            } => write!(f, "oci://{}/{}@sha256:{}", registry, repository, digest),
+            Self::Https { url, digest } => write!(f, "{}#sha256={}", url, digest),
+        }
+    }
+}
+
+impl Serialize for ConfigRef {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ConfigRef {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let reference = String::deserialize(deserializer)?;
+        reference.parse().map_err(D::Error::custom)
+    }
+}
+
+impl ConfigRef {
+    /// the digest the content must have
+    pub fn digest(&self) -> Digest {
+        match self {
+            Self::Oci { digest, .. } | Self::Https { digest, .. } => *digest,
+        }
+    }
+
+    /// check `content` against the digest of the reference
+    pub fn verify(&self, content: &[u8]) -> Result<(), ResolveError> {
+        let actual = Digest::of(content);
+        match actual == self.digest() {
+            true => Ok(()),
+            false => Err(ResolveError::DigestMismatch {
+                reference: Box::new(self.clone()),
+                actual,
+            }),
+        }
+    }
+
+    /// get the content of the reference with `resolver`, checked against the digest
+    pub fn resolve(&self, resolver: &dyn RefResolver) -> Result<Vec<u8>, ResolveError> {
+        let content = resolver
+            .resolve(self)
+            .map_err(|source| ResolveError::Resolver {
+                reference: Box::new(self.clone()),
+                source,
+            })?;
+        self.verify(&content)?;
+        Ok(content)
+    }
+
+    /// get the configuration in TOML format the reference points to with `resolver`,
+    /// checked against the digest and [validated](crate::EnarxConfig::validate)
+    ///
+    /// Requires the `toml` feature.
+    #[cfg(feature = "toml")]
+    pub fn load(&self, resolver: &dyn RefResolver) -> Result<crate::EnarxConfig, ResolveError> {
+        let content = self.resolve(resolver)?;
+        let source = std::str::from_utf8(&content)
+            .map_err(|e| crate::Error::parse(format!("invalid UTF-8: {}", e)))?;
+
+        let config = crate::EnarxConfig::from_toml_str(source)?;
+        config.validate().map_err(crate::Error::from)?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = "args = [ \"--verbose\" ]\n";
+
+    #[cfg(feature = "toml")]
+    struct Static(&'static str);
+
+    #[cfg(feature = "toml")]
+    impl RefResolver for Static {
+        fn resolve(
+            &self,
+            _reference: &ConfigRef,
+        ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(self.0.as_bytes().to_vec())
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let digest = Digest::of(CONFIG);
+
+        for reference in [
+            format!("oci://ghcr.io/enarx/api@sha256:{}", digest),
+            format!("oci://localhost:5000/api@sha256:{}", digest),
+            format!("oci://[::1]:5000/my-org/api__v2@sha256:{}", digest),
+            format!("oci://[::1]/api@sha256:{}", digest),
+            format!(
+                "https://configs.example.com/api/Enarx.toml#sha256={}",
+                digest
+            ),
+            format!(
+                "https://configs.example.com:8443/Enarx.toml?v=2#sha256={}",
+                digest
+            ),
+        ] {
+            let parsed = reference.parse::<ConfigRef>().unwrap();
+            assert_eq!(parsed.to_string(), reference);
+            assert_eq!(parsed.digest(), digest);
+
+            let json = serde_json::to_string(&parsed).unwrap();
+            assert_eq!(serde_json::from_str::<ConfigRef>(&json).unwrap(), parsed);
+        }
+
+        let oci = format!("oci://ghcr.io/enarx/api@sha256:{}", digest)
+            .parse::<ConfigRef>()
+            .unwrap();
+        assert_eq!(
+            oci,
+            ConfigRef::Oci {
+                registry: "ghcr.io".into(),
+                repository: "enarx/api".into(),
+                digest,
+            }
+        );
+
+        let err = |reference: &str| reference.parse::<ConfigRef>().unwrap_err().to_string();
+        assert!(err("file:///Enarx.toml").ends_with("expected `oci://` or `https://`"));
+        assert!(err("oci://ghcr.io/enarx/api:latest").ends_with("expected a `@sha256:` digest"));
+        assert!(err(&format!("oci://ghcr.io@sha256:{}", digest))
+            .ends_with("expected `<registry>/<repository>`"));
+        assert!(err(&format!("oci://ghcr.io/Enarx/API@sha256:{}", digest))
+            .contains("repository path components must be lowercase"));
+        assert!(err(&format!("oci://ghcr.io/enarx//api@sha256:{}", digest))
+            .contains("repository path components"));
+        assert!(err(&format!("oci://ghcr.io:x/api@sha256:{}", digest))
+            .ends_with("invalid registry port"));
+        assert!(err("oci://ghcr.io/api@sha256:xyz").starts_with("invalid digest"));
+        assert!(err("https://example.com/Enarx.toml").ends_with("expected a `#sha256=` digest"));
+        assert!(err(&format!(
+            "https://user:pw@example.com/Enarx.toml#sha256={}",
+            digest
+        ))
+        .ends_with("must not contain credentials"));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn resolve() {
+        let reference = format!(
+            "https://example.com/Enarx.toml#sha256={}",
+            Digest::of(CONFIG)
+        )
+        .parse::<ConfigRef>()
+        .unwrap();
+
+        assert_eq!(
+            reference.resolve(&Static(CONFIG)).unwrap(),
+            CONFIG.as_bytes()
+        );
+        assert_eq!(reference.load(&Static(CONFIG)).unwrap().args, ["--verbose"]);
+
+        let err = reference.resolve(&Static("args = []")).unwrap_err();
+        assert!(matches!(
+            &err,
+            ResolveError::DigestMismatch { actual, .. } if *actual == Digest::of("args = []")
+        ));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "`{}` resolved to content with digest `{}`",
+                reference,
+                Digest::of("args = []")
+            )
+        );
+    }
+}
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inline content of `kind = "stdin"` files
+
+use std::{fmt, ops::Deref};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::File;
+
+/// Binary data written as base64 string
+#[derive(Clone, PartialEq, Eq)]
+pub struct Base64(Vec<u8>);
+
+impl Base64 {
+    /// get the decoded bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for Base64 {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl Deref for Base64 {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Base64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Base64({:?})", STANDARD.encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Base64 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let encoded = String::deserialize(deserializer)?;
+        // allow wrapping long values in multi-line strings
+        let encoded = encoded.split_whitespace().collect::<String>();
+
+        STANDARD
+            .decode(encoded)
+            .map(Self)
+            .map_err(|e| D::Error::custom(format!("invalid base64 content: {}", e)))
+    }
+}
+
+impl Serialize for Base64 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        STANDARD.encode(&self.0).serialize(serializer)
+    }
+}
+
+impl File {
+    /// get the inline content of a `kind = "stdin"` file
+    ///
+    /// Returns the bytes of `content` or `content_base64`.
+    pub fn content(&self) -> Option<&[u8]> {
+        match self {
+            File::Stdin {
+                content: Some(content),
+                ..
+            } => Some(content.as_bytes()),
+            File::Stdin {
+                content_base64: Some(content),
+                ..
+            } => Some(content.as_bytes()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::EnarxConfig;
+
+    #[test]
+    fn content() {
+        const CONFIG: &str = r#"
+        [[files]]
+        kind = "stdin"
+        content = '{ "request": 1 }'
+
+        [[files]]
+        name = "seed"
+        kind = "stdin"
+        content_base64 = """
+            AAEC
+            /w==
+        """
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.files[0].content(), Some(&br#"{ "request": 1 }"#[..]));
+        assert_eq!(cfg.files[1].content(), Some(&[0, 1, 2, 255][..]));
+
+        let err =
+            toml::from_str::<EnarxConfig>("[[files]]\nkind = \"stdin\"\ncontent_base64 = \"!\"")
+                .unwrap_err();
+        assert!(err.to_string().starts_with("invalid base64 content"));
+    }
+}
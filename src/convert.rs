@@ -0,0 +1,801 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Best-effort conversion of docker-compose services and Kubernetes pods, and export of
+//! Kubernetes objects matching a configuration
+//!
+//! Requires the `convert` feature.
+//!
+//! The sources are taken as a [`Value`], any format deserializable into one works, e.g. the
+//! YAML of a `docker-compose.yml` or a Kubernetes manifest. The converted settings are the
+//! arguments, the environment and the ports, which become `kind = "listen"` files with
+//! `prot = "tcp"`. Everything else is reported as [`Unconverted`].
+//!
+//! [`to_kubernetes`] goes the other way, and generates the `Service` and `NetworkPolicy`
+//! of a configuration.
+
+use std::fmt;
+
+use serde_json::{json, Map, Value};
+
+use crate::{args, EnarxConfig, File, HostPattern};
+
+/// An item of the source without an equivalent in the configuration
+///
+/// Contains the path of the item in the source, e.g. `volumes[0]`, and the reason.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unconverted {
+    path: String,
+    reason: String,
+}
+
+impl Unconverted {
+    fn new(path: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// the path of the item in the source
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// why the item was not converted
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl fmt::Display for Unconverted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.reason)
+    }
+}
+
+/// The result of a conversion
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conversion {
+    /// the converted configuration
+    pub config: EnarxConfig,
+
+    /// the items of the source, which were not converted
+    pub unconverted: Vec<Unconverted>,
+}
+
+const IMAGE: &str = "the WASM workload is deployed instead of the image";
+const ENTRYPOINT: &str = "the WASM workload is the entrypoint";
+
+/// the conversion in progress
+struct Converter {
+    config: EnarxConfig,
+    unconverted: Vec<Unconverted>,
+}
+
+impl Converter {
+    fn new() -> Self {
+        Self {
+            config: EnarxConfig::default(),
+            unconverted: vec![],
+        }
+    }
+
+    fn skip(&mut self, path: impl Into<String>, reason: impl Into<String>) {
+        self.unconverted.push(Unconverted::new(path, reason));
+    }
+
+    fn unsupported(&mut self, path: impl Into<String>) {
+        self.skip(path, "not supported");
+    }
+
+    fn finish(self) -> Conversion {
+        Conversion {
+            config: self.config,
+            unconverted: self.unconverted,
+        }
+    }
+
+    /// set the arguments of a list of strings, or of a string split like a shell does
+    fn args(&mut self, path: &str, value: &Value) {
+        let args = match value {
+            Value::String(line) => args::split(line).ok(),
+            Value::Array(values) => values.iter().map(scalar).collect(),
+            _ => None,
+        };
+        match args {
+            Some(args) => self.config.args = args,
+            None => self.skip(path, "expected a string or a list of strings"),
+        }
+    }
+
+    fn env(&mut self, path: &str, name: &str, value: Option<String>) {
+        match value {
+            Some(value) => {
+                self.config.env.insert(name.into(), value);
+            }
+            None => self.skip(path, "the value is taken from the host"),
+        }
+    }
+
+    /// add a listener, `port` is a port or a port range
+    fn listen(&mut self, path: &str, name: Option<&str>, addr: Option<&str>, port: &str) {
+        let name = match name {
+            Some(name) => name.into(),
+            None => format!("PORT_{}", port.replace('-', "_")),
+        };
+
+        let mut file = json!({ "kind": "listen", "name": name, "prot": "tcp" });
+        match port.parse::<u16>() {
+            Ok(port) => file["port"] = port.into(),
+            Err(_) => file["ports"] = port.into(),
+        }
+        if let Some(addr) = addr {
+            file["addr"] = addr.into();
+        }
+
+        match serde_json::from_value::<File>(file) {
+            Ok(file) => self.config.files.push(file),
+            Err(e) => self.skip(path, e.to_string()),
+        }
+    }
+}
+
+/// a string, number or boolean as string
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// split the short syntax of a compose port, `[[HOST_IP:]PUBLISHED:]TARGET[/PROTOCOL]`
+///
+/// Returns the host IP, the published port or range, the target port or range, and the protocol.
+fn split_compose_port(spec: &str) -> (Option<&str>, Option<&str>, &str, &str) {
+    let (spec, protocol) = spec.split_once('/').unwrap_or((spec, "tcp"));
+    let (rest, target) = match spec.rsplit_once(':') {
+        Some((rest, target)) => (Some(rest), target),
+        None => (None, spec),
+    };
+    let (host_ip, published) = match rest.map(|rest| rest.rsplit_once(':')) {
+        Some(Some((ip, published))) => (Some(ip), Some(published)),
+        Some(None) => (None, rest),
+        None => (None, None),
+    };
+    let host_ip = host_ip.map(|ip| {
+        ip.strip_prefix('[')
+            .and_then(|ip| ip.strip_suffix(']'))
+            .unwrap_or(ip)
+    });
+    (
+        host_ip,
+        published.filter(|published| !published.is_empty()),
+        target,
+        protocol,
+    )
+}
+
+/// convert a service of a `docker-compose.yml`, the value of its entry under `services`
+///
+/// The `command` becomes the arguments, the `environment` the environment variables,
+/// and the published, or otherwise the target, `ports` the listeners.
+///
+/// # Examples
+///
+/// ```
+/// use enarx_config::convert::from_compose_service;
+/// use serde_json::json;
+///
+/// let service = json!({
+///     "image": "example/api:1.0",
+///     "command": "serve --verbose",
+///     "environment": { "LOG": "debug" },
+///     "ports": [ "8080:80" ],
+/// });
+///
+/// let conversion = from_compose_service(&service);
+/// assert_eq!(conversion.config.args, [ "serve", "--verbose" ]);
+/// assert_eq!(conversion.config.env["LOG"], "debug");
+/// assert_eq!(conversion.config.files[3].as_listen().unwrap().port, 8080.into());
+/// assert_eq!(conversion.unconverted[0].path(), "image");
+/// ```
+pub fn from_compose_service(service: &Value) -> Conversion {
+    let mut converter = Converter::new();
+    let service = match service.as_object() {
+        Some(service) => service,
+        None => {
+            converter.skip("", "expected a service");
+            return converter.finish();
+        }
+    };
+
+    for (key, value) in service {
+        match key.as_str() {
+            "image" => converter.skip(key, IMAGE),
+            "entrypoint" => converter.skip(key, ENTRYPOINT),
+            "command" => converter.args(key, value),
+            "environment" => compose_environment(&mut converter, value),
+            "ports" => compose_ports(&mut converter, value),
+            _ => converter.unsupported(key),
+        }
+    }
+
+    converter.finish()
+}
+
+fn compose_environment(converter: &mut Converter, value: &Value) {
+    match value {
+        Value::Object(map) => {
+            for (name, value) in map {
+                let path = format!("environment.{}", name);
+                converter.env(&path, name, scalar(value));
+            }
+        }
+        Value::Array(entries) => {
+            for (i, entry) in entries.iter().enumerate() {
+                let path = format!("environment[{}]", i);
+                match entry.as_str().map(|entry| entry.split_once('=')) {
+                    Some(Some((name, value))) => converter.env(&path, name, Some(value.into())),
+                    Some(None) => converter.env(&path, "", None),
+                    None => converter.skip(path, "expected `NAME=VALUE`"),
+                }
+            }
+        }
+        _ => converter.skip("environment", "expected a map or a list"),
+    }
+}
+
+fn compose_ports(converter: &mut Converter, value: &Value) {
+    let ports = match value.as_array() {
+        Some(ports) => ports,
+        None => return converter.skip("ports", "expected a list"),
+    };
+
+    for (i, port) in ports.iter().enumerate() {
+        let path = format!("ports[{}]", i);
+        match port {
+            Value::Object(port) => {
+                let field = |name: &str| port.get(name).and_then(scalar);
+                let protocol = field("protocol").unwrap_or_else(|| "tcp".into());
+                let port_number = field("published").or_else(|| field("target"));
+                match (protocol.as_str(), port_number) {
+                    ("tcp", Some(number)) => converter.listen(
+                        &path,
+                        field("name").as_deref(),
+                        field("host_ip").as_deref(),
+                        &number,
+                    ),
+                    ("tcp", None) => converter.skip(path, "expected a `target` port"),
+                    (protocol, _) => {
+                        converter.skip(path, format!("protocol `{}` is not supported", protocol))
+                    }
+                }
+            }
+            port => match scalar(port) {
+                Some(spec) => {
+                    let (host_ip, published, target, protocol) = split_compose_port(&spec);
+                    if protocol != "tcp" {
+                        converter.skip(path, format!("protocol `{}` is not supported", protocol));
+                    } else {
+                        converter.listen(&path, None, host_ip, published.unwrap_or(target));
+                    }
+                }
+                None => converter.skip(path, "expected a port"),
+            },
+        }
+    }
+}
+
+/// convert a Kubernetes `Pod`, or the `spec` of one
+///
+/// Only the first of the `containers` is converted. Its `args` become the arguments,
+/// its `env` the environment variables, and its `ports` the listeners, on the `hostPort`
+/// or otherwise the `containerPort`, named after the port.
+///
+/// # Examples
+///
+/// ```
+/// use enarx_config::convert::from_pod_spec;
+/// use serde_json::json;
+///
+/// let pod = json!({
+///     "apiVersion": "v1",
+///     "kind": "Pod",
+///     "metadata": { "name": "api" },
+///     "spec": {
+///         "containers": [{
+///             "name": "api",
+///             "image": "example/api:1.0",
+///             "args": [ "serve" ],
+///             "ports": [ { "name": "http", "containerPort": 8080 } ],
+///         }],
+///     },
+/// });
+///
+/// let conversion = from_pod_spec(&pod);
+/// assert_eq!(conversion.config.args, [ "serve" ]);
+/// assert_eq!(conversion.config.files[3].name(), "http");
+/// assert_eq!(conversion.unconverted[0].path(), "spec.containers[0].image");
+/// ```
+pub fn from_pod_spec(pod: &Value) -> Conversion {
+    let mut converter = Converter::new();
+
+    let (spec, prefix) = match pod.get("spec") {
+        Some(spec) if pod.get("kind").and_then(Value::as_str) == Some("Pod") => (spec, "spec."),
+        _ => (pod, ""),
+    };
+    let spec = match spec.as_object() {
+        Some(spec) => spec,
+        None => {
+            converter.skip(prefix.trim_end_matches('.'), "expected a pod spec");
+            return converter.finish();
+        }
+    };
+
+    for (key, value) in spec {
+        let path = format!("{}{}", prefix, key);
+        match (key.as_str(), value.as_array()) {
+            ("containers", Some(containers)) => {
+                for (i, container) in containers.iter().enumerate() {
+                    let path = format!("{}[{}]", path, i);
+                    match container.as_object() {
+                        Some(container) if i == 0 => {
+                            pod_container(&mut converter, &path, container)
+                        }
+                        _ => converter.skip(path, "only the first container is converted"),
+                    }
+                }
+            }
+            _ => converter.unsupported(path),
+        }
+    }
+
+    converter.finish()
+}
+
+fn pod_container(converter: &mut Converter, prefix: &str, container: &Map<String, Value>) {
+    for (key, value) in container {
+        let path = format!("{}.{}", prefix, key);
+        match key.as_str() {
+            "name" => {}
+            "image" => converter.skip(path, IMAGE),
+            "command" => converter.skip(path, ENTRYPOINT),
+            "args" => converter.args(&path, value),
+            "env" => {
+                for (i, var) in value.as_array().into_iter().flatten().enumerate() {
+                    let path = format!("{}[{}]", path, i);
+                    match var.get("name").and_then(Value::as_str) {
+                        Some(name) => converter.env(&path, name, var.get("value").and_then(scalar)),
+                        None => converter.skip(path, "expected a `name`"),
+                    }
+                }
+            }
+            "ports" => {
+                for (i, port) in value.as_array().into_iter().flatten().enumerate() {
+                    let path = format!("{}[{}]", path, i);
+                    let field = |name: &str| port.get(name).and_then(scalar);
+                    let protocol = field("protocol").unwrap_or_else(|| "TCP".into());
+                    let number = field("hostPort").or_else(|| field("containerPort"));
+                    match (protocol.as_str(), number) {
+                        ("TCP", Some(number)) => converter.listen(
+                            &path,
+                            field("name").as_deref(),
+                            field("hostIP").as_deref(),
+                            &number,
+                        ),
+                        ("TCP", None) => converter.skip(path, "expected a `containerPort`"),
+                        (protocol, _) => converter
+                            .skip(path, format!("protocol `{}` is not supported", protocol)),
+                    }
+                }
+            }
+            _ => converter.unsupported(path),
+        }
+    }
+}
+
+/// The Kubernetes objects matching a configuration, generated by [`to_kubernetes`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KubernetesObjects {
+    /// the `Service` exposing the listeners
+    pub service: Value,
+
+    /// the `NetworkPolicy` of the listeners and the `[network.egress]` policy
+    pub network_policy: Value,
+
+    /// the settings of the configuration, which are not reflected in the objects
+    pub unconverted: Vec<Unconverted>,
+}
+
+/// get a valid Kubernetes port name, a lowercase DNS label of at most 15 characters
+fn port_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            c @ ('a'..='z' | '0'..='9') => c,
+            _ => '-',
+        })
+        .take(15)
+        .collect::<String>();
+    name.trim_matches('-').into()
+}
+
+/// generate the `Service` and `NetworkPolicy` of the pods named `name` running `config`
+///
+/// The objects select the pods by the label `app: <name>`, and carry the `labels` of the
+/// configuration. The `Service` exposes the port of every `kind = "listen"` file, the
+/// `NetworkPolicy` allows connections to them from their `allow` networks, and, with a
+/// `[network.egress]` policy, only the outbound connections allowed by it.
+///
+/// Files with a `when` condition are not exported, use [`EnarxConfig::for_target`] first.
+///
+/// # Examples
+///
+/// ```
+/// extern crate toml;
+/// use enarx_config::{convert::to_kubernetes, EnarxConfig};
+///
+/// let config: EnarxConfig = toml::from_str(r#"
+/// [[files]]
+/// name = "API"
+/// kind = "listen"
+/// port = 8443
+/// "#).unwrap();
+///
+/// let objects = to_kubernetes(&config, "api");
+/// assert_eq!(objects.service["spec"]["ports"][0]["port"], 8443);
+/// assert_eq!(objects.service["spec"]["selector"]["app"], "api");
+/// assert_eq!(objects.network_policy["spec"]["policyTypes"][0], "Ingress");
+/// ```
+pub fn to_kubernetes(config: &EnarxConfig, name: &str) -> KubernetesObjects {
+    let mut unconverted = vec![];
+    let config = config.normalize();
+
+    let mut labels = config
+        .labels
+        .iter()
+        .map(|(key, value)| (key.clone(), Value::from(value.as_str())))
+        .collect::<Map<_, _>>();
+    labels.insert("app".into(), name.into());
+    let metadata = json!({ "name": name, "labels": labels });
+    let selector = json!({ "app": name });
+
+    let mut ports = vec![];
+    let mut ingress = vec![];
+    for (i, file) in config.files.iter().enumerate() {
+        if file.when().is_some() && file.kind() == "listen" {
+            unconverted.push(Unconverted::new(
+                format!("files[{}]", i),
+                "files with a `when` condition are not exported",
+            ));
+            continue;
+        }
+        let listen = match file.as_listen() {
+            Some(listen) => listen,
+            None => continue,
+        };
+        if listen.ephemeral {
+            unconverted.push(Unconverted::new(
+                format!("files[{}].ephemeral", i),
+                "the port is not known in advance",
+            ));
+            continue;
+        }
+
+        let port = listen.port.get();
+        ports.push(json!({
+            "name": port_name(listen.name),
+            "protocol": "TCP",
+            "port": port,
+            "targetPort": port,
+        }));
+
+        let mut rule = json!({ "ports": [ { "protocol": "TCP", "port": port } ] });
+        if !listen.allow.is_empty() {
+            let from = listen
+                .allow
+                .iter()
+                .map(|cidr| json!({ "ipBlock": { "cidr": cidr.to_string() } }))
+                .collect::<Vec<_>>();
+            rule["from"] = from.into();
+        }
+        ingress.push(rule);
+    }
+
+    let service = json!({
+        "apiVersion": "v1",
+        "kind": "Service",
+        "metadata": metadata,
+        "spec": { "selector": selector, "ports": ports },
+    });
+
+    let mut spec = json!({
+        "podSelector": { "matchLabels": selector },
+        "policyTypes": [ "Ingress" ],
+        "ingress": ingress,
+    });
+    if let Some(egress) = config.network.as_ref().and_then(|n| n.egress.as_ref()) {
+        let ports = egress
+            .ports
+            .iter()
+            .map(|range| match range.start() == range.end() {
+                true => json!({ "protocol": "TCP", "port": range.start().get() }),
+                false => json!({
+                    "protocol": "TCP",
+                    "port": range.start().get(),
+                    "endPort": range.end().get(),
+                }),
+            })
+            .collect::<Vec<_>>();
+
+        let mut cidrs = egress
+            .cidrs
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let mut names = false;
+        for (i, host) in egress.hosts.iter().enumerate() {
+            match host {
+                HostPattern::Cidr(cidr) => cidrs.push(cidr.to_string()),
+                HostPattern::Exact(host) => match host.ip() {
+                    Some(ip) if ip.is_ipv4() => cidrs.push(format!("{}/32", ip)),
+                    Some(ip) => cidrs.push(format!("{}/128", ip)),
+                    None => names = true,
+                },
+                HostPattern::Subdomains(_) => names = true,
+            }
+            if !host.is_ip() {
+                unconverted.push(Unconverted::new(
+                    format!("network.egress.hosts[{}]", i),
+                    "a NetworkPolicy can not select DNS names, all destinations are allowed on the egress ports",
+                ));
+            }
+        }
+
+        let mut rules = vec![];
+        if !cidrs.is_empty() {
+            let to = cidrs
+                .iter()
+                .map(|cidr| json!({ "ipBlock": { "cidr": cidr } }))
+                .collect::<Vec<_>>();
+            rules.push(json!({ "to": to }));
+        }
+        if names {
+            rules.push(json!({}));
+        }
+        if !ports.is_empty() {
+            for rule in &mut rules {
+                rule["ports"] = ports.clone().into();
+            }
+        }
+
+        spec["policyTypes"] = json!(["Ingress", "Egress"]);
+        spec["egress"] = rules.into();
+    }
+
+    let network_policy = json!({
+        "apiVersion": "networking.k8s.io/v1",
+        "kind": "NetworkPolicy",
+        "metadata": metadata,
+        "spec": spec,
+    });
+
+    KubernetesObjects {
+        service,
+        network_policy,
+        unconverted,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unconverted(conversion: &Conversion) -> Vec<String> {
+        conversion
+            .unconverted
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn compose() {
+        let service = json!({
+            "image": "example/api:1.0",
+            "command": [ "serve", "--port", 80 ],
+            "environment": [ "LOG=debug", "TZ" ],
+            "ports": [
+                "80",
+                "127.0.0.1:8443:443",
+                "9000-9002:9000-9002",
+                "53:53/udp",
+                { "target": 81, "published": "8081", "name": "admin" },
+            ],
+            "volumes": [ "./data:/data" ],
+        });
+
+        let conversion = from_compose_service(&service);
+        let cfg = &conversion.config;
+        assert_eq!(cfg.args, ["serve", "--port", "80"]);
+        assert_eq!(cfg.env.len(), 1);
+        assert_eq!(cfg.env["LOG"], "debug");
+        assert_eq!(
+            cfg.files[3..]
+                .iter()
+                .map(|file| {
+                    let listen = file.as_listen().unwrap();
+                    (listen.name, listen.addr.to_string(), listen.port.get())
+                })
+                .collect::<Vec<_>>(),
+            [
+                ("PORT_80", "::".to_string(), 80),
+                ("PORT_8443", "127.0.0.1".to_string(), 8443),
+                ("PORT_9000_9002", "::".to_string(), 9000),
+                ("admin", "::".to_string(), 8081),
+            ]
+        );
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.normalize().files.len(), 9);
+
+        assert_eq!(
+            unconverted(&conversion),
+            [
+                "environment[1]: the value is taken from the host",
+                "image: the WASM workload is deployed instead of the image",
+                "ports[3]: protocol `udp` is not supported",
+                "volumes: not supported",
+            ]
+        );
+    }
+
+    #[test]
+    fn pod() {
+        let spec = json!({
+            "containers": [
+                {
+                    "name": "api",
+                    "command": [ "/bin/api" ],
+                    "args": "serve --verbose",
+                    "env": [
+                        { "name": "LOG", "value": "debug" },
+                        { "name": "TOKEN", "valueFrom": { "secretKeyRef": { "name": "api", "key": "token" } } },
+                    ],
+                    "ports": [
+                        { "name": "http", "containerPort": 8080 },
+                        { "containerPort": 9090, "hostPort": 19090 },
+                        { "name": "dns", "containerPort": 53, "protocol": "UDP" },
+                    ],
+                    "volumeMounts": [ { "name": "data", "mountPath": "/data" } ],
+                },
+                { "name": "sidecar" },
+            ],
+            "restartPolicy": "Always",
+        });
+
+        let conversion = from_pod_spec(&spec);
+        let cfg = &conversion.config;
+        assert_eq!(cfg.args, ["serve", "--verbose"]);
+        assert_eq!(cfg.env.keys().collect::<Vec<_>>(), ["LOG"]);
+        assert_eq!(
+            cfg.files[3..]
+                .iter()
+                .map(|file| (file.name(), file.as_listen().unwrap().port.get()))
+                .collect::<Vec<_>>(),
+            [("http", 8080), ("PORT_19090", 19090)]
+        );
+        assert_eq!(cfg.validate(), Ok(()));
+
+        assert_eq!(
+            unconverted(&conversion),
+            [
+                "containers[0].command: the WASM workload is the entrypoint",
+                "containers[0].env[1]: the value is taken from the host",
+                "containers[0].ports[2]: protocol `UDP` is not supported",
+                "containers[0].volumeMounts: not supported",
+                "containers[1]: only the first container is converted",
+                "restartPolicy: not supported",
+            ]
+        );
+
+        let conversion = from_pod_spec(&json!("Pod"));
+        assert_eq!(unconverted(&conversion), [": expected a pod spec"]);
+        assert_eq!(conversion.config, EnarxConfig::default());
+    }
+
+    #[test]
+    fn kubernetes() {
+        let cfg: EnarxConfig = toml::from_str(
+            r#"
+            labels = { "team" = "payments" }
+
+            [network.egress]
+            hosts = [ "*.example.com", "10.1.1.1" ]
+            cidrs = [ "192.168.0.0/16" ]
+            ports = [ 443, "5432-5433" ]
+
+            [[files]]
+            name = "Public_API"
+            kind = "listen"
+            port = [ 8080, 8443 ]
+            allow = [ "10.0.0.0/8" ]
+
+            [[files]]
+            name = "RANDOM"
+            kind = "listen"
+            ephemeral = true
+
+            [[files]]
+            name = "DEBUG"
+            kind = "listen"
+            port = 9000
+            when = { profile = "dev" }
+            "#,
+        )
+        .unwrap();
+
+        let objects = to_kubernetes(&cfg, "payments-api");
+        assert_eq!(
+            objects.service,
+            json!({
+                "apiVersion": "v1",
+                "kind": "Service",
+                "metadata": { "name": "payments-api", "labels": { "app": "payments-api", "team": "payments" } },
+                "spec": {
+                    "selector": { "app": "payments-api" },
+                    "ports": [
+                        { "name": "public-api-8080", "protocol": "TCP", "port": 8080, "targetPort": 8080 },
+                        { "name": "public-api-8443", "protocol": "TCP", "port": 8443, "targetPort": 8443 },
+                    ],
+                },
+            })
+        );
+
+        let spec = &objects.network_policy["spec"];
+        assert_eq!(spec["policyTypes"], json!(["Ingress", "Egress"]));
+        assert_eq!(
+            spec["ingress"][1],
+            json!({
+                "ports": [ { "protocol": "TCP", "port": 8443 } ],
+                "from": [ { "ipBlock": { "cidr": "10.0.0.0/8" } } ],
+            })
+        );
+        let ports = json!([
+            { "protocol": "TCP", "port": 443 },
+            { "protocol": "TCP", "port": 5432, "endPort": 5433 },
+        ]);
+        assert_eq!(
+            spec["egress"],
+            json!([
+                {
+                    "to": [
+                        { "ipBlock": { "cidr": "192.168.0.0/16" } },
+                        { "ipBlock": { "cidr": "10.1.1.1/32" } },
+                    ],
+                    "ports": ports,
+                },
+                { "ports": ports },
+            ])
+        );
+
+        assert_eq!(
+            objects
+                .unconverted
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "files[2].ephemeral: the port is not known in advance",
+                "files[3]: files with a `when` condition are not exported",
+                "network.egress.hosts[0]: a NetworkPolicy can not select DNS names, all destinations are allowed on the egress ports",
+            ]
+        );
+
+        let objects = to_kubernetes(&EnarxConfig::default(), "empty");
+        assert_eq!(objects.service["spec"]["ports"], json!([]));
+        assert_eq!(objects.network_policy["spec"]["ingress"], json!([]));
+        assert!(objects.network_policy["spec"].get("egress").is_none());
+    }
+}
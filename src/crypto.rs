@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Key policy of the `[crypto]` section
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{EnarxConfig, KeyAlgorithm, ValidationError};
+
+/// RSA key sizes `min_rsa_bits` can require
+const RSA_BITS: &[u32] = &[2048, 3072, 4096];
+
+/// elliptic curve key sizes `min_ec_bits` can require
+const EC_BITS: &[u32] = &[256, 384, 521];
+
+/// Type of a key generated by the keep
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum KeyType {
+    /// RSA, with a size of at least `min_rsa_bits`
+    #[serde(rename = "rsa")]
+    Rsa,
+
+    /// ECDSA with the NIST P-256 curve
+    #[serde(rename = "p256")]
+    P256,
+
+    /// ECDSA with the NIST P-384 curve
+    #[serde(rename = "p384")]
+    P384,
+
+    /// ECDSA with the NIST P-521 curve
+    #[serde(rename = "p521")]
+    P521,
+
+    /// Ed25519
+    #[serde(rename = "ed25519")]
+    Ed25519,
+}
+
+impl KeyType {
+    /// get the size of an elliptic curve key in bits, `None` for RSA
+    pub fn ec_bits(self) -> Option<u32> {
+        match self {
+            Self::Rsa => None,
+            Self::P256 | Self::Ed25519 => Some(256),
+            Self::P384 => Some(384),
+            Self::P521 => Some(521),
+        }
+    }
+}
+
+impl From<KeyAlgorithm> for KeyType {
+    fn from(algorithm: KeyAlgorithm) -> Self {
+        match algorithm {
+            KeyAlgorithm::Ed25519 => Self::Ed25519,
+            KeyAlgorithm::P256 => Self::P256,
+        }
+    }
+}
+
+impl fmt::Display for KeyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rsa => f.write_str("rsa"),
+            Self::P256 => f.write_str("p256"),
+            Self::P384 => f.write_str("p384"),
+            Self::P521 => f.write_str("p521"),
+            Self::Ed25519 => f.write_str("ed25519"),
+        }
+    }
+}
+
+/// The `[crypto]` section, restricting the keys the keep generates for TLS and sealing
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "RawCrypto")]
+pub struct Crypto {
+    /// the allowed key types, all types are allowed if not specified
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_types: Option<Vec<KeyType>>,
+
+    /// minimum size of RSA keys in bits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_rsa_bits: Option<u32>,
+
+    /// minimum size of elliptic curve keys in bits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_ec_bits: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct RawCrypto {
+    #[serde(default)]
+    key_types: Option<Vec<KeyType>>,
+    #[serde(default)]
+    min_rsa_bits: Option<u32>,
+    #[serde(default)]
+    min_ec_bits: Option<u32>,
+}
+
+impl TryFrom<RawCrypto> for Crypto {
+    type Error = String;
+
+    fn try_from(raw: RawCrypto) -> Result<Self, Self::Error> {
+        let check = |bits: Option<u32>, known: &[u32], name: &str| match bits {
+            Some(bits) if !known.contains(&bits) => Err(format!(
+                "unsupported `{}` {}, expected one of {:?}",
+                name, bits, known
+            )),
+            _ => Ok(()),
+        };
+        check(raw.min_rsa_bits, RSA_BITS, "min_rsa_bits")?;
+        check(raw.min_ec_bits, EC_BITS, "min_ec_bits")?;
+
+        let crypto = Self {
+            key_types: raw.key_types,
+            min_rsa_bits: raw.min_rsa_bits,
+            min_ec_bits: raw.min_ec_bits,
+        };
+        if let Some(key_types) = &crypto.key_types {
+            if !key_types.iter().any(|&key_type| crypto.allows(key_type)) {
+                return Err("none of `key_types` satisfies the minimum key sizes".into());
+            }
+        }
+
+        Ok(crypto)
+    }
+}
+
+impl Crypto {
+    /// check if the keep may generate keys of `key_type`
+    ///
+    /// RSA keys have to be generated with at least [`min_rsa_bits`](Self::min_rsa_bits).
+    pub fn allows(&self, key_type: KeyType) -> bool {
+        if matches!(&self.key_types, Some(key_types) if !key_types.contains(&key_type)) {
+            return false;
+        }
+
+        match (key_type.ec_bits(), self.min_ec_bits) {
+            (Some(bits), Some(min)) => bits >= min,
+            _ => true,
+        }
+    }
+}
+
+impl EnarxConfig {
+    pub(crate) fn validate_crypto(&self, crypto: &Crypto) -> Result<(), ValidationError> {
+        let csr = self.steward.as_ref().and_then(|s| s.csr.as_ref());
+        if let Some(algorithm) = csr.and_then(|csr| csr.key_algorithm) {
+            if !crypto.allows(algorithm.into()) {
+                return Err(ValidationError::new(
+                    "steward.csr.key_algorithm",
+                    format!("`{}` is not allowed by `crypto`", algorithm),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn p384_only() {
+        const CONFIG: &str = r#"
+        [steward]
+        url = "https://steward.example.com/"
+        csr = { key_algorithm = "p256" }
+
+        [crypto]
+        key_types = [ "p384", "rsa" ]
+        min_rsa_bits = 3072
+        min_ec_bits = 384
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let crypto = cfg.crypto.as_ref().unwrap();
+
+        assert!(crypto.allows(KeyType::P384));
+        assert!(crypto.allows(KeyType::Rsa));
+        assert!(!crypto.allows(KeyType::P256));
+        assert!(!crypto.allows(KeyType::P521));
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "steward.csr.key_algorithm: `p256` is not allowed by `crypto`"
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        assert!(toml::from_str::<EnarxConfig>("[crypto]\nkey_types = [ \"dsa\" ]").is_err());
+
+        let err = toml::from_str::<EnarxConfig>("[crypto]\nmin_rsa_bits = 1024").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("unsupported `min_rsa_bits` 1024, expected one of [2048, 3072, 4096]"));
+
+        let err = toml::from_str::<EnarxConfig>(
+            "[crypto]\nkey_types = [ \"ed25519\" ]\nmin_ec_bits = 384",
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("none of `key_types` satisfies the minimum key sizes"));
+    }
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Files of kinds defined by the runtime, `kind = "custom"`
+
+use std::{collections::BTreeMap, fmt};
+
+use serde_json::{Map, Value};
+
+use crate::{EnarxConfig, File, ValidationError};
+
+/// A kind of `kind = "custom"` files defined by a runtime
+///
+/// Implemented for closures, so a kind is registered with [`CustomKinds::register`]
+/// by the function checking the `params` of its files.
+pub trait CustomKind {
+    /// check the `params` of a file of this kind, returning a description of the problem
+    fn validate(&self, params: &Map<String, Value>) -> Result<(), String>;
+}
+
+impl<F> CustomKind for F
+where
+    F: Fn(&Map<String, Value>) -> Result<(), String>,
+{
+    fn validate(&self, params: &Map<String, Value>) -> Result<(), String> {
+        self(params)
+    }
+}
+
+/// The kinds of `kind = "custom"` files a runtime supports, by their `type`
+///
+/// Passed to [`EnarxConfig::validate_with_kinds`].
+///
+/// # Examples
+///
+/// ```
+/// extern crate toml;
+/// use enarx_config::{CustomKinds, EnarxConfig};
+///
+/// let mut kinds = CustomKinds::new();
+/// kinds.register("vsock", |params: &serde_json::Map<_, _>| match params.get("cid") {
+///     Some(cid) if cid.is_u64() => Ok(()),
+///     _ => Err("`cid` has to be an integer".to_string()),
+/// });
+///
+/// let config: EnarxConfig = toml::from_str(r#"
+/// [[files]]
+/// name = "HOST"
+/// kind = "custom"
+/// type = "vsock"
+/// params = { cid = "2" }
+/// "#).unwrap();
+///
+/// let err = config.validate_with_kinds(&kinds).unwrap_err();
+/// assert_eq!(err.to_string(), "files[0].params: `cid` has to be an integer");
+/// ```
+#[derive(Default)]
+pub struct CustomKinds {
+    kinds: BTreeMap<String, Box<dyn CustomKind>>,
+}
+
+impl CustomKinds {
+    /// no custom kinds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// register the kind of the files with `type = name`, replacing a previous one
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        kind: impl CustomKind + 'static,
+    ) -> &mut Self {
+        self.kinds.insert(name.into(), Box::new(kind));
+        self
+    }
+
+    /// get the kind of the files with `type = name`
+    pub fn get(&self, name: &str) -> Option<&dyn CustomKind> {
+        self.kinds.get(name).map(|kind| &**kind)
+    }
+}
+
+impl fmt::Debug for CustomKinds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.kinds.keys()).finish()
+    }
+}
+
+impl EnarxConfig {
+    /// check the semantic consistency of the configuration, including the `params`
+    /// of its `kind = "custom"` files
+    ///
+    /// Like [`EnarxConfig::validate`], which accepts any `type`, but files of a
+    /// `type` not registered in `kinds` are rejected.
+    pub fn validate_with_kinds(&self, kinds: &CustomKinds) -> Result<(), ValidationError> {
+        self.validate()?;
+
+        for (i, file) in self.resolve_templates_lossy().iter().enumerate() {
+            let file = match file {
+                File::Conditional { file, .. } => file,
+                file => file,
+            };
+            if let File::Custom { r#type, params, .. } = file {
+                let kind = kinds.get(r#type).ok_or_else(|| {
+                    ValidationError::new(
+                        format!("files[{}].type", i),
+                        format!("unknown custom kind `{}`", r#type),
+                    )
+                })?;
+                kind.validate(params).map_err(|message| {
+                    ValidationError::new(format!("files[{}].params", i), message)
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn custom() {
+        const CONFIG: &str = r#"
+        [[files]]
+        name = "HOST"
+        kind = "custom"
+        type = "vsock"
+        params = { cid = 2, port = 5000, options = { buffer = "64KiB" } }
+        labels = { tier = "control" }
+
+        [[files]]
+        name = "GPU"
+        kind = "custom"
+        type = "accelerator"
+        when = { profile = "gpu" }
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.files[0].kind(), "custom");
+        assert_eq!(cfg.files[0].name(), "HOST");
+        assert_eq!(cfg.files[0].labels()["tier"], "control");
+        match &cfg.files[0] {
+            File::Custom { r#type, params, .. } => {
+                assert_eq!(r#type, "vsock");
+                assert_eq!(params["options"]["buffer"], "64KiB");
+            }
+            file => panic!("unexpected file {:?}", file),
+        }
+
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), cfg);
+
+        let mut kinds = CustomKinds::new();
+        kinds.register("vsock", |params: &Map<String, Value>| {
+            match params.contains_key("cid") {
+                true => Ok(()),
+                false => Err("`cid` is required".to_string()),
+            }
+        });
+        assert_eq!(
+            cfg.validate_with_kinds(&kinds).unwrap_err().to_string(),
+            "files[1].type: unknown custom kind `accelerator`"
+        );
+
+        kinds.register("accelerator", |_: &Map<String, Value>| Ok(()));
+        assert_eq!(cfg.validate_with_kinds(&kinds), Ok(()));
+        assert_eq!(format!("{:?}", kinds), r#"{"accelerator", "vsock"}"#);
+
+        let cfg: EnarxConfig =
+            toml::from_str("[[files]]\nname = \"HOST\"\nkind = \"custom\"\ntype = \"vsock\"")
+                .unwrap();
+        assert_eq!(
+            cfg.validate_with_kinds(&kinds).unwrap_err().to_string(),
+            "files[0].params: `cid` is required"
+        );
+
+        let cfg: EnarxConfig =
+            toml::from_str("[[files]]\nname = \"HOST\"\nkind = \"custom\"\ntype = \"\"").unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "files[0].type: must not be empty"
+        );
+    }
+}
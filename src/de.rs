@@ -0,0 +1,1104 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Preprocessing of `[[files]]` entries before they are deserialized into a [`File`]
+//!
+//! Every entry is buffered in a generic [`Value`] first, so shorthand and legacy spellings
+//! can be expanded into their canonical fields.
+
+use std::{collections::BTreeMap, fmt};
+
+use serde::{
+    de::{DeserializeOwned, DeserializeSeed, Error as _, MapAccess, SeqAccess, Visitor},
+    ser::{Error as _, Impossible, SerializeMap, SerializeStruct, SerializeStructVariant},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_json::{Map, Value};
+use serde_path_to_error::Segment;
+use url::Url;
+
+use crate::File;
+
+/// legacy field names of `[[files]]` entries and their canonical names
+const ALIASES: &[(&str, &str)] = &[("type", "kind"), ("protocol", "prot"), ("address", "addr")];
+
+impl<'de> Deserialize<'de> for File {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        from_entry(None, value).map_err(|(_, message)| D::Error::custom(message))
+    }
+}
+
+/// an error of a `[[files]]` entry, the path of the offending field inside the entry,
+/// empty if unknown, and the message
+type EntryError = (Vec<Key>, String);
+
+/// deserialize a `[[files]]` entry, or the `[files.NAME]` entry keyed by `key`
+fn from_entry(key: Option<&str>, value: Value) -> Result<File, EntryError> {
+    let mut map: Map<String, Value> = tracked(&[], value)?;
+
+    if let Some(key) = key {
+        match map.get("name") {
+            None => {
+                map.insert("name".into(), key.into());
+            }
+            Some(Value::String(name)) if name != key => {
+                return Err((
+                    vec![Key::Name("name".into())],
+                    format!("`{}` differs from the key `{}`", name, key),
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+
+    if let Some(when) = map.remove("when") {
+        let when = tracked(&[Key::Name("when".into())], when)?;
+        let file = from_entry(None, Value::Object(map))?;
+        return Ok(File::Conditional {
+            when,
+            file: Box::new(file),
+        });
+    }
+    if let Some(template) = map.remove("template") {
+        return templated(template, map);
+    }
+
+    let unlocated = |message| (vec![], message);
+    apply_aliases(&mut map).map_err(unlocated)?;
+    normalize_spellings(&mut map).map_err(unlocated)?;
+
+    default_alias_kind(&mut map);
+
+    match map.get("kind").and_then(Value::as_str) {
+        Some("connect") => {
+            expand_connect_hosts(&mut map).map_err(unlocated)?;
+            expand_connect_url(&mut map).map_err(unlocated)?;
+        }
+        Some("listen") => {
+            expand_listen_ports(&mut map)?;
+            default_ephemeral_port(&mut map);
+        }
+        _ => {}
+    }
+
+    // the variant is selected by hand, the path of an error inside of an internally
+    // tagged enum would be lost
+    let kind = match map.remove("kind") {
+        Some(kind) => tracked::<String>(&[Key::Name("kind".into())], kind)?,
+        None => return Err(unlocated("missing field `kind`".into())),
+    };
+    let mut tagged = Map::new();
+    tagged.insert(kind, Value::Object(map));
+
+    serde_path_to_error::deserialize(Value::Object(tagged))
+        .map(|Tagged(file)| file)
+        .map_err(|e| {
+            // the only error outside of the variant is an unknown `kind`
+            let path = match e.path().iter().next() {
+                Some(_) => keys(e.path()),
+                None => vec![Key::Name("kind".into())],
+            };
+            (path, e.into_inner().to_string())
+        })
+}
+
+/// a [`File`] in the externally tagged form of the remote derive, `{ "listen": { .. } }`
+struct Tagged(File);
+
+impl<'de> Deserialize<'de> for Tagged {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // the inherent `File::deserialize` of the remote derive skips the preprocessing
+        File::deserialize(deserializer).map(Tagged)
+    }
+}
+
+/// deserialize the value at `path` of a `[[files]]` entry, tracking the path of an error
+fn tracked<T: DeserializeOwned>(path: &[Key], value: Value) -> Result<T, EntryError> {
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        let mut path = path.to_vec();
+        path.extend(keys(e.path()));
+        (path, e.into_inner().to_string())
+    })
+}
+
+/// get the keys of the maps and sequences of a tracked `path`
+fn keys(path: &serde_path_to_error::Path) -> Vec<Key> {
+    path.iter()
+        .filter_map(|segment| match segment {
+            Segment::Seq { index } => Some(Key::Index(*index)),
+            Segment::Map { key } => Some(Key::Name(key.clone())),
+            Segment::Enum { .. } | Segment::Unknown => None,
+        })
+        .collect()
+}
+
+/// get the path of the offending field of the `files` entry at `path` of a configuration in
+/// its generic form, which fails to deserialize
+///
+/// The errors of a `[[files]]` entry don't carry the path inside of the entry, as the
+/// entries are preprocessed in their generic form.
+#[cfg(any(feature = "toml", feature = "edit"))]
+pub(crate) fn entry_field_path(config: &Value, path: &[Key]) -> Vec<Key> {
+    let key = match path {
+        [.., Key::Name(files), Key::Index(_)] if files == "files" => None,
+        [.., Key::Name(files), Key::Name(key)] if files == "files" => Some(key.as_str()),
+        _ => return vec![],
+    };
+    let entry = path.iter().try_fold(config, |value, key| match key {
+        Key::Name(name) => value.get(name),
+        Key::Index(i) => value.get(i),
+    });
+
+    match entry.map(|entry| from_entry(key, entry.clone())) {
+        Some(Err((path, _))) => path,
+        _ => vec![],
+    }
+}
+
+/// deserialize the `files` from an array of tables or from a table of tables keyed by name
+///
+/// The entries of the `[files.NAME]` form of kind `stdin`, `stdout` and `stderr` come first,
+/// the others are ordered by name.
+pub(crate) fn deserialize_files<'de, D>(deserializer: D) -> Result<Vec<File>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(FilesVisitor)
+}
+
+struct FilesVisitor;
+
+impl<'de> Visitor<'de> for FilesVisitor {
+    type Value = Vec<File>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("an array of files or a table of files keyed by name")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut files = vec![];
+        while let Some(file) = seq.next_element()? {
+            files.push(file);
+        }
+        Ok(files)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut files = vec![];
+        while let Some(name) = map.next_key::<String>()? {
+            let file = map.next_value_seed(NamedFile(&name))?;
+            files.push((name, file));
+        }
+
+        let rank = |file: &File| match file {
+            File::Stdin { .. } => 0,
+            File::Stdout { .. } => 1,
+            File::Stderr { .. } => 2,
+            _ => 3,
+        };
+        files.sort_by(|(a, fa), (b, fb)| rank(fa).cmp(&rank(fb)).then_with(|| a.cmp(b)));
+        Ok(files.into_iter().map(|(_, file)| file).collect())
+    }
+}
+
+/// a `[files.NAME]` entry, named by its key
+struct NamedFile<'a>(&'a str);
+
+impl<'de> DeserializeSeed<'de> for NamedFile<'_> {
+    type Value = File;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        from_entry(Some(self.0), value).map_err(|(_, message)| D::Error::custom(message))
+    }
+}
+
+/// a segment of the path of a value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Key {
+    Name(String),
+    Index(usize),
+}
+
+/// format `keys` like the paths of [`ValidationError`](crate::ValidationError)s
+#[cfg(any(feature = "toml", feature = "edit"))]
+pub(crate) fn format_path(keys: &[Key]) -> String {
+    let mut path = String::new();
+    for key in keys {
+        match key {
+            Key::Name(name) => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(name);
+            }
+            Key::Index(i) => path.push_str(&format!("[{}]", i)),
+        }
+    }
+    path
+}
+
+impl Serialize for File {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            File::Templated {
+                template,
+                fields,
+                labels,
+            } => {
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_entry("template", template)?;
+                for (key, value) in fields {
+                    map.serialize_entry(key, value)?;
+                }
+                if !labels.is_empty() {
+                    map.serialize_entry("labels", labels)?;
+                }
+                map.end()
+            }
+            File::Conditional { when, file } => {
+                let fields = match serde_json::to_value(file).map_err(S::Error::custom)? {
+                    Value::Object(fields) => fields,
+                    _ => unreachable!("files are serialized as maps"),
+                };
+                let mut map = serializer.serialize_map(None)?;
+                for (key, value) in &fields {
+                    map.serialize_entry(key, value)?;
+                }
+                map.serialize_entry("when", when)?;
+                map.end()
+            }
+            _ => File::serialize(self, TaggedSerializer(serializer)),
+        }
+    }
+}
+
+/// a serializer writing the struct variants of the remote derive of [`File`] as structs,
+/// with the variant name as their `kind`
+struct TaggedSerializer<S>(S);
+
+/// the fields of a struct variant serialized by a [`TaggedSerializer`]
+struct TaggedFields<S>(S);
+
+macro_rules! not_a_struct_variant {
+    ($($method:ident($($arg:ty),*) -> $ok:ty;)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<$ok, S::Error> {
+                Err(S::Error::custom("files are serialized from struct variants"))
+            }
+        )*
+    };
+}
+
+impl<S: Serializer> Serializer for TaggedSerializer<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Impossible<S::Ok, S::Error>;
+    type SerializeTuple = Impossible<S::Ok, S::Error>;
+    type SerializeTupleStruct = Impossible<S::Ok, S::Error>;
+    type SerializeTupleVariant = Impossible<S::Ok, S::Error>;
+    type SerializeMap = Impossible<S::Ok, S::Error>;
+    type SerializeStruct = Impossible<S::Ok, S::Error>;
+    type SerializeStructVariant = TaggedFields<S::SerializeStruct>;
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        let mut fields = self.0.serialize_struct(name, len + 1)?;
+        fields.serialize_field("kind", variant)?;
+        Ok(TaggedFields(fields))
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<S::Ok, S::Error> {
+        Err(S::Error::custom(
+            "files are serialized from struct variants",
+        ))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: &T,
+    ) -> Result<S::Ok, S::Error> {
+        Err(S::Error::custom(
+            "files are serialized from struct variants",
+        ))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<S::Ok, S::Error> {
+        Err(S::Error::custom(
+            "files are serialized from struct variants",
+        ))
+    }
+
+    not_a_struct_variant! {
+        serialize_bool(bool) -> S::Ok;
+        serialize_i8(i8) -> S::Ok;
+        serialize_i16(i16) -> S::Ok;
+        serialize_i32(i32) -> S::Ok;
+        serialize_i64(i64) -> S::Ok;
+        serialize_u8(u8) -> S::Ok;
+        serialize_u16(u16) -> S::Ok;
+        serialize_u32(u32) -> S::Ok;
+        serialize_u64(u64) -> S::Ok;
+        serialize_f32(f32) -> S::Ok;
+        serialize_f64(f64) -> S::Ok;
+        serialize_char(char) -> S::Ok;
+        serialize_str(&str) -> S::Ok;
+        serialize_bytes(&[u8]) -> S::Ok;
+        serialize_none() -> S::Ok;
+        serialize_unit() -> S::Ok;
+        serialize_unit_struct(&'static str) -> S::Ok;
+        serialize_unit_variant(&'static str, u32, &'static str) -> S::Ok;
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+    }
+}
+
+impl<S: SerializeStruct> SerializeStructVariant for TaggedFields<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        self.0.serialize_field(key, value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> {
+        self.0.skip_field(key)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.0.end()
+    }
+}
+
+/// an entry based on the template named `template`, resolved later with the `[templates]`
+fn templated(template: Value, mut fields: Map<String, Value>) -> Result<File, EntryError> {
+    let template = match template {
+        Value::String(template) => template,
+        other => {
+            return Err((
+                vec![Key::Name("template".into())],
+                format!("expected a template name, got `{}`", other),
+            ))
+        }
+    };
+    let labels = match fields.remove("labels") {
+        Some(labels) => tracked(&[Key::Name("labels".into())], labels)?,
+        None => BTreeMap::new(),
+    };
+
+    Ok(File::Templated {
+        template,
+        fields,
+        labels,
+    })
+}
+
+/// rename the legacy field names of [`ALIASES`] to their canonical names
+///
+/// Returns the renamed fields as `(legacy, canonical)`.
+/// The `type` of a `kind = "custom"` file is its own field, not the legacy `kind`.
+fn apply_aliases(map: &mut Map<String, Value>) -> Result<Vec<(String, String)>, String> {
+    let custom = map.get("kind").and_then(Value::as_str) == Some("custom");
+    let renames = map
+        .keys()
+        .filter_map(|key| {
+            if custom && key == "type" {
+                return None;
+            }
+            let (_, canonical) = ALIASES.iter().find(|(alias, _)| alias == key)?;
+            Some((key.clone(), canonical.to_string()))
+        })
+        .collect::<Vec<_>>();
+
+    for (legacy, canonical) in &renames {
+        if map.contains_key(canonical) {
+            return Err(format!(
+                "`{}` is a legacy spelling of `{}`, only one of them can be specified",
+                legacy, canonical
+            ));
+        }
+        let value = map.remove(legacy).expect("key was found");
+        map.insert(canonical.clone(), value);
+    }
+
+    Ok(renames)
+}
+
+/// bring case-insensitive and snake-case spellings of `kind` and `prot` into their canonical form
+///
+/// A combined `kind = "tcp-listen"` is split into `kind` and `prot`.
+fn normalize_spellings(map: &mut Map<String, Value>) -> Result<(), String> {
+    if let Some(Value::String(prot)) = map.get_mut("prot") {
+        *prot = prot.to_ascii_lowercase();
+    }
+
+    let kind = match map.get_mut("kind") {
+        Some(Value::String(kind)) => kind,
+        _ => return Ok(()),
+    };
+    let spelling = kind.clone();
+    let normalized = spelling.to_ascii_lowercase().replace('_', "-");
+    let prot = match normalized.split_once('-') {
+        Some((prot @ ("tcp" | "tls"), canonical @ ("listen" | "connect"))) => {
+            *kind = canonical.into();
+            prot.to_string()
+        }
+        _ => {
+            *kind = normalized;
+            return Ok(());
+        }
+    };
+
+    match map.get("prot") {
+        Some(other) if other.as_str() != Some(&prot) => Err(format!(
+            "`kind = \"{}\"` conflicts with `prot = {}`",
+            spelling, other
+        )),
+        _ => {
+            map.insert("prot".into(), prot.into());
+            Ok(())
+        }
+    }
+}
+
+/// the entries of the `files` of a configuration in its generic form, with their paths
+#[cfg(any(test, feature = "toml", feature = "edit"))]
+fn file_entries(config: &Value) -> Vec<(String, &Map<String, Value>)> {
+    let files: Vec<(String, &Value)> = match config.get("files") {
+        Some(Value::Array(files)) => files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| (format!("files[{}]", i), file))
+            .collect(),
+        Some(Value::Object(files)) => files
+            .iter()
+            .map(|(name, file)| (format!("files.{}", name), file))
+            .collect(),
+        _ => return vec![],
+    };
+
+    files
+        .into_iter()
+        .filter_map(|(path, file)| file.as_object().map(|map| (path, map)))
+        .collect()
+}
+
+/// fill in `defaults` for the fields left out of the `files` of a configuration in its
+/// generic form
+///
+/// Entries failing to preprocess are left as they are, for the deserialization to report.
+#[cfg(feature = "toml")]
+pub(crate) fn apply_defaults(config: &mut Value, defaults: &dyn crate::DefaultsProvider) {
+    let files: Vec<&mut Value> = match config.get_mut("files") {
+        Some(Value::Array(files)) => files.iter_mut().collect(),
+        Some(Value::Object(files)) => files.values_mut().collect(),
+        _ => return,
+    };
+
+    for map in files.into_iter().filter_map(Value::as_object_mut) {
+        let mut normalized = map.clone();
+        if apply_aliases(&mut normalized).is_err() || normalize_spellings(&mut normalized).is_err()
+        {
+            continue;
+        }
+
+        let kind = normalized.get("kind").and_then(Value::as_str);
+        let ephemeral = normalized.get("ephemeral") == Some(&Value::Bool(true));
+        let fields: Vec<(&str, Value)> = match kind {
+            Some("listen") => {
+                let mut fields = vec![
+                    ("addr", defaults.addr().to_string().into()),
+                    ("prot", defaults.prot().to_string().into()),
+                    ("backlog", defaults.backlog().into()),
+                ];
+                if !ephemeral {
+                    fields.push(("port", defaults.port().get().into()));
+                }
+                fields
+            }
+            Some("connect") if !normalized.contains_key("url") => vec![
+                ("port", defaults.port().get().into()),
+                ("prot", defaults.prot().to_string().into()),
+            ],
+            _ => continue,
+        };
+        for (key, value) in fields {
+            if !normalized.contains_key(key) {
+                normalized.insert(key.into(), value);
+            }
+        }
+        *map = normalized;
+    }
+}
+
+/// find the legacy spellings in the `files` of a configuration in its generic form
+#[cfg(any(test, feature = "toml", feature = "edit"))]
+pub(crate) fn deprecations(config: &Value) -> Vec<crate::Warning> {
+    let mut warnings = vec![];
+    for (path, map) in file_entries(config) {
+        let renames = apply_aliases(&mut map.clone()).unwrap_or_default();
+        warnings.extend(renames.into_iter().map(|(legacy, canonical)| {
+            crate::Warning::new(
+                format!("{}.{}", path, legacy),
+                format!("`{}` is deprecated, use `{}`", legacy, canonical),
+            )
+        }));
+    }
+    warnings
+}
+
+/// find the first `kind` or `prot` in the `files` of a configuration in its generic form,
+/// which is not spelled canonically
+///
+/// Returns the path of the value and the error message.
+#[cfg(feature = "toml")]
+pub(crate) fn non_canonical_spelling(config: &Value) -> Option<(String, String)> {
+    for (path, map) in file_entries(config) {
+        let mut map = map.clone();
+        if apply_aliases(&mut map).is_err() {
+            continue;
+        }
+        let mut normalized = map.clone();
+        if normalize_spellings(&mut normalized).is_err() {
+            continue;
+        }
+
+        for field in ["kind", "prot"] {
+            let (spelling, canonical) = match (map.get(field), normalized.get(field)) {
+                (Some(Value::String(spelling)), Some(Value::String(canonical)))
+                    if spelling != canonical =>
+                {
+                    (spelling, canonical)
+                }
+                _ => continue,
+            };
+            let mut message = format!(
+                "`{}` is not spelled canonically, use `{} = \"{}\"`",
+                spelling, field, canonical
+            );
+            if field == "kind" && !map.contains_key("prot") {
+                if let Some(Value::String(prot)) = normalized.get("prot") {
+                    message.push_str(&format!(" and `prot = \"{}\"`", prot));
+                }
+            }
+            return Some((format!("{}.{}", path, field), message));
+        }
+    }
+    None
+}
+
+/// an entry with `alias_of` and without `kind` is a `kind = "alias"`
+fn default_alias_kind(map: &mut Map<String, Value>) {
+    if map.contains_key("alias_of") && !map.contains_key("kind") {
+        map.insert("kind".into(), "alias".into());
+    }
+}
+
+/// `ephemeral = true` without a `port` listens on port `0`
+fn default_ephemeral_port(map: &mut Map<String, Value>) {
+    if map.get("ephemeral") == Some(&Value::Bool(true)) && !map.contains_key("port") {
+        map.insert("port".into(), 0.into());
+    }
+}
+
+/// move `port = [8080, 8443]` into `ports`, and set `port` to the first port of `ports`
+fn expand_listen_ports(map: &mut Map<String, Value>) -> Result<(), EntryError> {
+    let unlocated = |message: &str| (vec![], message.to_string());
+
+    if let Some(Value::Array(_)) = map.get("port") {
+        if map.contains_key("ports") {
+            return Err(unlocated("`port = [...]` can not be combined with `ports`"));
+        }
+        let ports = map.remove("port").expect("port was found");
+        map.insert("ports".into(), ports);
+    }
+
+    let first = match map.get_mut("ports") {
+        Some(Value::Array(ports)) => ports.first().cloned(),
+        Some(port) => {
+            let port = port.take();
+            map.insert("ports".into(), Value::Array(vec![port.clone()]));
+            Some(port)
+        }
+        None => return Ok(()),
+    };
+    let first = first.ok_or_else(|| unlocated("`ports` must contain at least one port"))?;
+    let first: crate::PortRange = tracked(&[Key::Name("ports".into()), Key::Index(0)], first)?;
+
+    let start = Value::from(first.start().get());
+    match map.get("port") {
+        Some(port) if *port != start => Err(unlocated(
+            "`ports` can not be combined with a different `port`",
+        )),
+        _ => {
+            map.insert("port".into(), start);
+            Ok(())
+        }
+    }
+}
+
+/// expand `hosts = [{ host, port }, ..]` into `host`, `port` and the `fallback` endpoints
+///
+/// Endpoints without a `port` use the `port` of the entry.
+fn expand_connect_hosts(map: &mut Map<String, Value>) -> Result<(), String> {
+    let hosts = match map.remove("hosts") {
+        Some(Value::Array(hosts)) => hosts,
+        Some(_) => return Err("invalid type for `hosts`, expected an array".into()),
+        None => return Ok(()),
+    };
+
+    if ["host", "url", "fallback"]
+        .iter()
+        .any(|k| map.contains_key(*k))
+    {
+        return Err("`hosts` can not be combined with `host`, `url` or `fallback`".into());
+    }
+
+    let port = map.remove("port");
+    let mut endpoints = Vec::with_capacity(hosts.len());
+    for host in hosts {
+        let mut endpoint = match host {
+            Value::Object(endpoint) => endpoint,
+            Value::String(host) => {
+                let mut endpoint = Map::new();
+                endpoint.insert("host".into(), host.into());
+                endpoint
+            }
+            _ => return Err("invalid `hosts` entry, expected a table or string".into()),
+        };
+        if let Some(key) = endpoint
+            .keys()
+            .find(|k| !matches!(k.as_str(), "host" | "port"))
+        {
+            return Err(format!(
+                "invalid `hosts` entry, unknown field `{}`, expected `host` or `port`",
+                key
+            ));
+        }
+        if let (false, Some(port)) = (endpoint.contains_key("port"), &port) {
+            endpoint.insert("port".into(), port.clone());
+        }
+        endpoints.push(endpoint);
+    }
+
+    let mut endpoints = endpoints.into_iter();
+    let first = endpoints
+        .next()
+        .ok_or("`hosts` must contain at least one endpoint")?;
+    for key in ["host", "port"] {
+        if let Some(value) = first.get(key) {
+            map.insert(key.into(), value.clone());
+        }
+    }
+    map.insert(
+        "fallback".into(),
+        Value::Array(endpoints.map(Value::Object).collect()),
+    );
+
+    Ok(())
+}
+
+/// expand `url = "tls://host:port"` into `host`, `port` and `prot`
+fn expand_connect_url(map: &mut Map<String, Value>) -> Result<(), String> {
+    let url = match map.remove("url") {
+        Some(Value::String(url)) => url,
+        Some(_) => return Err("invalid type for `url`, expected a string".into()),
+        None => return Ok(()),
+    };
+
+    if ["host", "port", "prot"]
+        .iter()
+        .any(|k| map.contains_key(*k))
+    {
+        return Err("`url` can not be combined with `host`, `port` or `prot`".into());
+    }
+
+    let parsed = Url::parse(&url).map_err(|e| format!("invalid `url` `{}`: {}", url, e))?;
+
+    let prot = match parsed.scheme() {
+        "tls" => "tls",
+        "tcp" => "tcp",
+        scheme => {
+            return Err(format!(
+                "invalid `url` scheme `{}`, expected `tls` or `tcp`",
+                scheme
+            ))
+        }
+    };
+
+    if !parsed.username().is_empty()
+        || parsed.password().is_some()
+        || !matches!(parsed.path(), "" | "/")
+        || parsed.query().is_some()
+        || parsed.fragment().is_some()
+    {
+        return Err(format!(
+            "invalid `url` `{}`, only scheme, host and port are allowed",
+            url
+        ));
+    }
+
+    let host = parsed
+        .host_str()
+        .filter(|h| !h.is_empty())
+        .ok_or_else(|| format!("invalid `url` `{}`, missing host", url))?;
+
+    map.insert("host".into(), host.into());
+    map.insert("prot".into(), prot.into());
+    if let Some(port) = parsed.port() {
+        map.insert("port".into(), port.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use crate::{AddressFamily, EnarxConfig, File, Protocol};
+
+    #[test]
+    fn connect_url() {
+        const CONFIG: &str = r#"
+        [[files]]
+        kind = "connect"
+        url = "tcp://db.example.com:5432"
+
+        [[files]]
+        kind = "connect"
+        url = "tls://[::1]"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(
+            cfg.files,
+            vec![
+                File::Connect {
+                    name: None,
+                    host: "db.example.com".parse().unwrap(),
+                    port: 5432.into(),
+                    fallback: vec![],
+                    family: AddressFamily::Any,
+                    happy_eyeballs: true,
+                    proxy: true,
+                    prot: Protocol::Tcp,
+                    tls: None,
+                    psk: None,
+                    advertise_identity: false,
+                    keepalive: None,
+                    nodelay: false,
+                    priority: None,
+                    idle_timeout: None,
+                    timeout: None,
+                    retries: 0,
+                    retry_backoff: None,
+                    service: None,
+                    labels: BTreeMap::new(),
+                },
+                File::Connect {
+                    name: None,
+                    host: "::1".parse().unwrap(),
+                    port: 443.into(),
+                    fallback: vec![],
+                    family: AddressFamily::Any,
+                    happy_eyeballs: true,
+                    proxy: true,
+                    prot: Protocol::Tls,
+                    tls: None,
+                    psk: None,
+                    advertise_identity: false,
+                    keepalive: None,
+                    nodelay: false,
+                    priority: None,
+                    idle_timeout: None,
+                    timeout: None,
+                    retries: 0,
+                    retry_backoff: None,
+                    service: None,
+                    labels: BTreeMap::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn connect_hosts() {
+        const CONFIG: &str = r#"
+        [[files]]
+        name = "DB"
+        kind = "connect"
+        port = 5432
+        hosts = [ { host = "primary.example.com" }, { host = "replica.example.com", port = 5433 }, "backup.example.com" ]
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let connect = cfg.files[0].as_connect().unwrap();
+        assert_eq!(
+            connect
+                .endpoints()
+                .map(|e| format!("{}:{}", e.host, e.port))
+                .collect::<Vec<_>>(),
+            vec![
+                "primary.example.com:5432",
+                "replica.example.com:5433",
+                "backup.example.com:5432"
+            ]
+        );
+
+        let err =
+            toml::from_str::<EnarxConfig>("[[files]]\nkind = \"connect\"\nhosts = []").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("`hosts` must contain at least one endpoint"));
+
+        let err = toml::from_str::<EnarxConfig>(
+            "[[files]]\nkind = \"connect\"\nhosts = [ { host = \"a.example.com\", kind = \"listen\" } ]",
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("invalid `hosts` entry, unknown field `kind`, expected `host` or `port`"));
+
+        let err = toml::from_str::<EnarxConfig>(
+            "[[files]]\nkind = \"connect\"\nurl = \"tls://a.example.com\"\nhosts = [ \"b.example.com\" ]",
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("`hosts` can not be combined with `host`, `url` or `fallback`"));
+    }
+
+    #[test]
+    fn invalid_connect_url() {
+        let parse = |url: &str| {
+            toml::from_str::<EnarxConfig>(&format!(
+                "[[files]]\nkind = \"connect\"\nurl = \"{}\"",
+                url
+            ))
+            .unwrap_err()
+            .to_string()
+        };
+
+        assert!(parse("http://example.com").starts_with("invalid `url` scheme `http`"));
+        assert!(parse("tls://example.com/path").contains("only scheme, host and port"));
+        assert!(parse("tls://user@example.com").contains("only scheme, host and port"));
+
+        let err = toml::from_str::<EnarxConfig>(
+            "[[files]]\nkind = \"connect\"\nurl = \"tls://example.com\"\nport = 1",
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("`url` can not be combined with `host`, `port` or `prot`"));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn field_path() {
+        let err = |config: &str| {
+            let err = EnarxConfig::from_toml_str(config).unwrap_err();
+            (err.path().unwrap().to_string(), err.location().unwrap())
+        };
+        let at = |line, column| crate::Location { line, column };
+
+        assert_eq!(
+            err("[[files]]\nkind = \"listen\"\nname = \"A\"\nkeepalive = { idle = \"x\" }"),
+            ("files[0].keepalive.idle".into(), at(4, 22))
+        );
+        assert_eq!(
+            err("[[files]]\nkind = \"connect\"\nhost = \"a.example.com\"\nfallback = [ { host = \"b.example.com\", port = -1 } ]"),
+            ("files[0].fallback[0].port".into(), at(4, 47))
+        );
+        assert_eq!(
+            err("[[files]]\nkind = \"null\"\n[[files]]\nkind = \"socket\""),
+            ("files[1].kind".into(), at(4, 8))
+        );
+        assert_eq!(
+            err("[[files]]\nkind = \"stdin\"\nwhen = { backend = \"tdx\" }"),
+            ("files[0].when.backend".into(), at(3, 20))
+        );
+        assert_eq!(
+            err("[[files]]\nkind = \"listen\"\nname = \"A\"\nports = [ \"x\" ]"),
+            ("files[0].ports[0]".into(), at(4, 11))
+        );
+
+        // the path is only part of the `Error`, not of the message
+        let err = toml::from_str::<EnarxConfig>("[[files]]\nkind = \"socket\"").unwrap_err();
+        assert!(err.to_string().starts_with("unknown variant `socket`"));
+    }
+
+    #[test]
+    fn aliases() {
+        const CONFIG: &str = r#"
+        [[files]]
+        type = "listen"
+        name = "API"
+        protocol = "tcp"
+        address = "127.0.0.1"
+        port = 8080
+        max_concurrent_connections = 10
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let listen = cfg.files[0].as_listen().unwrap();
+        assert_eq!(listen.prot, Protocol::Tcp);
+        assert_eq!(listen.max_concurrent_connections.map(|m| m.get()), Some(10));
+
+        let value = serde_json::to_value(toml::from_str::<toml::Value>(CONFIG).unwrap()).unwrap();
+        let warnings = super::deprecations(&value)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            [
+                "files[0].address: `address` is deprecated, use `addr`",
+                "files[0].protocol: `protocol` is deprecated, use `prot`",
+                "files[0].type: `type` is deprecated, use `kind`",
+            ]
+        );
+
+        let err = toml::from_str::<EnarxConfig>("[[files]]\nkind = \"null\"\ntype = \"null\"")
+            .unwrap_err();
+        assert!(err.to_string().starts_with(
+            "`type` is a legacy spelling of `kind`, only one of them can be specified"
+        ));
+
+        // only the legacy names are renamed, not any kebab-case key
+        let value = serde_json::json!({
+            "files": [ { "kind": "listen", "name": "API", "max-concurrent-connections": 10 } ]
+        });
+        assert!(super::deprecations(&value).is_empty());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn keyed_files() {
+        const CONFIG: &str = r#"
+        [files.API]
+        kind = "listen"
+        port = 8443
+
+        [files.DB]
+        kind = "connect"
+        host = "db.example.com"
+
+        [files.stdout]
+        kind = "stdout"
+
+        [files.stdin]
+        kind = "stdin"
+        "#;
+
+        let cfg = EnarxConfig::from_toml_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(
+            cfg.fd_names().collect::<Vec<_>>(),
+            ["stdin", "stdout", "API", "DB"]
+        );
+
+        let err = EnarxConfig::from_toml_str(&CONFIG.replace("port = 8443", "name = \"WEB\""))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("files.API.name: `WEB` differs from the key `API`"));
+        assert_eq!(
+            err.location(),
+            Some(crate::Location {
+                line: 4,
+                column: 16
+            })
+        );
+
+        let err =
+            EnarxConfig::from_toml_str(&CONFIG.replace("port = 8443", "port = \"x\"")).unwrap_err();
+        assert_eq!(err.path(), Some("files.API.port"));
+
+        let (_, warnings) = EnarxConfig::from_toml_str_with_warnings(
+            &CONFIG.replace("kind = \"listen\"", "type = \"listen\""),
+        )
+        .unwrap();
+        assert_eq!(warnings[0].path(), "files.API.type");
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn spellings() {
+        const CONFIG: &str = r#"
+        [[files]]
+        name = "API"
+        kind = "TLS_Listen"
+
+        [[files]]
+        kind = "Connect"
+        host = "db.example.com"
+        prot = "TCP"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let listen = cfg.files[0].as_listen().unwrap();
+        assert_eq!(listen.prot, Protocol::Tls);
+        assert_eq!(cfg.files[1].as_connect().unwrap().prot, Protocol::Tcp);
+
+        let value = serde_json::to_value(&cfg).unwrap();
+        assert_eq!(value["files"][0]["kind"], "listen");
+        assert_eq!(value["files"][0]["prot"], "tls");
+        assert_eq!(value["files"][1]["prot"], "tcp");
+
+        let err = EnarxConfig::from_toml_str_strict(CONFIG).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "files[0].kind: `TLS_Listen` is not spelled canonically, use `kind = \"listen\"` and `prot = \"tls\"`"
+        );
+        let err =
+            EnarxConfig::from_toml_str_strict(&CONFIG.replace("TLS_Listen", "listen")).unwrap_err();
+        assert_eq!(err.path(), Some("files[1].kind"));
+
+        let err = toml::from_str::<EnarxConfig>(
+            "[[files]]\nname = \"API\"\nkind = \"tls-listen\"\nprot = \"tcp\"",
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("`kind = \"tls-listen\"` conflicts with `prot = \"tcp\"`"));
+    }
+}
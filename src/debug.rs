@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Debugging facilities of the `[debug]` section
+
+use serde::{Deserialize, Serialize};
+
+use crate::Port;
+
+/// The `[debug]` section
+///
+/// All debugging facilities expose the state of the keep, so they are rejected by
+/// [`validate`](crate::EnarxConfig::validate) unless `allow_debug = true` is set.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DebugOptions {
+    /// explicitly allow the debugging facilities of this section
+    #[serde(default)]
+    pub allow_debug: bool,
+
+    /// print WASM backtraces on traps
+    #[serde(default)]
+    pub backtraces: bool,
+
+    /// write a coredump of the WASM application on traps
+    #[serde(default)]
+    pub coredump_on_trap: bool,
+
+    /// port of a gdb remote debugging listener
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gdb_port: Option<Port>,
+}
+
+impl DebugOptions {
+    /// is any debugging facility enabled
+    pub fn is_enabled(&self) -> bool {
+        self.backtraces || self.coredump_on_trap || self.gdb_port.is_some()
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), (&'static str, String)> {
+        let enabled = [
+            ("backtraces", self.backtraces),
+            ("coredump_on_trap", self.coredump_on_trap),
+            ("gdb_port", self.gdb_port.is_some()),
+        ];
+        if !self.allow_debug {
+            if let Some((field, _)) = enabled.iter().find(|(_, enabled)| *enabled) {
+                return Err((field, "debugging requires `allow_debug = true`".into()));
+            }
+        }
+        if matches!(self.gdb_port, Some(port) if port.is_ephemeral()) {
+            return Err(("gdb_port", "can not listen on port 0".into()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::EnarxConfig;
+
+    #[test]
+    fn allow_debug() {
+        let mut cfg: EnarxConfig = toml::from_str(
+            r#"
+            [debug]
+            backtraces = true
+            gdb_port = 1234
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "debug.backtraces: debugging requires `allow_debug = true`"
+        );
+
+        let debug = cfg.debug.as_mut().unwrap();
+        assert!(debug.is_enabled());
+        debug.allow_debug = true;
+        assert_eq!(cfg.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,335 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Defaults of the schema, and parsing configurations with other defaults
+
+use crate::{EnarxConfig, ListenAddr, Port, Protocol, Steward};
+
+/// the `port` of `kind = "listen"` and `kind = "connect"` files without one
+pub const DEFAULT_PORT: Port = Port::new(443);
+
+/// the `addr` of `kind = "listen"` files without one, any IPv6 and IPv4 interface
+pub const DEFAULT_ADDR: ListenAddr = ListenAddr::Any;
+
+/// the `prot` of `kind = "listen"` and `kind = "connect"` files without one
+pub const DEFAULT_PROTOCOL: Protocol = Protocol::Tls;
+
+/// the `backlog` of `kind = "listen"` files without one
+pub const DEFAULT_BACKLOG: u32 = 128;
+
+/// the kinds of the files of [`EnarxConfig::default`](crate::EnarxConfig::default),
+/// the standard streams
+pub const IMPLICIT_FILES: [&str; 3] = ["stdin", "stdout", "stderr"];
+
+/// A source of the values filled in for the settings left out of a configuration
+///
+/// Consulted by [`EnarxConfig::with_defaults`], [`EnarxConfig::normalize_with`] and
+/// [`EnarxConfig::from_toml_str_with_defaults`], so distributions can ship their own
+/// defaults, like a hosted Steward. The provided methods return the defaults of the schema.
+///
+/// # Examples
+///
+/// ```
+/// use enarx_config::{DefaultsProvider, EnarxConfig, Steward};
+///
+/// struct Hosted;
+///
+/// impl DefaultsProvider for Hosted {
+///     fn steward(&self) -> Option<Steward> {
+///         Some("https://steward.example.com".parse().unwrap())
+///     }
+/// }
+///
+/// let config = EnarxConfig::with_defaults(&Hosted);
+/// assert_eq!(config.steward.unwrap().url.as_str(), "https://steward.example.com/");
+/// ```
+pub trait DefaultsProvider {
+    /// the Steward of configurations without a `steward`
+    fn steward(&self) -> Option<Steward> {
+        None
+    }
+
+    /// the `port` of `kind = "listen"` and `kind = "connect"` files
+    fn port(&self) -> Port {
+        DEFAULT_PORT
+    }
+
+    /// the `addr` of `kind = "listen"` files
+    fn addr(&self) -> ListenAddr {
+        DEFAULT_ADDR
+    }
+
+    /// the `prot` of `kind = "listen"` and `kind = "connect"` files
+    fn prot(&self) -> Protocol {
+        DEFAULT_PROTOCOL
+    }
+
+    /// the `backlog` of `kind = "listen"` files
+    fn backlog(&self) -> u32 {
+        DEFAULT_BACKLOG
+    }
+}
+
+/// The values filled in for the settings left out of a configuration
+///
+/// [`Defaults::default`] has the defaults of the schema, the `DEFAULT_*` constants.
+/// Loaders with other policies pass their own to
+/// [`EnarxConfig::from_toml_str_with_defaults`], or implement [`DefaultsProvider`].
+///
+/// # Examples
+///
+/// ```
+/// use enarx_config::{Defaults, EnarxConfig, Protocol};
+///
+/// let defaults = Defaults {
+///     prot: Protocol::Tcp,
+///     ..Default::default()
+/// };
+/// let config = EnarxConfig::from_toml_str_with_defaults(
+///     "[[files]]\nname = \"API\"\nkind = \"listen\"",
+///     &defaults,
+/// )
+/// .unwrap();
+/// assert_eq!(config.files[0].prot(), Some(Protocol::Tcp));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Defaults {
+    /// the `port` of `kind = "listen"` and `kind = "connect"` files
+    pub port: Port,
+
+    /// the `addr` of `kind = "listen"` files
+    pub addr: ListenAddr,
+
+    /// the `prot` of `kind = "listen"` and `kind = "connect"` files
+    pub prot: Protocol,
+
+    /// the `backlog` of `kind = "listen"` files
+    pub backlog: u32,
+
+    /// the Steward of configurations without a `steward`
+    pub steward: Option<Steward>,
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_PORT,
+            addr: DEFAULT_ADDR,
+            prot: DEFAULT_PROTOCOL,
+            backlog: DEFAULT_BACKLOG,
+            steward: None,
+        }
+    }
+}
+
+impl DefaultsProvider for Defaults {
+    fn steward(&self) -> Option<Steward> {
+        self.steward.clone()
+    }
+
+    fn port(&self) -> Port {
+        self.port
+    }
+
+    fn addr(&self) -> ListenAddr {
+        self.addr.clone()
+    }
+
+    fn prot(&self) -> Protocol {
+        self.prot
+    }
+
+    fn backlog(&self) -> u32 {
+        self.backlog
+    }
+}
+
+impl EnarxConfig {
+    /// get the [default](EnarxConfig::default) configuration with the defaults of `provider`
+    pub fn with_defaults(provider: &dyn DefaultsProvider) -> Self {
+        Self {
+            steward: provider.steward(),
+            ..Default::default()
+        }
+    }
+
+    /// get the [normalized](EnarxConfig::normalize) configuration, with the Steward of
+    /// `provider` if it has none
+    pub fn normalize_with(&self, provider: &dyn DefaultsProvider) -> Self {
+        let mut config = self.normalize();
+        if config.steward.is_none() {
+            config.steward = provider.steward();
+        }
+        config
+    }
+
+    /// parse a configuration in TOML format, filling in the defaults of `provider` for
+    /// the settings left out
+    ///
+    /// Fields implied otherwise, like the `port` of an `ephemeral` listener or the
+    /// `prot` of a connect `url`, are not filled in. Requires the `toml` feature.
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str_with_defaults(
+        s: &str,
+        provider: &dyn DefaultsProvider,
+    ) -> Result<Self, crate::Error> {
+        let document = toml::from_str::<toml::Value>(s)?;
+        let mut document =
+            serde_json::to_value(document).map_err(|e| crate::Error::parse(e.to_string()))?;
+        crate::de::apply_defaults(&mut document, provider);
+        let document =
+            toml::Value::try_from(document).map_err(|e| crate::Error::parse(e.to_string()))?;
+
+        let mut config: Self = serde_path_to_error::deserialize(document)
+            .map_err(|e| crate::Error::from_toml_source(s, e))?;
+        if config.steward.is_none() {
+            config.steward = provider.steward();
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{EnarxConfig, File};
+
+    use super::*;
+
+    #[test]
+    fn schema_defaults() {
+        let config: EnarxConfig =
+            toml::from_str("[[files]]\nname = \"API\"\nkind = \"listen\"").unwrap();
+        let listen = config.files[0].as_listen().unwrap();
+        assert_eq!(listen.port, DEFAULT_PORT);
+        assert_eq!(*listen.addr, DEFAULT_ADDR);
+        assert_eq!(listen.prot, DEFAULT_PROTOCOL);
+        assert_eq!(listen.backlog, DEFAULT_BACKLOG);
+
+        assert_eq!(
+            EnarxConfig::default()
+                .files
+                .iter()
+                .map(File::kind)
+                .collect::<Vec<_>>(),
+            IMPLICIT_FILES
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn other_defaults() {
+        const CONFIG: &str = r#"
+        [[files]]
+        name = "API"
+        kind = "listen"
+
+        [[files]]
+        name = "ADMIN"
+        kind = "listen"
+        port = 9000
+        prot = "tls"
+
+        [[files]]
+        name = "RANDOM"
+        kind = "listen"
+        ephemeral = true
+
+        [[files]]
+        kind = "connect"
+        host = "db.example.com"
+
+        [[files]]
+        kind = "connect"
+        url = "tls://cache.example.com"
+        "#;
+
+        let defaults = Defaults {
+            port: Port::new(8080),
+            addr: ListenAddr::V4Any,
+            prot: Protocol::Tcp,
+            backlog: 16,
+            steward: None,
+        };
+        let config = EnarxConfig::from_toml_str_with_defaults(CONFIG, &defaults).unwrap();
+
+        let api = config.files[0].as_listen().unwrap();
+        assert_eq!(api.port, Port::new(8080));
+        assert_eq!(*api.addr, ListenAddr::V4Any);
+        assert_eq!(api.prot, Protocol::Tcp);
+        assert_eq!(api.backlog, 16);
+
+        let admin = config.files[1].as_listen().unwrap();
+        assert_eq!(admin.port, Port::new(9000));
+        assert_eq!(admin.prot, Protocol::Tls);
+
+        assert!(config.files[2].as_listen().unwrap().port.is_ephemeral());
+
+        let db = config.files[3].as_connect().unwrap();
+        assert_eq!(db.port, Port::new(8080));
+        assert_eq!(db.prot, Protocol::Tcp);
+
+        let cache = config.files[4].as_connect().unwrap();
+        assert_eq!(cache.port, DEFAULT_PORT);
+        assert_eq!(cache.prot, Protocol::Tls);
+
+        assert_eq!(
+            EnarxConfig::from_toml_str_with_defaults(CONFIG, &Defaults::default()).unwrap(),
+            toml::from_str(CONFIG).unwrap()
+        );
+
+        let err = EnarxConfig::from_toml_str_with_defaults(
+            "[[files]]\nkind = \"listen\"\nname = \"API\"\nport = \"x\"",
+            &defaults,
+        )
+        .unwrap_err();
+        assert_eq!(err.path(), Some("files[0].port"));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn provider() {
+        struct Hosted;
+
+        impl DefaultsProvider for Hosted {
+            fn steward(&self) -> Option<Steward> {
+                Some("https://steward.example.com".parse().unwrap())
+            }
+
+            fn addr(&self) -> ListenAddr {
+                ListenAddr::V6Any
+            }
+        }
+
+        let config = EnarxConfig::from_toml_str_with_defaults(
+            "[[files]]
+name = \"API\"
+kind = \"listen\"",
+            &Hosted,
+        )
+        .unwrap();
+        assert_eq!(
+            config.steward.as_ref().unwrap().url.as_str(),
+            "https://steward.example.com/"
+        );
+        let listen = config.files[0].as_listen().unwrap();
+        assert_eq!(*listen.addr, ListenAddr::V6Any);
+        assert_eq!(listen.port, DEFAULT_PORT);
+
+        let config =
+            EnarxConfig::from_toml_str_with_defaults("steward = \"https://steward.test\"", &Hosted)
+                .unwrap();
+        assert_eq!(
+            config.steward.as_ref().unwrap().url.as_str(),
+            "https://steward.test/"
+        );
+
+        let config: EnarxConfig = toml::from_str("[[files]]\nkind = \"stdin\"").unwrap();
+        assert!(config.normalize().steward.is_none());
+        let normalized = config.normalize_with(&Hosted);
+        assert!(normalized.steward.is_some());
+        assert_eq!(normalized.normalize_with(&Hosted), normalized);
+        assert_eq!(
+            config.normalize_with(&Defaults::default()),
+            config.normalize()
+        );
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! SHA-256 digest of an [`EnarxConfig`]
+
+use std::{fmt, str::FromStr};
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest as _, Sha256};
+
+use crate::EnarxConfig;
+
+/// SHA-256 digest of the canonical form of an [`EnarxConfig`] or of a referenced file
+///
+/// Displays as a lowercase hexadecimal string and can be parsed back with [`FromStr`].
+/// It is serialized as the same hexadecimal string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Digest([u8; 32]);
+
+impl Digest {
+    /// calculate the SHA-256 digest of arbitrary bytes
+    pub fn of(bytes: impl AsRef<[u8]>) -> Self {
+        Self(Sha256::digest(bytes.as_ref()).into())
+    }
+
+    /// get the raw digest bytes
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for Digest {
+    fn from(value: [u8; 32]) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+/// Error parsing a [`Digest`] from a hexadecimal string
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseDigestError;
+
+impl fmt::Display for ParseDigestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid digest, expected 64 hexadecimal characters")
+    }
+}
+
+impl std::error::Error for ParseDigestError {}
+
+impl FromStr for Digest {
+    type Err = ParseDigestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 64 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ParseDigestError);
+        }
+
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).map_err(|_| ParseDigestError)?;
+        }
+
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for Digest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Digest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let digest = String::deserialize(deserializer)?;
+        digest.parse().map_err(D::Error::custom)
+    }
+}
+
+impl EnarxConfig {
+    /// get the SHA-256 digest over the [canonical form](EnarxConfig::to_canonical_bytes)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::EnarxConfig;
+    ///
+    /// let config: EnarxConfig = toml::from_str(r#"args = [ "--verbose" ]"#).unwrap();
+    /// println!("running config {}", config.digest());
+    /// ```
+    pub fn digest(&self) -> Digest {
+        Digest::of(self.to_canonical_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn digest() {
+        let cfg: EnarxConfig = toml::from_str("").unwrap();
+
+        assert_eq!(
+            cfg.digest(),
+            Digest::of(r#"{"args":[],"env":{},"files":[]}"#)
+        );
+        assert_eq!(
+            Digest::of("").to_string(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn parse() {
+        let digest = Digest::of("enarx");
+        assert_eq!(digest.to_string().parse::<Digest>(), Ok(digest));
+        assert_eq!("xyz".parse::<Digest>(), Err(ParseDigestError));
+        assert_eq!("+f".repeat(32).parse::<Digest>(), Err(ParseDigestError));
+    }
+}
@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Discovery of the `Enarx.toml` of a project
+
+use std::{
+    env,
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{EnarxConfig, Error};
+
+/// file name of the configuration searched by [`EnarxConfig::discover`]
+pub const CONFIG_FILE_NAME: &str = "Enarx.toml";
+
+/// environment variable overriding the path of the configuration
+pub const CONFIG_ENV: &str = "ENARX_CONFIG";
+
+impl EnarxConfig {
+    /// search for `Enarx.toml` in `start_dir` and all its parent directories
+    ///
+    /// Like `cargo` searches for `Cargo.toml`, the closest file wins. If the `ENARX_CONFIG`
+    /// environment variable is set, its value is used as path instead, a relative path is
+    /// resolved against the current working directory, not `start_dir`. The configuration is
+    /// [loaded and validated](EnarxConfig::from_toml_file), and returned with its path.
+    ///
+    /// Requires the `toml` feature.
+    pub fn discover(start_dir: impl AsRef<Path>) -> Result<(Self, PathBuf), Error> {
+        discover(
+            start_dir.as_ref(),
+            env::var_os(CONFIG_ENV),
+            env::current_dir,
+        )
+    }
+}
+
+fn discover(
+    start_dir: &Path,
+    env_override: Option<OsString>,
+    current_dir: impl FnOnce() -> io::Result<PathBuf>,
+) -> Result<(EnarxConfig, PathBuf), Error> {
+    let path = match env_override
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+    {
+        Some(path) if path.is_relative() => current_dir()
+            .map_err(|source| Error::Io {
+                path: path.clone(),
+                source,
+            })?
+            .join(path),
+        Some(path) => path,
+        None => start_dir
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+            .ok_or_else(|| Error::NotFound {
+                start: start_dir.into(),
+            })?,
+    };
+
+    let config = EnarxConfig::from_toml_file(&path)?;
+    Ok((config, path))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn discover() {
+        let root = env::temp_dir().join(format!("enarx-config-discover-{}", std::process::id()));
+        let nested = root.join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join(CONFIG_FILE_NAME), "args = [ \"root\" ]\n").unwrap();
+        std::fs::write(root.join("a").join("other.toml"), "args = [ \"other\" ]\n").unwrap();
+
+        let cwd = || -> io::Result<PathBuf> { unreachable!() };
+        let (config, path) = super::discover(&nested, None, cwd).unwrap();
+        assert_eq!(config.args, ["root"]);
+        assert_eq!(path, root.join(CONFIG_FILE_NAME));
+
+        // a relative `ENARX_CONFIG` is resolved against the current directory
+        let in_a = || Ok(root.join("a"));
+        let (config, path) = super::discover(&nested, Some("other.toml".into()), in_a).unwrap();
+        assert_eq!(config.args, ["other"]);
+        assert_eq!(path, root.join("a").join("other.toml"));
+
+        let absolute = root.join("a").join("other.toml");
+        let (config, path) = super::discover(&nested, Some(absolute.clone().into()), cwd).unwrap();
+        assert_eq!(config.args, ["other"]);
+        assert_eq!(path, absolute);
+
+        let (_, path) = super::discover(&nested, Some("".into()), cwd).unwrap();
+        assert_eq!(path, root.join(CONFIG_FILE_NAME));
+
+        std::fs::remove_file(root.join(CONFIG_FILE_NAME)).unwrap();
+        let err = super::discover(&nested, None, cwd);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        // a parent of the temporary directory might have an `Enarx.toml`
+        if let Err(err) = err {
+            assert!(matches!(err, Error::NotFound { .. }));
+        }
+    }
+}
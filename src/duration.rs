@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Human-readable durations like `"30s"`, `"5m"` or `"1h30m"`
+
+use std::{fmt, ops::Deref, str::FromStr, time::Duration};
+
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
+
+const UNITS: &[(&str, u64)] = &[
+    ("ms", 1),
+    ("s", 1_000),
+    ("m", 60_000),
+    ("h", 3_600_000),
+    ("d", 86_400_000),
+];
+
+/// parse a duration like `"250ms"`, `"30s"`, `"5m"` or `"1h30m"`
+pub(crate) fn parse(s: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "invalid duration `{}`, expected e.g. `250ms`, `30s`, `5m`, `2h` or `1d`",
+            s
+        )
+    };
+
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut millis = 0u64;
+    while !rest.is_empty() {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let value = rest[..digits].parse::<u64>().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+
+        let unit = rest.bytes().take_while(u8::is_ascii_alphabetic).count();
+        let factor = UNITS
+            .iter()
+            .find(|(name, _)| *name == &rest[..unit])
+            .map(|(_, factor)| *factor)
+            .ok_or_else(invalid)?;
+        rest = &rest[unit..];
+
+        millis = value
+            .checked_mul(factor)
+            .and_then(|v| v.checked_add(millis))
+            .ok_or_else(invalid)?;
+    }
+
+    Ok(Duration::from_millis(millis))
+}
+
+/// format a duration with the largest unit representing it exactly
+pub(crate) fn format(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis == 0 {
+        return "0s".into();
+    }
+
+    UNITS
+        .iter()
+        .rev()
+        .find(|(_, factor)| millis.checked_rem(u128::from(*factor)) == Some(0))
+        .map(|(name, factor)| format!("{}{}", millis / u128::from(*factor), name))
+        .unwrap_or_else(|| format!("{}ms", millis))
+}
+
+/// A [`Duration`] written human-readable, like `"250ms"`, `"30s"`, `"5m"` or `"1h30m"`
+///
+/// Parsed from such a string or from an integer number of seconds, formatted with the
+/// largest unit representing it exactly, so `"5400s"` is formatted as `"90m"`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HumanDuration(pub Duration);
+
+/// The error of parsing a [`HumanDuration`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseDurationError(String);
+
+impl fmt::Display for ParseDurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseDurationError {}
+
+impl FromStr for HumanDuration {
+    type Err = ParseDurationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s).map(Self).map_err(ParseDurationError)
+    }
+}
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format(self.0))
+    }
+}
+
+impl Deref for HumanDuration {
+    type Target = Duration;
+
+    fn deref(&self) -> &Duration {
+        &self.0
+    }
+}
+
+impl From<Duration> for HumanDuration {
+    fn from(duration: Duration) -> Self {
+        Self(duration)
+    }
+}
+
+impl From<HumanDuration> for Duration {
+    fn from(duration: HumanDuration) -> Self {
+        duration.0
+    }
+}
+
+impl Serialize for HumanDuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for HumanDuration {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize(deserializer).map(Self)
+    }
+}
+
+struct DurationVisitor;
+
+impl<'de> Visitor<'de> for DurationVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a duration string like `30s` or a number of seconds")
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Duration::from_secs(v))
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        u64::try_from(v)
+            .map(Duration::from_secs)
+            .map_err(|_| E::custom("duration must not be negative"))
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        parse(v).map_err(E::custom)
+    }
+}
+
+/// serde support for `Duration` fields
+pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(DurationVisitor)
+}
+
+/// serde support for `Duration` fields
+pub(crate) fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format(*duration))
+}
+
+/// serde support for `Option<Duration>` fields
+pub(crate) mod option {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::HumanDuration;
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Option::<HumanDuration>::deserialize(deserializer)?.map(Duration::from))
+    }
+
+    pub(crate) fn serialize<S>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match duration {
+            Some(duration) => super::serialize(duration, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_format() {
+        assert_eq!(parse("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse("1d"), Ok(Duration::from_secs(86400)));
+        assert!(parse("").is_err());
+        assert!(parse("30").is_err());
+        assert!(parse("5 minutes").is_err());
+        assert!(parse("-5s").is_err());
+
+        assert_eq!(format(Duration::from_secs(5400)), "90m");
+        assert_eq!(format(Duration::from_millis(1500)), "1500ms");
+        assert_eq!(format(Duration::from_secs(7200)), "2h");
+        assert_eq!(format(Duration::ZERO), "0s");
+    }
+
+    #[test]
+    fn human_duration() {
+        let duration: HumanDuration = "1h30m".parse().unwrap();
+        assert_eq!(*duration, Duration::from_secs(5400));
+        assert_eq!(duration.to_string(), "90m");
+        assert_eq!(
+            "30".parse::<HumanDuration>().unwrap_err().to_string(),
+            "invalid duration `30`, expected e.g. `250ms`, `30s`, `5m`, `2h` or `1d`"
+        );
+
+        assert_eq!(
+            serde_json::from_str::<HumanDuration>("\"250ms\"").unwrap(),
+            HumanDuration(Duration::from_millis(250))
+        );
+        assert_eq!(
+            serde_json::from_str::<HumanDuration>("60").unwrap(),
+            HumanDuration(Duration::from_secs(60))
+        );
+        assert_eq!(serde_json::to_string(&duration).unwrap(), "\"90m\"");
+    }
+}
@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Comment-preserving editing of an `Enarx.toml`
+//!
+//! Requires the `edit` feature. Unlike deserializing into an [`EnarxConfig`] and
+//! serializing it again, [`ConfigDocument`] keeps the comments and the formatting
+//! of the parts of the document that are not changed.
+//!
+//! # Examples
+//!
+//! ```
+//! use enarx_config::edit::ConfigDocument;
+//!
+//! let mut doc: ConfigDocument = r#"
+//! [[files]]
+//! name = "API"
+//! kind = "listen"
+//! port = 8080 # change me
+//! "#
+//! .parse()
+//! .unwrap();
+//!
+//! doc.set_port("API", 443.into()).unwrap();
+//! assert!(doc.to_string().contains("port = 443 # change me"));
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use toml_edit::{ArrayOfTables, DocumentMut, Item, TableLike, Value};
+use url::Url;
+
+use crate::{EnarxConfig, File, Port};
+
+/// Error editing a [`ConfigDocument`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EditError {
+    /// the document is not valid TOML
+    Parse(String),
+
+    /// the document or a value does not match the configuration schema
+    Invalid(String),
+
+    /// there is no socket file with the name
+    UnknownFile(String),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "invalid TOML: {}", e),
+            Self::Invalid(e) => write!(f, "invalid configuration: {}", e),
+            Self::UnknownFile(name) => write!(f, "no `listen` or `connect` file `{}`", name),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// An `Enarx.toml` document with typed mutators preserving comments and formatting
+#[derive(Clone, Debug)]
+pub struct ConfigDocument {
+    doc: DocumentMut,
+}
+
+impl FromStr for ConfigDocument {
+    type Err = EditError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let doc = s
+            .parse::<DocumentMut>()
+            .map_err(|e| EditError::Parse(e.to_string()))?;
+        let doc = Self { doc };
+
+        // reject documents, which are not a configuration in the first place
+        doc.config()?;
+        Ok(doc)
+    }
+}
+
+impl fmt::Display for ConfigDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.doc.fmt(f)
+    }
+}
+
+impl ConfigDocument {
+    /// deserialize the current state of the document
+    pub fn config(&self) -> Result<EnarxConfig, EditError> {
+        toml_edit::de::from_document(self.doc.clone())
+            .map_err(|e| EditError::Invalid(e.to_string()))
+    }
+
+    /// set the `steward` URL, keeping a `[steward.csr]` table
+    pub fn set_steward(&mut self, steward: &Url) {
+        let root = self.doc.as_table_mut();
+        match root.get_mut("steward").and_then(Item::as_table_like_mut) {
+            Some(table) => set_value(table, "url", steward.as_str().into()),
+            None => set_value(root, "steward", steward.as_str().into()),
+        }
+    }
+
+    /// set the `port` of the `listen` or `connect` file with `name`
+    pub fn set_port(&mut self, name: &str, port: Port) -> Result<(), EditError> {
+        let index = self
+            .config()?
+            .files
+            .iter()
+            .position(|f| {
+                f.name() == name && matches!(f, File::Listen { .. } | File::Connect { .. })
+            })
+            .ok_or_else(|| EditError::UnknownFile(name.into()))?;
+
+        let table = self
+            .files()?
+            .get_mut(index)
+            .expect("`files` was deserialized from the document");
+        set_value(table, "port", i64::from(port.get()).into());
+        Ok(())
+    }
+
+    /// append a file to the `files`
+    pub fn add_file(&mut self, file: &File) -> Result<(), EditError> {
+        let table = toml_edit::ser::to_document(file)
+            .map_err(|e| EditError::Invalid(e.to_string()))?
+            .as_table()
+            .clone();
+        self.files()?.push(table);
+        Ok(())
+    }
+
+    fn files(&mut self) -> Result<&mut ArrayOfTables, EditError> {
+        self.doc
+            .as_table_mut()
+            .entry("files")
+            .or_insert_with(|| Item::ArrayOfTables(ArrayOfTables::new()))
+            .as_array_of_tables_mut()
+            .ok_or_else(|| EditError::Invalid("`files` is not an array of tables".into()))
+    }
+}
+
+/// replace the value of `key` in `table`, keeping the comments around it
+fn set_value(table: &mut dyn TableLike, key: &str, mut value: Value) {
+    match table.get_mut(key).and_then(Item::as_value_mut) {
+        Some(old) => {
+            *value.decor_mut() = old.decor().clone();
+            *old = value;
+        }
+        None => {
+            table.insert(key, Item::Value(value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"# my application
+steward = "https://steward.example.com/" # production
+
+# the public API
+[[files]]
+name = "API"
+kind = "listen"
+port = 8080
+"#;
+
+    #[test]
+    fn edit() {
+        let mut doc: ConfigDocument = CONFIG.parse().unwrap();
+
+        doc.set_steward(&"https://steward.test/".parse().unwrap());
+        doc.set_port("API", 443.into()).unwrap();
+        doc.add_file(&File::Null {
+            name: None,
+            access: None,
+            labels: Default::default(),
+        })
+        .unwrap();
+
+        assert_eq!(
+            doc.to_string(),
+            r#"# my application
+steward = "https://steward.test/" # production
+
+# the public API
+[[files]]
+name = "API"
+kind = "listen"
+port = 443
+
+[[files]]
+kind = "null"
+"#
+        );
+        assert_eq!(doc.config().unwrap().files.len(), 2);
+    }
+
+    #[test]
+    fn steward_table() {
+        let mut doc: ConfigDocument = r#"[steward]
+url = "https://steward.example.com/" # production
+csr = { dns_sans = [ "api.example.com" ] }
+"#
+        .parse()
+        .unwrap();
+
+        doc.set_steward(&"https://steward.test/".parse().unwrap());
+        assert_eq!(
+            doc.to_string(),
+            r#"[steward]
+url = "https://steward.test/" # production
+csr = { dns_sans = [ "api.example.com" ] }
+"#
+        );
+    }
+
+    #[test]
+    fn unknown_file() {
+        let mut doc: ConfigDocument = CONFIG.parse().unwrap();
+
+        assert_eq!(
+            doc.set_port("DB", 5432.into()),
+            Err(EditError::UnknownFile("DB".into()))
+        );
+        assert!(matches!(
+            "files = 1".parse::<ConfigDocument>(),
+            Err(EditError::Invalid(_))
+        ));
+    }
+}
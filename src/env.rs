@@ -0,0 +1,479 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The environment of the application and the `[runtime]` section
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use serde::{
+    de::{Error, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+
+use crate::{EnarxConfig, File, ValidationError, Warning};
+
+/// A value of the `env`, written as TOML string, integer, float or boolean
+///
+/// Scalars are converted to their decimal representation, `PORT = 8080` is `"8080"`,
+/// floats always carry a fraction or exponent, like `1.0` or `1e-7`.
+struct EnvValue(String);
+
+impl<'de> Deserialize<'de> for EnvValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct EnvValueVisitor;
+
+        impl<'de> Visitor<'de> for EnvValueVisitor {
+            type Value = EnvValue;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a string, integer, float or boolean")
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(EnvValue(v.into()))
+            }
+
+            fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
+                Ok(EnvValue(v))
+            }
+
+            fn visit_i64<E: Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(EnvValue(v.to_string()))
+            }
+
+            fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(EnvValue(v.to_string()))
+            }
+
+            fn visit_f64<E: Error>(self, v: f64) -> Result<Self::Value, E> {
+                // unlike `Display`, `Debug` keeps the fraction of `1.0`
+                Ok(EnvValue(format!("{:?}", v)))
+            }
+
+            fn visit_bool<E: Error>(self, v: bool) -> Result<Self::Value, E> {
+                Ok(EnvValue(v.to_string()))
+            }
+        }
+
+        deserializer.deserialize_any(EnvValueVisitor)
+    }
+}
+
+/// deserialize the `env`, converting scalar values to strings
+pub(crate) fn deserialize_env<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let env = BTreeMap::<String, EnvValue>::deserialize(deserializer)?;
+    Ok(env
+        .into_iter()
+        .map(|(name, value)| (name, value.0))
+        .collect())
+}
+
+/// The `[runtime]` section with conveniences for the environment of the application
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Runtime {
+    /// IANA time zone name like `Europe/Berlin`, exported as `TZ`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+
+    /// POSIX locale name like `de_DE.UTF-8`, exported as `LANG`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+
+    /// export the address and port of every `listen` and `connect` file
+    /// as `<NAME>_ADDR` and `<NAME>_PORT`
+    #[serde(default)]
+    pub export_env: bool,
+}
+
+impl Runtime {
+    pub(crate) fn validate(&self) -> Result<(), (&'static str, String)> {
+        if let Some(timezone) = &self.timezone {
+            if !is_timezone(timezone) {
+                return Err((
+                    "timezone",
+                    format!("`{}` is not an IANA time zone name", timezone),
+                ));
+            }
+        }
+        if let Some(locale) = &self.locale {
+            if !is_locale(locale) {
+                return Err(("locale", format!("`{}` is not a locale name", locale)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// check the format of an IANA time zone name, e.g. `America/Argentina/Buenos_Aires` or `Etc/GMT+5`
+fn is_timezone(name: &str) -> bool {
+    name.split('/').all(|part| {
+        part.starts_with(|c: char| c.is_ascii_alphabetic())
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+'))
+    })
+}
+
+/// check the format of a locale name `language[_territory][.codeset][@modifier]`, or `C` and `POSIX`
+fn is_locale(name: &str) -> bool {
+    if matches!(name, "C" | "POSIX" | "C.UTF-8") {
+        return true;
+    }
+
+    let (name, modifier) = match name.split_once('@') {
+        Some((name, modifier)) => (name, Some(modifier)),
+        None => (name, None),
+    };
+    let (name, codeset) = match name.split_once('.') {
+        Some((name, codeset)) => (name, Some(codeset)),
+        None => (name, None),
+    };
+    let (language, territory) = match name.split_once('_') {
+        Some((language, territory)) => (language, Some(territory)),
+        None => (name, None),
+    };
+
+    let territory_ok = |t: &str| t.len() == 2 && t.chars().all(|c| c.is_ascii_uppercase());
+    let codeset_ok =
+        |c: &str| !c.is_empty() && c.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    let modifier_ok = |m: &str| !m.is_empty() && m.chars().all(|c| c.is_ascii_alphanumeric());
+
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_lowercase())
+        && !matches!(territory, Some(t) if !territory_ok(t))
+        && !matches!(codeset, Some(c) if !codeset_ok(c))
+        && !matches!(modifier, Some(m) if !modifier_ok(m))
+}
+
+/// get the prefix of the address variables of a file, e.g. `DB_EXAMPLE_COM` for `db.example.com`
+fn env_prefix(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect()
+}
+
+/// get the exported address and port of a `listen` or `connect` file
+///
+/// The port of an ephemeral listener is only known at runtime and not exported.
+fn socket_env(file: &File) -> Option<(String, Option<String>)> {
+    if let Some(listen) = file.as_listen() {
+        let port = (!listen.ephemeral).then(|| listen.port.to_string());
+        return Some((listen.addr.to_string(), port));
+    }
+    let connect = file.as_connect()?;
+    Some((connect.host.to_string(), Some(connect.port.to_string())))
+}
+
+/// check if the environment variable `name` matches a pattern of `env_exports`,
+/// the name itself or a prefix followed by `*`
+fn is_exported(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
+}
+
+/// check the format of a pattern of `env_exports`
+fn is_export_pattern(pattern: &str) -> bool {
+    let name = pattern.strip_suffix('*').unwrap_or(pattern);
+    (!name.is_empty() || pattern == "*")
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl EnarxConfig {
+    /// get the environment variables provided to the application
+    ///
+    /// These are the variables derived from the `[runtime]` section, like `TZ`
+    /// or the `<NAME>_ADDR` and `<NAME>_PORT` of the files with `export_env = true`,
+    /// overridden by the explicit `env` table. With `env_exports`, only the variables
+    /// matching one of its patterns are provided.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::EnarxConfig;
+    ///
+    /// let config: EnarxConfig = toml::from_str(r#"
+    /// env = { "APP_MODE" = "prod", "APP_LOG" = "info", "AWS_SECRET" = "..." }
+    /// env_exports = [ "APP_*" ]
+    /// "#).unwrap();
+    ///
+    /// let env = config.runtime_env();
+    /// assert_eq!(env.len(), 2);
+    /// assert!(!env.contains_key("AWS_SECRET"));
+    /// ```
+    pub fn runtime_env(&self) -> BTreeMap<String, String> {
+        let mut env = self.declared_env();
+        if let Some(exports) = &self.env_exports {
+            env.retain(|name, _| exports.iter().any(|pattern| is_exported(pattern, name)));
+        }
+        env
+    }
+
+    /// get the environment variables of the configuration, before applying `env_exports`
+    fn declared_env(&self) -> BTreeMap<String, String> {
+        let mut env = BTreeMap::new();
+
+        if let Some(runtime) = &self.runtime {
+            if let Some(timezone) = &runtime.timezone {
+                env.insert("TZ".into(), timezone.clone());
+            }
+            if let Some(locale) = &runtime.locale {
+                env.insert("LANG".into(), locale.clone());
+            }
+            if runtime.export_env {
+                for file in &self.files {
+                    let (addr, port) = match socket_env(file) {
+                        Some(socket) => socket,
+                        None => continue,
+                    };
+                    let prefix = env_prefix(file.name());
+                    env.insert(format!("{}_ADDR", prefix), addr);
+                    if let Some(port) = port {
+                        env.insert(format!("{}_PORT", prefix), port);
+                    }
+                }
+            }
+        }
+
+        env.extend(self.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+        env
+    }
+
+    pub(crate) fn validate_env_exports(&self) -> Result<(), ValidationError> {
+        for (i, pattern) in self.env_exports.iter().flatten().enumerate() {
+            if !is_export_pattern(pattern) {
+                return Err(ValidationError::new(
+                    format!("env_exports[{}]", i),
+                    format!(
+                        "invalid pattern `{}`, expected a variable name, optionally followed by `*`",
+                        pattern
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// get the warnings about the patterns of `env_exports` matching no variable
+    ///
+    /// The variables of the `[[conditional]]` sections are taken into account.
+    pub(crate) fn env_export_warnings(&self) -> Vec<Warning> {
+        let mut names = self.declared_env().into_keys().collect::<Vec<_>>();
+        names.extend(
+            self.conditional
+                .iter()
+                .flat_map(|conditional| conditional.env.keys().cloned()),
+        );
+
+        self.env_exports
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, pattern)| !names.iter().any(|name| is_exported(pattern, name)))
+            .map(|(i, pattern)| {
+                Warning::new(
+                    format!("env_exports[{}]", i),
+                    format!("`{}` matches no environment variable", pattern),
+                )
+            })
+            .collect()
+    }
+
+    pub(crate) fn validate_export_env(&self) -> Result<(), ValidationError> {
+        let mut prefixes = HashMap::new();
+        for file in &self.files {
+            if socket_env(file).is_none() {
+                continue;
+            }
+            let prefix = env_prefix(file.name());
+            if let Some(other) = prefixes.insert(prefix.clone(), file.name()) {
+                return Err(ValidationError::new(
+                    "runtime.export_env",
+                    format!(
+                        "files `{}` and `{}` both export `{}_ADDR`",
+                        other,
+                        file.name(),
+                        prefix
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn scalar_env() {
+        let cfg: EnarxConfig = toml::from_str(
+            "[env]\nPORT = 8080\nVERBOSE = true\nRATIO = 1.0\nEPSILON = 1e-7\nNAME = \"api\"",
+        )
+        .unwrap();
+        assert_eq!(cfg.env["PORT"], "8080");
+        assert_eq!(cfg.env["VERBOSE"], "true");
+        assert_eq!(cfg.env["RATIO"], "1.0");
+        assert_eq!(cfg.env["EPSILON"], "1e-7");
+        assert_eq!(cfg.env["NAME"], "api");
+
+        let err = EnarxConfig::from_toml_str("[env]\nHOSTS = [ \"a\" ]").unwrap_err();
+        assert_eq!(err.path(), Some("env.HOSTS"));
+    }
+
+    #[test]
+    fn runtime_env() {
+        const CONFIG: &str = r#"
+        env = { "LANG" = "en_US.UTF-8", "FOO" = "foo" }
+
+        [runtime]
+        timezone = "Europe/Berlin"
+        locale = "de_DE.UTF-8"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let env = cfg.runtime_env();
+        assert_eq!(env["TZ"], "Europe/Berlin");
+        assert_eq!(env["LANG"], "en_US.UTF-8");
+        assert_eq!(env["FOO"], "foo");
+    }
+
+    #[test]
+    fn export_env() {
+        const CONFIG: &str = r#"
+        env = { "DB_EXAMPLE_COM_PORT" = "6432" }
+
+        [runtime]
+        export_env = true
+
+        [[files]]
+        name = "API"
+        kind = "listen"
+        addr = "127.0.0.1"
+        port = 8443
+
+        [[files]]
+        name = "ADMIN"
+        kind = "listen"
+        ephemeral = true
+
+        [[files]]
+        kind = "connect"
+        host = "db.example.com"
+        port = 5432
+
+        [[files]]
+        kind = "stdin"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let env = cfg.runtime_env();
+        assert_eq!(env["API_ADDR"], "127.0.0.1");
+        assert_eq!(env["API_PORT"], "8443");
+        assert_eq!(env["ADMIN_ADDR"], "::");
+        assert!(!env.contains_key("ADMIN_PORT"));
+        assert_eq!(env["DB_EXAMPLE_COM_ADDR"], "db.example.com");
+        assert_eq!(env["DB_EXAMPLE_COM_PORT"], "6432");
+        assert!(!env.contains_key("STDIN_ADDR"));
+
+        let cfg: EnarxConfig = toml::from_str(&CONFIG.replace("\"ADMIN\"", "\"api\"")).unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "runtime.export_env: files `API` and `api` both export `API_ADDR`"
+        );
+
+        let cfg: EnarxConfig = toml::from_str(&CONFIG.replace("true", "false")).unwrap();
+        assert!(!cfg.runtime_env().contains_key("API_ADDR"));
+    }
+
+    #[test]
+    fn env_exports() {
+        const CONFIG: &str = r#"
+        env = { "APP_MODE" = "prod", "APP_LOG" = "info", "DB_PASSWORD" = "secret" }
+        env_exports = [ "APP_*", "TZ", "LEGACY_*", "DEBUG" ]
+
+        [runtime]
+        timezone = "UTC"
+
+        [[conditional]]
+        when = { profile = "dev" }
+        env = { "DEBUG" = "1" }
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(
+            cfg.runtime_env().keys().collect::<Vec<_>>(),
+            ["APP_LOG", "APP_MODE", "TZ"]
+        );
+        assert_eq!(
+            cfg.for_target(crate::Backend::Nil, Some("dev"))
+                .runtime_env()["DEBUG"],
+            "1"
+        );
+        assert_eq!(
+            cfg.warnings()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["env_exports[2]: `LEGACY_*` matches no environment variable"]
+        );
+
+        let cfg: EnarxConfig = toml::from_str("env = { \"A\" = \"a\" }\nenv_exports = []").unwrap();
+        assert!(cfg.runtime_env().is_empty());
+        assert!(cfg.warnings().is_empty());
+        let cfg: EnarxConfig = toml::from_str("env = { \"A\" = \"a\" }").unwrap();
+        assert_eq!(cfg.runtime_env().len(), 1);
+
+        for invalid in ["", "A*B", "**", "A-B", "*A"] {
+            let cfg = EnarxConfig {
+                env_exports: Some(vec!["A".into(), invalid.into()]),
+                ..Default::default()
+            };
+            assert_eq!(
+                cfg.validate().unwrap_err().path(),
+                "env_exports[1]",
+                "{}",
+                invalid
+            );
+        }
+        assert!(is_export_pattern("*"));
+        assert!(is_exported("*", "ANY"));
+    }
+
+    #[test]
+    fn names() {
+        assert!(is_timezone("UTC"));
+        assert!(is_timezone("America/Argentina/Buenos_Aires"));
+        assert!(is_timezone("Etc/GMT+5"));
+        assert!(!is_timezone("Europe/../etc/passwd"));
+        assert!(!is_timezone("/Europe/Berlin"));
+
+        assert!(is_locale("C"));
+        assert!(is_locale("en"));
+        assert!(is_locale("sr_RS.UTF-8@latin"));
+        assert!(!is_locale("de-DE"));
+        assert!(!is_locale("en_US."));
+    }
+}
@@ -0,0 +1,296 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The error type of loading an [`EnarxConfig`](crate::EnarxConfig)
+
+use std::fmt;
+#[cfg(feature = "std")]
+use std::{io, path::PathBuf};
+
+#[cfg(feature = "toml")]
+use serde_path_to_error::Segment;
+
+#[cfg(feature = "toml")]
+use crate::de::Key;
+use crate::ValidationError;
+
+/// A position in the source of a configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    /// line number, starting at 1
+    pub line: usize,
+
+    /// column number, starting at 1
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} column {}", self.line, self.column)
+    }
+}
+
+/// Error loading a configuration
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// the source is not a syntactically valid configuration
+    #[error("{}{message}{}", path.as_ref().map(|p| format!("{}: ", p)).unwrap_or_default(), location.map(|l| format!(" at {}", l)).unwrap_or_default())]
+    Parse {
+        /// path of the offending value, e.g. `files[2].port`, if known
+        path: Option<String>,
+
+        /// position of the offending value in the source, if known
+        location: Option<Location>,
+
+        /// description of the problem
+        message: String,
+    },
+
+    /// the source exceeds the [`ParseLimits`](crate::ParseLimits) of the caller
+    #[error("{}{message}", path.as_ref().map(|p| format!("{}: ", p)).unwrap_or_default())]
+    Limit {
+        /// path of the offending value, e.g. `files`, `None` for the whole source
+        path: Option<String>,
+
+        /// description of the exceeded limit
+        message: String,
+    },
+
+    /// the configuration is not semantically consistent
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+
+    /// no configuration was found by [`EnarxConfig::discover`](crate::EnarxConfig::discover)
+    #[cfg(feature = "std")]
+    #[error("no `Enarx.toml` found in `{}` or any parent directory", start.display())]
+    NotFound {
+        /// the directory the search started in
+        start: PathBuf,
+    },
+
+    /// the source could not be read
+    #[cfg(feature = "std")]
+    #[error("failed to read `{}`: {source}", path.display())]
+    Io {
+        /// path of the source
+        path: PathBuf,
+
+        /// the underlying error
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl Error {
+    /// create an [`Error::Parse`] without path and location
+    pub(crate) fn parse(message: impl Into<String>) -> Self {
+        Self::Parse {
+            path: None,
+            location: None,
+            message: message.into(),
+        }
+    }
+
+    /// the path of the offending value, if known
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::Parse { path, .. } | Self::Limit { path, .. } => path.as_deref(),
+            Self::Validation(e) => Some(e.path()),
+            #[cfg(feature = "std")]
+            Self::NotFound { .. } | Self::Io { .. } => None,
+        }
+    }
+
+    /// the position of the offending value in the source, if known
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            Self::Parse { location, .. } => *location,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Self::from_toml(None, e)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl Error {
+    /// create an [`Error::Parse`] from a `toml` error of the value at `path`
+    fn from_toml(path: Option<String>, e: toml::de::Error) -> Self {
+        let location = e.line_col().map(|(line, column)| Location {
+            line: line + 1,
+            column: column + 1,
+        });
+
+        // the message of `toml` ends with the key and the location
+        let mut message = e.to_string();
+        if location.is_some() {
+            if let Some(end) = message.rfind(" at line ") {
+                message.truncate(end);
+            }
+        }
+        if path.is_some() {
+            if let Some(end) = message.rfind(" for key `") {
+                message.truncate(end);
+            }
+        }
+
+        Self::Parse {
+            path,
+            location,
+            message,
+        }
+    }
+
+    /// create an [`Error::Parse`] from the error of deserializing the TOML `source`,
+    /// located at the offending value
+    pub(crate) fn from_toml_source(
+        source: &str,
+        e: serde_path_to_error::Error<toml::de::Error>,
+    ) -> Self {
+        let mut path = e
+            .path()
+            .iter()
+            .map_while(|segment| match segment {
+                Segment::Seq { index } => Some(Key::Index(*index)),
+                Segment::Map { key } => Some(Key::Name(key.clone())),
+                Segment::Enum { .. } | Segment::Unknown => None,
+            })
+            .collect::<Vec<_>>();
+        if path.is_empty() {
+            return Self::from_toml(None, e.into_inner());
+        }
+
+        // the path inside of a `[[files]]` entry is lost by its preprocessing
+        if let Ok(config) = toml::from_str::<serde_json::Value>(source) {
+            path.extend(crate::de::entry_field_path(&config, &path));
+        }
+
+        let mut error = Self::from_toml(Some(crate::de::format_path(&path)), e.into_inner());
+        if let (Self::Parse { location, .. }, Some(found)) =
+            (&mut error, crate::span::locate(source, &path))
+        {
+            *location = Some(found);
+        }
+        error
+    }
+}
+
+#[cfg(feature = "toml")]
+impl crate::EnarxConfig {
+    /// parse a configuration in TOML format
+    ///
+    /// Requires the `toml` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enarx_config::{EnarxConfig, Location};
+    ///
+    /// let err = EnarxConfig::from_toml_str("[[files]]\nkind = \"listen\"\nname = \"A:B\"").unwrap_err();
+    /// assert_eq!(err.path(), Some("files[0].name"));
+    /// assert_eq!(err.location(), Some(Location { line: 3, column: 8 }));
+    /// ```
+    pub fn from_toml_str(s: &str) -> Result<Self, Error> {
+        let mut deserializer = toml::Deserializer::new(s);
+        serde_path_to_error::deserialize(&mut deserializer)
+            .map_err(|e| Error::from_toml_source(s, e))
+    }
+
+    /// parse a configuration in TOML format, accepting only the canonical spellings of the
+    /// `kind` and `prot` of the `files`
+    ///
+    /// [`from_toml_str`](Self::from_toml_str) also accepts spellings like `kind = "Listen"`,
+    /// `prot = "TLS"` or `kind = "tls_listen"`.
+    ///
+    /// Requires the `toml` feature.
+    pub fn from_toml_str_strict(s: &str) -> Result<Self, Error> {
+        let config = Self::from_toml_str(s)?;
+
+        let value = toml::from_str::<toml::Value>(s)?;
+        // converting a TOML value into a `Value` cannot fail, all map keys are strings
+        let value = serde_json::to_value(value).expect("TOML is representable as JSON");
+
+        match crate::de::non_canonical_spelling(&value) {
+            Some((path, message)) => Err(Error::Parse {
+                path: Some(path),
+                location: None,
+                message,
+            }),
+            None => Ok(config),
+        }
+    }
+
+    /// parse a configuration in TOML format and report the deprecated spellings it uses
+    ///
+    /// Requires the `toml` feature.
+    pub fn from_toml_str_with_warnings(s: &str) -> Result<(Self, Vec<crate::Warning>), Error> {
+        let config = Self::from_toml_str(s)?;
+
+        let value = toml::from_str::<toml::Value>(s)?;
+        // converting a TOML value into a `Value` cannot fail, all map keys are strings
+        let value = serde_json::to_value(value).expect("TOML is representable as JSON");
+
+        Ok((config, crate::de::deprecations(&value)))
+    }
+
+    /// read, parse and [validate](crate::EnarxConfig::validate) a configuration file in TOML format
+    ///
+    /// Requires the `toml` and `std` features.
+    #[cfg(feature = "std")]
+    pub fn from_toml_file(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.into(),
+            source,
+        })?;
+
+        let config = Self::from_toml_str(&source)?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+#[cfg(all(test, feature = "toml"))]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    #[test]
+    fn parse() {
+        let err =
+            EnarxConfig::from_toml_str("[[files]]\nkind = \"listen\"\nport = \"x\"").unwrap_err();
+
+        assert!(matches!(err, Error::Parse { .. }));
+        assert_eq!(err.path(), Some("files[0].port"));
+        assert_eq!(err.location(), Some(Location { line: 3, column: 8 }));
+        assert!(err.to_string().starts_with("files[0].port: invalid type: "));
+        assert!(err.to_string().ends_with(" at line 3 column 8"));
+
+        // a missing field is located at its entry
+        let err = EnarxConfig::from_toml_str("[[files]]\nkind = \"alias\"").unwrap_err();
+        assert_eq!(err.path(), Some("files[0]"));
+        assert_eq!(err.location(), Some(Location { line: 1, column: 1 }));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_and_validation() {
+        let err = EnarxConfig::from_toml_file("/nonexistent/Enarx.toml").unwrap_err();
+        assert!(matches!(err, Error::Io { .. }));
+        assert!(err
+            .to_string()
+            .starts_with("failed to read `/nonexistent/Enarx.toml`: "));
+
+        let err = Error::from(ValidationError::new(
+            "files[0].port",
+            "can not connect to port 0",
+        ));
+        assert_eq!(err.path(), Some("files[0].port"));
+        assert_eq!(err.to_string(), "files[0].port: can not connect to port 0");
+    }
+}
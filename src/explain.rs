@@ -0,0 +1,543 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Machine-readable explanation of the effective values of a layered configuration
+
+use std::{collections::HashMap, fmt, path::PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{Backend, EnarxConfig, Error, Merge, OVERRIDE_PREFIX};
+
+/// Where an effective value of a configuration came from
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum Origin {
+    /// set by the configuration itself
+    #[serde(rename = "explicit")]
+    Explicit,
+
+    /// filled in by [`EnarxConfig::normalize`]
+    #[serde(rename = "default")]
+    Default,
+
+    /// set by a `[[conditional]]` section or a file with a `when` condition of the target
+    #[serde(rename = "profile")]
+    Profile,
+
+    /// set by an `ENARX_*` environment variable, see [`EnarxConfig::apply_overrides`]
+    #[serde(rename = "env")]
+    EnvOverride(String),
+
+    /// set by an included configuration file, and not overridden by the configuration itself
+    #[serde(rename = "include")]
+    Include(PathBuf),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Explicit => f.write_str("explicit"),
+            Self::Default => f.write_str("default"),
+            Self::Profile => f.write_str("profile overlay"),
+            Self::EnvOverride(var) => write!(f, "env override `{}`", var),
+            Self::Include(path) => write!(f, "include file `{}`", path.display()),
+        }
+    }
+}
+
+/// An effective value of a configuration and where it came from
+///
+/// Contains the path of the value, e.g. `files[2].port`. Arrays of plain values, like
+/// `args`, are a single value.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ExplainedValue {
+    path: String,
+    value: Value,
+    origin: Origin,
+}
+
+impl ExplainedValue {
+    /// the path of the value
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// the effective value
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// where the value came from
+    pub fn origin(&self) -> &Origin {
+        &self.origin
+    }
+}
+
+impl fmt::Display for ExplainedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {} ({})", self.path, self.value, self.origin)
+    }
+}
+
+/// A layer applied by [`Explain::assemble`] and the values it touched
+///
+/// Contains where the layer came from and the paths of the values it set or changed, in
+/// the order of the configuration, followed by the paths of the values it removed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AppliedLayer {
+    origin: Origin,
+    paths: Vec<String>,
+}
+
+impl AppliedLayer {
+    /// where the layer came from
+    pub fn origin(&self) -> &Origin {
+        &self.origin
+    }
+
+    /// the paths of the values the layer set, changed or removed
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+}
+
+impl fmt::Display for AppliedLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.origin, self.paths.join(", "))
+    }
+}
+
+/// The layers of a configuration to explain the effective values of
+///
+/// Created by [`EnarxConfig::explain`]. The layers are applied in the order
+/// included files, the configuration itself, the conditional settings of the
+/// [`target`](Explain::target), the [`overrides`](Explain::overrides) and the
+/// defaults of [`EnarxConfig::normalize`]. Each value is attributed to the last layer
+/// changing it.
+///
+/// Values filled in on parsing, like the `addr` of a `kind = "listen"`, are attributed
+/// to the layer they were parsed with.
+///
+/// # Examples
+///
+/// ```
+/// extern crate toml;
+/// use enarx_config::{EnarxConfig, Origin};
+///
+/// let base: EnarxConfig = toml::from_str(r#"
+/// env = { "LOG" = "info", "TZ" = "UTC" }
+///
+/// [[files]]
+/// name = "API"
+/// kind = "listen"
+/// "#).unwrap();
+/// let config: EnarxConfig = toml::from_str(r#"
+/// env = { "LOG" = "debug" }
+///
+/// [[files]]
+/// kind = "stdin"
+/// "#).unwrap();
+///
+/// let report = config
+///     .explain()
+///     .include("base.toml", base)
+///     .overrides([("ENARX_FILES_API_PORT", "8443")])
+///     .report()
+///     .unwrap();
+///
+/// let origin = |path: &str| report.iter().find(|v| v.path() == path).unwrap().origin().clone();
+/// assert_eq!(origin("env.LOG"), Origin::Explicit);
+/// assert_eq!(origin("env.TZ"), Origin::Include("base.toml".into()));
+/// assert_eq!(origin("files[0].name"), Origin::Include("base.toml".into()));
+/// assert_eq!(origin("files[0].port"), Origin::EnvOverride("ENARX_FILES_API_PORT".into()));
+/// assert_eq!(origin("files[1].kind"), Origin::Explicit);
+/// assert_eq!(origin("files[1].name"), Origin::Default);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Explain<'a> {
+    config: &'a EnarxConfig,
+    includes: Vec<(PathBuf, EnarxConfig)>,
+    target: Option<(Backend, Option<String>)>,
+    overrides: Vec<(String, String)>,
+}
+
+impl<'a> Explain<'a> {
+    pub(crate) fn new(config: &'a EnarxConfig) -> Self {
+        Self {
+            config,
+            includes: vec![],
+            target: None,
+            overrides: vec![],
+        }
+    }
+
+    /// add the configuration of an included file at `path`, later includes override earlier ones
+    pub fn include(mut self, path: impl Into<PathBuf>, config: EnarxConfig) -> Self {
+        self.includes.push((path.into(), config));
+        self
+    }
+
+    /// apply the conditional settings of a backend and profile, see [`EnarxConfig::for_target`]
+    pub fn target(mut self, backend: Backend, profile: Option<&str>) -> Self {
+        self.target = Some((backend, profile.map(Into::into)));
+        self
+    }
+
+    /// apply the `ENARX_*` variables of `vars`, see [`EnarxConfig::apply_overrides`]
+    ///
+    /// Other variables are ignored.
+    pub fn overrides<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        self.overrides.extend(
+            vars.into_iter()
+                .filter(|(key, _)| key.as_ref().starts_with(OVERRIDE_PREFIX))
+                .map(|(key, value)| (key.as_ref().into(), value.as_ref().into())),
+        );
+        self
+    }
+
+    /// get all effective values and their origin, in the order of the configuration
+    ///
+    /// Fails like [`EnarxConfig::apply_overrides`] for invalid overrides.
+    pub fn report(&self) -> Result<Vec<ExplainedValue>, Error> {
+        self.apply().map(|(_, report, _)| report.into_values())
+    }
+
+    /// get the effective configuration and the log of the layers it was assembled from
+    ///
+    /// The log lists the layers in the order they are applied, each [`ENARX_*`
+    /// override](Explain::overrides) as a layer of its own, including layers not
+    /// touching any value. Fails like [`Explain::report`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::{EnarxConfig, Origin};
+    ///
+    /// let config: EnarxConfig = toml::from_str(r#"
+    /// [[files]]
+    /// name = "API"
+    /// kind = "listen"
+    /// "#).unwrap();
+    ///
+    /// let (effective, log) = config
+    ///     .explain()
+    ///     .overrides([("ENARX_FILES_API_PORT", "8443")])
+    ///     .assemble()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(effective.files[0].as_listen().unwrap().port, 8443.into());
+    /// assert_eq!(log[1].origin(), &Origin::EnvOverride("ENARX_FILES_API_PORT".into()));
+    /// assert_eq!(log[1].paths(), ["files[0].port"]);
+    /// ```
+    pub fn assemble(&self) -> Result<(EnarxConfig, Vec<AppliedLayer>), Error> {
+        self.apply().map(|(config, _, log)| (config, log))
+    }
+
+    /// apply the layers, returning the effective configuration, its values and the log
+    fn apply(&self) -> Result<(EnarxConfig, Report, Vec<AppliedLayer>), Error> {
+        let mut report = Report::default();
+        let mut log = vec![];
+        let mut layer = |report: &mut Report, config: &EnarxConfig, own, origin: Origin| {
+            let paths = report.layer(config, own, origin.clone());
+            log.push(AppliedLayer { origin, paths });
+        };
+
+        let mut config = match self.includes.first() {
+            Some((_, first)) => first.clone(),
+            None => self.config.clone(),
+        };
+        for (i, (path, include)) in self.includes.iter().enumerate() {
+            if i > 0 {
+                config.merge(include.clone());
+            }
+            layer(
+                &mut report,
+                &config,
+                Some(include),
+                Origin::Include(path.clone()),
+            );
+        }
+        if !self.includes.is_empty() {
+            config.merge(self.config.clone());
+        }
+        layer(&mut report, &config, Some(self.config), Origin::Explicit);
+
+        if let Some((backend, profile)) = &self.target {
+            config = config.for_target(*backend, profile.as_deref());
+            layer(&mut report, &config, None, Origin::Profile);
+        }
+
+        for (key, value) in &self.overrides {
+            config.apply_overrides([(key, value)])?;
+            layer(&mut report, &config, None, Origin::EnvOverride(key.clone()));
+        }
+
+        let config = config.normalize();
+        layer(&mut report, &config, None, Origin::Default);
+
+        Ok((config, report, log))
+    }
+}
+
+/// a value of a configuration: the path, the key identifying it across layers, and the value
+type Leaf = (String, String, Value);
+
+/// get the values of `config`
+///
+/// Files are identified by their name, their index may change between the layers.
+fn leaves(config: &EnarxConfig) -> Vec<Leaf> {
+    // serializing the config types into a `Value` cannot fail, all map keys are strings
+    let mut value = serde_json::to_value(config).expect("config is serializable");
+    let mut out = vec![];
+
+    let files = value.as_object_mut().and_then(|map| map.remove("files"));
+    collect(&value, String::new(), String::new(), &mut out);
+    if files.is_some() {
+        for (i, file) in config.files.iter().enumerate() {
+            let file_value = serde_json::to_value(file).expect("file is serializable");
+            collect(
+                &file_value,
+                format!("files[{}]", i),
+                format!("files[{:?}]", file.name()),
+                &mut out,
+            );
+        }
+    }
+
+    out
+}
+
+fn collect(value: &Value, path: String, key: String, out: &mut Vec<Leaf>) {
+    let join = |base: &str, name: &str| match base.is_empty() {
+        true => name.to_string(),
+        false => format!("{}.{}", base, name),
+    };
+
+    match value {
+        Value::Object(map) => {
+            for (name, value) in map {
+                collect(value, join(&path, name), join(&key, name), out);
+            }
+        }
+        Value::Array(values) if !values.is_empty() && values.iter().all(Value::is_object) => {
+            for (i, value) in values.iter().enumerate() {
+                let index = format!("[{}]", i);
+                collect(value, path.clone() + &index, key.clone() + &index, out);
+            }
+        }
+        value => out.push((path, key, value.clone())),
+    }
+}
+
+/// the values of the last layer, by their key, and their origin
+#[derive(Default)]
+struct Report {
+    values: Vec<(String, ExplainedValue)>,
+}
+
+impl Report {
+    /// add the layer `config`, returning the paths of the values it set, changed or removed
+    ///
+    /// Its values are attributed to `origin`, if `own`, the layer itself, sets them,
+    /// or they changed since the previous layer. Other values keep their origin.
+    fn layer(
+        &mut self,
+        config: &EnarxConfig,
+        own: Option<&EnarxConfig>,
+        origin: Origin,
+    ) -> Vec<String> {
+        let own = own
+            .map(|own| {
+                leaves(own)
+                    .into_iter()
+                    .map(|(_, key, value)| (key, value))
+                    .collect::<HashMap<_, _>>()
+            })
+            .unwrap_or_default();
+        let mut previous = self.values.drain(..).collect::<Vec<_>>();
+        let index = previous
+            .iter()
+            .enumerate()
+            .map(|(i, (key, _))| (key.clone(), i))
+            .collect::<HashMap<_, _>>();
+        let mut kept = vec![false; previous.len()];
+
+        let mut touched = vec![];
+        for (path, key, value) in leaves(config) {
+            let origin = match index.get(&key) {
+                Some(&i) => {
+                    kept[i] = true;
+                    let previous = &previous[i].1;
+                    if previous.value == value && own.get(&key) != Some(&value) {
+                        previous.origin.clone()
+                    } else {
+                        touched.push(path.clone());
+                        origin.clone()
+                    }
+                }
+                None => {
+                    touched.push(path.clone());
+                    origin.clone()
+                }
+            };
+            self.values.push((
+                key,
+                ExplainedValue {
+                    path,
+                    value,
+                    origin,
+                },
+            ));
+        }
+
+        let removed = previous
+            .drain(..)
+            .zip(kept)
+            .filter(|(_, kept)| !kept)
+            .map(|((_, value), _)| value.path);
+        touched.extend(removed);
+        touched
+    }
+
+    fn into_values(self) -> Vec<ExplainedValue> {
+        self.values.into_iter().map(|(_, value)| value).collect()
+    }
+}
+
+impl EnarxConfig {
+    /// explain where the effective values of the configuration come from
+    ///
+    /// See [`Explain`] for the layers taken into account.
+    pub fn explain(&self) -> Explain<'_> {
+        Explain::new(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+    env = { "LOG" = "info" }
+
+    [[files]]
+    kind = "stdout"
+
+    [[files]]
+    name = "DEBUG"
+    kind = "listen"
+    port = 9000
+    when = { profile = "dev" }
+
+    [[files]]
+    name = "API"
+    kind = "listen"
+    port = 8080
+
+    [[conditional]]
+    when = { profile = "dev" }
+    env = { "LOG" = "debug" }
+    "#;
+
+    #[test]
+    fn report() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let report = cfg
+            .explain()
+            .target(Backend::Kvm, Some("dev"))
+            .overrides([("ENARX_FILES_API_PORT", "8443"), ("PATH", "/bin")])
+            .report()
+            .unwrap();
+        let explained = |path: &str| {
+            let value = report.iter().find(|v| v.path() == path).unwrap();
+            (value.value().clone(), value.origin().clone())
+        };
+
+        assert_eq!(explained("env.LOG"), ("debug".into(), Origin::Profile));
+        assert_eq!(
+            explained("files[0].kind"),
+            ("stdout".into(), Origin::Explicit)
+        );
+        assert_eq!(
+            explained("files[0].name"),
+            ("stdout".into(), Origin::Default)
+        );
+        assert_eq!(explained("files[1].port"), (9000.into(), Origin::Explicit));
+        assert_eq!(
+            explained("files[2].port"),
+            (
+                8443.into(),
+                Origin::EnvOverride("ENARX_FILES_API_PORT".into())
+            )
+        );
+        assert!(report.iter().all(|v| !v.path().starts_with("conditional")));
+        assert_eq!(
+            report
+                .iter()
+                .find(|v| v.path() == "files[2].port")
+                .unwrap()
+                .to_string(),
+            "files[2].port = 8443 (env override `ENARX_FILES_API_PORT`)"
+        );
+        assert_eq!(
+            serde_json::to_value(report.iter().find(|v| v.path() == "env.LOG")).unwrap(),
+            serde_json::json!({ "path": "env.LOG", "value": "debug", "origin": "profile" })
+        );
+
+        // without a target, the conditional files keep their index and `when`
+        let report = cfg.explain().report().unwrap();
+        assert!(report
+            .iter()
+            .any(|v| v.path() == "files[1].when.profile" && *v.origin() == Origin::Explicit));
+        assert!(report.iter().any(|v| v.path() == "conditional[0].env.LOG"));
+
+        let err = cfg
+            .explain()
+            .overrides([("ENARX_FILES_NONE_PORT", "1")])
+            .report()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "files: no file matches `ENARX_FILES_NONE_PORT`"
+        );
+    }
+
+    #[test]
+    fn assemble() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let base: EnarxConfig = toml::from_str("env = { \"TZ\" = \"UTC\" }").unwrap();
+        let (effective, log) = cfg
+            .explain()
+            .include("base.toml", base)
+            .target(Backend::Kvm, Some("dev"))
+            .overrides([("ENARX_ENV_TZ", "CET"), ("ENARX_FILES_API_PORT", "8080")])
+            .assemble()
+            .unwrap();
+
+        assert_eq!(effective.env["TZ"], "CET");
+        assert_eq!(effective.env["LOG"], "debug");
+        assert_eq!(effective.files[0].name(), "stdout");
+
+        assert_eq!(log.len(), 6);
+        assert_eq!(log[0].to_string(), "include file `base.toml`: args, env.TZ");
+        assert_eq!(log[1].origin(), &Origin::Explicit);
+        assert!(log[1].paths().contains(&"files[2].port".to_string()));
+        assert_eq!(
+            log[2].to_string(),
+            "profile overlay: env.LOG, conditional[0].env.LOG, conditional[0].when.profile, files[1].when.profile"
+        );
+        assert_eq!(log[3].paths(), ["env.TZ"]);
+        assert!(log[4].paths().is_empty());
+        assert_eq!(log[5].paths(), ["files[0].access", "files[0].name"]);
+        assert_eq!(
+            serde_json::to_value(&log[3]).unwrap(),
+            serde_json::json!({ "origin": { "env": "ENARX_ENV_TZ" }, "paths": [ "env.TZ" ] })
+        );
+    }
+}
@@ -0,0 +1,406 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Loading configurations from remote sources
+//!
+//! Requires the `fetch` feature.
+//!
+//! [`EnarxConfig::fetch`] loads a configuration from a `file://` or `https://` URL, bounded by
+//! the [`ParseLimits`] of the caller, optionally pinned to a [`Digest`] and checked by a
+//! verification hook, e.g. checking `EnarxConfig::verify_signature` with the `signature`
+//! feature.
+//!
+//! The crate does not depend on an HTTP client or an async runtime, `https://` sources are
+//! fetched with the [`Transport`] of the caller, `file://` sources are read directly, with
+//! blocking IO.
+
+use std::{fs, future::Future, io::Read, pin::Pin};
+
+use url::Url;
+
+use crate::{Digest, EnarxConfig, Error, ParseLimits};
+
+/// A boxed error of a [`Transport`] or a verification hook
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A boxed future, as returned by a [`Transport`]
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Error fetching a configuration
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum FetchError {
+    /// the source is not a valid URL
+    #[error("invalid URL `{url}`: {source}")]
+    InvalidUrl {
+        /// the URL
+        url: String,
+
+        /// the underlying error
+        #[source]
+        source: url::ParseError,
+    },
+
+    /// the scheme of the URL is neither `file` nor `https`
+    #[error("unsupported URL scheme `{0}`, expected `file` or `https`")]
+    UnsupportedScheme(String),
+
+    /// an `https://` source was requested without a [`Transport`]
+    #[error("no transport to fetch `{0}`")]
+    NoTransport(Url),
+
+    /// the source exceeds the `max_size` of the [`ParseLimits`]
+    #[error("`{url}` exceeds the limit of {limit} bytes")]
+    TooLarge {
+        /// the URL
+        url: Url,
+
+        /// the limit in bytes
+        limit: usize,
+    },
+
+    /// the source could not be read or transferred
+    #[error("failed to fetch `{url}`: {source}")]
+    Transport {
+        /// the URL
+        url: Url,
+
+        /// the underlying error
+        #[source]
+        source: BoxError,
+    },
+
+    /// the digest of the source does not match the expected digest
+    #[error("digest mismatch, expected `{expected}`, got `{actual}`")]
+    DigestMismatch {
+        /// the expected digest
+        expected: Digest,
+
+        /// the digest of the source
+        actual: Digest,
+    },
+
+    /// the source is not a valid configuration
+    #[error(transparent)]
+    Config(#[from] Error),
+
+    /// the verification hook rejected the configuration
+    #[error("verification failed: {0}")]
+    Verification(#[source] BoxError),
+}
+
+/// Fetches the bytes of `https://` sources, e.g. with the HTTP client of the caller
+pub trait Transport: Sync {
+    /// get the body of `url`, reading at most `limit` bytes
+    ///
+    /// A body larger than `limit` may be returned truncated to `limit + 1` bytes, it is
+    /// rejected with [`FetchError::TooLarge`].
+    fn get<'a>(&'a self, url: &'a Url, limit: usize) -> BoxFuture<'a, Result<Vec<u8>, BoxError>>;
+}
+
+/// A verification hook of a [`Fetch`], rejecting a configuration with an error
+pub type VerifyFn<'a> = dyn Fn(&EnarxConfig) -> Result<(), BoxError> + Sync + 'a;
+
+/// Loads a configuration from a URL, created with [`EnarxConfig::fetch`]
+///
+/// Await [`Fetch::load`] to get the configuration.
+#[must_use = "a fetch does nothing until it is loaded"]
+pub struct Fetch<'a> {
+    url: String,
+    transport: Option<&'a dyn Transport>,
+    limits: ParseLimits,
+    digest: Option<Digest>,
+    verify: Option<&'a VerifyFn<'a>>,
+}
+
+impl<'a> Fetch<'a> {
+    /// fetch `https://` sources with `transport`
+    pub fn transport(mut self, transport: &'a dyn Transport) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// bound the source by `limits`, the default is [`ParseLimits::default`]
+    pub fn limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// require the SHA-256 digest of the source to be `digest`
+    pub fn digest(mut self, digest: Digest) -> Self {
+        self.digest = Some(digest);
+        self
+    }
+
+    /// verify the parsed and validated configuration with `verify`, e.g. its signature
+    pub fn verify(mut self, verify: &'a VerifyFn<'a>) -> Self {
+        self.verify = Some(verify);
+        self
+    }
+
+    /// load, parse, validate and verify the configuration
+    ///
+    /// A `file://` source is read with blocking [`std::fs`] IO, on the task awaiting the
+    /// returned future. Async callers reading large or slow files should spawn the load on
+    /// a thread allowed to block, or fetch the file with their [`Transport`] as `https://`
+    /// source instead.
+    pub async fn load(self) -> Result<EnarxConfig, FetchError> {
+        let url = Url::parse(&self.url).map_err(|source| FetchError::InvalidUrl {
+            url: self.url.clone(),
+            source,
+        })?;
+        let limit = self.limits.max_size;
+
+        let bytes = match url.scheme() {
+            "file" => read_file(&url, limit)?,
+            "https" => {
+                let transport = self
+                    .transport
+                    .ok_or_else(|| FetchError::NoTransport(url.clone()))?;
+                transport
+                    .get(&url, limit)
+                    .await
+                    .map_err(|source| FetchError::Transport {
+                        url: url.clone(),
+                        source,
+                    })?
+            }
+            scheme => return Err(FetchError::UnsupportedScheme(scheme.into())),
+        };
+        if bytes.len() > limit {
+            return Err(FetchError::TooLarge { url, limit });
+        }
+
+        if let Some(expected) = self.digest {
+            let actual = Digest::of(&bytes);
+            if actual != expected {
+                return Err(FetchError::DigestMismatch { expected, actual });
+            }
+        }
+
+        let source = std::str::from_utf8(&bytes)
+            .map_err(|e| Error::parse(format!("invalid UTF-8: {}", e)))?;
+        let config = EnarxConfig::from_toml_str_with_limits(source, &self.limits)?;
+        config.validate().map_err(Error::from)?;
+
+        if let Some(verify) = self.verify {
+            verify(&config).map_err(FetchError::Verification)?;
+        }
+        Ok(config)
+    }
+}
+
+/// read the file of a `file://` URL, reading at most `limit + 1` bytes, blocking
+fn read_file(url: &Url, limit: usize) -> Result<Vec<u8>, FetchError> {
+    let transport = |source: BoxError| FetchError::Transport {
+        url: url.clone(),
+        source,
+    };
+
+    let path = url
+        .to_file_path()
+        .map_err(|_| transport("not a local file path".into()))?;
+    let file = fs::File::open(path).map_err(|e| transport(e.into()))?;
+
+    let mut bytes = vec![];
+    file.take(limit as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| transport(e.into()))?;
+    Ok(bytes)
+}
+
+impl EnarxConfig {
+    /// load, parse and [validate](EnarxConfig::validate) a configuration in TOML format from a
+    /// `file://` or `https://` URL
+    ///
+    /// The returned [`Fetch`] configures the [`Transport`] for `https://` sources, the limits,
+    /// the expected digest and the verification hook, and is [loaded](Fetch::load) to get the
+    /// configuration.
+    ///
+    /// Requires the `fetch` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enarx_config::{
+    ///     fetch::{BoxError, BoxFuture, FetchError, Transport},
+    ///     Digest, EnarxConfig,
+    /// };
+    /// use url::Url;
+    ///
+    /// struct Client;
+    ///
+    /// impl Transport for Client {
+    ///     fn get<'a>(&'a self, url: &'a Url, limit: usize) -> BoxFuture<'a, Result<Vec<u8>, BoxError>> {
+    ///         Box::pin(async move {
+    ///             // fetch `url` with an HTTP client, reading at most `limit + 1` bytes
+    ///             Ok(b"args = [ \"--verbose\" ]".to_vec())
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// async fn load(digest: Digest) -> Result<EnarxConfig, FetchError> {
+    ///     EnarxConfig::fetch("https://configs.example.com/api/Enarx.toml")
+    ///         .transport(&Client)
+    ///         .digest(digest)
+    ///         .load()
+    ///         .await
+    /// }
+    /// ```
+    pub fn fetch<'a>(url: impl Into<String>) -> Fetch<'a> {
+        Fetch {
+            url: url.into(),
+            transport: None,
+            limits: ParseLimits::default(),
+            digest: None,
+            verify: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::Arc,
+        task::{Context, Poll, Wake, Waker},
+    };
+
+    use super::*;
+
+    const CONFIG: &str = "args = [ \"--verbose\" ]\n";
+
+    struct Noop;
+
+    // `Waker::noop` is not available on the MSRV, older clippy versions lack the lint
+    #[allow(unknown_lints, clippy::manual_noop_waker)]
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// load a configuration, with a transport which never waits
+    fn load(fetch: Fetch<'_>) -> Result<EnarxConfig, FetchError> {
+        let mut future = Box::pin(fetch.load());
+        let waker = Waker::from(Arc::new(Noop));
+        match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("the future is pending"),
+        }
+    }
+
+    struct Static(&'static str);
+
+    impl Transport for Static {
+        fn get<'a>(
+            &'a self,
+            _url: &'a Url,
+            _limit: usize,
+        ) -> BoxFuture<'a, Result<Vec<u8>, BoxError>> {
+            Box::pin(async move { Ok(self.0.as_bytes().to_vec()) })
+        }
+    }
+
+    #[test]
+    fn https() {
+        const URL: &str = "https://configs.example.com/Enarx.toml";
+
+        let config = load(EnarxConfig::fetch(URL).transport(&Static(CONFIG))).unwrap();
+        assert_eq!(config.args, ["--verbose"]);
+
+        let digest = Digest::of(CONFIG);
+        assert!(load(
+            EnarxConfig::fetch(URL)
+                .transport(&Static(CONFIG))
+                .digest(digest)
+        )
+        .is_ok());
+        assert!(matches!(
+            load(
+                EnarxConfig::fetch(URL)
+                    .transport(&Static("args = []"))
+                    .digest(digest)
+            ),
+            Err(FetchError::DigestMismatch { expected, .. }) if expected == digest
+        ));
+
+        let verify = |config: &EnarxConfig| match config.args.is_empty() {
+            true => Err("no arguments".into()),
+            false => Ok(()),
+        };
+        assert!(load(
+            EnarxConfig::fetch(URL)
+                .transport(&Static(CONFIG))
+                .verify(&verify)
+        )
+        .is_ok());
+        assert_eq!(
+            load(
+                EnarxConfig::fetch(URL)
+                    .transport(&Static("args = []"))
+                    .verify(&verify)
+            )
+            .unwrap_err()
+            .to_string(),
+            "verification failed: no arguments"
+        );
+
+        let limits = ParseLimits {
+            max_size: 10,
+            ..Default::default()
+        };
+        assert_eq!(
+            load(
+                EnarxConfig::fetch(URL)
+                    .transport(&Static(CONFIG))
+                    .limits(limits)
+            )
+            .unwrap_err()
+            .to_string(),
+            format!("`{}` exceeds the limit of 10 bytes", URL)
+        );
+
+        assert!(matches!(
+            load(EnarxConfig::fetch(URL)),
+            Err(FetchError::NoTransport(_))
+        ));
+        assert!(matches!(
+            load(EnarxConfig::fetch(URL).transport(&Static(
+                "[[files]]\nkind = \"stdin\"\n[[files]]\nkind = \"stdin\""
+            ))),
+            Err(FetchError::Config(Error::Validation(_)))
+        ));
+    }
+
+    #[test]
+    fn file() {
+        let path =
+            std::env::temp_dir().join(format!("enarx-config-fetch-{}.toml", std::process::id()));
+        fs::write(&path, CONFIG).unwrap();
+        let url = Url::from_file_path(&path).unwrap();
+
+        let config = load(EnarxConfig::fetch(url.as_str())).unwrap();
+        assert_eq!(config.args, ["--verbose"]);
+
+        let limits = ParseLimits {
+            max_size: 10,
+            ..Default::default()
+        };
+        assert!(matches!(
+            load(EnarxConfig::fetch(url.as_str()).limits(limits)),
+            Err(FetchError::TooLarge { limit: 10, .. })
+        ));
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            load(EnarxConfig::fetch(url.as_str())),
+            Err(FetchError::Transport { .. })
+        ));
+
+        assert!(matches!(
+            load(EnarxConfig::fetch("http://configs.example.com/Enarx.toml")),
+            Err(FetchError::UnsupportedScheme(scheme)) if scheme == "http"
+        ));
+        assert!(matches!(
+            load(EnarxConfig::fetch("Enarx.toml")),
+            Err(FetchError::InvalidUrl { .. })
+        ));
+    }
+}
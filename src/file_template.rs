@@ -0,0 +1,184 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Templates of the `[templates]` section the `files` can be based on
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{EnarxConfig, File, ValidationError};
+
+impl File {
+    /// get the name of the template of a file based on one, which is not resolved yet
+    pub fn template(&self) -> Option<&str> {
+        match self {
+            Self::Templated { template, .. } => Some(template),
+            Self::Conditional { file, .. } => file.template(),
+            _ => None,
+        }
+    }
+}
+
+impl EnarxConfig {
+    /// resolve a file based on a template, other files are returned as they are
+    ///
+    /// The fields of the file override the ones of the template.
+    /// Returns the offending field, if any, and the error message.
+    fn resolve_template(&self, file: &File) -> Result<File, (Option<&'static str>, String)> {
+        let (template, fields, labels) = match file {
+            File::Templated {
+                template,
+                fields,
+                labels,
+            } => (template, fields, labels),
+            File::Conditional { when, file } => {
+                return Ok(File::Conditional {
+                    when: when.clone(),
+                    file: Box::new(self.resolve_template(file)?),
+                })
+            }
+            _ => return Ok(file.clone()),
+        };
+
+        let mut merged = match self.templates.get(template) {
+            Some(base) => base.clone(),
+            None => return Err((Some("template"), format!("unknown template `{}`", template))),
+        };
+        // templates based on templates are rejected by `validate_templates`
+        merged.remove("template");
+        merged.extend(fields.clone());
+        if !labels.is_empty() {
+            // a map of strings is representable as JSON
+            merged.insert("labels".into(), serde_json::to_value(labels).unwrap());
+        }
+
+        <File as Deserialize>::deserialize(Value::Object(merged)).map_err(|e| {
+            (
+                None,
+                format!("invalid file based on template `{}`: {}", template, e),
+            )
+        })
+    }
+
+    /// get the configuration with all files based on a template resolved
+    pub(crate) fn resolve_templates(&self) -> Result<Self, ValidationError> {
+        let files = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| {
+                self.resolve_template(file)
+                    .map_err(|(field, message)| match field {
+                        Some(field) => {
+                            ValidationError::new(format!("files[{}].{}", i, field), message)
+                        }
+                        None => ValidationError::new(format!("files[{}]", i), message),
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            files,
+            ..self.clone()
+        })
+    }
+
+    /// resolve the files based on a template, keeping the ones failing to resolve
+    pub(crate) fn resolve_templates_lossy(&self) -> Vec<File> {
+        self.files
+            .iter()
+            .map(|file| self.resolve_template(file).unwrap_or_else(|_| file.clone()))
+            .collect()
+    }
+
+    pub(crate) fn validate_templates(&self) -> Result<(), ValidationError> {
+        for (name, template) in &self.templates {
+            if template.contains_key("template") {
+                return Err(ValidationError::new(
+                    format!("templates.{}.template", name),
+                    "templates can not be based on other templates",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Port;
+
+    use super::*;
+
+    const CONFIG: &str = r#"
+    [templates.backend]
+    kind = "connect"
+    prot = "tcp"
+    port = 5432
+    timeout = "5s"
+    labels = { tier = "database" }
+
+    [[files]]
+    name = "PRIMARY"
+    template = "backend"
+    host = "db1.example.com"
+
+    [[files]]
+    name = "REPLICA"
+    template = "backend"
+    host = "db2.example.com"
+    port = 5433
+    labels = { tier = "replica" }
+    "#;
+
+    #[test]
+    fn templates() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.files[0].template(), Some("backend"));
+        assert_eq!(cfg.files[0].name(), "PRIMARY");
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), cfg);
+
+        let normalized = cfg.normalize();
+        let primary = normalized.files[0].as_connect().unwrap();
+        assert_eq!(primary.port, Port::new(5432));
+        assert_eq!(&**primary.host, "db1.example.com");
+        assert_eq!(primary.labels["tier"], "database");
+        let replica = normalized.files[1].as_connect().unwrap();
+        assert_eq!(replica.port, Port::new(5433));
+        assert_eq!(replica.timeout, Some(std::time::Duration::from_secs(5)));
+        assert_eq!(replica.labels["tier"], "replica");
+        assert_eq!(normalized.normalize(), normalized);
+    }
+
+    #[test]
+    fn invalid() {
+        let validate = |config: &str| {
+            toml::from_str::<EnarxConfig>(config)
+                .unwrap()
+                .validate()
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            validate("[[files]]\ntemplate = \"web\"\nname = \"API\""),
+            "files[0].template: unknown template `web`"
+        );
+        assert_eq!(
+            validate("[templates.web]\nkind = \"listen\"\n[[files]]\ntemplate = \"web\""),
+            "files[0]: invalid file based on template `web`: missing field `name`"
+        );
+        assert_eq!(
+            validate("[templates.a]\ntemplate = \"b\"\n[templates.b]\nkind = \"null\""),
+            "templates.a.template: templates can not be based on other templates"
+        );
+
+        // the resolved files are validated
+        assert_eq!(
+            validate("[templates.web]\nkind = \"listen\"\nport = 0\n[[files]]\ntemplate = \"web\"\nname = \"API\""),
+            "files[0].port: port 0 selects a random port, set `ephemeral = true` to request this explicitly"
+        );
+    }
+}
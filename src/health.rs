@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Liveness checks of the `[health]` section
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{EnarxConfig, File, Port, ValidationError};
+
+/// Protocol of a [`Health`] check
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum HealthProtocol {
+    /// `GET` request of `path`, succeeding with a `2xx` status
+    #[serde(rename = "http")]
+    Http,
+
+    /// plain TCP connection, succeeding if it is accepted
+    #[serde(rename = "tcp")]
+    Tcp,
+}
+
+// `#[default]` on enum variants is not available on the MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for HealthProtocol {
+    fn default() -> Self {
+        HealthProtocol::Http
+    }
+}
+
+impl fmt::Display for HealthProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http => f.write_str("http"),
+            Self::Tcp => f.write_str("tcp"),
+        }
+    }
+}
+
+/// The `[health]` section, declaring how orchestrators probe the liveness of the keep
+///
+/// Exactly one of `listener` and `port` has to be specified. With `listener`, the checks
+/// are served by the workload on a `kind = "listen"` file, with `port` by the runtime itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Health {
+    /// name of the `kind = "listen"` file serving the checks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listener: Option<String>,
+
+    /// port the runtime serves the checks on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<Port>,
+
+    /// request path of `prot = "http"` checks, defaults to `/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// protocol of the checks
+    #[serde(default)]
+    pub prot: HealthProtocol,
+}
+
+impl Health {
+    /// get the effective request path of `prot = "http"` checks
+    pub fn path(&self) -> &str {
+        self.path.as_deref().unwrap_or("/")
+    }
+}
+
+impl EnarxConfig {
+    pub(crate) fn validate_health(&self, health: &Health) -> Result<(), ValidationError> {
+        match (&health.listener, health.port) {
+            (Some(_), Some(_)) => {
+                return Err(ValidationError::new(
+                    "health.port",
+                    "only one of `listener` and `port` can be specified",
+                ))
+            }
+            (None, None) => {
+                return Err(ValidationError::new(
+                    "health",
+                    "one of `listener` and `port` has to be specified",
+                ))
+            }
+            (Some(listener), None) => match self.files.iter().find(|f| f.name() == listener) {
+                Some(File::Listen { .. }) => {}
+                Some(file) => {
+                    return Err(ValidationError::new(
+                        "health.listener",
+                        format!("`{}` is a `{}` file, not `listen`", listener, file.kind()),
+                    ))
+                }
+                None => {
+                    return Err(ValidationError::new(
+                        "health.listener",
+                        format!("unknown file `{}`", listener),
+                    ))
+                }
+            },
+            (None, Some(port)) if port.is_ephemeral() => {
+                return Err(ValidationError::new(
+                    "health.port",
+                    "can not listen on port 0",
+                ))
+            }
+            (None, Some(_)) => {}
+        }
+
+        match (&health.path, health.prot) {
+            (Some(_), HealthProtocol::Tcp) => Err(ValidationError::new(
+                "health.path",
+                "`path` requires `prot = \"http\"`",
+            )),
+            (Some(path), HealthProtocol::Http) if !path.starts_with('/') => {
+                Err(ValidationError::new("health.path", "must start with `/`"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn listener() {
+        const CONFIG: &str = r#"
+        [health]
+        listener = "API"
+        path = "/healthz"
+
+        [[files]]
+        name = "API"
+        kind = "listen"
+        port = 8080
+        "#;
+
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.health.as_ref().unwrap().prot, HealthProtocol::Http);
+        assert_eq!(cfg.validate(), Ok(()));
+
+        cfg.files.clear();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "health.listener: unknown file `API`"
+        );
+    }
+
+    #[test]
+    fn port() {
+        let cfg: EnarxConfig = toml::from_str("[health]\nport = 9000\nprot = \"tcp\"").unwrap();
+        let health = cfg.health.as_ref().unwrap();
+
+        assert_eq!(health.path(), "/");
+        assert_eq!(cfg.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,345 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! HTTP methods and URL patterns
+
+use std::{fmt, str::FromStr};
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use url::Url;
+
+use crate::{Error, FileName, Host, HostPattern, Port};
+
+/// An HTTP request method
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum HttpMethod {
+    /// `GET`
+    #[serde(rename = "GET")]
+    Get,
+
+    /// `HEAD`
+    #[serde(rename = "HEAD")]
+    Head,
+
+    /// `POST`
+    #[serde(rename = "POST")]
+    Post,
+
+    /// `PUT`
+    #[serde(rename = "PUT")]
+    Put,
+
+    /// `DELETE`
+    #[serde(rename = "DELETE")]
+    Delete,
+
+    /// `PATCH`
+    #[serde(rename = "PATCH")]
+    Patch,
+
+    /// `OPTIONS`
+    #[serde(rename = "OPTIONS")]
+    Options,
+}
+
+impl fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Get => "GET",
+            Self::Head => "HEAD",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+            Self::Delete => "DELETE",
+            Self::Patch => "PATCH",
+            Self::Options => "OPTIONS",
+        })
+    }
+}
+
+/// How a `kind = "listen"` file hands accepted connections to the application
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ListenMode {
+    /// the connections themselves, after the TLS handshake with `prot = "tls"`
+    #[serde(rename = "raw")]
+    Raw,
+
+    /// the runtime speaks HTTP/1.1 with the clients and passes the requests on
+    #[serde(rename = "http")]
+    Http,
+
+    /// the runtime speaks HTTP/2 with the clients and passes the requests on
+    #[serde(rename = "h2")]
+    H2,
+}
+
+// `#[default]` on enum variants is not available on the MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for ListenMode {
+    fn default() -> Self {
+        ListenMode::Raw
+    }
+}
+
+impl ListenMode {
+    /// does the runtime speak HTTP with the clients
+    pub fn is_http(self) -> bool {
+        self != Self::Raw
+    }
+}
+
+impl fmt::Display for ListenMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Raw => f.write_str("raw"),
+            Self::Http => f.write_str("http"),
+            Self::H2 => f.write_str("h2"),
+        }
+    }
+}
+
+/// A route of an HTTP listener, passing requests with a path prefix to another file descriptor
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HttpRoute {
+    /// prefix of the request paths, e.g. `/admin`
+    pub prefix: String,
+
+    /// name of the file descriptor the requests are passed to
+    pub name: FileName,
+}
+
+impl HttpRoute {
+    /// check if the route matches the request `path`
+    ///
+    /// The prefix matches whole path segments, `/admin` matches `/admin/users`,
+    /// but not `/administrator`.
+    pub fn matches(&self, path: &str) -> bool {
+        let prefix = self.prefix.trim_end_matches('/');
+        match path.strip_prefix(prefix) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/') || prefix.is_empty(),
+            None => false,
+        }
+    }
+}
+
+/// A pattern matching `http://` and `https://` URLs, e.g. `https://*.example.com/api/*`
+///
+/// A host `*.example.com` matches all subdomains of `example.com`. A path ending with `*`
+/// matches all paths starting with the part before the `*`, other paths match exactly.
+/// Without a port, the default port of the scheme is matched.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UrlPattern {
+    https: bool,
+    host: HostPattern,
+    port: Option<Port>,
+    path: String,
+    prefix: bool,
+}
+
+impl UrlPattern {
+    /// the host of the pattern, `None` for a `*.` wildcard host
+    pub fn host(&self) -> Option<&Host> {
+        match &self.host {
+            HostPattern::Exact(host) => Some(host),
+            _ => None,
+        }
+    }
+
+    /// the port of the pattern, or the default port of its scheme
+    pub fn port(&self) -> Port {
+        self.port
+            .unwrap_or_else(|| Port::new(if self.https { 443 } else { 80 }))
+    }
+
+    /// check if `url` matches the pattern
+    pub fn matches(&self, url: &Url) -> bool {
+        let https = match url.scheme() {
+            "https" => true,
+            "http" => false,
+            _ => return false,
+        };
+
+        let host = match url.host_str().and_then(|h| h.parse::<Host>().ok()) {
+            Some(host) => host,
+            None => return false,
+        };
+        let host_matches = self.host.matches(&host);
+
+        let port_matches = url.port_or_known_default() == Some(self.port().get());
+        let path_matches = if self.prefix {
+            url.path().starts_with(&self.path)
+        } else {
+            url.path() == self.path
+        };
+
+        https == self.https && host_matches && port_matches && path_matches
+    }
+}
+
+impl FromStr for UrlPattern {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |reason: &str| Error::parse(format!("invalid URL pattern `{}`: {}", s, reason));
+
+        let (https, rest) = match (s.strip_prefix("https://"), s.strip_prefix("http://")) {
+            (Some(rest), _) => (true, rest),
+            (None, Some(rest)) => (false, rest),
+            (None, None) => return Err(invalid("expected the scheme `http://` or `https://`")),
+        };
+
+        let (authority, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, "/*"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, _)) if !host.ends_with(']') && host.contains(':') => (authority, None),
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        };
+        let port = port
+            .map(|port| port.parse::<u16>().map(Port::new))
+            .transpose()
+            .map_err(|_| invalid("invalid port"))?;
+
+        let host = host
+            .parse::<HostPattern>()
+            .map_err(|e| invalid(&e.to_string()))?;
+
+        let (path, prefix) = match path.strip_suffix('*') {
+            Some(path) => (path, true),
+            None => (path, false),
+        };
+        if path.contains('*') {
+            return Err(invalid("`*` is only allowed at the end of the path"));
+        }
+
+        Ok(Self {
+            https,
+            host,
+            port,
+            path: path.into(),
+            prefix,
+        })
+    }
+}
+
+impl fmt::Display for UrlPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.https { "https://" } else { "http://" })?;
+        if matches!(&self.host, HostPattern::Exact(host) if host.contains(':')) {
+            write!(f, "[{}]", self.host)?;
+        } else {
+            self.host.fmt(f)?;
+        }
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        f.write_str(&self.path)?;
+        if self.prefix {
+            f.write_str("*")?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for UrlPattern {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for UrlPattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pattern = String::deserialize(deserializer)?;
+        pattern.parse().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    fn url(s: &str) -> Url {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn url_pattern() {
+        let pattern: UrlPattern = "https://*.example.com/api/*".parse().unwrap();
+        assert_eq!(pattern.to_string(), "https://*.example.com/api/*");
+        assert_eq!(pattern.host(), None);
+        assert_eq!(pattern.port(), Port::new(443));
+        assert!(pattern.matches(&url("https://eu.example.com/api/v1?x=1")));
+        assert!(!pattern.matches(&url("https://example.com/api/v1")));
+        assert!(!pattern.matches(&url("http://eu.example.com/api/v1")));
+        assert!(!pattern.matches(&url("https://eu.example.com:8443/api/v1")));
+        assert!(!pattern.matches(&url("https://eu.example.com/admin")));
+
+        let pattern: UrlPattern = "http://[::1]:8080/health".parse().unwrap();
+        assert_eq!(pattern.to_string(), "http://[::1]:8080/health");
+        assert!(pattern.matches(&url("http://[::1]:8080/health")));
+        assert!(!pattern.matches(&url("http://[::1]:8080/health/x")));
+
+        let pattern: UrlPattern = "https://API.example.com".parse().unwrap();
+        assert_eq!(pattern.to_string(), "https://api.example.com/*");
+        assert!(pattern.matches(&url("https://api.example.com/")));
+    }
+
+    #[test]
+    fn route() {
+        let route = HttpRoute {
+            prefix: "/admin".into(),
+            name: "ADMIN".into(),
+        };
+        assert!(route.matches("/admin"));
+        assert!(route.matches("/admin/users"));
+        assert!(!route.matches("/administrator"));
+        assert!(!route.matches("/"));
+    }
+
+    #[test]
+    fn http_listener() {
+        const CONFIG: &str = r#"
+        [[files]]
+        name = "API"
+        kind = "listen"
+        mode = "h2"
+        routes = [ { prefix = "/admin", name = "ADMIN" } ]
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.fd_names().collect::<Vec<_>>(), ["API", "ADMIN"]);
+
+        let listen = cfg.files[0].as_listen().unwrap();
+        assert_eq!(listen.mode, ListenMode::H2);
+        assert!(listen.routes[0].matches("/admin/users"));
+
+        let cfg: EnarxConfig = toml::from_str(&CONFIG.replace("\"ADMIN\"", "\"API\"")).unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "files[0].routes[0].name: duplicate file name `API`"
+        );
+
+        let cfg: EnarxConfig = toml::from_str(&CONFIG.replace("\"h2\"", "\"raw\"")).unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "files[0].routes: `routes` require `mode = \"http\"` or `mode = \"h2\"`"
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!("ftp://example.com/".parse::<UrlPattern>().is_err());
+        assert!("https://example.com:x/".parse::<UrlPattern>().is_err());
+        assert!("https://*.10.0.0.1/".parse::<UrlPattern>().is_err());
+        assert!("https://example.com/*/x".parse::<UrlPattern>().is_err());
+    }
+}
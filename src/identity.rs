@@ -0,0 +1,309 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Workload identity of the `[identity]` section
+
+use std::{fmt, str::FromStr};
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{EnarxConfig, Error, File, Protocol, ValidationError};
+
+/// A [SPIFFE ID](https://github.com/spiffe/spiffe/blob/main/standards/SPIFFE-ID.md)
+/// of a workload, e.g. `spiffe://example.com/payments/api`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SpiffeId(String);
+
+impl SpiffeId {
+    /// the trust domain, e.g. `example.com`
+    pub fn trust_domain(&self) -> &str {
+        let rest = &self.0["spiffe://".len()..];
+        rest.split('/').next().unwrap_or(rest)
+    }
+
+    /// the path identifying the workload in the trust domain, e.g. `/payments/api`
+    pub fn path(&self) -> &str {
+        &self.0["spiffe://".len() + self.trust_domain().len()..]
+    }
+
+    /// the SPIFFE ID as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// check the characters of a trust domain name
+fn is_trust_domain(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '-' | '_'))
+}
+
+impl FromStr for SpiffeId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| Error::parse(format!("invalid SPIFFE ID `{}`: {}", s, reason));
+
+        let rest = s
+            .strip_prefix("spiffe://")
+            .ok_or_else(|| invalid("expected the scheme `spiffe://`"))?;
+        let (domain, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, ""),
+        };
+
+        if !is_trust_domain(domain) {
+            return Err(invalid(
+                "the trust domain may only contain lowercase letters, digits, `.`, `-` and `_`",
+            ));
+        }
+        if path.is_empty() {
+            return Err(invalid("a workload identity requires a path"));
+        }
+        for segment in path[1..].split('/') {
+            if segment.is_empty() || segment == "." || segment == ".." {
+                return Err(invalid("path segments may not be empty, `.` or `..`"));
+            }
+            if !segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+            {
+                return Err(invalid(
+                    "path segments may only contain letters, digits, `.`, `-` and `_`",
+                ));
+            }
+        }
+
+        Ok(Self(s.into()))
+    }
+}
+
+impl fmt::Display for SpiffeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for SpiffeId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SpiffeId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let id = String::deserialize(deserializer)?;
+        id.parse().map_err(D::Error::custom)
+    }
+}
+
+/// The `[identity]` section declaring the workload identity
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Identity {
+    /// the SPIFFE ID of the workload
+    pub spiffe_id: SpiffeId,
+
+    /// the trust domain the workload belongs to, defaults to the one of `spiffe_id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trust_domain: Option<String>,
+
+    /// embed `spiffe_id` as URI SAN in the certificate issued by the Steward
+    #[serde(default)]
+    pub uri_san: bool,
+}
+
+impl Identity {
+    /// the effective trust domain
+    pub fn trust_domain(&self) -> &str {
+        self.trust_domain
+            .as_deref()
+            .unwrap_or_else(|| self.spiffe_id.trust_domain())
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), (&'static str, String)> {
+        if let Some(domain) = &self.trust_domain {
+            if !is_trust_domain(domain) {
+                return Err((
+                    "trust_domain",
+                    format!("`{}` is not a valid trust domain name", domain),
+                ));
+            }
+            if domain != self.spiffe_id.trust_domain() {
+                return Err((
+                    "trust_domain",
+                    format!(
+                        "`{}` does not match the trust domain of `{}`",
+                        domain, self.spiffe_id
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl EnarxConfig {
+    pub(crate) fn validate_identity(&self, identity: &Identity) -> Result<(), ValidationError> {
+        identity.validate().map_err(|(field, message)| {
+            ValidationError::new(format!("identity.{}", field), message)
+        })?;
+
+        if identity.uri_san && self.steward.is_none() {
+            return Err(ValidationError::new(
+                "identity.uri_san",
+                "embedding the identity in a certificate requires a `steward`",
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn validate_advertised_identity(&self) -> Result<(), ValidationError> {
+        for (i, file) in self.files.iter().enumerate() {
+            let file = match file {
+                File::Conditional { file, .. } => file,
+                file => file,
+            };
+            let (prot, psk) = match file {
+                File::Connect {
+                    advertise_identity: true,
+                    prot,
+                    psk,
+                    ..
+                } => (*prot, psk),
+                _ => continue,
+            };
+
+            let path = format!("files[{}].advertise_identity", i);
+            if prot != Protocol::Tls {
+                return Err(ValidationError::new(
+                    path,
+                    "`advertise_identity` requires `prot = \"tls\"`",
+                ));
+            }
+            if psk.is_some() {
+                return Err(ValidationError::new(
+                    path,
+                    "`advertise_identity` can not be combined with `psk`",
+                ));
+            }
+            if self.steward.is_none() {
+                return Err(ValidationError::new(
+                    path,
+                    "presenting the identity of the keep requires a `steward`",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn spiffe_id() {
+        let id: SpiffeId = "spiffe://example.com/payments/api".parse().unwrap();
+        assert_eq!(id.trust_domain(), "example.com");
+        assert_eq!(id.path(), "/payments/api");
+
+        for invalid in [
+            "https://example.com/api",
+            "spiffe://Example.com/api",
+            "spiffe://example.com",
+            "spiffe://example.com/",
+            "spiffe://example.com/a//b",
+            "spiffe://example.com/../api",
+            "spiffe://example.com/api?x",
+        ] {
+            assert!(invalid.parse::<SpiffeId>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let mut cfg: EnarxConfig = toml::from_str(
+            r#"
+            [identity]
+            spiffe_id = "spiffe://example.com/api"
+            trust_domain = "example.org"
+            uri_san = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "identity.trust_domain: `example.org` does not match the trust domain of `spiffe://example.com/api`"
+        );
+
+        let identity = cfg.identity.as_mut().unwrap();
+        identity.trust_domain = None;
+        assert_eq!(identity.trust_domain(), "example.com");
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "identity.uri_san: embedding the identity in a certificate requires a `steward`"
+        );
+
+        cfg.steward = Some("https://steward.example.com".parse().unwrap());
+        assert_eq!(cfg.validate(), Ok(()));
+    }
+
+    #[test]
+    fn advertise_identity() {
+        let validate = |config: &str| {
+            toml::from_str::<EnarxConfig>(config)
+                .unwrap()
+                .validate()
+                .map_err(|e| e.to_string())
+        };
+
+        const CONNECT: &str =
+            "[[files]]\nkind = \"connect\"\nhost = \"db.example.com\"\nadvertise_identity = true\n";
+        assert_eq!(
+            validate(CONNECT),
+            Err("files[0].advertise_identity: presenting the identity of the keep requires a `steward`".into())
+        );
+
+        let config = format!("steward = \"https://steward.example.com\"\n{}", CONNECT);
+        assert_eq!(validate(&config), Ok(()));
+        let cfg: EnarxConfig = toml::from_str(&config).unwrap();
+        assert!(cfg.files[0].as_connect().unwrap().advertise_identity);
+        assert_eq!(
+            serde_json::to_value(&cfg.files[0]).unwrap()["advertise_identity"],
+            true
+        );
+
+        assert_eq!(
+            validate(&format!("{}prot = \"tcp\"", config)),
+            Err(
+                "files[0].advertise_identity: `advertise_identity` requires `prot = \"tls\"`"
+                    .into()
+            )
+        );
+        assert_eq!(
+            validate(&format!(
+                "{}psk = {{ identity = \"keep-1\", key = \"link-key\" }}\n[secrets]\nlink-key = {{ env = \"KEY\" }}",
+                config
+            )),
+            Err("files[0].advertise_identity: `advertise_identity` can not be combined with `psk`".into())
+        );
+
+        let cfg: EnarxConfig =
+            toml::from_str("[[files]]\nkind = \"connect\"\nhost = \"db.example.com\"").unwrap();
+        assert!(!cfg.files[0].as_connect().unwrap().advertise_identity);
+        assert!(serde_json::to_value(&cfg.files[0])
+            .unwrap()
+            .get("advertise_identity")
+            .is_none());
+    }
+}
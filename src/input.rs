@@ -0,0 +1,413 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Bounds of configurations received from semi-trusted channels
+
+use toml::Value;
+
+use crate::{args, EnarxConfig, Error};
+
+/// Limits of the input accepted by [`EnarxConfig::from_toml_str_with_limits`]
+///
+/// The size and the nesting depth are checked before parsing, so the recursive parser never
+/// sees deeply nested input. All other limits are checked on the parsed document, before it
+/// is deserialized into an [`EnarxConfig`]. The number of `files`,
+/// `env` and `args` is limited per `[apps.NAME]` section as well.
+///
+/// # Examples
+///
+/// ```
+/// use enarx_config::{EnarxConfig, ParseLimits};
+///
+/// let limits = ParseLimits {
+///     max_files: 1,
+///     ..Default::default()
+/// };
+/// let err = EnarxConfig::from_toml_str_with_limits(
+///     "[[files]]\nkind = \"stdin\"\n\n[[files]]\nkind = \"stdout\"",
+///     &limits,
+/// )
+/// .unwrap_err();
+/// assert_eq!(err.path(), Some("files"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// maximum size of the source in bytes
+    pub max_size: usize,
+
+    /// maximum number of `files` entries
+    pub max_files: usize,
+
+    /// maximum number of `env` entries
+    pub max_env: usize,
+
+    /// maximum number of `args`, also if given as a command line
+    pub max_args: usize,
+
+    /// maximum length of any string or key in bytes
+    pub max_string_len: usize,
+
+    /// maximum nesting depth of tables and arrays, including the tables of dotted keys
+    pub max_depth: usize,
+}
+
+/// a limit exceeded by a value of the document
+enum Exceeded {
+    /// a string or key of the given length
+    String(usize),
+
+    /// the nesting depth
+    Depth,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_size: 1 << 20,
+            max_files: 1024,
+            max_env: 1024,
+            max_args: 1024,
+            max_string_len: 64 << 10,
+            max_depth: 32,
+        }
+    }
+}
+
+impl ParseLimits {
+    fn check(&self, document: &Value) -> Result<(), Error> {
+        let exceeded = |path: &str, message: String| Error::Limit {
+            path: Some(path.into()).filter(|p: &String| !p.is_empty()),
+            message,
+        };
+
+        let mut path = String::new();
+        self.check_values(document, &mut path, 0)
+            .map_err(|limit| match limit {
+                Exceeded::String(len) => exceeded(
+                    &path,
+                    format!(
+                        "string of {} bytes exceeds the limit of {}",
+                        len, self.max_string_len
+                    ),
+                ),
+                Exceeded::Depth => exceeded(
+                    &path,
+                    format!("nesting exceeds the limit of {} levels", self.max_depth),
+                ),
+            })?;
+
+        self.check_counts(document, "")?;
+        if let Some(Value::Table(apps)) = document.get("apps") {
+            for (name, app) in apps {
+                self.check_counts(app, &format!("apps.{}.", name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// check the number of `files`, `env` and `args` of the top level or of an application
+    fn check_counts(&self, section: &Value, prefix: &str) -> Result<(), Error> {
+        for (key, max, what) in [
+            ("files", self.max_files, "entries"),
+            ("env", self.max_env, "variables"),
+            ("args", self.max_args, "arguments"),
+        ] {
+            let len = match section.get(key) {
+                Some(Value::Array(values)) => values.len(),
+                Some(Value::Table(table)) => table.len(),
+                // an invalid command line is reported when deserializing
+                Some(Value::String(line)) if key == "args" => {
+                    args::split(line).map_or(0, |args| args.len())
+                }
+                _ => continue,
+            };
+            if len > max {
+                return Err(Error::Limit {
+                    path: Some(format!("{}{}", prefix, key)),
+                    message: format!("{} {} exceed the limit of {}", len, what, max),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// check the length of all strings and keys and the nesting depth, leaving the path
+    /// of the first offending value, or the table of the offending key, in `path`
+    fn check_values(&self, value: &Value, path: &mut String, depth: usize) -> Result<(), Exceeded> {
+        let len = path.len();
+        match value {
+            Value::String(s) if s.len() > self.max_string_len => {
+                return Err(Exceeded::String(s.len()))
+            }
+            Value::Array(_) | Value::Table(_) if depth >= self.max_depth => {
+                return Err(Exceeded::Depth)
+            }
+            Value::Array(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    path.push_str(&format!("[{}]", i));
+                    self.check_values(value, path, depth + 1)?;
+                    path.truncate(len);
+                }
+            }
+            Value::Table(table) => {
+                for (key, value) in table {
+                    if key.len() > self.max_string_len {
+                        return Err(Exceeded::String(key.len()));
+                    }
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(key);
+                    self.check_values(value, path, depth + 1)?;
+                    path.truncate(len);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// skip a string starting at `bytes[i]`, returning the index after it
+fn skip_string(bytes: &[u8], mut i: usize) -> usize {
+    let quote = bytes[i];
+    let multiline = bytes[i..].starts_with(&[quote; 3]);
+    i += if multiline { 3 } else { 1 };
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if quote == b'"' => i += 1,
+            b'\n' if !multiline => return i,
+            c if c == quote && (!multiline || bytes[i..].starts_with(&[quote; 3])) => {
+                return i + if multiline { 3 } else { 1 };
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    i
+}
+
+/// check if a TOML source nests tables and arrays deeper than `max_depth`
+///
+/// A linear scan of the source, counting the arrays and inline tables of values as well as
+/// the segments of dotted keys and table headers, skipping strings and comments.
+fn exceeds_depth(source: &str, max_depth: usize) -> bool {
+    let bytes = source.as_bytes();
+    // the open arrays and inline tables, with the depth of their values
+    let mut levels: Vec<(u8, usize)> = vec![];
+    // the depth of the keys of the current table header
+    let mut table = 0;
+    let mut depth = 0;
+    let mut key = true;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' | b'\'' => {
+                i = skip_string(bytes, i);
+                continue;
+            }
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'\n' if levels.is_empty() => {
+                depth = table;
+                key = true;
+            }
+            b'[' if key && levels.is_empty() => {
+                // a table header, `[[` adds the level of the array of tables
+                depth = 1;
+                if bytes.get(i + 1) == Some(&b'[') {
+                    depth += 1;
+                    i += 1;
+                }
+                while i < bytes.len() && bytes[i] != b']' && bytes[i] != b'\n' {
+                    match bytes[i] {
+                        b'"' | b'\'' => {
+                            i = skip_string(bytes, i);
+                            continue;
+                        }
+                        b'.' => depth += 1,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                table = depth;
+                key = false;
+            }
+            b'.' if key => depth += 1,
+            b'=' if key => key = false,
+            open @ (b'[' | b'{') => {
+                depth += 1;
+                levels.push((open, depth));
+                key = open == b'{';
+            }
+            b']' | b'}' => {
+                levels.pop();
+                key = false;
+            }
+            b',' => {
+                if let Some(&(open, inner)) = levels.last() {
+                    depth = inner;
+                    key = open == b'{';
+                }
+            }
+            _ => {}
+        }
+        if depth >= max_depth {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+impl EnarxConfig {
+    /// parse a configuration in TOML format, rejecting input exceeding `limits`
+    ///
+    /// Requires the `toml` feature.
+    pub fn from_toml_str_with_limits(s: &str, limits: &ParseLimits) -> Result<Self, Error> {
+        if s.len() > limits.max_size {
+            return Err(Error::Limit {
+                path: None,
+                message: format!("{} bytes exceed the limit of {}", s.len(), limits.max_size),
+            });
+        }
+
+        if exceeds_depth(s, limits.max_depth) {
+            return Err(Error::Limit {
+                path: None,
+                message: format!("nesting exceeds the limit of {} levels", limits.max_depth),
+            });
+        }
+
+        let document = toml::from_str::<Value>(s)?;
+        limits.check(&document)?;
+        Self::from_toml_str(s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+    args = [ "--verbose" ]
+    env = { "A" = "a", "B" = "b" }
+
+    [[files]]
+    kind = "stdin"
+    content = "0123456789"
+    "#;
+
+    fn err(limits: ParseLimits) -> String {
+        EnarxConfig::from_toml_str_with_limits(CONFIG, &limits)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn limits() {
+        assert!(EnarxConfig::from_toml_str_with_limits(CONFIG, &ParseLimits::default()).is_ok());
+
+        assert_eq!(
+            err(ParseLimits {
+                max_size: 10,
+                ..Default::default()
+            }),
+            format!("{} bytes exceed the limit of 10", CONFIG.len())
+        );
+        assert_eq!(
+            err(ParseLimits {
+                max_env: 1,
+                ..Default::default()
+            }),
+            "env: 2 variables exceed the limit of 1"
+        );
+        assert_eq!(
+            err(ParseLimits {
+                max_string_len: 9,
+                ..Default::default()
+            }),
+            "files[0].content: string of 10 bytes exceeds the limit of 9"
+        );
+        assert_eq!(
+            err(ParseLimits {
+                max_depth: 2,
+                ..Default::default()
+            }),
+            "nesting exceeds the limit of 2 levels"
+        );
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let err = |source: &str| {
+            EnarxConfig::from_toml_str_with_limits(source, &ParseLimits::default())
+                .unwrap_err()
+                .to_string()
+        };
+
+        // would overflow the stack of the recursive parser
+        for source in [
+            format!("args = {}", "[".repeat(100_000)),
+            format!("env = {}", "{ a = ".repeat(100_000)),
+            format!("{} = 1", vec!["a"; 100_000].join(".")),
+            format!("[{}]", vec!["a"; 100_000].join(".")),
+        ] {
+            assert_eq!(err(&source), "nesting exceeds the limit of 32 levels");
+        }
+
+        // brackets in strings and comments do not nest
+        let source = format!(
+            "args = [ \"{0}\", '{0}', \"\"\"\n{0}\"\"\" ] # {0}\n[[files]]\nkind = \"stdin\"",
+            "[{".repeat(100)
+        );
+        assert!(EnarxConfig::from_toml_str_with_limits(&source, &ParseLimits::default()).is_ok());
+
+        let limits = ParseLimits {
+            max_depth: 4,
+            ..Default::default()
+        };
+        let nested = "[[files]]\nkind = \"listen\"\nname = \"API\"\ntls = { alpn = [ \"h2\" ] }";
+        assert!(EnarxConfig::from_toml_str_with_limits(nested, &limits).is_err());
+        let limits = ParseLimits {
+            max_depth: 5,
+            ..Default::default()
+        };
+        assert!(EnarxConfig::from_toml_str_with_limits(nested, &limits).is_ok());
+    }
+
+    #[test]
+    fn limits_of_apps() {
+        const CONFIG: &str = r#"
+        args = "serve --port 8080"
+
+        [apps.api]
+        env = { "A" = "a", "B" = "b", "C" = "c" }
+        "#;
+
+        let err = |limits: ParseLimits| {
+            EnarxConfig::from_toml_str_with_limits(CONFIG, &limits)
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(EnarxConfig::from_toml_str_with_limits(CONFIG, &ParseLimits::default()).is_ok());
+        assert_eq!(
+            err(ParseLimits {
+                max_args: 2,
+                ..Default::default()
+            }),
+            "args: 3 arguments exceed the limit of 2"
+        );
+        assert_eq!(
+            err(ParseLimits {
+                max_env: 2,
+                ..Default::default()
+            }),
+            "apps.api.env: 3 variables exceed the limit of 2"
+        );
+    }
+}
@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Translation between a configuration and the options of an `enarx run` invocation
+
+use crate::{EnarxConfig, Error, File, Steward};
+
+impl EnarxConfig {
+    /// the options of an `enarx run` invocation equivalent to the configuration
+    ///
+    /// | Option              | Sets                                          |
+    /// |---------------------|-----------------------------------------------|
+    /// | `--steward <URL>`   | the URL of `steward`                          |
+    /// | `--env <VAR>=<VALUE>` | the environment variable `<VAR>` of `env`   |
+    /// | `--file <SPEC>`     | a `files` entry, in the [`File`] spec syntax  |
+    /// | `-- <ARGS>...`      | the `args`                                    |
+    ///
+    /// `--file` options are only produced, if the `files` differ from the default
+    /// `stdin`, `stdout` and `stderr`. Returns `None`, if the configuration has settings
+    /// no option can express, like other sections or files with a `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enarx_config::EnarxConfig;
+    ///
+    /// let mut config = EnarxConfig::default();
+    /// config.env.insert("LOG".into(), "debug".into());
+    /// config.args.push("--verbose".into());
+    ///
+    /// let args = config.to_cli_args().unwrap();
+    /// assert_eq!(args, ["--env", "LOG=debug", "--", "--verbose"]);
+    /// assert_eq!(EnarxConfig::from_cli_args(&args).unwrap(), config);
+    /// ```
+    pub fn to_cli_args(&self) -> Option<Vec<String>> {
+        let rest = Self {
+            env: Default::default(),
+            args: vec![],
+            files: Self::default().files,
+            steward: None,
+            ..self.clone()
+        };
+        if rest != Self::default() {
+            return None;
+        }
+
+        let mut args = vec![];
+
+        if let Some(steward) = &self.steward {
+            if *steward != Steward::from(steward.url.clone()) {
+                return None;
+            }
+            args.push("--steward".into());
+            args.push(steward.url.to_string());
+        }
+
+        for (var, value) in &self.env {
+            if var.contains('=') {
+                return None;
+            }
+            args.push("--env".into());
+            args.push(format!("{}={}", var, value));
+        }
+
+        if self.files != Self::default().files {
+            for file in &self.files {
+                args.push("--file".into());
+                args.push(file.to_spec_string()?);
+            }
+        }
+
+        if !self.args.is_empty() {
+            args.push("--".into());
+            args.extend(self.args.iter().cloned());
+        }
+
+        Some(args)
+    }
+
+    /// parse the options of an `enarx run` invocation, the inverse of
+    /// [`to_cli_args`](EnarxConfig::to_cli_args)
+    ///
+    /// The parsing is best effort: the options of [`to_cli_args`](EnarxConfig::to_cli_args)
+    /// are accepted as `--option value` or `--option=value`, all other options and
+    /// positional arguments before `--`, like the path of the WASM module, are ignored.
+    /// Without any `--file` option, the files are the default `stdin`, `stdout` and `stderr`.
+    pub fn from_cli_args<I, S>(args: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let invalid = |path: String, message: String| Error::Parse {
+            path: Some(path),
+            location: None,
+            message,
+        };
+
+        let mut config = Self::default();
+        let mut files = vec![];
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let arg = arg.as_ref();
+            if arg == "--" {
+                config.args.extend(args.map(|arg| arg.as_ref().to_string()));
+                break;
+            }
+
+            let (option, value) = match arg.split_once('=') {
+                Some((option, value)) if option.starts_with("--") => (option, Some(value.into())),
+                _ => (arg, None),
+            };
+            if !matches!(option, "--steward" | "--env" | "--file") {
+                continue;
+            }
+            let value: String = match value.or_else(|| Some(args.next()?.as_ref().into())) {
+                Some(value) => value,
+                None => {
+                    return Err(Error::parse(format!("missing value of `{}`", option)));
+                }
+            };
+
+            match option {
+                "--steward" => {
+                    let url = value.parse::<url::Url>().map_err(|e| {
+                        invalid(
+                            "steward".into(),
+                            format!("invalid URL `{}` of `--steward`: {}", value, e),
+                        )
+                    })?;
+                    config.steward = Some(Steward::from(url));
+                }
+                "--env" => match value.split_once('=') {
+                    Some((var, value)) => {
+                        config.env.insert(var.into(), value.into());
+                    }
+                    None => {
+                        return Err(invalid(
+                            "env".into(),
+                            format!("expected `<VAR>=<VALUE>` for `--env`, got `{}`", value),
+                        ))
+                    }
+                },
+                _ => {
+                    let file = value
+                        .parse::<File>()
+                        .map_err(|e| invalid(format!("files[{}]", files.len()), e.to_string()))?;
+                    files.push(file);
+                }
+            }
+        }
+
+        if !files.is_empty() {
+            config.files = files;
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let config: EnarxConfig = toml::from_str(
+            r#"
+            steward = "https://steward.example.com/"
+            args = [ "serve", "--name", "my app" ]
+            env = { "LOG" = "debug", "EQ" = "a=b" }
+
+            [[files]]
+            kind = "stdin"
+
+            [[files]]
+            kind = "stdout"
+
+            [[files]]
+            kind = "stderr"
+
+            [[files]]
+            kind = "listen"
+            name = "API"
+            port = 8443
+            "#,
+        )
+        .unwrap();
+
+        let args = config.to_cli_args().unwrap();
+        assert_eq!(
+            args,
+            [
+                "--steward",
+                "https://steward.example.com/",
+                "--env",
+                "EQ=a=b",
+                "--env",
+                "LOG=debug",
+                "--file",
+                "stdin",
+                "--file",
+                "stdout",
+                "--file",
+                "stderr",
+                "--file",
+                "tls-listen://[::]:8443/API",
+                "--",
+                "serve",
+                "--name",
+                "my app",
+            ]
+        );
+        assert_eq!(EnarxConfig::from_cli_args(&args).unwrap(), config);
+    }
+
+    #[test]
+    fn inexpressible() {
+        let config: EnarxConfig = toml::from_str("[debug]\nbacktraces = true").unwrap();
+        assert_eq!(config.to_cli_args(), None);
+
+        let config: EnarxConfig =
+            toml::from_str("[[files]]\nkind = \"stdout\"\npath = \"/var/log/app.log\"").unwrap();
+        assert_eq!(config.to_cli_args(), None);
+    }
+
+    #[test]
+    fn best_effort() {
+        let config = EnarxConfig::from_cli_args([
+            "--backend",
+            "kvm",
+            "--env=LOG=debug",
+            "app.wasm",
+            "--",
+            "--env",
+            "X=y",
+        ])
+        .unwrap();
+        assert_eq!(config.env["LOG"], "debug");
+        assert_eq!(config.args, ["--env", "X=y"]);
+        assert_eq!(config.files, EnarxConfig::default().files);
+
+        let err = EnarxConfig::from_cli_args(["--file", "stdio"]).unwrap_err();
+        assert_eq!(err.path(), Some("files[0]"));
+        assert!(EnarxConfig::from_cli_args(["--env"]).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Key-value stores of the `[[kv]]` array
+
+use std::{
+    collections::HashSet,
+    fmt,
+    num::{NonZeroU32, NonZeroU64},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{EnarxConfig, FileName, ValidationError};
+
+/// Storage of a [`KvStore`]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "backend")]
+pub enum KvBackend {
+    /// kept in the memory of the keep, lost on exit
+    #[serde(rename = "memory")]
+    Memory,
+
+    /// persisted in a file of the host
+    #[serde(rename = "file")]
+    File {
+        /// path of the file on the host
+        path: PathBuf,
+    },
+
+    /// a remote store accessed over HTTP
+    #[serde(rename = "url")]
+    Url {
+        /// `http://` or `https://` URL of the store
+        url: Url,
+    },
+}
+
+impl fmt::Display for KvBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Memory => f.write_str("memory"),
+            Self::File { .. } => f.write_str("file"),
+            Self::Url { .. } => f.write_str("url"),
+        }
+    }
+}
+
+/// A key-value store provided to the application with `wasi-keyvalue`
+///
+/// Stores are opened by their name, which follows the rules of a [`FileName`]
+/// and must not be used by any file descriptor.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KvStore {
+    /// name the application opens the store by
+    pub name: FileName,
+
+    /// storage of the store
+    #[serde(flatten)]
+    pub backend: KvBackend,
+
+    /// maximum number of keys
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_keys: Option<NonZeroU32>,
+
+    /// maximum size of a single value in bytes
+    #[serde(
+        default,
+        with = "crate::size::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_value_bytes: Option<NonZeroU64>,
+
+    /// maximum size of all keys and values in bytes
+    #[serde(
+        default,
+        with = "crate::size::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_bytes: Option<NonZeroU64>,
+}
+
+impl EnarxConfig {
+    /// get the key-value store named `name`
+    pub fn kv_store(&self, name: &str) -> Option<&KvStore> {
+        self.kv.iter().find(|store| &*store.name == name)
+    }
+
+    pub(crate) fn validate_kv(&self) -> Result<(), ValidationError> {
+        let files = self.fd_names().collect::<HashSet<_>>();
+        let mut names = HashSet::new();
+
+        for (i, store) in self.kv.iter().enumerate() {
+            let path = |field: &str| format!("kv[{}].{}", i, field);
+
+            if files.contains(&*store.name) {
+                return Err(ValidationError::new(
+                    path("name"),
+                    format!("`{}` is already the name of a file", &*store.name),
+                ));
+            }
+            if !names.insert(&*store.name) {
+                return Err(ValidationError::new(
+                    path("name"),
+                    format!("duplicate key-value store name `{}`", &*store.name),
+                ));
+            }
+
+            match &store.backend {
+                KvBackend::File { path: file } if file.as_os_str().is_empty() => {
+                    return Err(ValidationError::new(path("path"), "empty path"));
+                }
+                KvBackend::Url { url } if !matches!(url.scheme(), "http" | "https") => {
+                    return Err(ValidationError::new(
+                        path("url"),
+                        format!(
+                            "unsupported scheme `{}`, expected `http` or `https`",
+                            url.scheme()
+                        ),
+                    ));
+                }
+                _ => {}
+            }
+
+            if let (Some(value), Some(total)) = (store.max_value_bytes, store.max_bytes) {
+                if value > total {
+                    return Err(ValidationError::new(
+                        path("max_value_bytes"),
+                        format!("exceeds `max_bytes` of {}", total),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+    [[kv]]
+    name = "cache"
+    backend = "memory"
+    max_keys = 1000
+    max_value_bytes = 4096
+    max_bytes = 1048576
+
+    [[kv]]
+    name = "state"
+    backend = "file"
+    path = "/var/lib/app/state"
+
+    [[kv]]
+    name = "remote"
+    backend = "url"
+    url = "https://kv.example.com/app"
+    "#;
+
+    #[test]
+    fn kv() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let cache = cfg.kv_store("cache").unwrap();
+        assert_eq!(cache.backend, KvBackend::Memory);
+        assert_eq!(cache.max_keys, NonZeroU32::new(1000));
+        assert_eq!(
+            cfg.kv_store("state").unwrap().backend,
+            KvBackend::File {
+                path: "/var/lib/app/state".into()
+            }
+        );
+        assert_eq!(cfg.kv_store("remote").unwrap().backend.to_string(), "url");
+        assert!(cfg.kv_store("stdin").is_none());
+    }
+
+    #[test]
+    fn names() {
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        cfg.kv[1].name = "stdin".into();
+        cfg.files = EnarxConfig::default().files;
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "kv[1].name: `stdin` is already the name of a file"
+        );
+
+        cfg.kv[1].name = "cache".into();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "kv[1].name: duplicate key-value store name `cache`"
+        );
+
+        assert!(
+            toml::from_str::<EnarxConfig>("[[kv]]\nname = \"a:b\"\nbackend = \"memory\"").is_err()
+        );
+    }
+
+    #[test]
+    fn url_scheme() {
+        let cfg: EnarxConfig =
+            toml::from_str("[[kv]]\nname = \"s\"\nbackend = \"url\"\nurl = \"redis://kv\"")
+                .unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "kv[0].url: unsupported scheme `redis`, expected `http` or `https`"
+        );
+    }
+}
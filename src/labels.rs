@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Free-form labels of the configuration, its files and its services
+
+use std::collections::BTreeMap;
+
+use crate::{EnarxConfig, File, ValidationError};
+
+/// maximum number of labels of a single table
+const MAX_LABELS: usize = 64;
+
+/// maximum length of a label key in bytes
+const MAX_KEY_LEN: usize = 63;
+
+/// maximum length of a label value in bytes
+const MAX_VALUE_LEN: usize = 255;
+
+/// check the number, the keys and the values of `labels`
+///
+/// Returns the offending key, if any, and the error message.
+fn validate_labels(labels: &BTreeMap<String, String>) -> Result<(), (Option<&str>, String)> {
+    if labels.len() > MAX_LABELS {
+        return Err((
+            None,
+            format!("{} labels exceed the limit of {}", labels.len(), MAX_LABELS),
+        ));
+    }
+
+    for (key, value) in labels {
+        if key.is_empty() || key.len() > MAX_KEY_LEN {
+            return Err((
+                Some(key),
+                format!("keys must have 1 to {} characters", MAX_KEY_LEN),
+            ));
+        }
+        if !key.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+            return Err((Some(key), "keys must start with a letter or digit".into()));
+        }
+        if let Some(c) = key
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/')))
+        {
+            return Err((
+                Some(key),
+                format!(
+                    "invalid character `{}`, keys must only contain letters, digits, `-`, `_`, `.` and `/`",
+                    c
+                ),
+            ));
+        }
+        if value.len() > MAX_VALUE_LEN {
+            return Err((
+                Some(key),
+                format!(
+                    "value of {} bytes exceeds the limit of {}",
+                    value.len(),
+                    MAX_VALUE_LEN
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+impl File {
+    /// get the free-form labels of the file
+    pub fn labels(&self) -> &BTreeMap<String, String> {
+        match self {
+            Self::Null { labels, .. }
+            | Self::Stdin { labels, .. }
+            | Self::Stdout { labels, .. }
+            | Self::Stderr { labels, .. }
+            | Self::Listen { labels, .. }
+            | Self::Fifo { labels, .. }
+            | Self::Pipe { labels, .. }
+            | Self::Alias { labels, .. }
+            | Self::Connect { labels, .. }
+            | Self::Custom { labels, .. }
+            | Self::Templated { labels, .. } => labels,
+            Self::Conditional { file, .. } => file.labels(),
+        }
+    }
+}
+
+impl EnarxConfig {
+    pub(crate) fn validate_labels(&self) -> Result<(), ValidationError> {
+        let tables = std::iter::once(("labels".to_string(), &self.labels))
+            .chain(
+                self.files
+                    .iter()
+                    .enumerate()
+                    .map(|(i, file)| (format!("files[{}].labels", i), file.labels())),
+            )
+            .chain(
+                self.services
+                    .iter()
+                    .enumerate()
+                    .map(|(i, service)| (format!("services[{}].labels", i), &service.labels)),
+            );
+
+        for (path, labels) in tables {
+            validate_labels(labels).map_err(|(key, message)| match key {
+                Some(key) => ValidationError::new(format!("{}.{}", path, key), message),
+                None => ValidationError::new(path, message),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn labels() {
+        const CONFIG: &str = r#"
+        labels = { team = "payments", "example.com/owner" = "alice" }
+
+        [[files]]
+        kind = "stdin"
+
+        [[files]]
+        name = "API"
+        kind = "listen"
+        labels = { route = "public" }
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.labels["example.com/owner"], "alice");
+        assert!(cfg.files[0].labels().is_empty());
+        assert_eq!(cfg.files[1].labels()["route"], "public");
+        assert_eq!(cfg.files[1].as_listen().unwrap().labels["route"], "public");
+
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), cfg);
+
+        let validate = |config: &str| {
+            toml::from_str::<EnarxConfig>(config)
+                .unwrap()
+                .validate()
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            validate("labels = { \"-team\" = \"x\" }"),
+            "labels.-team: keys must start with a letter or digit"
+        );
+        assert_eq!(
+            validate("[[files]]\nkind = \"null\"\nlabels = { \"a b\" = \"x\" }"),
+            "files[0].labels.a b: invalid character ` `, keys must only contain letters, digits, `-`, `_`, `.` and `/`"
+        );
+        assert_eq!(
+            validate(&format!("labels = {{ a = \"{}\" }}", "x".repeat(256))),
+            "labels.a: value of 256 bytes exceeds the limit of 255"
+        );
+
+        let cfg = EnarxConfig {
+            labels: (0..65)
+                .map(|i| (format!("l{}", i), String::new()))
+                .collect(),
+            ..Default::default()
+        };
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "labels: 65 labels exceed the limit of 64"
+        );
+    }
+}
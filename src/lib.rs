@@ -8,17 +8,203 @@
 #![deny(clippy::all)]
 #![warn(rust_2018_idioms)]
 
-use std::{collections::HashMap, ops::Deref};
+// renamed, so the `clap` feature can enable the features it needs
+#[cfg(feature = "clap")]
+extern crate dep_clap as clap;
 
-use serde::{de::Error as _, Deserialize, Deserializer};
-use url::Url;
+mod anonymize;
+mod app;
+mod args;
+mod attestation;
+#[cfg(any(feature = "cbor", feature = "postcard"))]
+mod binary;
+mod borrowed;
+#[cfg(feature = "std")]
+mod bundle;
+mod canonical;
+mod claims;
+#[cfg(feature = "clap")]
+pub mod cli;
+mod compat;
+mod conditional;
+mod config_ref;
+mod content;
+#[cfg(feature = "convert")]
+pub mod convert;
+mod crypto;
+mod custom;
+mod de;
+mod debug;
+mod defaults;
+mod digest;
+#[cfg(all(feature = "toml", feature = "std"))]
+mod discover;
+mod duration;
+#[cfg(feature = "edit")]
+pub mod edit;
+mod env;
+mod error;
+mod explain;
+#[cfg(feature = "fetch")]
+pub mod fetch;
+mod file_template;
+mod health;
+mod http;
+mod identity;
+#[cfg(feature = "toml")]
+mod input;
+mod invocation;
+mod kv;
+mod labels;
+mod lifecycle;
+mod limits;
+#[cfg(feature = "edit")]
+mod lossy;
+mod merge;
+mod net;
+mod network;
+mod output;
+mod overrides;
+mod policy;
+mod ports;
+mod presets;
+mod proxy;
+mod random;
+mod reference;
+mod report;
+#[cfg(feature = "runtime")]
+pub mod runtime;
+mod secret;
+mod service;
+#[cfg(feature = "signature")]
+pub mod signature;
+mod signer;
+mod size;
+mod socket;
+#[cfg(feature = "toml")]
+mod span;
+mod spec;
+mod stdio;
+mod steward;
+mod summary;
+mod telemetry;
+mod template;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod time;
+mod timestamp;
+mod tls;
+mod trust;
+mod validate;
+mod view;
+mod warning;
+mod wasm;
 
-const fn default_port() -> u16 {
-    443
+pub use app::App;
+pub use attestation::{Attestation, AttestationMode, Collateral};
+pub use borrowed::EnarxConfigRef;
+#[cfg(feature = "std")]
+pub use bundle::{Bundle, BundleError, LOCK_FILE_NAME};
+pub use compat::{Feature, Incompatibility, Requirement, RuntimeCapabilities};
+pub use conditional::{Backend, Conditional, When};
+pub use config_ref::{ConfigRef, ParseConfigRefError, RefResolver, ResolveError};
+pub use content::Base64;
+pub use crypto::{Crypto, KeyType};
+pub use custom::{CustomKind, CustomKinds};
+pub use debug::DebugOptions;
+pub use defaults::{
+    Defaults, DefaultsProvider, DEFAULT_ADDR, DEFAULT_BACKLOG, DEFAULT_PORT, DEFAULT_PROTOCOL,
+    IMPLICIT_FILES,
+};
+pub use digest::{Digest, ParseDigestError};
+#[cfg(all(feature = "toml", feature = "std"))]
+pub use discover::{CONFIG_ENV, CONFIG_FILE_NAME};
+pub use duration::{HumanDuration, ParseDurationError};
+pub use env::Runtime;
+pub use error::{Error, Location};
+pub use explain::{AppliedLayer, Explain, ExplainedValue, Origin};
+pub use health::{Health, HealthProtocol};
+pub use http::{HttpMethod, HttpRoute, ListenMode, UrlPattern};
+pub use identity::{Identity, SpiffeId};
+#[cfg(feature = "toml")]
+pub use input::ParseLimits;
+pub use kv::{KvBackend, KvStore};
+pub use lifecycle::{
+    LifecyclePolicy, ParseSignalError, Restart, RestartPolicy, Signal, SignalAction,
+};
+pub use limits::Limits;
+#[cfg(feature = "edit")]
+pub use lossy::{Diagnostic, PartialConfig, Severity};
+pub use merge::Merge;
+pub use net::{
+    AddressFamily, Cidr, Endpoint, Host, HostPattern, InvalidAddress, ListenAddr, Port, PortRange,
+};
+pub use network::{Dns, EgressPolicy, Nameserver, Network, Resolver};
+pub use output::{OutputLimit, Overflow};
+pub use overrides::OVERRIDE_PREFIX;
+pub use policy::{PolicyProfile, PolicyViolation};
+pub use proxy::{Proxy, ProxyKind};
+pub use random::{EntropySource, Random};
+pub use reference::SchemaField;
+pub use report::UsageReport;
+pub use secret::{Secret, SecretString};
+pub use service::Service;
+pub use signer::{KeyAlgorithm, KeyMaterial, SignedArtifact, Signer, TrustedSigners};
+pub use size::{ByteSize, ParseSizeError};
+pub use socket::{KeepAlive, Priority};
+pub use stdio::StdioPolicy;
+pub use steward::{Csr, Steward};
+pub use summary::Summary;
+pub use telemetry::{Exporter, Telemetry};
+pub use time::{Clock, Time, TimeSource, TimeSourceKind};
+pub use timestamp::{ParseTimestampError, Timestamp};
+pub use tls::{
+    ClientAuth, CrlSource, KeyExchange, OcspMode, PemSource, Psk, Revocation, RotationPolicy,
+    TlsOptions, TlsVersion,
+};
+pub use trust::{BuiltinRoot, TrustAnchor};
+pub use validate::ValidationError;
+pub use view::{ConnectView, EndpointRef, ListenView};
+pub use warning::Warning;
+pub use wasm::{
+    CryptoAlgorithm, HttpRule, NnBackend, NnModel, NnTarget, WasiCrypto, WasiHttp, WasiNn, Wasm,
+};
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    num::{NonZeroU32, NonZeroU64},
+    ops::Deref,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+const fn default_port() -> Port {
+    DEFAULT_PORT
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+const fn default_backlog() -> u32 {
+    DEFAULT_BACKLOG
 }
 
-fn default_addr() -> String {
-    "::".into()
+fn is_default_backlog(backlog: &u32) -> bool {
+    *backlog == DEFAULT_BACKLOG
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -47,6 +233,15 @@ impl Deref for FileName {
     }
 }
 
+impl Serialize for FileName {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
 impl<'de> Deserialize<'de> for FileName {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -81,72 +276,380 @@ impl<'de> Deserialize<'de> for FileName {
 ///
 /// let config: EnarxConfig = toml::from_str(CONFIG).unwrap();
 /// ```
-#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct EnarxConfig {
-    /// The environment variables to provide to the application
-    #[serde(default)]
-    pub env: HashMap<String, String>,
+    /// The environment variables to provide to the application, sorted by name
+    ///
+    /// Integer, float and boolean values are converted to strings.
+    #[serde(default, deserialize_with = "env::deserialize_env")]
+    pub env: BTreeMap<String, String>,
+
+    /// Patterns of the environment variables the application may read, like `"APP_*"`
+    ///
+    /// Without it, all variables are provided, see [`EnarxConfig::runtime_env`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_exports: Option<Vec<String>>,
 
     /// The arguments to provide to the application
-    #[serde(default)]
+    ///
+    /// Written as an array of strings, or as a command line split with the quoting rules of a shell.
+    #[serde(default, deserialize_with = "args::deserialize_args")]
     pub args: Vec<String>,
 
     /// The array of pre-opened file descriptors
-    #[serde(default)]
+    ///
+    /// Written as an array of tables `[[files]]`, or as a table of tables `[files.NAME]`.
+    #[serde(default, deserialize_with = "de::deserialize_files")]
     pub files: Vec<File>,
 
-    /// An optional Steward URL
-    #[serde(default)]
-    pub steward: Option<Url>,
+    /// What happens to the standard streams a non-empty `files` does not declare
+    ///
+    /// Without a policy, only the declared ones are opened, see [`EnarxConfig::stdio_files`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdio: Option<StdioPolicy>,
+
+    /// Partial `files` entries, by name, the `files` can be based on
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, Map<String, Value>>,
+
+    /// An optional Steward and the certificate request sent to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub steward: Option<Steward>,
+
+    /// Allow a plaintext `http://` Steward, only for local development
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub steward_insecure: bool,
+
+    /// The time before which the configuration must not be used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<Timestamp>,
+
+    /// The time after which the configuration must not be used anymore
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<Timestamp>,
+
+    /// The attestation policy of the keep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<Attestation>,
+
+    /// The identity of the workload
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<Identity>,
+
+    /// The signers trusted for referenced artifacts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<TrustedSigners>,
+
+    /// Named secrets referenced by other sections
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub secrets: BTreeMap<String, Secret>,
+
+    /// Named trust anchors referenced by other sections
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub trust: BTreeMap<String, TrustAnchor>,
+
+    /// TLS settings of all `prot = "tls"` files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsOptions>,
+
+    /// The policy of the keys generated by the keep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crypto: Option<Crypto>,
+
+    /// An optional proxy for all outbound connections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<Proxy>,
+
+    /// The network policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<Network>,
+
+    /// Resource limits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<Limits>,
+
+    /// An optional metrics endpoint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<Telemetry>,
+
+    /// Debugging facilities, only for development
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug: Option<DebugOptions>,
+
+    /// An optional liveness check of the keep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<Health>,
+
+    /// How the keep shuts down
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lifecycle: Option<LifecyclePolicy>,
+
+    /// Optional periodic resource usage reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<UsageReport>,
+
+    /// The clock policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<Time>,
+
+    /// The entropy policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub random: Option<Random>,
+
+    /// Conveniences for the environment of the application
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<Runtime>,
+
+    /// The WASI proposals exposed to the application
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasm: Option<Wasm>,
+
+    /// The key-value stores provided to the application
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kv: Vec<KvStore>,
+
+    /// Settings shared by groups of files
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub services: Vec<Service>,
+
+    /// Free-form labels of the configuration, e.g. for routing or ownership
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+
+    /// The applications of a multi-application configuration, by name
+    ///
+    /// The top-level settings are shared by all applications, see [`EnarxConfig::app`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub apps: BTreeMap<String, App>,
+
+    /// The `[[conditional]]` sections, settings only applying to some keeps
+    ///
+    /// Evaluated, like the `when` of the `files`, by [`EnarxConfig::for_target`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditional: Vec<Conditional>,
+}
+
+impl EnarxConfig {
+    /// get a human-readable summary of the configuration
+    ///
+    /// The returned [`Summary`] implements [`Display`](fmt::Display) and renders
+    /// a compact table of the file descriptors, suitable for logs and `inspect` commands.
+    pub fn summary(&self) -> Summary<'_> {
+        Summary::new(self)
+    }
+
+    /// get the file named `name`, following `kind = "alias"` files to the file they refer to
+    ///
+    /// Returns `None` for unknown names and cyclic aliases.
+    pub fn resolve(&self, name: &str) -> Option<&File> {
+        let mut name = name;
+        for _ in 0..self.files.len() {
+            match self.files.iter().find(|file| file.name() == name)? {
+                File::Alias { alias_of, .. } => name = alias_of,
+                file => return Some(file),
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for EnarxConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.summary().fmt(f)
+    }
 }
 
 impl Default for EnarxConfig {
     fn default() -> Self {
         let files = vec![
-            File::Stdin { name: None },
-            File::Stdout { name: None },
-            File::Stderr { name: None },
+            File::Stdin {
+                name: None,
+                access: None,
+                path: None,
+                content: None,
+                content_base64: None,
+                labels: BTreeMap::new(),
+            },
+            File::Stdout {
+                name: None,
+                access: None,
+                path: None,
+                mode: None,
+                max_bytes: None,
+                on_overflow: None,
+                labels: BTreeMap::new(),
+            },
+            File::Stderr {
+                name: None,
+                access: None,
+                path: None,
+                mode: None,
+                max_bytes: None,
+                on_overflow: None,
+                labels: BTreeMap::new(),
+            },
         ];
 
         Self {
-            env: HashMap::new(),
+            env: BTreeMap::new(),
+            env_exports: None,
             args: vec![],
             files,
-            steward: None, // TODO: Default to a deployed Steward instance
+            stdio: None,
+            steward: None, // see `EnarxConfig::with_defaults` for a default Steward
+            steward_insecure: false,
+            not_before: None,
+            not_after: None,
+            attestation: None,
+            identity: None,
+            signature: None,
+            secrets: BTreeMap::new(),
+            trust: BTreeMap::new(),
+            tls: None,
+            crypto: None,
+            proxy: None,
+            network: None,
+            limits: None,
+            telemetry: None,
+            debug: None,
+            health: None,
+            lifecycle: None,
+            report: None,
+            time: None,
+            random: None,
+            runtime: None,
+            wasm: None,
+            kv: vec![],
+            services: vec![],
+            labels: BTreeMap::new(),
+            apps: BTreeMap::new(),
+            templates: BTreeMap::new(),
+            conditional: vec![],
         }
     }
 }
 
 /// Parameters for a pre-opened file descriptor
-#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
-#[serde(tag = "kind")]
+///
+/// The `kind` tags the entries, added and selected by hand in the `Serialize` and
+/// `Deserialize` implementations of the `de` module.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(remote = "Self")]
 pub enum File {
     /// file descriptor to `/dev/null`
     #[serde(rename = "null")]
     Null {
         /// name of the file descriptor
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         name: Option<FileName>,
+
+        /// access mode of the file descriptor
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        access: Option<Access>,
+
+        /// free-form labels of the file, e.g. for routing or ownership
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        labels: BTreeMap<String, String>,
     },
 
     /// file descriptor to stdin
     #[serde(rename = "stdin")]
     Stdin {
         /// name of the file descriptor
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         name: Option<FileName>,
+
+        /// access mode of the file descriptor
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        access: Option<Access>,
+
+        /// host file to read from instead of the stdin of the keep
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<PathBuf>,
+
+        /// inline content to read
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content: Option<String>,
+
+        /// inline binary content to read, written as base64
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content_base64: Option<Base64>,
+
+        /// free-form labels of the file, e.g. for routing or ownership
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        labels: BTreeMap<String, String>,
     },
 
     /// file descriptor to stdout
     #[serde(rename = "stdout")]
     Stdout {
         /// name of the file descriptor
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         name: Option<FileName>,
+
+        /// access mode of the file descriptor
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        access: Option<Access>,
+
+        /// host file to write to instead of the stdout of the keep
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<PathBuf>,
+
+        /// how an existing `path` is opened
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<WriteMode>,
+
+        /// maximum number of bytes captured
+        #[serde(
+            default,
+            with = "crate::size::option",
+            skip_serializing_if = "Option::is_none"
+        )]
+        max_bytes: Option<NonZeroU64>,
+
+        /// what happens when `max_bytes` is exceeded
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        on_overflow: Option<Overflow>,
+
+        /// free-form labels of the file, e.g. for routing or ownership
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        labels: BTreeMap<String, String>,
     },
 
     /// file descriptor to stderr
     #[serde(rename = "stderr")]
     Stderr {
         /// name of the file descriptor
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         name: Option<FileName>,
+
+        /// access mode of the file descriptor
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        access: Option<Access>,
+
+        /// host file to write to instead of the stderr of the keep
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<PathBuf>,
+
+        /// how an existing `path` is opened
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<WriteMode>,
+
+        /// maximum number of bytes captured
+        #[serde(
+            default,
+            with = "crate::size::option",
+            skip_serializing_if = "Option::is_none"
+        )]
+        max_bytes: Option<NonZeroU64>,
+
+        /// what happens when `max_bytes` is exceeded
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        on_overflow: Option<Overflow>,
+
+        /// free-form labels of the file, e.g. for routing or ownership
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        labels: BTreeMap<String, String>,
     },
 
     /// file descriptor to a TCP listen socket
@@ -156,34 +659,311 @@ pub enum File {
         name: FileName,
 
         /// address to listen on
-        #[serde(default = "default_addr")]
-        addr: String,
+        #[serde(default)]
+        addr: ListenAddr,
 
         /// port to listen on
         #[serde(default = "default_port")]
-        port: u16,
+        port: Port,
+
+        /// all ports to listen on, if more than `port`, expanded into one file per port
+        /// by [`EnarxConfig::normalize`]
+        ///
+        /// Written as `ports = "8000-8010"` or `port = [8080, 8443]`, `port` is the first one.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        ports: Vec<PortRange>,
+
+        /// listen on an ephemeral port chosen by the operating system
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        ephemeral: bool,
+
+        /// maximum length of the queue of pending connections
+        #[serde(
+            default = "default_backlog",
+            skip_serializing_if = "is_default_backlog"
+        )]
+        backlog: u32,
+
+        /// set `SO_REUSEADDR` on the socket
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        reuseaddr: bool,
+
+        /// set `SO_REUSEPORT` on the socket
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        reuseport: bool,
+
+        /// TCP keepalive settings of accepted connections
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        keepalive: Option<KeepAlive>,
+
+        /// disable Nagle's algorithm on accepted connections (`TCP_NODELAY`)
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        nodelay: bool,
+
+        /// traffic priority of accepted connections
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        priority: Option<Priority>,
+
+        /// time after which accepted connections without any traffic are closed
+        #[serde(
+            default,
+            with = "crate::duration::option",
+            skip_serializing_if = "Option::is_none"
+        )]
+        idle_timeout: Option<Duration>,
+
+        /// networks clients are allowed to connect from, all if empty
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        allow: Vec<Cidr>,
+
+        /// maximum number of connections accepted per second
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_connections_per_second: Option<NonZeroU32>,
+
+        /// maximum number of simultaneously open accepted connections
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_concurrent_connections: Option<NonZeroU32>,
+
+        /// verify that the socket can be bound and, with `prot = "tls"`, that a TLS
+        /// handshake succeeds, before the workload is started
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        selftest: bool,
 
         /// protocol to use
         #[serde(default)]
         prot: Protocol,
+
+        /// TLS settings overriding the `[tls]` section, requires `prot = "tls"`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tls: Option<TlsOptions>,
+
+        /// certificate chain presented instead of the one issued by the Steward,
+        /// requires `key` and `prot = "tls"`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cert: Option<PemSource>,
+
+        /// private key of `cert`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        key: Option<PemSource>,
+
+        /// pre-shared key authenticating the clients instead of certificates
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        psk: Option<Psk>,
+
+        /// how accepted connections are handed to the application
+        #[serde(default, skip_serializing_if = "is_default")]
+        mode: ListenMode,
+
+        /// routes of requests to other file descriptors, requires an HTTP `mode`
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        routes: Vec<HttpRoute>,
+
+        /// name of the `[[services]]` entry the file inherits settings from
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        service: Option<String>,
+
+        /// free-form labels of the file, e.g. for routing or ownership
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        labels: BTreeMap<String, String>,
+    },
+
+    /// file descriptor to a named pipe (FIFO) of the host
+    #[serde(rename = "fifo")]
+    Fifo {
+        /// name of the file descriptor
+        name: FileName,
+
+        /// path of the FIFO on the host
+        path: PathBuf,
+
+        /// whether the application reads from or writes to the FIFO
+        direction: Direction,
+
+        /// free-form labels of the file, e.g. for routing or ownership
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        labels: BTreeMap<String, String>,
+    },
+
+    /// file descriptor to one end of a pipe inside the keep
+    ///
+    /// The other end is a second `kind = "pipe"` file with the same `pipe`
+    /// and the opposite `direction`.
+    #[serde(rename = "pipe")]
+    Pipe {
+        /// name of the file descriptor
+        name: FileName,
+
+        /// name of the pipe connecting both ends
+        pipe: FileName,
+
+        /// whether this end reads from or writes to the pipe
+        direction: Direction,
+
+        /// free-form labels of the file, e.g. for routing or ownership
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        labels: BTreeMap<String, String>,
+    },
+
+    /// another name of the file descriptor of a different file
+    ///
+    /// Written without `kind`, an entry with `alias_of` is an alias.
+    #[serde(rename = "alias")]
+    Alias {
+        /// name of the file descriptor
+        name: FileName,
+
+        /// name of the file this is an alias of
+        alias_of: FileName,
+
+        /// free-form labels of the file, e.g. for routing or ownership
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        labels: BTreeMap<String, String>,
     },
 
     /// file descriptor to a TCP stream socket
     #[serde(rename = "connect")]
     Connect {
-        /// name of the file descriptor
+        /// name of the file descriptor, defaults to the normalized [`Host`]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         name: Option<FileName>,
 
         /// host address to connect to
-        host: String,
+        host: Host,
 
         /// port to connect to
         #[serde(default = "default_port")]
-        port: u16,
+        port: Port,
+
+        /// endpoints to try in order, if `host` and `port` can not be connected to
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        fallback: Vec<Endpoint>,
+
+        /// address family to resolve `host` with
+        #[serde(default, skip_serializing_if = "is_default")]
+        family: AddressFamily,
+
+        /// race IPv6 and IPv4 connection attempts (RFC 8305)
+        #[serde(default = "default_true", skip_serializing_if = "is_true")]
+        happy_eyeballs: bool,
+
+        /// tunnel the connection through the `[proxy]`, if configured
+        #[serde(default = "default_true", skip_serializing_if = "is_true")]
+        proxy: bool,
 
         /// protocol to use
         #[serde(default)]
         prot: Protocol,
+
+        /// TLS settings overriding the `[tls]` section, requires `prot = "tls"`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tls: Option<TlsOptions>,
+
+        /// pre-shared key authenticating the connection instead of certificates
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        psk: Option<Psk>,
+
+        /// present the attested identity of the keep as client certificate, even to
+        /// servers not requesting one
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        advertise_identity: bool,
+
+        /// TCP keepalive settings
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        keepalive: Option<KeepAlive>,
+
+        /// disable Nagle's algorithm (`TCP_NODELAY`)
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        nodelay: bool,
+
+        /// traffic priority of the connection
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        priority: Option<Priority>,
+
+        /// time after which the connection is closed without any traffic
+        #[serde(
+            default,
+            with = "crate::duration::option",
+            skip_serializing_if = "Option::is_none"
+        )]
+        idle_timeout: Option<Duration>,
+
+        /// timeout of a single connection attempt
+        #[serde(
+            default,
+            with = "crate::duration::option",
+            skip_serializing_if = "Option::is_none"
+        )]
+        timeout: Option<Duration>,
+
+        /// number of additional connection attempts after a failure
+        #[serde(default, skip_serializing_if = "is_default")]
+        retries: u32,
+
+        /// delay between connection attempts
+        #[serde(
+            default,
+            with = "crate::duration::option",
+            skip_serializing_if = "Option::is_none"
+        )]
+        retry_backoff: Option<Duration>,
+
+        /// name of the `[[services]]` entry the file inherits settings from
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        service: Option<String>,
+
+        /// free-form labels of the file, e.g. for routing or ownership
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        labels: BTreeMap<String, String>,
+    },
+
+    /// file descriptor of a kind defined by the runtime, not by this crate
+    ///
+    /// The `params` are kept as written, runtimes check them by registering the kind with
+    /// [`CustomKinds`] for [`EnarxConfig::validate_with_kinds`].
+    #[serde(rename = "custom")]
+    Custom {
+        /// name of the file descriptor
+        name: FileName,
+
+        /// the kind defined by the runtime, like `"vsock"`
+        #[serde(rename = "type")]
+        r#type: String,
+
+        /// the parameters of the kind
+        #[serde(default, skip_serializing_if = "Map::is_empty")]
+        params: Map<String, Value>,
+
+        /// free-form labels of the file, e.g. for routing or ownership
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        labels: BTreeMap<String, String>,
+    },
+
+    /// a file based on an entry of the `[templates]` section, resolved by
+    /// [`EnarxConfig::normalize`]
+    ///
+    /// Written without `kind`, an entry with `template` is based on the template
+    /// of that name. The other fields of the entry override the ones of the template.
+    #[serde(skip)]
+    Templated {
+        /// name of the template
+        template: String,
+
+        /// the fields overriding the ones of the template
+        fields: Map<String, Value>,
+
+        /// free-form labels of the file, overriding the ones of the template
+        labels: BTreeMap<String, String>,
+    },
+
+    /// a file only opened if its `when` condition holds, resolved by
+    /// [`EnarxConfig::for_target`]
+    #[serde(skip)]
+    Conditional {
+        /// the condition
+        when: When,
+
+        /// the file
+        file: Box<File>,
     },
 }
 
@@ -191,18 +971,84 @@ impl File {
     /// get the name for a file descriptor
     pub fn name(&self) -> &str {
         match self {
-            Self::Null { name } => name.as_deref().unwrap_or("null"),
-            Self::Stdin { name } => name.as_deref().unwrap_or("stdin"),
-            Self::Stdout { name } => name.as_deref().unwrap_or("stdout"),
-            Self::Stderr { name } => name.as_deref().unwrap_or("stderr"),
-            Self::Listen { name, .. } => name,
+            Self::Null { name, .. } => name.as_deref().unwrap_or("null"),
+            Self::Stdin { name, .. } => name.as_deref().unwrap_or("stdin"),
+            Self::Stdout { name, .. } => name.as_deref().unwrap_or("stdout"),
+            Self::Stderr { name, .. } => name.as_deref().unwrap_or("stderr"),
+            Self::Listen { name, .. }
+            | Self::Fifo { name, .. }
+            | Self::Pipe { name, .. }
+            | Self::Alias { name, .. }
+            | Self::Custom { name, .. } => name,
             Self::Connect { name, host, .. } => name.as_deref().unwrap_or(host),
+            Self::Templated {
+                template, fields, ..
+            } => fields
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or(template),
+            Self::Conditional { file, .. } => file.name(),
+        }
+    }
+
+    /// get the effective access mode of a file descriptor
+    ///
+    /// Defaults to read-only for `stdin`, write-only for `stdout` and `stderr`
+    /// and read-write for all other kinds. An alias has the access mode of the file
+    /// it resolves to, see [`EnarxConfig::resolve`].
+    pub fn access(&self) -> Access {
+        match self {
+            Self::Null { access, .. } => access.unwrap_or(Access::ReadWrite),
+            Self::Stdin { access, .. } => access.unwrap_or(Access::ReadOnly),
+            Self::Stdout { access, .. } | Self::Stderr { access, .. } => {
+                access.unwrap_or(Access::WriteOnly)
+            }
+            Self::Fifo { direction, .. } | Self::Pipe { direction, .. } => match direction {
+                Direction::Read => Access::ReadOnly,
+                Direction::Write => Access::WriteOnly,
+            },
+            Self::Listen { .. }
+            | Self::Connect { .. }
+            | Self::Alias { .. }
+            | Self::Custom { .. }
+            | Self::Templated { .. } => Access::ReadWrite,
+            Self::Conditional { file, .. } => file.access(),
+        }
+    }
+
+    /// get the host file a stdio file descriptor is redirected to, or the path of a FIFO
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Stdin { path, .. } | Self::Stdout { path, .. } | Self::Stderr { path, .. } => {
+                path.as_deref()
+            }
+            Self::Fifo { path, .. } => Some(path),
+            Self::Conditional { file, .. } => file.path(),
+            _ => None,
+        }
+    }
+
+    /// get the `kind` of a file descriptor as written in the configuration
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Null { .. } => "null",
+            Self::Stdin { .. } => "stdin",
+            Self::Stdout { .. } => "stdout",
+            Self::Stderr { .. } => "stderr",
+            Self::Listen { .. } => "listen",
+            Self::Fifo { .. } => "fifo",
+            Self::Pipe { .. } => "pipe",
+            Self::Alias { .. } => "alias",
+            Self::Connect { .. } => "connect",
+            Self::Custom { .. } => "custom",
+            Self::Templated { .. } => "template",
+            Self::Conditional { file, .. } => file.kind(),
         }
     }
 }
 
 /// Protocol to use for a connection
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Protocol {
     /// transparently wrap the TCP connection with the TLS protocol
     #[serde(rename = "tls")]
@@ -213,9 +1059,112 @@ pub enum Protocol {
     Tcp,
 }
 
+/// How an existing host file of a `stdout` or `stderr` `path` is opened
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum WriteMode {
+    /// append to the existing content
+    #[serde(rename = "append")]
+    Append,
+
+    /// discard the existing content
+    #[serde(rename = "truncate")]
+    Truncate,
+}
+
+// `#[default]` on enum variants is not available on the MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for WriteMode {
+    fn default() -> Self {
+        WriteMode::Truncate
+    }
+}
+
+/// Direction of the data of a `kind = "fifo"` or `kind = "pipe"` file
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Direction {
+    /// the application reads from the FIFO or pipe
+    #[serde(rename = "read")]
+    Read,
+
+    /// the application writes to the FIFO or pipe
+    #[serde(rename = "write")]
+    Write,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read => f.write_str("read"),
+            Self::Write => f.write_str("write"),
+        }
+    }
+}
+
+/// Access mode of a file descriptor
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Access {
+    /// read-only
+    #[serde(rename = "ro")]
+    ReadOnly,
+
+    /// write-only
+    #[serde(rename = "wo")]
+    WriteOnly,
+
+    /// read-write
+    #[serde(rename = "rw")]
+    ReadWrite,
+}
+
+impl Access {
+    /// can the file descriptor be read from
+    pub fn is_readable(self) -> bool {
+        matches!(self, Self::ReadOnly | Self::ReadWrite)
+    }
+
+    /// can the file descriptor be written to
+    pub fn is_writable(self) -> bool {
+        matches!(self, Self::WriteOnly | Self::ReadWrite)
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReadOnly => f.write_str("ro"),
+            Self::WriteOnly => f.write_str("wo"),
+            Self::ReadWrite => f.write_str("rw"),
+        }
+    }
+}
+
 impl Default for Protocol {
     fn default() -> Self {
-        Self::Tls
+        DEFAULT_PROTOCOL
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tls => f.write_str("tls"),
+            Self::Tcp => f.write_str("tcp"),
+        }
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tls" => Ok(Self::Tls),
+            "tcp" => Ok(Self::Tcp),
+            _ => Err(Error::parse(format!(
+                "invalid protocol `{}`, expected `tls` or `tcp`",
+                s
+            ))),
+        }
     }
 }
 
@@ -254,21 +1203,85 @@ mod test {
         assert_eq!(
             cfg.files,
             vec![
-                File::Stdin { name: None },
+                File::Stdin {
+                    name: None,
+                    access: None,
+                    path: None,
+                    content: None,
+                    content_base64: None,
+                    labels: BTreeMap::new(),
+                },
                 File::Listen {
                     name: "X".into(),
-                    port: 9000,
+                    port: 9000.into(),
+                    ports: vec![],
+                    ephemeral: false,
+                    backlog: default_backlog(),
+                    reuseaddr: false,
+                    reuseport: false,
+                    keepalive: None,
+                    nodelay: false,
+                    priority: None,
+                    idle_timeout: None,
+                    allow: vec![],
+                    max_connections_per_second: None,
+                    max_concurrent_connections: None,
+                    selftest: false,
                     prot: Protocol::Tcp,
-                    addr: default_addr()
+                    tls: None,
+                    cert: None,
+                    key: None,
+                    psk: None,
+                    mode: ListenMode::Raw,
+                    routes: vec![],
+                    service: None,
+                    labels: BTreeMap::new(),
+                    addr: ListenAddr::Any
+                },
+                File::Stdout {
+                    name: None,
+                    access: None,
+                    path: None,
+                    mode: None,
+                    max_bytes: None,
+                    on_overflow: None,
+                    labels: BTreeMap::new(),
+                },
+                File::Null {
+                    name: None,
+                    access: None,
+                    labels: BTreeMap::new(),
+                },
+                File::Stderr {
+                    name: None,
+                    access: None,
+                    path: None,
+                    mode: None,
+                    max_bytes: None,
+                    on_overflow: None,
+                    labels: BTreeMap::new(),
                 },
-                File::Stdout { name: None },
-                File::Null { name: None },
-                File::Stderr { name: None },
                 File::Connect {
                     name: None,
                     port: default_port(),
                     prot: Protocol::Tls,
-                    host: "example.com".into(),
+                    tls: None,
+                    psk: None,
+                    advertise_identity: false,
+                    host: "example.com".parse().unwrap(),
+                    fallback: vec![],
+                    family: AddressFamily::Any,
+                    happy_eyeballs: true,
+                    proxy: true,
+                    keepalive: None,
+                    nodelay: false,
+                    priority: None,
+                    idle_timeout: None,
+                    timeout: None,
+                    retries: 0,
+                    retry_backoff: None,
+                    service: None,
+                    labels: BTreeMap::new(),
                 },
             ]
         );
@@ -284,6 +1297,150 @@ mod test {
         );
     }
 
+    #[test]
+    fn connect_default_name() {
+        const CONFIG: &str = r#"
+        [[files]]
+        kind = "connect"
+        host = "DB.Example.com"
+        port = 5432
+
+        [[files]]
+        kind = "connect"
+        host = "bücher.example"
+        port = 443
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(
+            vec!["db.example.com", "xn--bcher-kva.example"],
+            cfg.files.iter().map(|f| f.name()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn env_order() {
+        let cfg: EnarxConfig =
+            toml::from_str("env = { \"B\" = \"b\", \"C\" = \"c\", \"A\" = \"a\" }").unwrap();
+
+        assert_eq!(cfg.env.keys().collect::<Vec<_>>(), ["A", "B", "C"]);
+        assert_eq!(
+            serde_json::to_string(&cfg.env).unwrap(),
+            r#"{"A":"a","B":"b","C":"c"}"#
+        );
+    }
+
+    #[test]
+    fn fifo() {
+        const CONFIG: &str = r#"
+        [[files]]
+        name = "EVENTS"
+        kind = "fifo"
+        path = "/run/app/events"
+        direction = "read"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let file = &cfg.files[0];
+        assert_eq!(file.kind(), "fifo");
+        assert_eq!(file.access(), Access::ReadOnly);
+        assert_eq!(file.path(), Some(Path::new("/run/app/events")));
+
+        let cfg: EnarxConfig = toml::from_str(&CONFIG.replace("read", "write")).unwrap();
+        assert_eq!(cfg.files[0].access(), Access::WriteOnly);
+
+        let cfg: EnarxConfig = toml::from_str(&CONFIG.replace("/run/app/events", "")).unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "files[0].path: empty path"
+        );
+
+        assert!(toml::from_str::<EnarxConfig>(&CONFIG.replace("name = \"EVENTS\"", "")).is_err());
+    }
+
+    #[test]
+    fn pipe() {
+        const CONFIG: &str = r#"
+        [[files]]
+        name = "JOBS_OUT"
+        kind = "pipe"
+        pipe = "jobs"
+        direction = "write"
+
+        [[files]]
+        name = "JOBS_IN"
+        kind = "pipe"
+        pipe = "jobs"
+        direction = "read"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.files[0].kind(), "pipe");
+        assert_eq!(cfg.files[0].access(), Access::WriteOnly);
+        assert_eq!(cfg.files[1].access(), Access::ReadOnly);
+
+        let cfg: EnarxConfig = toml::from_str(&CONFIG.replace("\"write\"", "\"read\"")).unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "files[1].direction: duplicate `read` end of pipe `jobs`"
+        );
+
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        cfg.files.pop();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "files[0].pipe: pipe `jobs` has no `read` end"
+        );
+    }
+
+    #[test]
+    fn alias() {
+        const CONFIG: &str = r#"
+        [[files]]
+        kind = "stderr"
+
+        [[files]]
+        name = "LOG"
+        alias_of = "stderr"
+
+        [[files]]
+        name = "AUDIT"
+        kind = "alias"
+        alias_of = "LOG"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.files[1].kind(), "alias");
+        assert_eq!(
+            cfg.fd_names().collect::<Vec<_>>(),
+            ["stderr", "LOG", "AUDIT"]
+        );
+        assert_eq!(cfg.resolve("AUDIT"), Some(&cfg.files[0]));
+        assert_eq!(cfg.resolve("AUDIT").unwrap().access(), Access::WriteOnly);
+        assert_eq!(cfg.resolve("missing"), None);
+
+        let cfg: EnarxConfig =
+            toml::from_str(&CONFIG.replace("alias_of = \"stderr\"", "alias_of = \"nope\""))
+                .unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "files[1].alias_of: unknown file `nope`"
+        );
+
+        let cfg: EnarxConfig =
+            toml::from_str(&CONFIG.replace("alias_of = \"stderr\"", "alias_of = \"AUDIT\""))
+                .unwrap();
+        assert_eq!(cfg.resolve("LOG"), None);
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "files[1].alias_of: alias cycle through `AUDIT`"
+        );
+    }
+
     #[test]
     fn invalid_name() {
         const CONFIG: &str = r#"
@@ -0,0 +1,461 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shutdown and restart behavior of the `[lifecycle]` section
+
+use std::{collections::BTreeMap, fmt, str::FromStr, time::Duration};
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A signal the keep can receive from its orchestrator
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Signal {
+    /// `SIGTERM`
+    Term,
+
+    /// `SIGINT`
+    Int,
+
+    /// `SIGQUIT`
+    Quit,
+
+    /// `SIGHUP`
+    Hup,
+
+    /// `SIGUSR1`
+    Usr1,
+
+    /// `SIGUSR2`
+    Usr2,
+}
+
+impl Signal {
+    /// all signals, in the order of their declaration
+    pub const ALL: [Signal; 6] = [
+        Self::Term,
+        Self::Int,
+        Self::Quit,
+        Self::Hup,
+        Self::Usr1,
+        Self::Usr2,
+    ];
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Term => "SIGTERM",
+            Self::Int => "SIGINT",
+            Self::Quit => "SIGQUIT",
+            Self::Hup => "SIGHUP",
+            Self::Usr1 => "SIGUSR1",
+            Self::Usr2 => "SIGUSR2",
+        })
+    }
+}
+
+/// Error parsing a [`Signal`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseSignalError(String);
+
+impl fmt::Display for ParseSignalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown signal `{}`, expected one of SIGTERM, SIGINT, SIGQUIT, SIGHUP, SIGUSR1 or SIGUSR2",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseSignalError {}
+
+impl FromStr for Signal {
+    type Err = ParseSignalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|signal| signal.to_string() == s)
+            .ok_or_else(|| ParseSignalError(s.into()))
+    }
+}
+
+impl Serialize for Signal {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Signal {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let signal = String::deserialize(deserializer)?;
+        signal.parse().map_err(D::Error::custom)
+    }
+}
+
+/// What the keep does on a [`Signal`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SignalAction {
+    /// shut down gracefully, draining the listeners and waiting up to the `shutdown_grace`
+    #[serde(rename = "graceful")]
+    Graceful,
+
+    /// exit immediately, closing all connections
+    #[serde(rename = "immediate")]
+    Immediate,
+
+    /// keep running
+    #[serde(rename = "ignore")]
+    Ignore,
+}
+
+impl fmt::Display for SignalAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Graceful => "graceful",
+            Self::Immediate => "immediate",
+            Self::Ignore => "ignore",
+        })
+    }
+}
+
+/// When the keep is restarted after the application exited, the `policy` of a [`RestartPolicy`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Restart {
+    /// never
+    #[serde(rename = "never")]
+    Never,
+
+    /// after the application exited with a non-zero status or trapped
+    #[serde(rename = "on-failure")]
+    OnFailure,
+
+    /// after every exit
+    #[serde(rename = "always")]
+    Always,
+}
+
+// `#[default]` on enum variants is not available on the MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for Restart {
+    fn default() -> Self {
+        Restart::Never
+    }
+}
+
+impl fmt::Display for Restart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Never => "never",
+            Self::OnFailure => "on-failure",
+            Self::Always => "always",
+        })
+    }
+}
+
+/// The `[lifecycle.restart]` sub-table, declaring how supervisors restart the keep
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RestartPolicy {
+    /// when the keep is restarted
+    #[serde(default)]
+    pub policy: Restart,
+
+    /// maximum number of consecutive restarts, unlimited if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+
+    /// delay before the first restart, doubled for every following one
+    #[serde(
+        default,
+        with = "crate::duration::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub backoff: Option<Duration>,
+
+    /// upper bound of the doubled `backoff`
+    #[serde(
+        default,
+        with = "crate::duration::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_backoff: Option<Duration>,
+}
+
+impl RestartPolicy {
+    /// should the keep be restarted after an exit, `failed` if the application exited with a
+    /// non-zero status or trapped
+    pub fn restarts(&self, failed: bool) -> bool {
+        match self.policy {
+            Restart::Never => false,
+            Restart::OnFailure => failed,
+            Restart::Always => true,
+        }
+    }
+
+    /// get the delay before the restart following `retries` consecutive restarts,
+    /// `None` if `max_retries` is exhausted
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use enarx_config::{Restart, RestartPolicy};
+    ///
+    /// let restart = RestartPolicy {
+    ///     policy: Restart::OnFailure,
+    ///     max_retries: Some(5),
+    ///     backoff: Some(Duration::from_secs(1)),
+    ///     max_backoff: Some(Duration::from_secs(5)),
+    /// };
+    /// assert_eq!(restart.delay(0), Some(Duration::from_secs(1)));
+    /// assert_eq!(restart.delay(2), Some(Duration::from_secs(4)));
+    /// assert_eq!(restart.delay(3), Some(Duration::from_secs(5)));
+    /// assert_eq!(restart.delay(5), None);
+    /// ```
+    pub fn delay(&self, retries: u32) -> Option<Duration> {
+        if matches!(self.max_retries, Some(max) if retries >= max) {
+            return None;
+        }
+
+        let backoff = self.backoff.unwrap_or_default();
+        let delay = match 1u32.checked_shl(retries) {
+            Some(factor) => backoff.checked_mul(factor),
+            None if backoff.is_zero() => Some(Duration::ZERO),
+            None => None,
+        }
+        .unwrap_or(Duration::MAX);
+        Some(match self.max_backoff {
+            Some(max) => delay.min(max),
+            None => delay,
+        })
+    }
+
+    fn validate(&self) -> Result<(), (&'static str, String)> {
+        if self.policy == Restart::Never {
+            for (field, set) in [
+                ("max_retries", self.max_retries.is_some()),
+                ("backoff", self.backoff.is_some()),
+                ("max_backoff", self.max_backoff.is_some()),
+            ] {
+                if set {
+                    return Err((field, "requires a `policy` restarting the keep".into()));
+                }
+            }
+        }
+        if self.backoff == Some(Duration::ZERO) {
+            return Err(("backoff", "must not be zero".into()));
+        }
+        match (self.backoff, self.max_backoff) {
+            (None, Some(_)) => Err(("max_backoff", "requires `backoff`".into())),
+            (Some(backoff), Some(max)) if max < backoff => {
+                Err(("max_backoff", "must not be less than `backoff`".into()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn default_drain_listeners() -> bool {
+    true
+}
+
+/// The `[lifecycle]` section, declaring how the keep winds down
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LifecyclePolicy {
+    /// time open connections get to finish on a graceful shutdown, before the keep exits,
+    /// unlimited if unset
+    #[serde(
+        default,
+        with = "crate::duration::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub shutdown_grace: Option<Duration>,
+
+    /// stop accepting connections on the listeners as soon as a graceful shutdown starts
+    #[serde(default = "default_drain_listeners")]
+    pub drain_listeners: bool,
+
+    /// the action of a signal, overriding the default of [`LifecyclePolicy::action`]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub signals: BTreeMap<Signal, SignalAction>,
+
+    /// how the keep is restarted after the application exited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart: Option<RestartPolicy>,
+}
+
+impl Default for LifecyclePolicy {
+    fn default() -> Self {
+        Self {
+            shutdown_grace: None,
+            drain_listeners: default_drain_listeners(),
+            signals: BTreeMap::new(),
+            restart: None,
+        }
+    }
+}
+
+impl LifecyclePolicy {
+    /// get the action of `signal`
+    ///
+    /// Without an entry in `signals`, `SIGTERM` and `SIGINT` shut down gracefully, `SIGQUIT`
+    /// exits immediately and all other signals are ignored.
+    pub fn action(&self, signal: Signal) -> SignalAction {
+        match self.signals.get(&signal) {
+            Some(action) => *action,
+            None => match signal {
+                Signal::Term | Signal::Int => SignalAction::Graceful,
+                Signal::Quit => SignalAction::Immediate,
+                _ => SignalAction::Ignore,
+            },
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), (&'static str, String)> {
+        if self.shutdown_grace == Some(Duration::ZERO) {
+            return Err(("shutdown_grace", "must not be zero".into()));
+        }
+        if Signal::ALL
+            .iter()
+            .all(|signal| self.action(*signal) == SignalAction::Ignore)
+        {
+            return Err(("signals", "at least one signal has to stop the keep".into()));
+        }
+        if let Some(restart) = &self.restart {
+            restart.validate().map_err(|(field, message)| match field {
+                "max_retries" => ("restart.max_retries", message),
+                "backoff" => ("restart.backoff", message),
+                _ => ("restart.max_backoff", message),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    #[test]
+    fn lifecycle() {
+        const CONFIG: &str = r#"
+        [lifecycle]
+        shutdown_grace = "30s"
+        drain_listeners = false
+        signals = { SIGHUP = "graceful", SIGINT = "ignore" }
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let lifecycle = cfg.lifecycle.as_ref().unwrap();
+        assert_eq!(lifecycle.shutdown_grace, Some(Duration::from_secs(30)));
+        assert!(!lifecycle.drain_listeners);
+        assert_eq!(lifecycle.action(Signal::Hup), SignalAction::Graceful);
+        assert_eq!(lifecycle.action(Signal::Int), SignalAction::Ignore);
+        assert_eq!(lifecycle.action(Signal::Term), SignalAction::Graceful);
+        assert_eq!(lifecycle.action(Signal::Quit), SignalAction::Immediate);
+        assert_eq!(lifecycle.action(Signal::Usr1), SignalAction::Ignore);
+
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), cfg);
+
+        let cfg: EnarxConfig = toml::from_str("[lifecycle]").unwrap();
+        assert_eq!(cfg.lifecycle, Some(LifecyclePolicy::default()));
+        assert!(cfg.lifecycle.unwrap().drain_listeners);
+    }
+
+    #[test]
+    fn restart() {
+        const CONFIG: &str = r#"
+        [lifecycle.restart]
+        policy = "on-failure"
+        max_retries = 3
+        backoff = "500ms"
+        max_backoff = "1s"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let restart = cfg.lifecycle.as_ref().unwrap().restart.unwrap();
+        assert_eq!(restart.policy, Restart::OnFailure);
+        assert!(restart.restarts(true));
+        assert!(!restart.restarts(false));
+        assert_eq!(
+            (0..4)
+                .map(|retries| restart.delay(retries))
+                .collect::<Vec<_>>(),
+            [
+                Some(Duration::from_millis(500)),
+                Some(Duration::from_secs(1)),
+                Some(Duration::from_secs(1)),
+                None
+            ]
+        );
+
+        let unbounded = RestartPolicy {
+            policy: Restart::Always,
+            backoff: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        assert!(unbounded.restarts(false));
+        assert_eq!(unbounded.delay(100), Some(Duration::MAX));
+        assert_eq!(RestartPolicy::default().delay(0), Some(Duration::ZERO));
+
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert!(json.contains(r#""policy":"on-failure""#));
+        assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), cfg);
+    }
+
+    #[test]
+    fn invalid() {
+        let validate = |config: &str| {
+            toml::from_str::<EnarxConfig>(config)
+                .map_err(|e| e.to_string())?
+                .validate()
+                .map_err(|e| e.to_string())
+        };
+
+        assert_eq!(
+            validate("[lifecycle]\nshutdown_grace = 0"),
+            Err("lifecycle.shutdown_grace: must not be zero".into())
+        );
+        assert_eq!(
+            validate("[lifecycle]\nsignals = { SIGTERM = \"ignore\", SIGINT = \"ignore\", SIGQUIT = \"ignore\" }"),
+            Err("lifecycle.signals: at least one signal has to stop the keep".into())
+        );
+        assert!(
+            validate("[lifecycle]\nsignals = { SIGKILL = \"graceful\" }")
+                .unwrap_err()
+                .contains("unknown signal `SIGKILL`")
+        );
+
+        assert_eq!(
+            validate("[lifecycle.restart]\nmax_retries = 3"),
+            Err("lifecycle.restart.max_retries: requires a `policy` restarting the keep".into())
+        );
+        assert_eq!(
+            validate("[lifecycle.restart]\npolicy = \"always\"\nbackoff = 0"),
+            Err("lifecycle.restart.backoff: must not be zero".into())
+        );
+        assert_eq!(
+            validate("[lifecycle.restart]\npolicy = \"always\"\nmax_backoff = \"1m\""),
+            Err("lifecycle.restart.max_backoff: requires `backoff`".into())
+        );
+        assert_eq!(
+            validate(
+                "[lifecycle.restart]\npolicy = \"always\"\nbackoff = \"1m\"\nmax_backoff = \"1s\""
+            ),
+            Err("lifecycle.restart.max_backoff: must not be less than `backoff`".into())
+        );
+        assert!(validate("[lifecycle.restart]\npolicy = \"sometimes\"").is_err());
+    }
+}
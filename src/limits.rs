@@ -0,0 +1,383 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resource limits of the `[limits]` section
+
+use std::{collections::BTreeMap, num::NonZeroU32};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{EnarxConfig, File, ValidationError};
+
+/// The `[limits]` section, guarding against configurations exhausting the resources of the keep
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Limits {
+    /// maximum number of pre-opened file descriptors, counted by [`EnarxConfig::fd_count`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fds: Option<NonZeroU32>,
+
+    /// maximum number of `args`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_args: Option<NonZeroU32>,
+
+    /// maximum length of a single argument in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_arg_len: Option<NonZeroU32>,
+
+    /// maximum number of environment variables, including the ones of the `[runtime]` section
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_env_vars: Option<NonZeroU32>,
+
+    /// maximum length of a single environment variable value in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_env_value_len: Option<NonZeroU32>,
+
+    /// maximum number of simultaneously open accepted connections of all listeners,
+    /// also the upper bound of `max_concurrent_connections` of a single listener
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<NonZeroU32>,
+}
+
+impl EnarxConfig {
+    /// get the number of file descriptors pre-opened for the application
+    ///
+    /// This is the number of entries of [`EnarxConfig::fd_layout`].
+    pub fn fd_count(&self) -> usize {
+        self.fd_layout().len()
+    }
+
+    /// get the names of the file descriptors pre-opened for the application
+    ///
+    /// These are the names of the entries of [`EnarxConfig::fd_layout`] in order.
+    pub fn fd_names(&self) -> impl Iterator<Item = String> {
+        self.fd_layout()
+            .into_values()
+            .map(|file| file.name().to_owned())
+    }
+
+    /// get the files owning the file descriptors pre-opened for the application, by number
+    ///
+    /// These are the [normalized](EnarxConfig::normalize) `files` in order, numbered from 0,
+    /// so the standard streams of the [default](EnarxConfig::default) configuration are
+    /// 0, 1 and 2, while an empty `files` has no descriptors at all. This includes the files
+    /// of the [`stdio`](crate::StdioPolicy) policy and one listener per port.
+    /// The descriptors of the `routes` of HTTP listeners follow, owned by their listener
+    /// with the name of the route. Files with a `when` condition keep their descriptor,
+    /// use [`EnarxConfig::for_target`] first to get the layout of a keep.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enarx_config::EnarxConfig;
+    ///
+    /// let layout = EnarxConfig::default().fd_layout();
+    /// assert_eq!(layout.len(), 3);
+    /// assert_eq!(layout[&1].name(), "stdout");
+    /// ```
+    pub fn fd_layout(&self) -> BTreeMap<u32, File> {
+        let normalized = self.normalize();
+        let routes = normalized.files.iter().flat_map(|file| {
+            let routes = match file {
+                File::Listen { routes, .. } => routes.as_slice(),
+                _ => &[],
+            };
+            routes.iter().map(move |route| {
+                let mut file = file.clone();
+                if let File::Listen { name, .. } = &mut file {
+                    *name = route.name.clone();
+                }
+                file
+            })
+        });
+
+        (0..)
+            .zip(normalized.files.iter().cloned().chain(routes))
+            .collect()
+    }
+
+    /// get the maximum number of simultaneously open accepted connections of a
+    /// `kind = "listen"` file
+    ///
+    /// This is the `max_concurrent_connections` of the file, bounded by the
+    /// `max_connections` of the `[limits]` section. Returns `None` for other kinds of files
+    /// and, if neither is set, for unlimited listeners.
+    pub fn max_connections(&self, file: &File) -> Option<NonZeroU32> {
+        let listen = file.as_listen()?;
+        let global = self.limits.as_ref().and_then(|l| l.max_connections);
+
+        match (listen.max_concurrent_connections, global) {
+            (Some(max), Some(global)) => Some(max.min(global)),
+            (max, global) => max.or(global),
+        }
+    }
+
+    pub(crate) fn validate_limits(&self) -> Result<(), ValidationError> {
+        let limits = match &self.limits {
+            Some(limits) => limits,
+            None => return Ok(()),
+        };
+        let exceeds = |len: usize, max: Option<NonZeroU32>| match max {
+            Some(max) if len > max.get() as usize => Some(max),
+            _ => None,
+        };
+
+        let fd_count = self.fd_count();
+        if let Some(max) = exceeds(fd_count, limits.max_fds) {
+            return Err(ValidationError::new(
+                "limits.max_fds",
+                format!("{} file descriptors exceed the limit of {}", fd_count, max),
+            ));
+        }
+
+        if let Some(max) = exceeds(self.args.len(), limits.max_args) {
+            return Err(ValidationError::new(
+                "limits.max_args",
+                format!("{} arguments exceed the limit of {}", self.args.len(), max),
+            ));
+        }
+        for (i, arg) in self.args.iter().enumerate() {
+            if let Some(max) = exceeds(arg.len(), limits.max_arg_len) {
+                return Err(ValidationError::new(
+                    "limits.max_arg_len",
+                    format!(
+                        "`args[{}]` of {} bytes exceeds the limit of {}",
+                        i,
+                        arg.len(),
+                        max
+                    ),
+                ));
+            }
+        }
+
+        let env = self.runtime_env();
+        if let Some(max) = exceeds(env.len(), limits.max_env_vars) {
+            return Err(ValidationError::new(
+                "limits.max_env_vars",
+                format!(
+                    "{} environment variables exceed the limit of {}",
+                    env.len(),
+                    max
+                ),
+            ));
+        }
+        for (name, value) in &env {
+            if let Some(max) = exceeds(value.len(), limits.max_env_value_len) {
+                return Err(ValidationError::new(
+                    "limits.max_env_value_len",
+                    format!(
+                        "the value of `{}` of {} bytes exceeds the limit of {}",
+                        name,
+                        value.len(),
+                        max
+                    ),
+                ));
+            }
+        }
+
+        for (i, file) in self.files.iter().enumerate() {
+            let max = match file {
+                File::Listen {
+                    max_concurrent_connections: Some(max),
+                    ..
+                } => *max,
+                _ => continue,
+            };
+            if let Some(global) = exceeds(max.get() as usize, limits.max_connections) {
+                return Err(ValidationError::new(
+                    format!("files[{}].max_concurrent_connections", i),
+                    format!(
+                        "{} connections exceed `limits.max_connections` of {}",
+                        max, global
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn max_fds() {
+        const CONFIG: &str = r#"
+        [limits]
+        max_fds = 2
+
+        [[files]]
+        kind = "stdin"
+
+        [[files]]
+        kind = "stdout"
+        "#;
+
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.fd_count(), 2);
+        assert_eq!(cfg.validate(), Ok(()));
+
+        cfg.files.push(crate::File::Stderr {
+            name: None,
+            access: None,
+            path: None,
+            mode: None,
+            max_bytes: None,
+            on_overflow: None,
+            labels: Default::default(),
+        });
+        let err = cfg.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "limits.max_fds: 3 file descriptors exceed the limit of 2"
+        );
+
+        // the streams added by the `stdio` policy count as well
+        cfg.files.pop();
+        cfg.stdio = Some(crate::StdioPolicy::NullMissing);
+        assert_eq!(cfg.fd_count(), 3);
+        let err = cfg.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "limits.max_fds: 3 file descriptors exceed the limit of 2"
+        );
+    }
+
+    #[test]
+    fn fd_layout() {
+        const CONFIG: &str = r#"
+        [[files]]
+        kind = "stdin"
+
+        [[files]]
+        name = "stdin"
+        kind = "stdin"
+
+        [[files]]
+        name = "API"
+        kind = "listen"
+        mode = "http"
+        routes = [ { prefix = "/a", name = "A" }, { prefix = "/b", name = "B" } ]
+
+        [[files]]
+        kind = "stderr"
+        "#;
+
+        // the duplicate `stdin` is dropped by the normalization
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let layout = cfg.fd_layout();
+        assert_eq!(
+            layout
+                .iter()
+                .map(|(fd, file)| (*fd, file.name()))
+                .collect::<Vec<_>>(),
+            [(0, "stdin"), (1, "API"), (2, "stderr"), (3, "A"), (4, "B")]
+        );
+        assert_eq!(layout[&0], cfg.normalize().files[0]);
+        assert_eq!(layout[&3].kind(), "listen");
+        assert_eq!(
+            cfg.fd_names().collect::<Vec<_>>(),
+            ["stdin", "API", "stderr", "A", "B"]
+        );
+        assert_eq!(cfg.fd_count(), layout.len());
+
+        assert_eq!(
+            EnarxConfig::default()
+                .fd_layout()
+                .values()
+                .map(File::name)
+                .collect::<Vec<_>>(),
+            crate::IMPLICIT_FILES
+        );
+        let cfg: EnarxConfig = toml::from_str("files = []").unwrap();
+        assert!(cfg.fd_layout().is_empty());
+    }
+
+    #[test]
+    fn max_connections() {
+        const CONFIG: &str = r#"
+        [limits]
+        max_connections = 100
+
+        [[files]]
+        name = "API"
+        kind = "listen"
+        max_concurrent_connections = 10
+
+        [[files]]
+        name = "ADMIN"
+        kind = "listen"
+
+        [[files]]
+        kind = "stdout"
+        "#;
+
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.max_connections(&cfg.files[0]), NonZeroU32::new(10));
+        assert_eq!(cfg.max_connections(&cfg.files[1]), NonZeroU32::new(100));
+        assert_eq!(cfg.max_connections(&cfg.files[2]), None);
+
+        if let File::Listen {
+            max_concurrent_connections,
+            ..
+        } = &mut cfg.files[0]
+        {
+            *max_concurrent_connections = NonZeroU32::new(101);
+        }
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "files[0].max_concurrent_connections: 101 connections exceed `limits.max_connections` of 100"
+        );
+
+        cfg.limits = None;
+        assert_eq!(cfg.max_connections(&cfg.files[0]), NonZeroU32::new(101));
+        assert_eq!(cfg.max_connections(&cfg.files[1]), None);
+    }
+
+    #[test]
+    fn args_env() {
+        const CONFIG: &str = r#"
+        args = [ "serve", "--name=api" ]
+        env = { "A" = "a", "B" = "bb" }
+
+        [runtime]
+        timezone = "Europe/Berlin"
+
+        [limits]
+        max_args = 2
+        max_arg_len = 10
+        max_env_vars = 3
+        max_env_value_len = 13
+        "#;
+
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        cfg.args.push("--verbose".into());
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "limits.max_args: 3 arguments exceed the limit of 2"
+        );
+
+        cfg.args = vec!["--name=frontend".into()];
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "limits.max_arg_len: `args[0]` of 15 bytes exceeds the limit of 10"
+        );
+
+        cfg.args = vec![];
+        cfg.env.insert("C".into(), "c".into());
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "limits.max_env_vars: 4 environment variables exceed the limit of 3"
+        );
+
+        cfg.env.remove("C");
+        cfg.runtime.as_mut().unwrap().timezone = Some("America/Argentina/Buenos_Aires".into());
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "limits.max_env_value_len: the value of `TZ` of 30 bytes exceeds the limit of 13"
+        );
+    }
+}
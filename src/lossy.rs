@@ -0,0 +1,415 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Error-tolerant parsing for editors and language servers
+//!
+//! Requires the `edit` feature. Unlike [`EnarxConfig::from_toml_str`], which stops at the
+//! first error, [`EnarxConfig::parse_lossy`] leaves the invalid values out, reports every one
+//! of them and locates them in the source.
+
+use std::{fmt, ops::Range};
+
+use serde_json::Value;
+use serde_path_to_error::Segment;
+use toml_edit::{ImDocument, Item, Table, TableLike};
+
+use crate::{
+    de::{format_path, Key},
+    EnarxConfig,
+};
+
+/// The severity of a [`Diagnostic`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// the value is left out of the configuration
+    Error,
+
+    /// the value is accepted, but should be changed, like a deprecated spelling
+    Warning,
+}
+
+/// A problem found by [`EnarxConfig::parse_lossy`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// the severity of the problem
+    pub severity: Severity,
+
+    /// path of the offending value, e.g. `files[2].port`, `None` for the whole document
+    pub path: Option<String>,
+
+    /// description of the problem
+    pub message: String,
+
+    /// byte range of the offending value in the source, or of the closest enclosing
+    /// value located, if any
+    pub span: Option<Range<usize>>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}: {}", path, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// The part of a configuration recovered by [`EnarxConfig::parse_lossy`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartialConfig {
+    /// the configuration with the invalid values left out
+    pub config: EnarxConfig,
+
+    /// the paths of the values left out, like `files[1]` for an invalid entry of the `files`
+    pub omitted: Vec<String>,
+}
+
+/// split a path like `files[2].port` into its segments
+fn parse_path(path: &str) -> Vec<Key> {
+    let mut keys = vec![];
+    for part in path.split('.') {
+        let (name, mut indices) = match part.find('[') {
+            Some(start) => part.split_at(start),
+            None => (part, ""),
+        };
+        if !name.is_empty() {
+            keys.push(Key::Name(name.into()));
+        }
+        while let Some(rest) = indices.strip_prefix('[') {
+            let end = match rest.find(']') {
+                Some(end) => end,
+                None => break,
+            };
+            match rest[..end].parse() {
+                Ok(i) => keys.push(Key::Index(i)),
+                Err(_) => break,
+            }
+            indices = &rest[end + 1..];
+        }
+    }
+    keys
+}
+
+/// a value of the document
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Item(&'a Item),
+    Value(&'a toml_edit::Value),
+    Table(&'a Table),
+}
+
+impl<'a> Node<'a> {
+    fn span(self) -> Option<Range<usize>> {
+        match self {
+            Self::Item(item) => item.span(),
+            Self::Value(value) => value.span(),
+            Self::Table(table) => table.span(),
+        }
+    }
+
+    fn get(self, key: &Key) -> Option<Node<'a>> {
+        let value = match self {
+            Self::Item(Item::Value(value)) | Self::Value(value) => Some(value),
+            _ => None,
+        };
+        match key {
+            Key::Name(name) => {
+                let table: &dyn TableLike = match self {
+                    Self::Item(item) => item.as_table_like()?,
+                    Self::Value(value) => value.as_inline_table()?,
+                    Self::Table(table) => table,
+                };
+                table.get(name).map(Node::Item)
+            }
+            Key::Index(i) => match (self, value) {
+                (Self::Item(Item::ArrayOfTables(tables)), _) => tables.get(*i).map(Node::Table),
+                (_, Some(toml_edit::Value::Array(array))) => array.get(*i).map(Node::Value),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// find the span of the value at `keys`, or of the closest enclosing value with one
+fn span(document: &ImDocument<&str>, keys: &[Key]) -> Option<Range<usize>> {
+    let mut node = Node::Item(document.as_item());
+    let mut found = None;
+
+    for key in keys {
+        node = match node.get(key) {
+            Some(node) => node,
+            None => break,
+        };
+        found = node.span().or(found);
+    }
+
+    found
+}
+
+/// translate `keys` into the path of the same value in the source, before the array
+/// elements `removed` were removed
+fn to_source(keys: &[Key], removed: &[(String, usize)]) -> Vec<Key> {
+    let mut source = vec![];
+    for key in keys {
+        match key {
+            Key::Name(name) => source.push(Key::Name(name.clone())),
+            Key::Index(index) => {
+                let array = format_path(&source);
+                let mut indices = removed
+                    .iter()
+                    .filter(|(path, _)| *path == array)
+                    .map(|(_, i)| *i)
+                    .collect::<Vec<_>>();
+                indices.sort_unstable();
+
+                let mut index = *index;
+                for i in indices {
+                    if i <= index {
+                        index += 1;
+                    }
+                }
+                source.push(Key::Index(index));
+            }
+        }
+    }
+    source
+}
+
+/// remove the value at `keys` from `value`, returning if it was found
+fn remove(value: &mut Value, keys: &[Key]) -> bool {
+    let (last, parents) = match keys.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+
+    let mut parent = value;
+    for key in parents {
+        let next = match (key, parent) {
+            (Key::Name(name), Value::Object(map)) => map.get_mut(name),
+            (Key::Index(i), Value::Array(values)) => values.get_mut(*i),
+            _ => None,
+        };
+        parent = match next {
+            Some(next) => next,
+            None => return false,
+        };
+    }
+
+    match (last, parent) {
+        (Key::Name(name), Value::Object(map)) => map.remove(name).is_some(),
+        (Key::Index(i), Value::Array(values)) if *i < values.len() => {
+            values.remove(*i);
+            true
+        }
+        _ => false,
+    }
+}
+
+impl EnarxConfig {
+    /// parse a configuration in TOML format, leaving out the invalid values
+    ///
+    /// Returns the configuration recovered and the problems found, each located in the
+    /// source: all values failing to deserialize, which are left out, the first
+    /// [validation](EnarxConfig::validate) error and the deprecated spellings. A document
+    /// which is not valid TOML at all results in an empty configuration.
+    ///
+    /// Requires the `edit` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enarx_config::{EnarxConfig, Severity};
+    ///
+    /// let source = "args = 42\n\n[[files]]\nkind = \"stdin\"\n\n[[files]]\nkind = \"nope\"\n";
+    /// let (partial, diagnostics) = EnarxConfig::parse_lossy(source);
+    ///
+    /// assert_eq!(partial.config.files.len(), 1);
+    /// assert_eq!(partial.omitted, ["args", "files[1]"]);
+    /// assert_eq!(diagnostics.len(), 2);
+    /// assert_eq!(diagnostics[0].severity, Severity::Error);
+    /// assert_eq!(&source[diagnostics[0].span.clone().unwrap()], "42");
+    /// ```
+    pub fn parse_lossy(s: &str) -> (PartialConfig, Vec<Diagnostic>) {
+        let empty = || PartialConfig {
+            config: serde_json::from_value(Value::Object(Default::default()))
+                .expect("all sections are optional"),
+            omitted: vec![],
+        };
+        let error = |message: String, span: Option<Range<usize>>| Diagnostic {
+            severity: Severity::Error,
+            path: None,
+            message,
+            span,
+        };
+
+        let document = match ImDocument::parse(s) {
+            Ok(document) => document,
+            Err(e) => return (empty(), vec![error(e.message().into(), e.span())]),
+        };
+        let mut value = match toml_edit::de::from_str::<Value>(s) {
+            Ok(value) => value,
+            Err(e) => return (empty(), vec![error(e.message().into(), e.span())]),
+        };
+        let original = value.clone();
+
+        let mut diagnostics = vec![];
+        let mut omitted = vec![];
+        // the indices of the array elements removed, by the path of the array
+        let mut removed: Vec<(String, usize)> = vec![];
+
+        let config = loop {
+            let e = match serde_path_to_error::deserialize::<_, EnarxConfig>(&value) {
+                Ok(config) => break config,
+                Err(e) => e,
+            };
+
+            let mut keys = vec![];
+            for segment in e.path().iter() {
+                match segment {
+                    Segment::Seq { index } => keys.push(Key::Index(*index)),
+                    Segment::Map { key } => keys.push(Key::Name(key.clone())),
+                    Segment::Enum { .. } | Segment::Unknown => break,
+                }
+            }
+            let source_keys = to_source(&keys, &removed);
+            // the path inside of a `[[files]]` entry is lost by its preprocessing
+            let field = crate::de::entry_field_path(&value, &keys);
+
+            let path = format_path(&source_keys);
+            if !remove(&mut value, &keys) {
+                diagnostics.push(error(e.inner().to_string(), None));
+                return (empty(), diagnostics);
+            }
+            if let (Some(Key::Index(i)), Some(Key::Index(_))) = (source_keys.last(), keys.last()) {
+                let array = format_path(&source_keys[..source_keys.len() - 1]);
+                removed.push((array, *i));
+            }
+
+            let mut diagnostic_keys = source_keys;
+            diagnostic_keys.extend(field);
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                path: Some(format_path(&diagnostic_keys)),
+                message: e.inner().to_string(),
+                span: span(&document, &diagnostic_keys),
+            });
+            omitted.push(path);
+        };
+
+        if let Err(e) = config.validate() {
+            let keys = to_source(&parse_path(e.path()), &removed);
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                path: Some(format_path(&keys)),
+                message: e.message().into(),
+                span: span(&document, &keys),
+            });
+        }
+
+        diagnostics.extend(
+            crate::de::deprecations(&original)
+                .into_iter()
+                .map(|warning| {
+                    let keys = parse_path(warning.path());
+                    Diagnostic {
+                        severity: Severity::Warning,
+                        path: Some(warning.path().into()),
+                        message: warning.message().into(),
+                        span: span(&document, &keys),
+                    }
+                }),
+        );
+
+        (PartialConfig { config, omitted }, diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SOURCE: &str = r#"env = { "LOG" = "debug", "BAD" = [] }
+args = [ "serve" ]
+
+[steward]
+url = "not a url"
+
+[[files]]
+kind = "stdin"
+
+[[files]]
+name = "API"
+kind = "listen"
+port = "https"
+
+[[files]]
+type = "stdout"
+
+[[files]]
+name = "DB"
+kind = "connect"
+host = "db.example.com"
+prot = "udp"
+
+[[files]]
+kind = "alias"
+name = "LOG"
+alias_of = "missing"
+"#;
+
+    #[test]
+    fn parse_lossy() {
+        let (partial, diagnostics) = EnarxConfig::parse_lossy(SOURCE);
+
+        assert_eq!(
+            partial.omitted,
+            ["env.BAD", "files[1]", "files[3]", "steward"]
+        );
+        assert_eq!(partial.config.env["LOG"], "debug");
+        assert_eq!(partial.config.args, ["serve"]);
+        assert_eq!(
+            partial
+                .config
+                .files
+                .iter()
+                .map(|file| file.name())
+                .collect::<Vec<_>>(),
+            ["stdin", "stdout", "LOG"]
+        );
+
+        let found = diagnostics
+            .iter()
+            .map(|d| {
+                (
+                    d.severity,
+                    d.path.as_deref().unwrap(),
+                    &SOURCE[d.span.clone().unwrap()],
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (Severity::Error, "env.BAD", "[]"),
+                (Severity::Error, "files[1].port", "\"https\""),
+                (Severity::Error, "files[3].prot", "\"udp\""),
+                (Severity::Error, "steward", "[steward]\nurl = \"not a url\""),
+                (Severity::Error, "files[4].alias_of", "\"missing\""),
+                (Severity::Warning, "files[2].type", "\"stdout\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn syntax_error() {
+        let (partial, diagnostics) = EnarxConfig::parse_lossy("args = [\n");
+        assert!(partial.config.files.is_empty());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, None);
+        assert!(diagnostics[0].span.is_some());
+
+        let (partial, diagnostics) = EnarxConfig::parse_lossy("[[files]]\nkind = \"stdin\"\n");
+        assert_eq!(partial.config.files.len(), 1);
+        assert!(partial.omitted.is_empty());
+        assert!(diagnostics.is_empty());
+    }
+}
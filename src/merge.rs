@@ -0,0 +1,328 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Layering of configurations
+
+use std::{collections::BTreeMap, num::NonZeroU32, path::PathBuf, time::Duration};
+
+use crate::{
+    Attestation, ClientAuth, CrlSource, Crypto, DebugOptions, Dns, EgressPolicy, EnarxConfig, File,
+    Health, HostPattern, Identity, KeyExchange, KvStore, LifecyclePolicy, Limits, Network,
+    OcspMode, Proxy, Random, Runtime, Secret, Service, Steward, Telemetry, Time, TlsOptions,
+    TlsVersion, TrustedSigners, UsageReport, WasiCrypto, WasiHttp, WasiNn, Wasm,
+};
+
+/// Merge another value of the same type on top of `self`
+///
+/// The values of `other` take precedence. The strategy depends on the field:
+///
+/// | Field                                                    | Strategy                                |
+/// |----------------------------------------------------------|-----------------------------------------|
+/// | `env`, `secrets`, `trust`, `labels`, `apps`, `templates` | union, the entries of `other` win       |
+/// | `args`                                                   | override, if `other` has any arguments  |
+/// | `conditional`                                            | append                                  |
+/// | `files`, `kv`, `services`                                | replace by name, append the new entries |
+/// | `network`, `limits`, `runtime`, `tls`, `wasm`            | merged field by field                   |
+/// | `steward_insecure`, `runtime.export_env`                 | enabled, if enabled in either           |
+/// | all other sections                                       | override, if present in `other`         |
+///
+/// The flags `steward_insecure` and `runtime.export_env` are one-way: a missing flag can not be
+/// told apart from `false`, so a layer can enable them, but not disable them again.
+///
+/// # Examples
+///
+/// ```
+/// extern crate toml;
+/// use enarx_config::{EnarxConfig, Merge};
+///
+/// let mut base: EnarxConfig = toml::from_str("args = [ \"--verbose\" ]\nenv = { \"A\" = \"a\" }").unwrap();
+/// let overlay: EnarxConfig = toml::from_str("env = { \"B\" = \"b\" }").unwrap();
+///
+/// base.merge(overlay);
+/// assert_eq!(base.args, [ "--verbose" ]);
+/// assert_eq!(base.env.len(), 2);
+/// ```
+pub trait Merge {
+    /// merge `other` on top of `self`
+    fn merge(&mut self, other: Self);
+}
+
+impl<T: Merge> Merge for Option<T> {
+    fn merge(&mut self, other: Self) {
+        match (self.as_mut(), other) {
+            (Some(value), Some(other)) => value.merge(other),
+            (None, Some(other)) => *self = Some(other),
+            (_, None) => {}
+        }
+    }
+}
+
+impl<V> Merge for BTreeMap<String, V> {
+    fn merge(&mut self, other: Self) {
+        self.extend(other);
+    }
+}
+
+impl Merge for Vec<File> {
+    fn merge(&mut self, other: Self) {
+        for file in other {
+            match self.iter_mut().find(|f| f.name() == file.name()) {
+                Some(existing) => *existing = file,
+                None => self.push(file),
+            }
+        }
+    }
+}
+
+impl Merge for Vec<KvStore> {
+    fn merge(&mut self, other: Self) {
+        for store in other {
+            match self.iter_mut().find(|s| s.name == store.name) {
+                Some(existing) => *existing = store,
+                None => self.push(store),
+            }
+        }
+    }
+}
+
+impl Merge for Vec<Service> {
+    fn merge(&mut self, other: Self) {
+        for service in other {
+            match self.iter_mut().find(|s| s.name == service.name) {
+                Some(existing) => *existing = service,
+                None => self.push(service),
+            }
+        }
+    }
+}
+
+/// implement [`Merge`] by replacing the whole value
+macro_rules! merge_by_replace {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Merge for $ty {
+                fn merge(&mut self, other: Self) {
+                    *self = other;
+                }
+            }
+        )*
+    };
+}
+
+merge_by_replace!(
+    String,
+    bool,
+    Attestation,
+    NonZeroU32,
+    Duration,
+    Steward,
+    File,
+    Secret,
+    TrustedSigners,
+    Proxy,
+    EgressPolicy,
+    Dns,
+    Telemetry,
+    DebugOptions,
+    Health,
+    LifecyclePolicy,
+    UsageReport,
+    Identity,
+    Crypto,
+    OcspMode,
+    Vec<CrlSource>,
+    TlsVersion,
+    KeyExchange,
+    Vec<String>,
+    Vec<HostPattern>,
+    PathBuf,
+    ClientAuth,
+    Time,
+    Random,
+    WasiCrypto,
+    WasiNn,
+    WasiHttp,
+);
+
+impl Merge for Network {
+    fn merge(&mut self, other: Self) {
+        self.egress.merge(other.egress);
+        self.dns.merge(other.dns);
+    }
+}
+
+impl Merge for TlsOptions {
+    fn merge(&mut self, other: Self) {
+        self.min_version.merge(other.min_version);
+        self.key_exchange.merge(other.key_exchange);
+        self.alpn.merge(other.alpn);
+        self.ca.merge(other.ca);
+        self.trust.merge(other.trust);
+        self.client_auth.merge(other.client_auth);
+        self.server_names.merge(other.server_names);
+        self.ocsp.merge(other.ocsp);
+        self.crl.merge(other.crl);
+        self.rotate_after.merge(other.rotate_after);
+        self.session_resumption.merge(other.session_resumption);
+        self.ticket_lifetime.merge(other.ticket_lifetime);
+    }
+}
+
+impl Merge for Limits {
+    fn merge(&mut self, other: Self) {
+        self.max_fds.merge(other.max_fds);
+        self.max_args.merge(other.max_args);
+        self.max_arg_len.merge(other.max_arg_len);
+        self.max_env_vars.merge(other.max_env_vars);
+        self.max_env_value_len.merge(other.max_env_value_len);
+        self.max_connections.merge(other.max_connections);
+    }
+}
+
+impl Merge for Runtime {
+    fn merge(&mut self, other: Self) {
+        self.timezone.merge(other.timezone);
+        self.locale.merge(other.locale);
+        self.export_env |= other.export_env;
+    }
+}
+
+impl Merge for Wasm {
+    fn merge(&mut self, other: Self) {
+        self.wasi_crypto.merge(other.wasi_crypto);
+        self.wasi_nn.merge(other.wasi_nn);
+        self.http.merge(other.http);
+    }
+}
+
+impl Merge for EnarxConfig {
+    fn merge(&mut self, other: Self) {
+        Merge::merge(&mut self.env, other.env);
+        if other.env_exports.is_some() {
+            self.env_exports = other.env_exports;
+        }
+        if !other.args.is_empty() {
+            self.args = other.args;
+        }
+        self.files.merge(other.files);
+        self.stdio = other.stdio.or(self.stdio);
+        self.steward.merge(other.steward);
+        self.steward_insecure |= other.steward_insecure;
+        self.not_before = other.not_before.or(self.not_before);
+        self.not_after = other.not_after.or(self.not_after);
+        self.attestation.merge(other.attestation);
+        self.identity.merge(other.identity);
+        self.signature.merge(other.signature);
+        Merge::merge(&mut self.secrets, other.secrets);
+        Merge::merge(&mut self.trust, other.trust);
+        self.tls.merge(other.tls);
+        self.crypto.merge(other.crypto);
+        self.proxy.merge(other.proxy);
+        self.network.merge(other.network);
+        self.limits.merge(other.limits);
+        self.telemetry.merge(other.telemetry);
+        self.debug.merge(other.debug);
+        self.health.merge(other.health);
+        self.lifecycle.merge(other.lifecycle);
+        self.report.merge(other.report);
+        self.time.merge(other.time);
+        self.random.merge(other.random);
+        self.runtime.merge(other.runtime);
+        self.wasm.merge(other.wasm);
+        self.kv.merge(other.kv);
+        self.services.merge(other.services);
+        Merge::merge(&mut self.labels, other.labels);
+        Merge::merge(&mut self.apps, other.apps);
+        Merge::merge(&mut self.templates, other.templates);
+        self.conditional.extend(other.conditional);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn merge() {
+        let mut base: EnarxConfig = toml::from_str(
+            r#"
+            args = [ "--verbose" ]
+            env = { "A" = "a", "B" = "b" }
+
+            [runtime]
+            timezone = "UTC"
+
+            [[files]]
+            kind = "stdin"
+
+            [[files]]
+            name = "API"
+            kind = "listen"
+            port = 8080
+            "#,
+        )
+        .unwrap();
+
+        let overlay: EnarxConfig = toml::from_str(
+            r#"
+            env = { "B" = "overlay" }
+
+            [runtime]
+            locale = "C"
+
+            [[files]]
+            name = "API"
+            kind = "listen"
+            port = 443
+
+            [[files]]
+            kind = "stderr"
+            "#,
+        )
+        .unwrap();
+
+        base.merge(overlay);
+
+        assert_eq!(base.args, ["--verbose"]);
+        assert_eq!(base.env["A"], "a");
+        assert_eq!(base.env["B"], "overlay");
+        assert_eq!(
+            base.files.iter().map(File::name).collect::<Vec<_>>(),
+            ["stdin", "API", "stderr"]
+        );
+        assert_eq!(base.files[1].as_listen().unwrap().port, 443.into());
+
+        let runtime = base.runtime.unwrap();
+        assert_eq!(runtime.timezone.as_deref(), Some("UTC"));
+        assert_eq!(runtime.locale.as_deref(), Some("C"));
+    }
+
+    #[test]
+    fn args_override() {
+        let mut base = EnarxConfig {
+            args: vec!["a".into()],
+            ..Default::default()
+        };
+        base.merge(EnarxConfig {
+            args: vec!["b".into(), "c".into()],
+            files: vec![],
+            ..Default::default()
+        });
+
+        assert_eq!(base.args, ["b", "c"]);
+        assert_eq!(base.files.len(), 3);
+    }
+
+    #[test]
+    fn flags_are_one_way() {
+        let mut base = EnarxConfig {
+            steward_insecure: true,
+            ..Default::default()
+        };
+        base.merge(EnarxConfig::default());
+        assert!(base.steward_insecure);
+
+        let mut runtime: Runtime = toml::from_str("export_env = true").unwrap();
+        runtime.merge(toml::from_str("export_env = false").unwrap());
+        assert!(runtime.export_env);
+    }
+}
@@ -0,0 +1,838 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed network values
+
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    ops::Deref,
+    str::FromStr,
+};
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Error parsing a network address
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidAddress(String);
+
+impl InvalidAddress {
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
+impl fmt::Display for InvalidAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InvalidAddress {}
+
+/// Address a listen socket binds to
+///
+/// Parsed from the `addr` string of a `kind = "listen"` file:
+///
+/// | `addr`                   | value                |
+/// |--------------------------|----------------------|
+/// | `"::"`, `"*"`, `"any"`   | [`ListenAddr::Any`]  |
+/// | `"0.0.0.0"`, `"any-v4"`  | [`ListenAddr::V4Any`] |
+/// | `"any-v6"`               | [`ListenAddr::V6Any`] |
+/// | an IP address            | [`ListenAddr::Ip`]   |
+/// | an interface name        | [`ListenAddr::Iface`] |
+///
+/// Interface names consist of ASCII letters, digits, `-` and `_`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ListenAddr {
+    /// any IPv6 and IPv4 interface
+    Any,
+
+    /// any IPv4 interface
+    V4Any,
+
+    /// any IPv6 interface, without IPv4
+    V6Any,
+
+    /// a specific IP address
+    Ip(IpAddr),
+
+    /// all addresses of a network interface
+    Iface(String),
+}
+
+impl Default for ListenAddr {
+    fn default() -> Self {
+        crate::DEFAULT_ADDR
+    }
+}
+
+impl ListenAddr {
+    /// get the IP address to bind to, if not bound to an interface
+    ///
+    /// [`ListenAddr::Any`] and [`ListenAddr::V6Any`] return the unspecified IPv6 address,
+    /// [`ListenAddr::V4Any`] the unspecified IPv4 address.
+    pub fn ip(&self) -> Option<IpAddr> {
+        match self {
+            Self::Any | Self::V6Any => Some(Ipv6Addr::UNSPECIFIED.into()),
+            Self::V4Any => Some(Ipv4Addr::UNSPECIFIED.into()),
+            Self::Ip(ip) => Some(*ip),
+            Self::Iface(_) => None,
+        }
+    }
+}
+
+impl From<IpAddr> for ListenAddr {
+    fn from(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V6(ip) if ip.is_unspecified() => Self::Any,
+            IpAddr::V4(ip) if ip.is_unspecified() => Self::V4Any,
+            ip => Self::Ip(ip),
+        }
+    }
+}
+
+impl FromStr for ListenAddr {
+    type Err = InvalidAddress;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "*" | "any" => return Ok(Self::Any),
+            "any-v4" => return Ok(Self::V4Any),
+            "any-v6" => return Ok(Self::V6Any),
+            _ => {}
+        }
+
+        let unbracketed = s
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .unwrap_or(s);
+        if let Ok(ip) = unbracketed.parse::<IpAddr>() {
+            return Ok(ip.into());
+        }
+
+        // see `IFNAMSIZ` of Linux, without dots, so a mistyped IP address or a host name
+        // is not taken for an interface
+        let valid_iface = !s.is_empty()
+            && s.len() < 16
+            && s.bytes()
+                .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_'));
+        if valid_iface {
+            Ok(Self::Iface(s.into()))
+        } else {
+            Err(InvalidAddress::new(format!(
+                "invalid listen address `{}`, expected an IP address or interface name",
+                s
+            )))
+        }
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => f.write_str("::"),
+            Self::V4Any => f.write_str("0.0.0.0"),
+            Self::V6Any => f.write_str("any-v6"),
+            Self::Ip(ip) => ip.fmt(f),
+            Self::Iface(name) => f.write_str(name),
+        }
+    }
+}
+
+impl Serialize for ListenAddr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ListenAddr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let addr = String::deserialize(deserializer)?;
+        addr.parse().map_err(D::Error::custom)
+    }
+}
+
+/// TCP port number
+///
+/// Port `0` requests an ephemeral port chosen by the operating system and is only
+/// valid for listeners with `ephemeral = true` (see [`EnarxConfig::validate`](crate::EnarxConfig::validate)).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Port(u16);
+
+impl Port {
+    /// the ephemeral port `0`
+    pub const EPHEMERAL: Self = Self(0);
+
+    /// create a new port
+    pub const fn new(port: u16) -> Self {
+        Self(port)
+    }
+
+    /// get the port number
+    pub const fn get(self) -> u16 {
+        self.0
+    }
+
+    /// is this the ephemeral port `0`
+    pub const fn is_ephemeral(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl From<u16> for Port {
+    fn from(port: u16) -> Self {
+        Self(port)
+    }
+}
+
+impl From<Port> for u16 {
+    fn from(port: Port) -> Self {
+        port.0
+    }
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// IP network in CIDR notation, like `"10.0.0.0/8"` or `"2001:db8::/32"`
+///
+/// A plain IP address is a network with a single address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// the network of `addr` with `prefix`, clearing the host bits of `addr`
+    pub(crate) fn new(addr: IpAddr, prefix: u8) -> Self {
+        Self {
+            addr: mask(addr, prefix),
+            prefix,
+        }
+    }
+
+    /// get the network address
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// get the prefix length
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// does the network contain `ip`
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.addr.is_ipv4() == ip.is_ipv4() && mask(ip, self.prefix) == self.addr
+    }
+}
+
+/// clear all but the first `prefix` bits of `ip`
+fn mask(ip: IpAddr, prefix: u8) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            Ipv4Addr::from(u32::from(ip) & mask).into()
+        }
+        IpAddr::V6(ip) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            Ipv6Addr::from(u128::from(ip) & mask).into()
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = InvalidAddress;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidAddress::new(format!("invalid CIDR `{}`", s));
+
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr = addr.parse::<IpAddr>().map_err(|_| invalid())?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().map_err(|_| invalid())?,
+            None => max,
+        };
+
+        // all host bits must be zero
+        if prefix > max || mask(addr, prefix) != addr {
+            return Err(invalid());
+        }
+
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+impl Serialize for Cidr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cidr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let cidr = String::deserialize(deserializer)?;
+        cidr.parse().map_err(D::Error::custom)
+    }
+}
+
+/// Inclusive range of ports, written as a port number or a `"8000-8100"` string
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PortRange {
+    start: Port,
+    end: Port,
+}
+
+impl PortRange {
+    /// create a range from `start` to `end` inclusive
+    pub fn new(start: Port, end: Port) -> Option<Self> {
+        if start <= end {
+            Some(Self { start, end })
+        } else {
+            None
+        }
+    }
+
+    /// get the first port of the range
+    pub fn start(&self) -> Port {
+        self.start
+    }
+
+    /// get the last port of the range
+    pub fn end(&self) -> Port {
+        self.end
+    }
+
+    /// does the range contain `port`
+    pub fn contains(&self, port: Port) -> bool {
+        self.start <= port && port <= self.end
+    }
+
+    /// iterate over all ports of the range
+    pub fn iter(&self) -> impl Iterator<Item = Port> {
+        (self.start.get()..=self.end.get()).map(Port::new)
+    }
+}
+
+impl From<Port> for PortRange {
+    fn from(port: Port) -> Self {
+        Self {
+            start: port,
+            end: port,
+        }
+    }
+}
+
+impl FromStr for PortRange {
+    type Err = InvalidAddress;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidAddress::new(format!("invalid port range `{}`", s));
+        let port = |s: &str| {
+            s.trim()
+                .parse::<u16>()
+                .map(Port::new)
+                .map_err(|_| invalid())
+        };
+
+        match s.split_once('-') {
+            Some((start, end)) => Self::new(port(start)?, port(end)?).ok_or_else(invalid),
+            None => port(s).map(Self::from),
+        }
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            self.start.fmt(f)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+impl Serialize for PortRange {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if self.start == self.end {
+            self.start.serialize(serializer)
+        } else {
+            serializer.collect_str(self)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PortRange {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Port(u16),
+            Range(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Port(port) => Ok(Port::new(port).into()),
+            Repr::Range(range) => range.parse().map_err(D::Error::custom),
+        }
+    }
+}
+
+/// Host to connect to
+///
+/// Either an IP address or a DNS name. Unicode names are converted to their
+/// punycode representation and all names are lowercased. The normalized
+/// spelling is also the default name of a [`File::Connect`](crate::File::Connect).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Host(String);
+
+impl Host {
+    /// get the IP address, if the host is an IP literal
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.0.parse().ok()
+    }
+
+    /// is the host an IP literal
+    pub fn is_ip(&self) -> bool {
+        self.ip().is_some()
+    }
+
+    /// get the DNS name, if the host is not an IP literal
+    ///
+    /// This is the value to be used for TLS server name indication.
+    pub fn domain(&self) -> Option<&str> {
+        if self.is_ip() {
+            None
+        } else {
+            Some(&self.0)
+        }
+    }
+}
+
+impl Deref for Host {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromStr for Host {
+    type Err = InvalidAddress;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |reason: &str| InvalidAddress::new(format!("invalid host `{}`: {}", s, reason));
+
+        let unbracketed = s
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .unwrap_or(s);
+        if let Ok(ip) = unbracketed.parse::<IpAddr>() {
+            return Ok(Self(ip.to_string()));
+        }
+
+        let domain = match url::Host::parse(s).map_err(|e| invalid(&e.to_string()))? {
+            url::Host::Domain(domain) => domain,
+            url::Host::Ipv4(ip) => return Ok(Self(ip.to_string())),
+            url::Host::Ipv6(ip) => return Ok(Self(ip.to_string())),
+        };
+        let domain = domain.strip_suffix('.').unwrap_or(&domain);
+
+        if domain.len() > 253 {
+            return Err(invalid("name longer than 253 characters"));
+        }
+
+        for label in domain.split('.') {
+            if label.is_empty() || label.len() > 63 {
+                return Err(invalid("labels must have 1 to 63 characters"));
+            }
+            if label.starts_with('-') || label.ends_with('-') {
+                return Err(invalid("labels must not start or end with `-`"));
+            }
+            if !label
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_'))
+            {
+                return Err(invalid(
+                    "labels must only contain letters, digits, `-` and `_`",
+                ));
+            }
+        }
+
+        Ok(Self(domain.to_ascii_lowercase()))
+    }
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for Host {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Host {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let host = String::deserialize(deserializer)?;
+        host.parse().map_err(D::Error::custom)
+    }
+}
+
+/// A pattern matching hosts, like `"api.example.com"`, `"*.example.com"`, `"10.1.1.1"` or `"10.0.0.0/8"`
+///
+/// Names are normalized like a [`Host`], Unicode names are converted to their punycode
+/// representation and all names are lowercased, so `"*.BÜCHER.example"` matches
+/// `"shop.xn--bcher-kva.example"`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HostPattern {
+    /// exactly this DNS name or IP address
+    Exact(Host),
+
+    /// all subdomains of this DNS name, written as `*.example.com`, but not the name itself
+    Subdomains(Host),
+
+    /// all IP addresses of this network
+    Cidr(Cidr),
+}
+
+impl HostPattern {
+    /// check if `host` matches the pattern
+    pub fn matches(&self, host: &Host) -> bool {
+        match self {
+            Self::Exact(exact) => exact == host,
+            Self::Subdomains(domain) => {
+                !host.is_ip()
+                    && matches!(
+                        host.strip_suffix(&**domain),
+                        Some(sub) if sub.len() > 1 && sub.ends_with('.')
+                    )
+            }
+            Self::Cidr(cidr) => matches!(host.ip(), Some(ip) if cidr.contains(ip)),
+        }
+    }
+
+    /// check if the TLS server name indication `name` matches the pattern
+    ///
+    /// `name` is normalized like a [`Host`]. IP addresses are not valid server names,
+    /// they never match.
+    pub fn matches_server_name(&self, name: &str) -> bool {
+        match name.parse::<Host>() {
+            Ok(host) if !host.is_ip() => self.matches(&host),
+            _ => false,
+        }
+    }
+
+    /// does the pattern only match IP addresses
+    pub fn is_ip(&self) -> bool {
+        match self {
+            Self::Exact(host) => host.is_ip(),
+            Self::Subdomains(_) => false,
+            Self::Cidr(_) => true,
+        }
+    }
+}
+
+impl FromStr for HostPattern {
+    type Err = InvalidAddress;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(domain) = s.strip_prefix("*.") {
+            let domain = domain.parse::<Host>()?;
+            if domain.is_ip() {
+                return Err(InvalidAddress::new(format!(
+                    "invalid host pattern `{}`: `*.` requires a DNS name",
+                    s
+                )));
+            }
+            return Ok(Self::Subdomains(domain));
+        }
+        if s.contains('/') {
+            return s.parse().map(Self::Cidr);
+        }
+        s.parse().map(Self::Exact)
+    }
+}
+
+impl fmt::Display for HostPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exact(host) => host.fmt(f),
+            Self::Subdomains(domain) => write!(f, "*.{}", domain),
+            Self::Cidr(cidr) => cidr.fmt(f),
+        }
+    }
+}
+
+impl Serialize for HostPattern {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for HostPattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pattern = String::deserialize(deserializer)?;
+        pattern.parse().map_err(D::Error::custom)
+    }
+}
+
+/// Address family to resolve and connect with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum AddressFamily {
+    /// IPv4 and IPv6
+    #[serde(rename = "any")]
+    Any,
+
+    /// IPv4 only
+    #[serde(rename = "v4")]
+    V4,
+
+    /// IPv6 only
+    #[serde(rename = "v6")]
+    V6,
+}
+
+// `#[default]` on enum variants is not available on the MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for AddressFamily {
+    fn default() -> Self {
+        Self::Any
+    }
+}
+
+impl AddressFamily {
+    /// does the address family include `ip`
+    pub fn matches(self, ip: IpAddr) -> bool {
+        match self {
+            Self::Any => true,
+            Self::V4 => ip.is_ipv4(),
+            Self::V6 => ip.is_ipv6(),
+        }
+    }
+}
+
+impl fmt::Display for AddressFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => f.write_str("any"),
+            Self::V4 => f.write_str("v4"),
+            Self::V6 => f.write_str("v6"),
+        }
+    }
+}
+
+/// A `host` and `port` pair to connect to
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Endpoint {
+    /// host to connect to
+    pub host: Host,
+
+    /// port to connect to
+    #[serde(default = "crate::default_port")]
+    pub port: Port,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn listen_addr() {
+        let parse = |s: &str| s.parse::<ListenAddr>();
+
+        assert_eq!(parse("::"), Ok(ListenAddr::Any));
+        assert_eq!(parse("0:0::0"), Ok(ListenAddr::Any));
+        assert_eq!(parse("*"), Ok(ListenAddr::Any));
+        assert_eq!(parse("0.0.0.0"), Ok(ListenAddr::V4Any));
+        assert_eq!(parse("any-v6"), Ok(ListenAddr::V6Any));
+        assert_eq!(parse("[::1]"), Ok(ListenAddr::Ip("::1".parse().unwrap())));
+        assert_eq!(
+            parse("192.168.1.1"),
+            Ok(ListenAddr::Ip("192.168.1.1".parse().unwrap()))
+        );
+        assert_eq!(parse("eth0"), Ok(ListenAddr::Iface("eth0".into())));
+        assert!(parse("").is_err());
+        assert!(parse("300.1.1.1 ").is_err());
+        assert!(parse("a-very-long-interface").is_err());
+
+        // mistyped IP addresses and host names are no interface names
+        assert_eq!(
+            parse("10.0.0").unwrap_err().to_string(),
+            "invalid listen address `10.0.0`, expected an IP address or interface name"
+        );
+        assert!(parse("db.example.com").is_err());
+        assert_eq!(parse("br_lan-1"), Ok(ListenAddr::Iface("br_lan-1".into())));
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        for addr in ["::", "0.0.0.0", "any-v6", "::1", "127.0.0.1", "eth0"] {
+            assert_eq!(addr.parse::<ListenAddr>().unwrap().to_string(), addr);
+        }
+    }
+
+    #[test]
+    fn host() {
+        let parse = |s: &str| s.parse::<Host>();
+
+        let host = parse("Example.COM").unwrap();
+        assert_eq!(&*host, "example.com");
+        assert_eq!(host.domain(), Some("example.com"));
+
+        let host = parse("bücher.example").unwrap();
+        assert_eq!(&*host, "xn--bcher-kva.example");
+
+        let host = parse("[::1]").unwrap();
+        assert_eq!(host.ip(), Some("::1".parse().unwrap()));
+        assert_eq!(host.domain(), None);
+        assert_eq!(&*parse("127.0.0.1").unwrap(), "127.0.0.1");
+
+        assert!(parse("").is_err());
+        assert!(parse("exa mple.com").is_err());
+        assert!(parse("-example.com").is_err());
+        assert!(parse("example..com").is_err());
+        assert!(parse(&"a".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn cidr() {
+        let cidr = "10.0.0.0/8".parse::<Cidr>().unwrap();
+        assert!(cidr.contains("10.1.2.3".parse().unwrap()));
+        assert!(!cidr.contains("11.0.0.1".parse().unwrap()));
+        assert!(!cidr.contains("::1".parse().unwrap()));
+
+        let cidr = "2001:db8::/32".parse::<Cidr>().unwrap();
+        assert!(cidr.contains("2001:db8:1::1".parse().unwrap()));
+        assert_eq!(cidr.to_string(), "2001:db8::/32");
+
+        let single = "192.168.1.1".parse::<Cidr>().unwrap();
+        assert_eq!(single.prefix(), 32);
+        assert!("0.0.0.0/0"
+            .parse::<Cidr>()
+            .unwrap()
+            .contains("1.2.3.4".parse().unwrap()));
+
+        assert!("10.0.0.1/8".parse::<Cidr>().is_err());
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("example.com/8".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn host_pattern() {
+        let pattern = |s: &str| s.parse::<HostPattern>().unwrap();
+        let host = |s: &str| s.parse::<Host>().unwrap();
+
+        let wildcard = pattern("*.Example.COM");
+        assert_eq!(wildcard, HostPattern::Subdomains(host("example.com")));
+        assert_eq!(wildcard.to_string(), "*.example.com");
+        assert!(wildcard.matches(&host("api.example.com")));
+        assert!(wildcard.matches(&host("A.B.EXAMPLE.COM")));
+        assert!(!wildcard.matches(&host("example.com")));
+        assert!(!wildcard.matches(&host("badexample.com")));
+        assert!(wildcard.matches_server_name("API.example.com."));
+        assert!(!wildcard.is_ip());
+
+        let idna = pattern("*.bücher.example");
+        assert_eq!(idna.to_string(), "*.xn--bcher-kva.example");
+        assert!(idna.matches(&host("shop.BÜCHER.example")));
+        assert!(idna.matches_server_name("shop.xn--bcher-kva.example"));
+
+        let exact = pattern("Bücher.Example");
+        assert_eq!(exact, HostPattern::Exact(host("xn--bcher-kva.example")));
+        assert!(exact.matches(&host("bücher.example")));
+        assert!(!exact.matches(&host("www.bücher.example")));
+
+        let ip = pattern("[2001:db8::1]");
+        assert_eq!(ip.to_string(), "2001:db8::1");
+        assert!(ip.is_ip());
+        assert!(ip.matches(&host("2001:db8:0::1")));
+        assert!(!ip.matches_server_name("2001:db8::1"));
+
+        let cidr = pattern("10.0.0.0/8");
+        assert!(cidr.matches(&host("10.1.2.3")));
+        assert!(!cidr.matches(&host("11.0.0.1")));
+        assert!(!cidr.matches(&host("example.com")));
+        assert!(!cidr.matches_server_name("10.1.2.3"));
+
+        for invalid in [
+            "",
+            "*.",
+            "*.10.0.0.1",
+            "**.example.com",
+            "example.com/8",
+            "10.0.0.1/8",
+        ] {
+            assert!(invalid.parse::<HostPattern>().is_err(), "{}", invalid);
+        }
+
+        let json = serde_json::to_string(&[&wildcard, &exact, &cidr]).unwrap();
+        assert_eq!(
+            json,
+            r#"["*.example.com","xn--bcher-kva.example","10.0.0.0/8"]"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Vec<HostPattern>>(&json).unwrap(),
+            [wildcard, exact, cidr]
+        );
+    }
+
+    #[test]
+    fn port_range() {
+        let range = "8000-8010".parse::<PortRange>().unwrap();
+        assert!(range.contains(Port::new(8005)));
+        assert!(!range.contains(Port::new(8011)));
+        assert_eq!(range.iter().count(), 11);
+        assert_eq!("443".parse::<PortRange>().unwrap().to_string(), "443");
+        assert!("9000-8000".parse::<PortRange>().is_err());
+    }
+}
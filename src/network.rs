@@ -0,0 +1,302 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Network policy of the `[network]` section
+
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+};
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Cidr, Host, HostPattern, InvalidAddress, Port, PortRange};
+
+/// The `[network]` section
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Network {
+    /// policy for outbound connections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress: Option<EgressPolicy>,
+
+    /// name resolution settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns: Option<Dns>,
+}
+
+/// The `[network.egress]` policy for outbound connections
+///
+/// If present, only connections to the listed destinations are allowed.
+/// A destination is allowed, if its host matches one of `hosts` or its IP address is in one
+/// of `cidrs`, and its port is in one of the `ports` ranges. Empty `ports` allow all ports.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EgressPolicy {
+    /// allowed hosts, `*.example.com` matches all subdomains of `example.com`
+    #[serde(default)]
+    pub hosts: Vec<HostPattern>,
+
+    /// allowed IP networks
+    #[serde(default)]
+    pub cidrs: Vec<Cidr>,
+
+    /// allowed ports
+    #[serde(default)]
+    pub ports: Vec<PortRange>,
+}
+
+impl EgressPolicy {
+    /// is a connection to `host` and `port` allowed by the policy
+    pub fn allows(&self, host: &Host, port: Port) -> bool {
+        let port_allowed = self.ports.is_empty() || self.ports.iter().any(|r| r.contains(port));
+
+        let host_allowed = self.hosts.iter().any(|pattern| pattern.matches(host))
+            || matches!(host.ip(), Some(ip) if self.cidrs.iter().any(|c| c.contains(ip)));
+
+        port_allowed && host_allowed
+    }
+}
+
+/// Resolver used for name resolution
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Resolver {
+    /// ask the resolver of the untrusted host
+    #[serde(rename = "host")]
+    Host,
+
+    /// resolve inside the keep with plain DNS to the `nameservers`
+    #[serde(rename = "in-keep")]
+    InKeep,
+
+    /// resolve inside the keep with DNS over TLS (RFC 7858) to the `nameservers`
+    #[serde(rename = "dns-over-tls")]
+    DnsOverTls,
+}
+
+// `#[default]` on enum variants is not available on the MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::Host
+    }
+}
+
+impl Resolver {
+    /// the default port of the nameservers
+    pub fn default_port(self) -> Port {
+        match self {
+            Self::DnsOverTls => Port::new(853),
+            Self::Host | Self::InKeep => Port::new(53),
+        }
+    }
+}
+
+impl fmt::Display for Resolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Host => f.write_str("host"),
+            Self::InKeep => f.write_str("in-keep"),
+            Self::DnsOverTls => f.write_str("dns-over-tls"),
+        }
+    }
+}
+
+/// Address of a nameserver, like `"1.1.1.1"`, `"1.1.1.1:5353"` or `"[2606:4700::1111]:853"`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Nameserver {
+    /// IP address of the nameserver
+    pub ip: IpAddr,
+
+    /// port of the nameserver, defaults to the port of the [`Resolver`]
+    pub port: Option<Port>,
+}
+
+impl FromStr for Nameserver {
+    type Err = InvalidAddress;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(ip) = s.parse() {
+            return Ok(Self { ip, port: None });
+        }
+
+        s.parse::<SocketAddr>()
+            .map(|addr| Self {
+                ip: addr.ip(),
+                port: Some(addr.port().into()),
+            })
+            .map_err(|_| InvalidAddress::new(format!("invalid nameserver address `{}`", s)))
+    }
+}
+
+impl fmt::Display for Nameserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.port {
+            Some(port) => SocketAddr::new(self.ip, port.get()).fmt(f),
+            None => self.ip.fmt(f),
+        }
+    }
+}
+
+impl Serialize for Nameserver {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Nameserver {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let nameserver = String::deserialize(deserializer)?;
+        nameserver.parse().map_err(D::Error::custom)
+    }
+}
+
+/// The `[network.dns]` name resolution settings
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Dns {
+    /// the resolver to use
+    #[serde(default)]
+    pub resolver: Resolver,
+
+    /// the nameservers of the `in-keep` and `dns-over-tls` resolvers
+    #[serde(default)]
+    pub nameservers: Vec<Nameserver>,
+
+    /// the TLS server name of the `nameservers` for `dns-over-tls`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_name: Option<Host>,
+
+    /// domains to append to names without dots
+    #[serde(default)]
+    pub search: Vec<Host>,
+}
+
+impl Dns {
+    /// get the socket addresses of the nameservers, with the default port filled in
+    pub fn nameserver_addrs(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        let port = self.resolver.default_port();
+        self.nameservers
+            .iter()
+            .map(move |ns| SocketAddr::new(ns.ip, ns.port.unwrap_or(port).get()))
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), (&'static str, String)> {
+        match (self.resolver, self.nameservers.is_empty()) {
+            (Resolver::Host, false) => {
+                return Err((
+                    "nameservers",
+                    "the `host` resolver does not use `nameservers`".into(),
+                ))
+            }
+            (Resolver::InKeep, true) | (Resolver::DnsOverTls, true) => {
+                return Err((
+                    "nameservers",
+                    format!("the `{}` resolver requires `nameservers`", self.resolver),
+                ))
+            }
+            _ => {}
+        }
+
+        if self.tls_name.is_some() && self.resolver != Resolver::DnsOverTls {
+            return Err((
+                "tls_name",
+                "only applies to the `dns-over-tls` resolver".into(),
+            ));
+        }
+
+        if let Some(domain) = self.search.iter().find(|d| d.is_ip()) {
+            return Err(("search", format!("`{}` is not a domain name", domain)));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::EnarxConfig;
+
+    const CONFIG: &str = r#"
+        [network.egress]
+        hosts = [ "*.example.com", "api.github.com" ]
+        cidrs = [ "10.0.0.0/8" ]
+        ports = [ 443, "5432-5433" ]
+
+        [[files]]
+        kind = "connect"
+        host = "db.example.com"
+        port = 5432
+
+        [[files]]
+        kind = "connect"
+        host = "10.1.1.1"
+    "#;
+
+    #[test]
+    fn allows() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let egress = cfg.network.unwrap().egress.unwrap();
+        let allows = |host: &str, port: u16| egress.allows(&host.parse().unwrap(), port.into());
+
+        assert!(allows("db.example.com", 5433));
+        assert!(allows("a.b.example.com", 443));
+        assert!(allows("api.github.com", 443));
+        assert!(allows("10.0.0.1", 443));
+        assert!(!allows("example.com", 443));
+        assert!(!allows("db.example.com", 80));
+        assert!(!allows("github.com", 443));
+        assert!(!allows("192.168.1.1", 443));
+        assert!(allows("DB.Example.COM", 443));
+
+        assert!(toml::from_str::<EnarxConfig>("[network.egress]\nhosts = [ \"*.\" ]").is_err());
+    }
+
+    #[test]
+    fn validate() {
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        if let crate::File::Connect { port, .. } = &mut cfg.files[1] {
+            *port = 22.into();
+        }
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "files[1].host: `10.1.1.1:22` is not allowed by `network.egress`"
+        );
+    }
+
+    #[test]
+    fn dns() {
+        const CONFIG: &str = r#"
+        [network.dns]
+        resolver = "dns-over-tls"
+        nameservers = [ "1.1.1.1", "[2606:4700::1111]:8853" ]
+        tls_name = "cloudflare-dns.com"
+        search = [ "corp.example.com" ]
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let dns = cfg.network.as_ref().unwrap().dns.as_ref().unwrap();
+        assert_eq!(
+            dns.nameserver_addrs().collect::<Vec<_>>(),
+            vec![
+                "1.1.1.1:853".parse().unwrap(),
+                "[2606:4700::1111]:8853".parse().unwrap()
+            ]
+        );
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let err = toml::from_str::<EnarxConfig>("[network.dns]\nresolver = \"in-keep\"")
+            .unwrap()
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "network.dns.nameservers: the `in-keep` resolver requires `nameservers`"
+        );
+    }
+}
@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Capture limits of `kind = "stdout"` and `kind = "stderr"` files
+
+use std::num::NonZeroU64;
+
+use serde::{Deserialize, Serialize};
+
+use crate::File;
+
+/// What happens when the output of a file exceeds its `max_bytes`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Overflow {
+    /// discard all further output
+    #[serde(rename = "truncate")]
+    Truncate,
+
+    /// move the full `path` to `<path>.1` and start a new file
+    #[serde(rename = "rotate")]
+    Rotate,
+
+    /// fail the write of the application
+    #[serde(rename = "fail")]
+    Fail,
+}
+
+// `#[default]` on enum variants is not available on the MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for Overflow {
+    fn default() -> Self {
+        Overflow::Truncate
+    }
+}
+
+/// The effective capture limit of an output file, see [`File::output_limit`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OutputLimit {
+    /// maximum number of bytes captured
+    pub max_bytes: NonZeroU64,
+
+    /// what happens when `max_bytes` is exceeded
+    pub on_overflow: Overflow,
+}
+
+impl File {
+    /// get the capture limit of a `kind = "stdout"` or `kind = "stderr"` file
+    pub fn output_limit(&self) -> Option<OutputLimit> {
+        match self {
+            File::Stdout {
+                max_bytes: Some(max_bytes),
+                on_overflow,
+                ..
+            }
+            | File::Stderr {
+                max_bytes: Some(max_bytes),
+                on_overflow,
+                ..
+            } => Some(OutputLimit {
+                max_bytes: *max_bytes,
+                on_overflow: on_overflow.unwrap_or_default(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    #[test]
+    fn output_limit() {
+        const CONFIG: &str = r#"
+        [[files]]
+        kind = "stdout"
+        max_bytes = 1048576
+
+        [[files]]
+        kind = "stderr"
+        path = "/var/log/app/stderr.log"
+        max_bytes = 4096
+        on_overflow = "rotate"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(
+            cfg.files[0].output_limit(),
+            Some(OutputLimit {
+                max_bytes: NonZeroU64::new(1048576).unwrap(),
+                on_overflow: Overflow::Truncate,
+            })
+        );
+        assert_eq!(
+            cfg.files[1].output_limit().map(|l| l.on_overflow),
+            Some(Overflow::Rotate)
+        );
+        assert_eq!(cfg.validate(), Ok(()));
+    }
+}
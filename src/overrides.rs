@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Overrides of configuration fields by `ENARX_*` environment variables
+
+use serde_json::Value;
+
+use crate::{EnarxConfig, Error, File, Steward};
+
+/// prefix of the environment variables overriding configuration fields
+pub const OVERRIDE_PREFIX: &str = "ENARX_";
+
+impl EnarxConfig {
+    /// apply the `ENARX_*` environment variables of the process, see [`EnarxConfig::apply_overrides`]
+    ///
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn apply_env_overrides(&mut self) -> Result<(), Error> {
+        self.apply_overrides(std::env::vars())
+    }
+
+    /// apply twelve-factor style overrides of configuration fields
+    ///
+    /// | Variable                  | Overrides                                         |
+    /// |---------------------------|---------------------------------------------------|
+    /// | `ENARX_STEWARD`           | the URL of `steward`                              |
+    /// | `ENARX_ENV_<VAR>`         | the environment variable `<VAR>` of `env`         |
+    /// | `ENARX_FILES_<NAME>_<FIELD>` | the field `<field>` of the file named `<NAME>` |
+    ///
+    /// File names are matched case-insensitively, field names are lowercase.
+    /// Values are interpreted as TOML integers and booleans, if the field accepts them,
+    /// and as strings otherwise. Other variables are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::EnarxConfig;
+    ///
+    /// let mut config: EnarxConfig = toml::from_str(r#"
+    /// [[files]]
+    /// name = "LISTEN"
+    /// kind = "listen"
+    /// port = 8080
+    /// "#).unwrap();
+    ///
+    /// config.apply_overrides([("ENARX_FILES_LISTEN_PORT", "443")]).unwrap();
+    /// assert_eq!(config.files[0].as_listen().unwrap().port, 443.into());
+    /// ```
+    pub fn apply_overrides<I, K, V>(&mut self, vars: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (key, value) in vars {
+            let (key, value) = (key.as_ref(), value.as_ref());
+            let key = match key.strip_prefix(OVERRIDE_PREFIX) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            if key == "STEWARD" {
+                let url = value.parse().map_err(|e| Error::Parse {
+                    path: Some("steward".into()),
+                    location: None,
+                    message: format!("invalid URL `{}` of `ENARX_STEWARD`: {}", value, e),
+                })?;
+                match &mut self.steward {
+                    Some(steward) => steward.url = url,
+                    None => self.steward = Some(Steward::from(url)),
+                }
+            } else if let Some(var) = key.strip_prefix("ENV_") {
+                self.env.insert(var.into(), value.into());
+            } else if let Some(spec) = key.strip_prefix("FILES_") {
+                self.override_file(spec, value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn override_file(&mut self, spec: &str, value: &str) -> Result<(), Error> {
+        // the longest matching name wins, names may contain `_`
+        let found = self
+            .files
+            .iter()
+            .enumerate()
+            .filter_map(|(i, file)| {
+                let name = file.name().to_ascii_uppercase();
+                let field = spec.strip_prefix(&name)?.strip_prefix('_')?;
+                Some((i, name.len(), field.to_ascii_lowercase()))
+            })
+            .max_by_key(|(_, len, _)| *len);
+
+        let (i, _, field) = match found {
+            Some(found) => found,
+            None => {
+                return Err(Error::Parse {
+                    path: Some("files".into()),
+                    location: None,
+                    message: format!("no file matches `ENARX_FILES_{}`", spec),
+                })
+            }
+        };
+
+        let file = &mut self.files[i];
+        // serializing the config types into a `Value` cannot fail, all map keys are strings
+        let mut entry = serde_json::to_value(&*file).expect("file is serializable");
+
+        let mut candidates = vec![Value::String(value.into())];
+        if let Ok(literal) = serde_json::from_str::<Value>(value) {
+            if literal.is_number() || literal.is_boolean() {
+                candidates.insert(0, literal);
+            }
+        }
+
+        let mut error = None;
+        for candidate in candidates {
+            entry[field.as_str()] = candidate;
+            match serde_json::from_value::<File>(entry.clone()) {
+                Ok(parsed) => {
+                    *file = parsed;
+                    return Ok(());
+                }
+                Err(e) => error = error.or(Some(e)),
+            }
+        }
+
+        Err(Error::Parse {
+            path: Some(format!("files[{}].{}", i, field)),
+            location: None,
+            message: format!(
+                "invalid value `{}` of `ENARX_FILES_{}`: {}",
+                value,
+                spec,
+                error.expect("at least one candidate was tried")
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+    steward = "https://steward.example.com/"
+
+    [[files]]
+    name = "MY_API"
+    kind = "listen"
+    port = 8080
+
+    [[files]]
+    kind = "connect"
+    host = "db.example.com"
+    "#;
+
+    #[test]
+    fn overrides() {
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        cfg.apply_overrides([
+            ("ENARX_STEWARD", "https://steward.test/"),
+            ("ENARX_ENV_FOO", "foo"),
+            ("ENARX_FILES_MY_API_PORT", "443"),
+            ("ENARX_FILES_MY_API_PROT", "tcp"),
+            ("ENARX_FILES_DB.EXAMPLE.COM_NODELAY", "true"),
+            ("ENARX_BACKEND", "kvm"),
+            ("PATH", "/bin"),
+        ])
+        .unwrap();
+
+        assert_eq!(cfg.steward.unwrap().url.as_str(), "https://steward.test/");
+        assert_eq!(cfg.env["FOO"], "foo");
+
+        let listen = cfg.files[0].as_listen().unwrap();
+        assert_eq!(listen.port, 443.into());
+        assert_eq!(listen.prot, crate::Protocol::Tcp);
+        assert!(cfg.files[1].as_connect().unwrap().nodelay);
+    }
+
+    #[test]
+    fn invalid() {
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+
+        let err = cfg
+            .apply_overrides([("ENARX_FILES_MY_API_PORT", "http")])
+            .unwrap_err();
+        assert_eq!(err.path(), Some("files[0].port"));
+
+        let err = cfg
+            .apply_overrides([("ENARX_FILES_UNKNOWN_PORT", "1")])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "files: no file matches `ENARX_FILES_UNKNOWN_PORT`"
+        );
+    }
+}
@@ -0,0 +1,293 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Deployment guardrails enforced on configurations
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{EnarxConfig, File, Protocol, SignedArtifact, TlsVersion};
+
+/// The guardrails of an organization, which configurations are checked against with
+/// [`EnarxConfig::enforce`]
+///
+/// Deserializable, e.g. from a TOML file maintained next to the deployment tooling.
+/// Unknown keys are rejected, so a misspelled guardrail is not silently ignored.
+///
+/// # Examples
+///
+/// ```
+/// extern crate toml;
+/// use enarx_config::{EnarxConfig, PolicyProfile};
+///
+/// let policy: PolicyProfile = toml::from_str(r#"
+/// forbid_plaintext_tcp = true
+/// forbid_debug = true
+/// "#).unwrap();
+///
+/// let config: EnarxConfig = toml::from_str(r#"
+/// [[files]]
+/// name = "API"
+/// kind = "listen"
+/// prot = "tcp"
+/// "#).unwrap();
+///
+/// let violations = config.enforce(&policy).unwrap_err();
+/// assert_eq!(violations[0].to_string(), "files[0].prot: plaintext TCP is forbidden");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyProfile {
+    /// forbid `listen` and `connect` files and the `[telemetry]` export with `prot = "tcp"`
+    #[serde(default)]
+    pub forbid_plaintext_tcp: bool,
+
+    /// forbid a plaintext `http://` Steward allowed by `steward_insecure`
+    #[serde(default)]
+    pub forbid_insecure_tls: bool,
+
+    /// the minimum TLS version all `prot = "tls"` files have to require with `min_version`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_tls_version: Option<TlsVersion>,
+
+    /// forbid the `[debug]` section
+    #[serde(default)]
+    pub forbid_debug: bool,
+
+    /// require a `steward` issuing the certificate of the keep
+    #[serde(default)]
+    pub require_steward: bool,
+
+    /// require the workload to be pinned by the `sha256` of every application, or by a
+    /// signer of the `[signature]` section trusted for the workload
+    #[serde(default)]
+    pub require_pinned_workload: bool,
+}
+
+impl PolicyProfile {
+    /// the guardrails of a production deployment, all enabled and requiring TLS 1.3
+    pub fn production() -> Self {
+        Self {
+            forbid_plaintext_tcp: true,
+            forbid_insecure_tls: true,
+            min_tls_version: Some(TlsVersion::Tls13),
+            forbid_debug: true,
+            require_steward: true,
+            require_pinned_workload: true,
+        }
+    }
+}
+
+/// A setting of a configuration violating a [`PolicyProfile`]
+///
+/// Contains the path of the setting, e.g. `files[2].prot`, and a description.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyViolation {
+    path: String,
+    message: String,
+}
+
+impl PolicyViolation {
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    /// the path of the setting
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// the description of the violation
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl EnarxConfig {
+    /// check the configuration against the guardrails of `policy`
+    ///
+    /// Returns all violations, files with a `when` condition are checked as well.
+    pub fn enforce(&self, policy: &PolicyProfile) -> Result<(), Vec<PolicyViolation>> {
+        let mut violations = vec![];
+        let mut violation = |path: String, message: &str| {
+            violations.push(PolicyViolation::new(path, message));
+        };
+
+        for (i, file) in self.resolve_templates_lossy().iter().enumerate() {
+            let file = match file {
+                File::Conditional { file, .. } => file,
+                file => file,
+            };
+            match file.prot() {
+                Some(Protocol::Tcp) if policy.forbid_plaintext_tcp => {
+                    violation(format!("files[{}].prot", i), "plaintext TCP is forbidden")
+                }
+                Some(Protocol::Tls) => {
+                    let min_version = file
+                        .effective_tls(self)
+                        .and_then(|tls| tls.min_version)
+                        .unwrap_or(TlsVersion::Tls12);
+                    if matches!(policy.min_tls_version, Some(required) if min_version < required) {
+                        violation(
+                            format!("files[{}].tls.min_version", i),
+                            "the TLS version is below the required minimum",
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if policy.forbid_plaintext_tcp
+            && matches!(&self.telemetry, Some(telemetry) if telemetry.prot == Protocol::Tcp)
+        {
+            violation("telemetry.prot".into(), "plaintext TCP is forbidden");
+        }
+        if policy.forbid_insecure_tls && self.steward_insecure {
+            violation(
+                "steward_insecure".into(),
+                "a plaintext Steward is forbidden",
+            );
+        }
+        if policy.forbid_debug && self.debug.is_some() {
+            violation("debug".into(), "debugging facilities are forbidden");
+        }
+        if policy.require_steward && self.steward.is_none() {
+            violation("steward".into(), "a Steward is required");
+        }
+
+        let signed = matches!(
+            &self.signature,
+            Some(signature) if signature.for_artifact(SignedArtifact::Workload).next().is_some()
+        );
+        if policy.require_pinned_workload && !signed {
+            if self.apps.is_empty() {
+                violation(
+                    "signature".into(),
+                    "the workload has to be pinned by a signer trusted for the workload",
+                );
+            }
+            for (name, app) in &self.apps {
+                if app.sha256.is_none() {
+                    violation(
+                        format!("apps.{}.sha256", name),
+                        "the workload has to be pinned by its digest or a trusted signer",
+                    );
+                }
+            }
+        }
+
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(violations),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn violations(config: &str, policy: &PolicyProfile) -> Vec<String> {
+        toml::from_str::<EnarxConfig>(config)
+            .unwrap()
+            .enforce(policy)
+            .err()
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn production() {
+        const CONFIG: &str = r#"
+        steward_insecure = true
+
+        [debug]
+        backtraces = true
+
+        [telemetry]
+        exporter = "statsd"
+        host = "10.0.0.1"
+        prot = "tcp"
+
+        [apps.api]
+        sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+
+        [apps.worker]
+
+        [[files]]
+        name = "API"
+        kind = "listen"
+        prot = "tls"
+        tls = { min_version = "1.3" }
+
+        [[files]]
+        kind = "connect"
+        host = "db.example.com"
+        prot = "tcp"
+        when = { profile = "dev" }
+
+        [[files]]
+        kind = "connect"
+        host = "cache.example.com"
+        "#;
+
+        assert_eq!(
+            violations(CONFIG, &PolicyProfile::production()),
+            [
+                "files[1].prot: plaintext TCP is forbidden",
+                "files[2].tls.min_version: the TLS version is below the required minimum",
+                "telemetry.prot: plaintext TCP is forbidden",
+                "steward_insecure: a plaintext Steward is forbidden",
+                "debug: debugging facilities are forbidden",
+                "steward: a Steward is required",
+                "apps.worker.sha256: the workload has to be pinned by its digest or a trusted signer",
+            ]
+        );
+        assert!(violations(CONFIG, &PolicyProfile::default()).is_empty());
+    }
+
+    #[test]
+    fn compliant() {
+        const CONFIG: &str = r#"
+        steward = "https://steward.example.com"
+
+        [tls]
+        min_version = "1.3"
+
+        [[signature.signers]]
+        id = "release"
+        algorithm = "ed25519"
+        artifacts = [ "workload" ]
+
+        [[files]]
+        name = "API"
+        kind = "listen"
+        "#;
+
+        let policy = PolicyProfile::production();
+        assert!(violations(CONFIG, &policy).is_empty());
+
+        let unsigned = CONFIG.replace("[ \"workload\" ]", "[ \"config\" ]");
+        assert_eq!(
+            violations(&unsigned, &policy),
+            ["signature: the workload has to be pinned by a signer trusted for the workload"]
+        );
+
+        let policy: PolicyProfile =
+            toml::from_str("min_tls_version = \"1.3\"\nrequire_steward = true").unwrap();
+        assert_eq!(policy.min_tls_version, Some(TlsVersion::Tls13));
+        assert!(toml::from_str::<PolicyProfile>("forbid_plaintext = true").is_err());
+    }
+}
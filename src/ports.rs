@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Listeners on multiple ports, expanded into one file per port
+
+use std::collections::BTreeSet;
+
+use crate::{EnarxConfig, File, PortRange, ValidationError};
+
+impl File {
+    /// get all ports of a `kind = "listen"` file listening on more than its `port`,
+    /// empty otherwise
+    pub fn ports(&self) -> &[PortRange] {
+        match self {
+            Self::Listen { ports, .. } => ports,
+            Self::Conditional { file, .. } => file.ports(),
+            _ => &[],
+        }
+    }
+}
+
+/// expand the listeners with `ports` into one listener per port, named `<name>_<port>`
+///
+/// A single port is just the `port` of the listener, which keeps its name.
+pub(crate) fn expand_ports(files: Vec<File>) -> Vec<File> {
+    files.into_iter().flat_map(expand).collect()
+}
+
+fn expand(file: File) -> Vec<File> {
+    match file {
+        File::Conditional { when, file } => expand(*file)
+            .into_iter()
+            .map(|file| File::Conditional {
+                when: when.clone(),
+                file: Box::new(file),
+            })
+            .collect(),
+        File::Listen {
+            ref name,
+            ref ports,
+            ..
+        } if !ports.is_empty() => {
+            let name = name.to_string();
+            let ports = ports.iter().flat_map(PortRange::iter).collect::<Vec<_>>();
+            let single = ports.len() == 1;
+
+            ports
+                .into_iter()
+                .map(|port| {
+                    let mut file = file.clone();
+                    if let File::Listen {
+                        name: file_name,
+                        port: file_port,
+                        ports,
+                        ..
+                    } = &mut file
+                    {
+                        if !single {
+                            *file_name = format!("{}_{}", name, port).into();
+                        }
+                        *file_port = port;
+                        ports.clear();
+                    }
+                    file
+                })
+                .collect()
+        }
+        file => vec![file],
+    }
+}
+
+impl EnarxConfig {
+    pub(crate) fn validate_ports(&self) -> Result<(), ValidationError> {
+        for (i, file) in self.files.iter().enumerate() {
+            if file.ports().is_empty() {
+                continue;
+            }
+            let path = format!("files[{}].ports", i);
+
+            let file = match file {
+                File::Conditional { file, .. } => file,
+                file => file,
+            };
+            if let File::Listen {
+                ephemeral: true, ..
+            } = file
+            {
+                return Err(ValidationError::new(
+                    path,
+                    "`ports` can not be combined with `ephemeral`",
+                ));
+            }
+
+            let mut seen = BTreeSet::new();
+            for port in file.ports().iter().flat_map(PortRange::iter) {
+                if port.is_ephemeral() {
+                    return Err(ValidationError::new(path, "can not listen on port 0"));
+                }
+                if !seen.insert(port) {
+                    return Err(ValidationError::new(
+                        path,
+                        format!("port {} is listed more than once", port),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Port;
+
+    #[test]
+    fn ports() {
+        const CONFIG: &str = r#"
+        [[files]]
+        name = "API"
+        kind = "listen"
+        port = [ 8080, 8443 ]
+
+        [[files]]
+        name = "RANGE"
+        kind = "listen"
+        prot = "tcp"
+        ports = "9000-9002"
+        when = { profile = "dev" }
+
+        [[files]]
+        name = "SINGLE"
+        kind = "listen"
+        ports = 7000
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let api = cfg.files[0].as_listen().unwrap();
+        assert_eq!(api.port, 8080.into());
+        assert_eq!(api.ports, [Port::new(8080).into(), Port::new(8443).into()]);
+        assert_eq!(cfg.files[1].ports(), ["9000-9002".parse().unwrap()]);
+
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), cfg);
+
+        let normalized = cfg.normalize();
+        assert_eq!(
+            normalized
+                .files
+                .iter()
+                .map(|file| (file.name(), file.as_listen().map(|l| l.port.get())))
+                .collect::<Vec<_>>(),
+            [
+                ("API_8080", Some(8080)),
+                ("API_8443", Some(8443)),
+                ("RANGE_9000", None),
+                ("RANGE_9001", None),
+                ("RANGE_9002", None),
+                ("SINGLE", Some(7000)),
+            ]
+        );
+        assert!(normalized.files.iter().all(|file| file.ports().is_empty()));
+        assert!(normalized.files[2].when().is_some());
+        assert_eq!(normalized.validate(), Ok(()));
+        assert_eq!(normalized.normalize(), normalized);
+    }
+
+    #[test]
+    fn invalid() {
+        let validate = |config: &str| {
+            toml::from_str::<EnarxConfig>(config)
+                .map_err(|e| e.to_string())?
+                .validate()
+                .map_err(|e| e.to_string())
+        };
+
+        assert_eq!(
+            validate("[[files]]\nname = \"A\"\nkind = \"listen\"\nports = \"8000-8002\"\n[[files]]\nname = \"A_8001\"\nkind = \"listen\""),
+            Err("files[3].name: duplicate file name `A_8001`".into())
+        );
+        assert_eq!(
+            validate("[[files]]\nname = \"A\"\nkind = \"listen\"\nports = [ \"8000-8002\", 8001 ]"),
+            Err("files[0].ports: port 8001 is listed more than once".into())
+        );
+        assert_eq!(
+            validate("[[files]]\nname = \"A\"\nkind = \"listen\"\nports = [ 0, 1 ]"),
+            Err("files[0].ports: can not listen on port 0".into())
+        );
+        assert_eq!(
+            validate("[limits]\nmax_fds = 3\n[[files]]\nname = \"A\"\nkind = \"listen\"\nports = \"8000-8003\""),
+            Err("limits.max_fds: 4 file descriptors exceed the limit of 3".into())
+        );
+        assert!(
+            validate("[[files]]\nname = \"A\"\nkind = \"listen\"\nport = 1\nports = [ 2, 3 ]")
+                .unwrap_err()
+                .contains("`ports` can not be combined with a different `port`")
+        );
+        assert!(
+            validate("[[files]]\nname = \"A\"\nkind = \"listen\"\nports = []")
+                .unwrap_err()
+                .contains("`ports` must contain at least one port")
+        );
+    }
+}
@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Example configurations for documentation, tests and templates
+
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::{
+    default_backlog, AddressFamily, EnarxConfig, File, Health, HealthProtocol, ListenAddr,
+    ListenMode, Protocol,
+};
+
+impl EnarxConfig {
+    /// a configuration without any pre-opened file descriptors, arguments or environment variables
+    pub fn minimal() -> Self {
+        Self {
+            files: vec![],
+            ..Default::default()
+        }
+    }
+
+    /// an HTTPS server listening on port 8443 with the stdio file descriptors
+    ///
+    /// The listener is named `API` and also serves the `/healthz` liveness check.
+    pub fn example_http_server() -> Self {
+        let mut config = Self::default();
+
+        config.files.push(File::Listen {
+            name: "API".into(),
+            addr: ListenAddr::Any,
+            port: 8443.into(),
+            ports: vec![],
+            ephemeral: false,
+            backlog: default_backlog(),
+            reuseaddr: false,
+            reuseport: false,
+            keepalive: None,
+            nodelay: true,
+            priority: None,
+            idle_timeout: None,
+            allow: vec![],
+            max_connections_per_second: None,
+            max_concurrent_connections: None,
+            selftest: false,
+            prot: Protocol::Tls,
+            tls: None,
+            cert: None,
+            key: None,
+            psk: None,
+            mode: ListenMode::Raw,
+            routes: vec![],
+            service: None,
+            labels: BTreeMap::new(),
+        });
+        config.health = Some(Health {
+            listener: Some("API".into()),
+            port: None,
+            path: Some("/healthz".into()),
+            prot: HealthProtocol::Http,
+        });
+
+        config
+    }
+
+    /// a client connecting to `api.example.com:443` with TLS, with the stdio file descriptors
+    ///
+    /// Connection attempts time out after 10 seconds and are retried 3 times.
+    pub fn example_client() -> Self {
+        let mut config = Self::default();
+
+        config.files.push(File::Connect {
+            name: Some("API".into()),
+            host: "api.example.com".parse().expect("valid host"),
+            port: 443.into(),
+            fallback: vec![],
+            family: AddressFamily::Any,
+            happy_eyeballs: true,
+            proxy: true,
+            prot: Protocol::Tls,
+            tls: None,
+            psk: None,
+            advertise_identity: false,
+            keepalive: None,
+            nodelay: false,
+            priority: None,
+            idle_timeout: None,
+            timeout: Some(Duration::from_secs(10)),
+            retries: 3,
+            retry_backoff: Some(Duration::from_millis(500)),
+            service: None,
+            labels: BTreeMap::new(),
+        });
+
+        config
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn presets() -> Vec<EnarxConfig> {
+        vec![
+            EnarxConfig::minimal(),
+            EnarxConfig::example_http_server(),
+            EnarxConfig::example_client(),
+        ]
+    }
+
+    #[test]
+    fn valid() {
+        for config in presets() {
+            assert_eq!(config.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for config in presets() {
+            let json = serde_json::to_string(&config).unwrap();
+            assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), config);
+
+            let toml = toml::to_string(&toml::Value::try_from(&config).unwrap()).unwrap();
+            assert_eq!(toml::from_str::<EnarxConfig>(&toml).unwrap(), config);
+
+            let commented = config.to_commented_toml();
+            assert_eq!(toml::from_str::<EnarxConfig>(&commented).unwrap(), config);
+        }
+    }
+}
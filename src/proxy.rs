@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Outbound proxy of the `[proxy]` section
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Host, Port};
+
+/// Protocol of an outbound [`Proxy`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ProxyKind {
+    /// SOCKS5 proxy (RFC 1928)
+    #[serde(rename = "socks5")]
+    Socks5,
+
+    /// HTTP proxy supporting the `CONNECT` method
+    #[serde(rename = "http")]
+    Http,
+}
+
+impl fmt::Display for ProxyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Socks5 => f.write_str("socks5"),
+            Self::Http => f.write_str("http"),
+        }
+    }
+}
+
+/// The `[proxy]` section, tunneling all `kind = "connect"` files through a proxy
+///
+/// Single files opt out with `proxy = false`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Proxy {
+    /// protocol of the proxy
+    pub kind: ProxyKind,
+
+    /// host of the proxy
+    pub host: Host,
+
+    /// port of the proxy
+    pub port: Port,
+
+    /// user name to authenticate with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// name of the secret in the `[secrets]` section holding the password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    const CONFIG: &str = r#"
+        [secrets]
+        proxy-password = { env = "PROXY_PASSWORD" }
+
+        [proxy]
+        kind = "socks5"
+        host = "proxy.corp.example.com"
+        port = 1080
+        username = "keep"
+        password = "proxy-password"
+
+        [[files]]
+        kind = "connect"
+        host = "internal.example.com"
+        proxy = false
+    "#;
+
+    #[test]
+    fn proxy() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let proxy = cfg.proxy.as_ref().unwrap();
+
+        assert_eq!(proxy.kind, ProxyKind::Socks5);
+        assert_eq!(proxy.port.get(), 1080);
+        assert!(!cfg.files[0].as_connect().unwrap().proxy);
+        assert_eq!(cfg.validate(), Ok(()));
+    }
+
+    #[test]
+    fn missing_secret() {
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        cfg.secrets.clear();
+
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "proxy.password: unknown secret `proxy-password`"
+        );
+    }
+}
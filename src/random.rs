@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Entropy policy of the `[random]` section
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Source of the random numbers provided to the application
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum EntropySource {
+    /// the random number generator of the CPU, e.g. `RDRAND`
+    #[serde(rename = "hardware")]
+    Hardware,
+
+    /// the random number generator of the untrusted host
+    #[serde(rename = "host")]
+    Host,
+
+    /// the hardware random number generator mixed with the one of the host
+    #[serde(rename = "mixed")]
+    Mixed,
+}
+
+// `#[default]` on enum variants is not available on the MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for EntropySource {
+    fn default() -> Self {
+        EntropySource::Hardware
+    }
+}
+
+impl fmt::Display for EntropySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hardware => f.write_str("hardware"),
+            Self::Host => f.write_str("host"),
+            Self::Mixed => f.write_str("mixed"),
+        }
+    }
+}
+
+/// The `[random]` section
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Random {
+    /// source of the random numbers
+    #[serde(default)]
+    pub source: EntropySource,
+
+    /// seed of a deterministic random number generator replacing `source`,
+    /// requires `debug.allow_debug = true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    #[test]
+    fn seed() {
+        let mut cfg: EnarxConfig = toml::from_str("[random]\nseed = 42").unwrap();
+        assert_eq!(cfg.random.as_ref().unwrap().source, EntropySource::Hardware);
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "random.seed: a deterministic seed requires `debug.allow_debug = true`"
+        );
+
+        cfg.debug = Some(crate::DebugOptions {
+            allow_debug: true,
+            ..Default::default()
+        });
+        assert_eq!(cfg.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,881 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reference documentation of the configuration fields, and the schema they describe
+
+use crate::EnarxConfig;
+
+/// A documented field of the configuration
+///
+/// Returned by [`EnarxConfig::schema`], e.g. for completion and hover documentation
+/// in editors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SchemaField {
+    /// dotted path of the field, array indices are left out, e.g. `files.port`
+    pub path: &'static str,
+
+    /// TOML type of the field, e.g. `"string"` or `"array of tables"`
+    pub ty: &'static str,
+
+    /// default value, if the field is optional
+    pub default: Option<&'static str>,
+
+    /// constraints checked by deserialization or [`EnarxConfig::validate`]
+    pub constraint: Option<&'static str>,
+
+    /// description of the field
+    pub doc: &'static str,
+
+    /// the allowed values of string fields, and of the elements of arrays of strings,
+    /// empty if any value is allowed
+    pub values: &'static [&'static str],
+}
+
+const fn field(path: &'static str, ty: &'static str, doc: &'static str) -> SchemaField {
+    SchemaField {
+        path,
+        ty,
+        default: None,
+        constraint: None,
+        doc,
+        values: &[],
+    }
+}
+
+impl SchemaField {
+    const fn default(self, default: &'static str) -> Self {
+        Self {
+            default: Some(default),
+            ..self
+        }
+    }
+
+    const fn constraint(self, constraint: &'static str) -> Self {
+        Self {
+            constraint: Some(constraint),
+            ..self
+        }
+    }
+
+    const fn values(self, values: &'static [&'static str]) -> Self {
+        Self { values, ..self }
+    }
+
+    /// get the name of the field, the last key of its path
+    pub fn name(&self) -> &'static str {
+        self.path.rsplit('.').next().unwrap_or(self.path)
+    }
+}
+
+/// all fields of the configuration, in the order of `Enarx_toml.md`
+#[rustfmt::skip]
+pub(crate) const FIELDS: &[SchemaField] = &[
+    field("env", "table of strings", "The environment variables to provide to the application").default("`{}`"),
+    field("env_exports", "array of strings", "patterns of the environment variables the application may read, like \"APP_*\"").default("all").constraint("variable names, optionally followed by `*`"),
+    field("args", "array of strings", "The arguments to provide to the application").default("`[]`"),
+    field("labels", "table of strings", "free-form labels of the configuration, e.g. for orchestration").default("`{}`").constraint("at most 64, keys of 1-63 letters, digits, `-`, `_`, `.` and `/`, values up to 255 bytes"),
+    field("steward", "string or table", "The Steward issuing the TLS certificate, a URL or a table").constraint("a `https://` URL"),
+    field("steward.url", "string", "URL of the Steward").constraint("a `https://` URL with a host, no credentials or fragment"),
+    field("steward.pin", "array of strings", "hex SHA-256 digests of the public keys (SPKI) the Steward may present").default("any key").constraint("requires `https://`"),
+    field("steward.ca", "string", "PEM bundle of CA certificates the Steward certificate is verified against").default("the trust store of the keep").constraint("a path of the host, requires `https://`"),
+    field("steward.trust", "array of strings", "names of trust anchors the Steward certificate is verified against").default("the trust store of the keep").constraint("`trust` names, requires `https://`"),
+    field("steward.connect_timeout", "duration", "timeout of establishing the connection to the Steward").default("chosen by the runtime").constraint("not zero"),
+    field("steward.request_timeout", "duration", "timeout of a single request to the Steward").default("chosen by the runtime").constraint("not zero"),
+    field("steward.retries", "integer", "number of additional attempts after a failed request").default("`0`"),
+    field("steward.retry_backoff", "duration", "delay between the attempts").constraint("requires `retries`"),
+    field("steward.csr", "table", "parameters of the certificate request"),
+    field("steward.csr.common_name", "string", "common name of the subject").default("chosen by the runtime").constraint("not empty"),
+    field("steward.csr.dns_sans", "array of strings", "additional DNS names of the subject alternative name extension").default("`[]`").constraint("DNS names"),
+    field("steward.csr.ip_sans", "array of strings", "additional IP addresses of the subject alternative name extension").default("`[]`").constraint("IP addresses"),
+    field("steward.csr.key_algorithm", "string", "algorithm of the key: \"p256\" or \"ed25519\"").default("chosen by the runtime").values(&["p256", "ed25519"]),
+    field("steward.csr.validity", "duration", "requested validity of the certificate").default("chosen by the Steward").constraint("not zero"),
+    field("not_before", "timestamp", "RFC 3339 time before which the configuration must not be used"),
+    field("not_after", "timestamp", "RFC 3339 time after which the configuration must not be used").constraint("after `not_before`"),
+    field("steward_insecure", "boolean", "allow a plaintext `http://` Steward, only for local development").default("`false`"),
+    field("attestation", "table", "The attestation policy of the keep"),
+    field("attestation.mode", "string", "how the collateral of the attestation evidence is obtained: \"online\", \"cached\" or \"none\"").default("`\"online\"`").constraint("not `\"none\"` with a `steward`").values(&["online", "cached", "none"]),
+    field("attestation.collateral", "string", "pre-fetched collateral like the VCEK certificate and CRLs").constraint("`http(s)://` URL or path of the host, required for and only with `mode = \"cached\"`"),
+    field("attestation.trust", "array of strings", "names of trust anchors the attestation evidence is verified against").default("`[]`").constraint("`trust` names"),
+    field("identity", "table", "The identity of the workload"),
+    field("identity.spiffe_id", "string", "SPIFFE ID of the workload").constraint("`spiffe://<trust domain>/<path>`"),
+    field("identity.trust_domain", "string", "trust domain of the workload").default("the trust domain of `spiffe_id`").constraint("the trust domain of `spiffe_id`"),
+    field("identity.uri_san", "boolean", "embed `spiffe_id` as URI SAN in the certificate issued by the Steward").default("`false`").constraint("requires `steward`"),
+    field("runtime", "table", "Conveniences for the environment of the application"),
+    field("runtime.timezone", "string", "IANA time zone name, provided as `TZ`").constraint("an IANA time zone name"),
+    field("runtime.locale", "string", "locale name, provided as `LANG`").constraint("`language[_territory][.codeset][@modifier]`, `C` or `POSIX`"),
+    field("runtime.export_env", "boolean", "provide the address and port of every `listen` and `connect` file as `<NAME>_ADDR` and `<NAME>_PORT`").default("`false`").constraint("unique `<NAME>`"),
+    field("signature", "table", "The signers trusted for referenced artifacts"),
+    field("signature.signers", "array of tables", "a trusted signer").default("`[]`"),
+    field("signature.signers.id", "string", "identifier of the key").constraint("`id` or `key` required"),
+    field("signature.signers.algorithm", "string", "algorithm of the key: \"ed25519\" or \"p256\"").values(&["ed25519", "p256"]),
+    field("signature.signers.key", "string", "base64 or PEM encoded public key").constraint("valid key of `algorithm`"),
+    field("signature.signers.artifacts", "array of strings", "the artifacts the signer is trusted for: \"workload\", \"config\" or \"include\"").default("all").values(&["workload", "config", "include"]),
+    field("secrets", "table of secrets", "Named secrets referenced by other sections").default("`{}`"),
+    field("trust", "table of trust anchors", "Named trust anchors referenced by other sections").default("`{}`"),
+    field("tls", "table", "TLS settings of all `prot = \"tls\"` files"),
+    field("tls.min_version", "string", "minimum accepted TLS version: \"1.2\" or \"1.3\"").default("`\"1.2\"`").values(&["1.2", "1.3"]),
+    field("tls.key_exchange", "string", "preferred key exchange: \"classical\", \"hybrid\" or \"pq-only\"").default("`\"classical\"`").constraint("`\"pq-only\"` not with `min_version = \"1.2\"`").values(&["classical", "hybrid", "pq-only"]),
+    field("tls.alpn", "array of strings", "offered application protocols (ALPN) in order of preference").constraint("1-255 bytes each"),
+    field("tls.ca", "string", "PEM bundle of CA certificates replacing the trust store of the keep").constraint("a path of the host"),
+    field("tls.trust", "array of strings", "names of trust anchors replacing the trust store of the keep").constraint("`trust` names"),
+    field("tls.client_auth", "string", "whether listeners request client certificates: \"none\", \"optional\" or \"required\"").default("`\"none\"`").constraint("`kind = \"listen\"` only").values(&["none", "optional", "required"]),
+    field("tls.server_names", "array of strings", "server names (SNI) listeners accept, all if not specified").constraint("`kind = \"listen\"` only, DNS names or `*.` wildcards"),
+    field("tls.ocsp", "string", "how the OCSP status of peer certificates is checked: \"off\", \"soft\" or \"hard\"").default("`\"off\"`").values(&["off", "soft", "hard"]),
+    field("tls.crl", "array of strings", "certificate revocation lists peer certificates are checked against").default("`[]`").constraint("`http(s)://` URLs or paths of the host"),
+    field("tls.rotate_after", "duration", "interval after which keep-held certificates and keys are re-issued by the Steward").constraint("not zero"),
+    field("tls.session_resumption", "boolean", "whether sessions are resumed with session tickets or IDs").default("`true`"),
+    field("tls.ticket_lifetime", "duration", "lifetime of the session tickets issued by listeners").constraint("1s to 7d, requires session resumption"),
+    field("crypto", "table", "The policy of the keys generated by the keep"),
+    field("crypto.key_types", "array of strings", "allowed key types: \"rsa\", \"p256\", \"p384\", \"p521\" or \"ed25519\"").default("all").constraint("at least one satisfying the minimum sizes").values(&["rsa", "p256", "p384", "p521", "ed25519"]),
+    field("crypto.min_rsa_bits", "integer", "minimum size of RSA keys in bits").constraint("2048, 3072 or 4096"),
+    field("crypto.min_ec_bits", "integer", "minimum size of elliptic curve keys in bits").constraint("256, 384 or 521"),
+    field("proxy", "table", "A proxy for all outbound connections"),
+    field("proxy.kind", "string", "protocol of the proxy: \"socks5\" or \"http\"").values(&["socks5", "http"]),
+    field("proxy.host", "string", "host of the proxy").constraint("a host name or IP address"),
+    field("proxy.port", "integer", "port of the proxy").constraint("1-65535"),
+    field("proxy.username", "string", "user name to authenticate with"),
+    field("proxy.password", "string", "name of the secret holding the password").constraint("a secret of `secrets`, requires `username`"),
+    field("network", "table", "The network policy"),
+    field("network.egress", "table", "policy for outbound connections"),
+    field("network.egress.hosts", "array of strings", "allowed host names, IP addresses or networks, \"*.example.com\" matches all subdomains").default("`[]`"),
+    field("network.egress.cidrs", "array of strings", "allowed IP networks").default("`[]`").constraint("CIDR notation without host bits"),
+    field("network.egress.ports", "array of integers or strings", "allowed ports or port ranges, all if empty").default("`[]`"),
+    field("network.dns", "table", "name resolution settings"),
+    field("network.dns.resolver", "string", "\"host\", \"in-keep\" or \"dns-over-tls\"").default("`\"host\"`").values(&["host", "in-keep", "dns-over-tls"]),
+    field("network.dns.nameservers", "array of strings", "nameserver addresses").default("`[]`"),
+    field("network.dns.tls_name", "string", "TLS server name of the nameservers"),
+    field("network.dns.search", "array of strings", "search domains appended to names without dots").default("`[]`"),
+    field("telemetry", "table", "An endpoint the runtime exports metrics to"),
+    field("telemetry.exporter", "string", "protocol of the metrics export: \"otlp\" or \"statsd\"").values(&["otlp", "statsd"]),
+    field("telemetry.host", "string", "host of the metrics endpoint").constraint("a host name or IP address"),
+    field("telemetry.port", "integer", "port of the metrics endpoint").default("`4317` or `8125`").constraint("1-65535"),
+    field("telemetry.prot", "string", "transport protocol: \"tls\" or \"tcp\"").default("`\"tls\"`").values(&["tls", "tcp"]),
+    field("telemetry.interval", "duration", "interval between two exports").constraint("not zero"),
+    field("time", "table", "The clock policy"),
+    field("time.clocks", "array of strings", "the WASI clocks exposed to the application").default("all"),
+    field("time.resolution", "duration", "granularity the clock values are truncated to").constraint("not zero"),
+    field("time.source", "table", "trusted source of the wall clock time"),
+    field("time.source.kind", "string", "protocol of the time source: \"ntp\" or \"roughtime\"").values(&["ntp", "roughtime"]),
+    field("time.source.host", "string", "host of the time source").constraint("a host name or IP address"),
+    field("time.source.port", "integer", "port of the time source").default("`123` or `2002`").constraint("1-65535"),
+    field("time.source.key", "string", "Ed25519 public key of a \"roughtime\" server").constraint("base64, required for \"roughtime\""),
+    field("random", "table", "The entropy policy"),
+    field("random.source", "string", "source of the random numbers: \"hardware\", \"host\" or \"mixed\"").default("`\"hardware\"`").values(&["hardware", "host", "mixed"]),
+    field("random.seed", "integer", "seed of a deterministic random number generator").constraint("requires `debug.allow_debug`"),
+    field("health", "table", "A liveness check of the keep"),
+    field("health.listener", "string", "name of the \"listen\" file serving the checks").constraint("a `listen` file, exclusive with `port`"),
+    field("health.port", "integer", "port the runtime serves the checks on").constraint("1-65535, exclusive with `listener`"),
+    field("health.path", "string", "request path of \"http\" checks").default("`\"/\"`").constraint("starts with `/`"),
+    field("health.prot", "string", "protocol of the checks: \"http\" or \"tcp\"").default("`\"http\"`").values(&["http", "tcp"]),
+    field("lifecycle", "table", "How the keep shuts down"),
+    field("lifecycle.shutdown_grace", "duration", "time open connections get to finish on a graceful shutdown").default("unlimited").constraint("not zero"),
+    field("lifecycle.drain_listeners", "boolean", "stop accepting connections when a graceful shutdown starts").default("`true`"),
+    field("lifecycle.signals", "table of strings", "the action of a signal: \"graceful\", \"immediate\" or \"ignore\"").default("SIGTERM and SIGINT \"graceful\", SIGQUIT \"immediate\"").constraint("keys SIGTERM, SIGINT, SIGQUIT, SIGHUP, SIGUSR1 or SIGUSR2, at least one signal stops the keep").values(&["graceful", "immediate", "ignore"]),
+    field("lifecycle.restart", "table", "how the keep is restarted after the application exited"),
+    field("lifecycle.restart.policy", "string", "when the keep is restarted: \"never\", \"on-failure\" or \"always\"").default("`\"never\"`").values(&["never", "on-failure", "always"]),
+    field("lifecycle.restart.max_retries", "integer", "maximum number of consecutive restarts").default("unlimited").constraint("requires a restarting `policy`"),
+    field("lifecycle.restart.backoff", "duration", "delay before the first restart, doubled for every following one").default("no delay").constraint("not zero, requires a restarting `policy`"),
+    field("lifecycle.restart.max_backoff", "duration", "upper bound of the doubled `backoff`").default("unbounded").constraint("at least `backoff`, requires `backoff`"),
+    field("report", "table", "Periodic resource usage reports of the runtime"),
+    field("report.interval", "duration", "interval between two reports").constraint("not zero"),
+    field("report.destination", "string", "name of the file the reports are written to").constraint("a writable file, not `listen`"),
+    field("debug", "table", "Debugging facilities, only for development"),
+    field("debug.allow_debug", "boolean", "explicitly allow the debugging facilities").default("`false`"),
+    field("debug.backtraces", "boolean", "print WASM backtraces on traps").default("`false`").constraint("requires `allow_debug`"),
+    field("debug.coredump_on_trap", "boolean", "write a coredump of the WASM application on traps").default("`false`").constraint("requires `allow_debug`"),
+    field("debug.gdb_port", "integer", "port of a gdb remote debugging listener").constraint("1-65535, requires `allow_debug`"),
+    field("limits", "table", "Resource limits"),
+    field("limits.max_fds", "integer", "maximum number of pre-opened file descriptors").constraint("greater than 0"),
+    field("limits.max_args", "integer", "maximum number of `args`").constraint("greater than 0"),
+    field("limits.max_arg_len", "integer", "maximum length of an argument in bytes").constraint("greater than 0"),
+    field("limits.max_env_vars", "integer", "maximum number of environment variables").constraint("greater than 0"),
+    field("limits.max_env_value_len", "integer", "maximum length of an environment variable value in bytes").constraint("greater than 0"),
+    field("limits.max_connections", "integer", "maximum number of open accepted connections of all listeners").constraint("greater than 0"),
+    field("wasm", "table", "The WASI proposals exposed to the application"),
+    field("wasm.wasi_crypto", "table", "the `wasi-crypto` proposal"),
+    field("wasm.wasi_crypto.enable", "boolean", "expose the proposal to the application").default("`false`"),
+    field("wasm.wasi_crypto.algorithms", "array of strings", "the algorithms the application may use, named like in the proposal, e.g. \"Ed25519\" or \"A256-GCM\"").default("all").constraint("requires `enable = true`"),
+    field("wasm.wasi_nn", "table", "the `wasi-nn` proposal"),
+    field("wasm.wasi_nn.enable", "boolean", "expose the proposal to the application").default("`false`"),
+    field("wasm.wasi_nn.backends", "array of strings", "the backends the application may use: \"openvino\", \"onnx\", \"tensorflow\", \"tensorflowlite\", \"pytorch\" or \"ggml\"").default("all").constraint("requires `enable = true`").values(&["openvino", "onnx", "tensorflow", "tensorflowlite", "pytorch", "ggml"]),
+    field("wasm.wasi_nn.models", "array of tables", "a model the application can load by name").default("`[]`").constraint("requires `enable = true`, unique names"),
+    field("wasm.wasi_nn.models.name", "string", "name the application loads the model by").constraint("must not contain `:`"),
+    field("wasm.wasi_nn.models.backend", "string", "backend executing the model").constraint("one of `backends`"),
+    field("wasm.wasi_nn.models.path", "string", "path of the model on the host").constraint("not empty"),
+    field("wasm.wasi_nn.models.sha256", "string", "SHA-256 digest the model file has to match").constraint("64 hexadecimal characters"),
+    field("wasm.wasi_nn.models.target", "string", "execution provider: \"cpu\", \"gpu\" or \"tpu\"").default("`\"cpu\"`").values(&["cpu", "gpu", "tpu"]),
+    field("wasm.http", "table", "outbound requests of the `wasi-http` proposal"),
+    field("wasm.http.enable", "boolean", "expose outbound requests to the application").default("`false`"),
+    field("wasm.http.allow", "array of tables", "a rule of the allowed requests").default("`[]`").constraint("requires `enable = true`"),
+    field("wasm.http.allow.url", "string", "pattern of the allowed URLs, e.g. `https://*.example.com/api/*`").constraint("`http(s)://`, allowed by `network.egress`"),
+    field("wasm.http.allow.methods", "array of strings", "the allowed request methods, e.g. \"GET\"").default("all"),
+    field("kv", "array of tables", "a key-value store provided with `wasi-keyvalue`").default("`[]`").constraint("unique names, not used by `files`"),
+    field("kv.name", "string", "name the application opens the store by").constraint("must not contain `:`"),
+    field("kv.backend", "string", "storage of the store: \"memory\", \"file\" or \"url\"").values(&["memory", "file", "url"]),
+    field("kv.path", "string", "path of the file on the host").constraint("required for `backend = \"file\"`"),
+    field("kv.url", "string", "URL of a remote store").constraint("required for `backend = \"url\"`, `http(s)://`"),
+    field("kv.max_keys", "integer", "maximum number of keys").constraint("not zero"),
+    field("kv.max_value_bytes", "size", "maximum size of a single value in bytes").constraint("not zero, at most `max_bytes`"),
+    field("kv.max_bytes", "size", "maximum size of all keys and values in bytes").constraint("not zero"),
+    field("services", "array of tables", "settings shared by the files of a service").default("`[]`").constraint("unique names"),
+    field("services.name", "string", "name the files refer to the service by"),
+    field("services.tls", "table", "TLS settings of the `prot = \"tls\"` files of the service"),
+    field("services.tls.min_version", "string", "minimum accepted TLS version").default("`tls.min_version`").values(&["1.2", "1.3"]),
+    field("services.tls.key_exchange", "string", "preferred key exchange").default("`tls.key_exchange`").values(&["classical", "hybrid", "pq-only"]),
+    field("services.tls.alpn", "array of strings", "offered application protocols (ALPN) in order of preference").default("`tls.alpn`"),
+    field("services.tls.ca", "string", "PEM bundle of CA certificates replacing the trust store of the keep").default("`tls.ca`"),
+    field("services.tls.trust", "array of strings", "names of trust anchors replacing the trust store of the keep").default("`tls.trust`"),
+    field("services.tls.client_auth", "string", "whether listeners requests client certificates").default("`tls.client_auth`").constraint("`kind = \"listen\"` only").values(&["none", "optional", "required"]),
+    field("services.tls.server_names", "array of strings", "server names (SNI) the listeners accept").default("`tls.server_names`").constraint("`kind = \"listen\"` only, DNS names or `*.` wildcards"),
+    field("services.tls.ocsp", "string", "how the OCSP status of peer certificates is checked").default("`tls.ocsp`").values(&["off", "soft", "hard"]),
+    field("services.tls.crl", "array of strings", "certificate revocation lists peer certificates are checked against").default("`tls.crl`"),
+    field("services.tls.rotate_after", "duration", "interval after which the certificates of the listeners are re-issued").default("`tls.rotate_after`").constraint("not zero"),
+    field("services.tls.session_resumption", "boolean", "whether sessions are resumed").default("`tls.session_resumption`"),
+    field("services.tls.ticket_lifetime", "duration", "lifetime of the session tickets issued by the listeners").default("`tls.ticket_lifetime`").constraint("1s to 7d"),
+    field("services.timeout", "duration", "timeout of a single connection attempt of the `connect` files").constraint("not zero"),
+    field("services.labels", "table of strings", "free-form labels of the service").default("`{}`").constraint("like `labels`"),
+    field("files", "array of tables", "A pre-opened file descriptor").default("`[]`").constraint("unique names"),
+    field("files.kind", "string", "\"null\", \"stdin\", \"stdout\", \"stderr\", \"fifo\", \"pipe\", \"alias\", \"listen\", \"connect\" or \"custom\"").default("`\"alias\"` with `alias_of`").values(&["null", "stdin", "stdout", "stderr", "fifo", "pipe", "alias", "listen", "connect", "custom"]),
+    field("files.name", "string", "name of the file descriptor").default("the `kind` or the `host`").constraint("no `:`, required for `fifo`, `pipe`, `alias` and `listen`"),
+    field("files.type", "string", "the kind of a `custom` file defined by the runtime").constraint("required for `custom`, not empty"),
+    field("files.params", "table", "the parameters of a `custom` file, checked by the runtime").default("`{}`"),
+    field("files.template", "string", "entry of `templates` the file is based on, its other fields override the ones of the template").constraint("a name of `templates`"),
+    field("files.when", "table", "condition the file is only opened if it holds, see `conditional.when`").constraint("requires `backend` or `profile`"),
+    field("files.when.backend", "string", "backend of the keep: \"nil\", \"kvm\", \"sgx\" or \"sev\"").values(&["nil", "kvm", "sgx", "sev"]),
+    field("files.when.profile", "string", "profile selected for the deployment").constraint("not empty"),
+    field("files.access", "string", "access mode: \"ro\", \"wo\" or \"rw\"").default("`\"ro\"` for `stdin`, `\"wo\"` for outputs, `\"rw\"` else").values(&["ro", "wo", "rw"]),
+    field("files.path", "string", "host file to redirect the stdio stream to, or the FIFO of the host").constraint("required for `fifo`"),
+    field("files.mode", "string", "`stdout`, `stderr`: how an existing `path` is opened: \"truncate\" or \"append\"; `listen`: how accepted connections are handed to the application: \"raw\", \"http\" or \"h2\"").default("`\"truncate\"`, `\"raw\"`").constraint("`stdout`, `stderr`: requires `path`").values(&["truncate", "append", "raw", "http", "h2"]),
+    field("files.direction", "string", "whether the application reads from or writes to the FIFO or pipe: \"read\" or \"write\"").constraint("required for `fifo` and `pipe`").values(&["read", "write"]),
+    field("files.pipe", "string", "name of the pipe connecting two `pipe` files").constraint("exactly one `read` and one `write` end"),
+    field("files.alias_of", "string", "name of the file an `alias` is another name of").constraint("an existing file, no cycles"),
+    field("files.max_bytes", "size", "maximum number of bytes captured").constraint("greater than 0"),
+    field("files.on_overflow", "string", "what happens when `max_bytes` is exceeded: \"truncate\", \"rotate\" or \"fail\"").default("`\"truncate\"`").constraint("requires `max_bytes`").values(&["truncate", "rotate", "fail"]),
+    field("files.content", "string", "inline content to read").constraint("exclusive with `content_base64` and `path`"),
+    field("files.content_base64", "string", "inline binary content to read, written as base64").constraint("exclusive with `content` and `path`"),
+    field("files.prot", "string", "transport protocol: \"tls\" or \"tcp\"").default("`\"tls\"`").values(&["tls", "tcp"]),
+    field("files.routes", "array of tables", "routes of requests to other file descriptors").default("`[]`").constraint("requires `mode = \"http\"` or `\"h2\"`, unique prefixes"),
+    field("files.routes.prefix", "string", "prefix of the request paths, matching whole path segments").constraint("starts with `/`"),
+    field("files.routes.name", "string", "name of the file descriptor the requests are passed to").constraint("unique among the file names"),
+    field("files.tls", "table", "TLS settings overriding the `[tls]` section").constraint("requires `prot = \"tls\"`"),
+    field("files.tls.min_version", "string", "minimum accepted TLS version").default("`tls.min_version`").values(&["1.2", "1.3"]),
+    field("files.tls.key_exchange", "string", "preferred key exchange").default("`tls.key_exchange`").constraint("`\"pq-only\"` not with an effective `min_version = \"1.2\"`").values(&["classical", "hybrid", "pq-only"]),
+    field("files.tls.alpn", "array of strings", "offered application protocols (ALPN) in order of preference").default("`tls.alpn`"),
+    field("files.tls.ca", "string", "PEM bundle of CA certificates replacing the trust store of the keep").default("`tls.ca`"),
+    field("files.tls.trust", "array of strings", "names of trust anchors replacing the trust store of the keep").default("`tls.trust`"),
+    field("files.tls.client_auth", "string", "whether the listener requests client certificates").default("`tls.client_auth`").constraint("`kind = \"listen\"` only").values(&["none", "optional", "required"]),
+    field("files.tls.server_names", "array of strings", "server names (SNI) the listener accepts").default("`tls.server_names`").constraint("`kind = \"listen\"` only, DNS names or `*.` wildcards"),
+    field("files.tls.ocsp", "string", "how the OCSP status of peer certificates is checked").default("`tls.ocsp`").values(&["off", "soft", "hard"]),
+    field("files.tls.crl", "array of strings", "certificate revocation lists peer certificates are checked against").default("`tls.crl`"),
+    field("files.tls.rotate_after", "duration", "interval after which the certificate and key of the listener are re-issued").default("`tls.rotate_after`").constraint("`kind = \"listen\"` only, not zero"),
+    field("files.tls.session_resumption", "boolean", "whether sessions are resumed").default("`tls.session_resumption`"),
+    field("files.tls.ticket_lifetime", "duration", "lifetime of the session tickets issued by the listener").default("`tls.ticket_lifetime`").constraint("`kind = \"listen\"` only, 1s to 7d"),
+    field("files.cert", "certificate source", "certificate chain replacing the one issued by the Steward: `{ path = ... }`, `{ pem = ... }` or `{ secret = ... }`").constraint("`kind = \"listen\"` only, requires `key` and `prot = \"tls\"`"),
+    field("files.key", "certificate source", "private key of `cert`: `{ path = ... }`, `{ pem = ... }` or `{ secret = ... }`").constraint("requires `cert`, a `secret` of `secrets`"),
+    field("files.psk", "table", "pre-shared key (TLS-PSK) authenticating the connection instead of certificates").constraint("requires `prot = \"tls\"`, exclusive with `cert` and the certificate options of `tls`"),
+    field("files.psk.identity", "string", "identity of the key, sent in plaintext").constraint("not empty"),
+    field("files.psk.key", "string", "name of the secret holding the key").constraint("a secret of `secrets`"),
+    field("files.advertise_identity", "boolean", "present the attested identity of the keep as client certificate, even to servers not requesting one").default("`false`").constraint("`kind = \"connect\"` only, requires `prot = \"tls\"` and a `steward`, exclusive with `psk`"),
+    field("files.service", "string", "name of the service the file inherits `tls` and `timeout` from").constraint("a `services` name, `listen` and `connect` only"),
+    field("files.labels", "table of strings", "free-form labels of the file").default("`{}`").constraint("like `labels`"),
+    field("files.host", "string", "host to connect to").constraint("a host name or IP address"),
+    field("files.fallback", "array of tables", "further endpoints tried in order").default("`[]`"),
+    field("files.fallback.host", "string", "host of the endpoint").constraint("a host name or IP address"),
+    field("files.fallback.port", "integer", "port of the endpoint").default("`443`").constraint("1-65535"),
+    field("files.family", "string", "address family: \"any\", \"v4\" or \"v6\"").default("`\"any\"`").values(&["any", "v4", "v6"]),
+    field("files.happy_eyeballs", "boolean", "race IPv6 and IPv4 connection attempts").default("`true`"),
+    field("files.proxy", "boolean", "tunnel the connection through the proxy").default("`true`"),
+    field("files.addr", "string", "address to listen on").default("`\"::\"`"),
+    field("files.port", "integer", "port to listen on or connect to").default("`443` for `connect`"),
+    field("files.ports", "array of integers or strings", "all ports or port ranges to listen on, one listener each").constraint("1-65535, without `ephemeral`"),
+    field("files.ephemeral", "boolean", "listen on a random port").default("`false`").constraint("requires `port = 0`"),
+    field("files.allow", "array of strings", "IP networks allowed to connect").default("all"),
+    field("files.max_connections_per_second", "integer", "maximum rate of accepted connections").constraint("greater than 0"),
+    field("files.max_concurrent_connections", "integer", "maximum number of open accepted connections").constraint("greater than 0, at most `limits.max_connections`"),
+    field("files.backlog", "integer", "length of the queue of pending connections").default("`128`").constraint("1-65535"),
+    field("files.reuseaddr", "boolean", "set `SO_REUSEADDR`").default("`false`"),
+    field("files.reuseport", "boolean", "set `SO_REUSEPORT`").default("`false`"),
+    field("files.selftest", "boolean", "verify binding and the TLS handshake before starting the workload").default("`false`"),
+    field("files.nodelay", "boolean", "disable Nagle's algorithm").default("`false`"),
+    field("files.priority", "string or integer", "traffic priority: \"low\", \"normal\", \"high\" or a DSCP value").constraint("DSCP 0-63").values(&["low", "normal", "high"]),
+    field("files.idle_timeout", "duration", "time after which connections without any traffic are closed").constraint("not zero"),
+    field("files.timeout", "duration", "timeout of a single connection attempt").constraint("not zero"),
+    field("files.retries", "integer", "number of additional connection attempts").default("`0`"),
+    field("files.retry_backoff", "duration", "delay between connection attempts").constraint("requires `retries`"),
+    field("files.keepalive", "table", "TCP keepalive settings"),
+    field("files.keepalive.idle", "duration", "idle time before the first keepalive probe").constraint("whole seconds"),
+    field("files.keepalive.interval", "duration", "time between keepalive probes").constraint("whole seconds"),
+    field("files.keepalive.count", "integer", "number of unanswered probes before the connection is dropped").constraint("greater than 0"),
+    field("stdio", "string", "what happens to the standard streams a non-empty `files` does not declare: \"require\", \"inject-missing\" or \"null-missing\"").default("only the declared ones are opened").values(&["require", "inject-missing", "null-missing"]),
+    field("apps", "table of tables", "applications by name, each with its own settings on top of the top-level ones and an optional `sha256` of the WASM binary").default("`{}`").constraint("no nested `apps`"),
+    field("templates", "table of tables", "file definitions by name, `files` can be based on with `template`").default("`{}`").constraint("no `template`"),
+    field("conditional", "array of tables", "settings only applying to some keeps, see `EnarxConfig::for_target`").default("`[]`"),
+    field("conditional.when", "table", "condition of the section, all given fields have to match").constraint("requires `backend` or `profile`"),
+    field("conditional.when.backend", "string", "backend of the keep: \"nil\", \"kvm\", \"sgx\" or \"sev\"").values(&["nil", "kvm", "sgx", "sev"]),
+    field("conditional.when.profile", "string", "profile selected for the deployment").constraint("not empty"),
+    field("conditional.env", "table", "environment variables added to, or overriding, the `env`").default("`{}`"),
+];
+
+/// get the documentation of the field at `path`
+pub(crate) fn doc(path: &str) -> Option<&'static str> {
+    FIELDS.iter().find(|f| f.path == path).map(|f| f.doc)
+}
+
+/// get the schema path of `path`, with array indices and the names of `apps` and
+/// `templates` entries left out
+fn schema_path(path: &str) -> String {
+    let mut keys = vec![];
+    for key in path.split('.') {
+        let key = match key.find('[') {
+            Some(index) => &key[..index],
+            None => key,
+        };
+        keys.push(key);
+    }
+
+    match keys.as_slice() {
+        // the entries of `apps` have the top-level settings, the ones of `templates` are files
+        ["apps", _] => "apps".into(),
+        ["apps", _, rest @ ..] => rest.join("."),
+        ["templates", _, rest @ ..] => {
+            let mut path = String::from("files");
+            for key in rest {
+                path.push('.');
+                path.push_str(key);
+            }
+            path
+        }
+        keys => keys.join("."),
+    }
+}
+
+impl EnarxConfig {
+    /// get the schema of the configuration, all fields in the order of `Enarx_toml.md`
+    ///
+    /// Tables precede their fields. The entries of maps like `env` or `secrets` are not
+    /// part of the schema.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enarx_config::EnarxConfig;
+    ///
+    /// let prot = EnarxConfig::schema()
+    ///     .iter()
+    ///     .find(|f| f.path == "files.prot")
+    ///     .unwrap();
+    /// assert_eq!(prot.ty, "string");
+    /// assert_eq!(prot.values, ["tls", "tcp"]);
+    /// assert_eq!(prot.default, Some("`\"tls\"`"));
+    /// ```
+    pub fn schema() -> &'static [SchemaField] {
+        FIELDS
+    }
+
+    /// get the field of the schema at `path`
+    ///
+    /// `path` may be a path of a configuration, like the ones of [`ValidationError`](crate::ValidationError),
+    /// array indices and the names of `apps` and `templates` entries are left out,
+    /// e.g. `files[2].tls.ocsp` is the field `files.tls.ocsp`.
+    pub fn schema_field(path: &str) -> Option<&'static SchemaField> {
+        let path = schema_path(path);
+        FIELDS.iter().find(|f| f.path == path)
+    }
+
+    /// get the fields of the table of the schema at `path`, the top-level fields if `path`
+    /// is empty
+    ///
+    /// `path` is interpreted like by [`EnarxConfig::schema_field`].
+    pub fn schema_children(path: &str) -> impl Iterator<Item = &'static SchemaField> {
+        let prefix = match schema_path(path) {
+            path if path.is_empty() || path == "apps" => String::new(),
+            path => format!("{}.", path),
+        };
+
+        FIELDS
+            .iter()
+            .filter(move |f| match f.path.strip_prefix(prefix.as_str()) {
+                Some(rest) => !rest.is_empty() && !rest.contains('.'),
+                None => false,
+            })
+    }
+
+    /// generate the field reference of the configuration as Markdown
+    ///
+    /// Lists the type, the default and the constraints of every field,
+    /// grouped by the top-level sections.
+    pub fn reference_markdown() -> String {
+        let mut out = String::from("# `Enarx.toml` field reference\n");
+        let mut section = "";
+
+        for field in FIELDS {
+            let top = field.path.split('.').next().unwrap_or(field.path);
+            if top != section {
+                section = top;
+                out.push_str(&format!(
+                    "\n## `{}`\n\n| Field | Type | Default | Constraints | Description |\n|---|---|---|---|---|\n",
+                    section
+                ));
+            }
+
+            out.push_str(&format!(
+                "| `{}` | {} | {} | {} | {} |\n",
+                field.path,
+                field.ty,
+                field.default.unwrap_or("-"),
+                field.constraint.unwrap_or("-"),
+                field.doc.replace('|', "\\|"),
+            ));
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use serde_json::Value;
+
+    use super::*;
+
+    /// a configuration setting every field at least once
+    const CONFIG: &str = r#"
+        env = { "FOO" = "foo" }
+        env_exports = [ "FOO", "APP_*" ]
+        args = [ "--verbose" ]
+        labels = { team = "payments" }
+        steward_insecure = true
+        not_before = "2024-05-01T00:00:00Z"
+        not_after = "2024-05-02T00:00:00Z"
+        stdio = "inject-missing"
+
+        [steward]
+        url = "https://steward.example.com/"
+        pin = [ "8f43288ad272f3103b6fb1428485ea3014bc0cb5a4d2f6e93e6e2c2a5b0e6c1d" ]
+        ca = "/etc/enarx/steward-ca.pem"
+        trust = [ "corp" ]
+        connect_timeout = "5s"
+        request_timeout = "30s"
+        retries = 3
+        retry_backoff = "2s"
+
+        [steward.csr]
+        common_name = "api"
+        dns_sans = [ "api.example.com" ]
+        ip_sans = [ "10.0.0.1" ]
+        key_algorithm = "p256"
+        validity = "7d"
+
+        [attestation]
+        mode = "cached"
+        collateral = "/var/lib/enarx/collateral"
+        trust = [ "amd" ]
+
+        [identity]
+        spiffe_id = "spiffe://example.com/api"
+        trust_domain = "example.com"
+        uri_san = true
+
+        [runtime]
+        timezone = "UTC"
+        locale = "C"
+        export_env = true
+
+        [[signature.signers]]
+        id = "release"
+        algorithm = "ed25519"
+        key = "A6EHv/POEL4dcN0Y50vAmWfk1jCbpQ1fHdiGbDrzZ5c="
+
+        [secrets]
+        password = { env = "PASSWORD" }
+
+        [trust]
+        corp = { path = "/etc/ssl/corp-ca.pem" }
+        amd = { builtin = "amd-milan" }
+
+        [tls]
+        min_version = "1.2"
+        key_exchange = "hybrid"
+        alpn = [ "h2", "http/1.1" ]
+        ca = "/etc/ssl/ca.pem"
+        trust = [ "corp" ]
+        client_auth = "none"
+        server_names = [ "*.example.com" ]
+        ocsp = "soft"
+        crl = [ "https://crl.example.com/ca.crl" ]
+        rotate_after = "24h"
+        session_resumption = true
+        ticket_lifetime = "12h"
+
+        [crypto]
+        key_types = [ "p256", "rsa" ]
+        min_rsa_bits = 3072
+        min_ec_bits = 256
+
+        [proxy]
+        kind = "socks5"
+        host = "proxy.example.com"
+        port = 1080
+        username = "keep"
+        password = "password"
+
+        [network.egress]
+        hosts = [ "*.example.com" ]
+        cidrs = [ "10.0.0.0/8" ]
+        ports = [ 443 ]
+
+        [network.dns]
+        resolver = "dns-over-tls"
+        nameservers = [ "1.1.1.1" ]
+        tls_name = "cloudflare-dns.com"
+        search = [ "example.com" ]
+
+        [telemetry]
+        exporter = "otlp"
+        host = "otel.example.com"
+        port = 4317
+        interval = "15s"
+
+        [time]
+        resolution = "1ms"
+        source = { kind = "ntp", host = "ntp.example.com", port = 123, key = "AA==" }
+
+        [random]
+        seed = 42
+
+        [health]
+        listener = "API"
+        port = 9000
+        path = "/"
+        prot = "http"
+
+        [lifecycle]
+        shutdown_grace = "30s"
+        drain_listeners = true
+        signals = { SIGHUP = "graceful" }
+        restart = { policy = "on-failure", max_retries = 5, backoff = "1s", max_backoff = "1m" }
+
+        [report]
+        interval = "5m"
+        destination = "stdout"
+
+        [debug]
+        allow_debug = true
+        backtraces = true
+        coredump_on_trap = true
+        gdb_port = 1234
+
+        [limits]
+        max_fds = 16
+        max_args = 16
+        max_arg_len = 4096
+        max_env_vars = 64
+        max_env_value_len = 4096
+        max_connections = 1024
+
+        [wasm.wasi_crypto]
+        enable = true
+        algorithms = [ "Ed25519" ]
+
+        [wasm.wasi_nn]
+        enable = true
+        backends = [ "onnx" ]
+
+        [[wasm.wasi_nn.models]]
+        name = "mobilenet"
+        backend = "onnx"
+        path = "/models/mobilenet.onnx"
+        sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        target = "cpu"
+
+        [wasm.http]
+        enable = true
+
+        [[wasm.http.allow]]
+        url = "https://api.example.com/*"
+        methods = [ "GET" ]
+
+        [[kv]]
+        name = "cache"
+        backend = "memory"
+        max_keys = 1
+        max_value_bytes = 1
+        max_bytes = 1
+
+        [[kv]]
+        name = "state"
+        backend = "file"
+        path = "/var/lib/state"
+
+        [[kv]]
+        name = "remote"
+        backend = "url"
+        url = "https://kv.example.com/"
+
+        [[services]]
+        name = "backend"
+        tls = { min_version = "1.3", key_exchange = "pq-only", alpn = [ "h2" ], ca = "/etc/ssl/ca.pem", trust = [ "corp" ], client_auth = "optional", server_names = [ "backend.example.com" ], ocsp = "soft", crl = [ "/etc/ssl/backend.crl" ], rotate_after = "1d", session_resumption = true, ticket_lifetime = "1h" }
+        timeout = "5s"
+        labels = { team = "payments" }
+
+        [[files]]
+        kind = "stdin"
+        access = "ro"
+        path = "/dev/null"
+        content = "x"
+        content_base64 = "AA=="
+
+        [[files]]
+        kind = "stdout"
+        path = "/var/log/out"
+        mode = "append"
+        max_bytes = 1
+        on_overflow = "fail"
+
+        [[files]]
+        name = "EVENTS"
+        kind = "fifo"
+        path = "/run/events"
+        direction = "read"
+
+        [[files]]
+        name = "JOBS_OUT"
+        kind = "pipe"
+        pipe = "jobs"
+        direction = "write"
+
+        [[files]]
+        name = "JOBS_IN"
+        kind = "pipe"
+        pipe = "jobs"
+        direction = "read"
+
+        [[files]]
+        name = "HOST"
+        kind = "custom"
+        type = "vsock"
+        params = { cid = 2 }
+
+        [[files]]
+        name = "LOG"
+        alias_of = "JOBS_IN"
+
+        [[files]]
+        name = "ADMIN"
+        kind = "listen"
+        port = 0
+        ephemeral = true
+        backlog = 16
+        reuseaddr = true
+        reuseport = true
+        nodelay = true
+
+        [[files]]
+        name = "API"
+        kind = "listen"
+        addr = "::"
+        port = 8080
+        ports = [ 8080 ]
+        allow = [ "10.0.0.0/8" ]
+        max_connections_per_second = 1
+        max_concurrent_connections = 1
+        selftest = true
+        tls = { min_version = "1.3", key_exchange = "pq-only", alpn = [ "h2" ], ca = "/etc/ssl/ca.pem", trust = [ "corp" ], client_auth = "required", server_names = [ "api.example.com" ], ocsp = "hard", crl = [ "/etc/ssl/ca.crl" ], rotate_after = "6h", session_resumption = true, ticket_lifetime = "2h" }
+        cert = { path = "/etc/ssl/api.pem" }
+        key = { secret = "password" }
+        mode = "http"
+        routes = [ { prefix = "/admin", name = "ADMIN" } ]
+        keepalive = { idle = "1m", interval = "10s", count = 3 }
+        idle_timeout = "5m"
+        priority = "high"
+
+        [[files]]
+        kind = "connect"
+        host = "db.example.com"
+        family = "v4"
+        fallback = [ { host = "db2.example.com", port = 5432 } ]
+        timeout = "1s"
+        retries = 1
+        retry_backoff = "1s"
+        service = "backend"
+        psk = { identity = "keep", key = "password" }
+        labels = { tier = "database" }
+
+        [[files]]
+        kind = "connect"
+        host = "audit.example.com"
+        happy_eyeballs = false
+        proxy = false
+        advertise_identity = true
+
+        [[files]]
+        name = "REPLICA"
+        template = "backend"
+
+        [[files]]
+        kind = "stderr"
+        when = { backend = "sgx", profile = "dev" }
+
+        [templates.backend]
+        kind = "connect"
+        host = "db3.example.com"
+
+        [[conditional]]
+        when = { backend = "kvm", profile = "dev" }
+        env = { "LOG" = "debug" }
+
+        [apps.sidecar]
+        sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        args = [ "proxy" ]
+    "#;
+
+    fn collect(path: &str, value: &Value, paths: &mut BTreeSet<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    // the entries of maps and the variants of sources are not part of the schema
+                    if !matches!(
+                        path.as_str(),
+                        "env"
+                            | "secrets"
+                            | "trust"
+                            | "labels"
+                            | "lifecycle.signals"
+                            | "apps"
+                            | "templates"
+                            | "conditional.env"
+                            | "services.labels"
+                            | "files.labels"
+                            | "files.params"
+                            | "files.cert"
+                            | "files.key"
+                    ) {
+                        collect(&path, value, paths);
+                    }
+                    paths.insert(path);
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    collect(path, value, paths);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn complete() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+
+        let mut serialized = BTreeSet::new();
+        collect("", &serde_json::to_value(&cfg).unwrap(), &mut serialized);
+        let documented = FIELDS
+            .iter()
+            .map(|f| f.path.to_string())
+            .collect::<BTreeSet<_>>();
+
+        assert_eq!(serialized, documented);
+        assert_eq!(FIELDS.len(), documented.len());
+    }
+
+    #[test]
+    fn schema() {
+        let field = EnarxConfig::schema_field("files[3].tls.ocsp").unwrap();
+        assert_eq!(field.path, "files.tls.ocsp");
+        assert_eq!(field.name(), "ocsp");
+        assert_eq!(field.values, ["off", "soft", "hard"]);
+        assert_eq!(
+            EnarxConfig::schema_field("apps.sidecar.steward.url")
+                .unwrap()
+                .path,
+            "steward.url"
+        );
+        assert_eq!(
+            EnarxConfig::schema_field("templates.tls_api.port")
+                .unwrap()
+                .path,
+            "files.port"
+        );
+        assert_eq!(
+            EnarxConfig::schema_field("apps.sidecar").unwrap().path,
+            "apps"
+        );
+        assert!(EnarxConfig::schema_field("files.nope").is_none());
+        assert!(EnarxConfig::schema_field("").is_none());
+
+        let names = |path: &str| {
+            EnarxConfig::schema_children(path)
+                .map(SchemaField::name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names("files[0].keepalive"), ["idle", "interval", "count"]);
+        assert_eq!(names("conditional[1].when"), ["backend", "profile"]);
+        assert!(names("").contains(&"files"));
+        assert!(!names("").contains(&"port"));
+        assert_eq!(names(""), names("apps.sidecar"));
+        assert!(names("files.port").is_empty());
+
+        // the allowed values are the ones of the description
+        for field in FIELDS {
+            for value in field.values {
+                assert!(
+                    field.doc.contains(&format!("\"{}\"", value))
+                        || field.path.starts_with("services.tls.")
+                        || field.path.starts_with("files.tls."),
+                    "{}: {}",
+                    field.path,
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn markdown() {
+        let markdown = EnarxConfig::reference_markdown();
+
+        assert!(markdown.contains(
+            "\n## `limits`\n\n| Field | Type | Default | Constraints | Description |\n|---|---|---|---|---|\n\
+             | `limits` | table | - | - | Resource limits |\n"
+        ));
+        assert!(markdown.contains(
+            "| `files.backlog` | integer | `128` | 1-65535 | length of the queue of pending connections |\n"
+        ));
+    }
+
+    #[test]
+    fn markdown_in_docs() {
+        const DOCS: &str = include_str!("../Enarx_toml.md");
+        const START: &str =
+            "<!-- generated by `EnarxConfig::reference_markdown`, do not edit -->\n";
+        const END: &str = "<!-- end of the generated reference -->\n";
+
+        let start = DOCS.find(START).expect("the reference is part of the docs") + START.len();
+        let end = start
+            + DOCS[start..]
+                .find(END)
+                .expect("the reference is terminated");
+        if std::env::var_os("WRITE_REFERENCE").is_some() {
+            let docs = [
+                &DOCS[..start],
+                &EnarxConfig::reference_markdown(),
+                &DOCS[end..],
+            ]
+            .concat();
+            std::fs::write(concat!(env!("CARGO_MANIFEST_DIR"), "/Enarx_toml.md"), docs).unwrap();
+            return;
+        }
+        assert!(
+            DOCS[start..end] == EnarxConfig::reference_markdown(),
+            "the reference in Enarx_toml.md is outdated, run the test with `WRITE_REFERENCE=1`"
+        );
+    }
+}
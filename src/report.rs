@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resource usage reports of the `[report]` section
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{EnarxConfig, ValidationError};
+
+/// The `[report]` section, opting into periodic resource usage reports of the runtime
+///
+/// The reports are written to the file descriptor named `destination`, e.g. a
+/// `kind = "connect"` file to an accounting service or `stderr`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UsageReport {
+    /// interval between two reports
+    #[serde(with = "crate::duration")]
+    pub interval: Duration,
+
+    /// name of the writable file the reports are written to
+    pub destination: String,
+}
+
+impl EnarxConfig {
+    pub(crate) fn validate_report(&self, report: &UsageReport) -> Result<(), ValidationError> {
+        if report.interval.is_zero() {
+            return Err(ValidationError::new("report.interval", "must not be zero"));
+        }
+
+        let destination = &report.destination;
+        match self.resolve(destination) {
+            None => Err(ValidationError::new(
+                "report.destination",
+                format!("unknown file `{}`", destination),
+            )),
+            Some(file) if file.kind() == "listen" => Err(ValidationError::new(
+                "report.destination",
+                format!(
+                    "`{}` is a `listen` file, which can not be written to",
+                    destination
+                ),
+            )),
+            Some(file) if !file.access().is_writable() => Err(ValidationError::new(
+                "report.destination",
+                format!("`{}` is not writable", destination),
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn report() {
+        const CONFIG: &str = r#"
+        [report]
+        interval = "1m"
+        destination = "USAGE"
+
+        [[files]]
+        name = "USAGE"
+        kind = "connect"
+        host = "accounting.example.com"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(
+            cfg.report.as_ref().unwrap().interval,
+            Duration::from_secs(60)
+        );
+
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), cfg);
+    }
+
+    #[test]
+    fn invalid() {
+        let validate = |destination: &str, interval: &str| {
+            let config = format!(
+                r#"
+                [report]
+                interval = "{}"
+                destination = "{}"
+
+                [[files]]
+                kind = "stdin"
+
+                [[files]]
+                name = "API"
+                kind = "listen"
+                port = 8080
+
+                [[files]]
+                name = "OUT"
+                kind = "alias"
+                alias_of = "stdout"
+
+                [[files]]
+                kind = "stdout"
+                "#,
+                interval, destination
+            );
+            toml::from_str::<EnarxConfig>(&config)
+                .unwrap()
+                .validate()
+                .map_err(|e| e.to_string())
+        };
+
+        assert_eq!(validate("OUT", "30s"), Ok(()));
+        assert_eq!(
+            validate("stdout", "0s"),
+            Err("report.interval: must not be zero".into())
+        );
+        assert_eq!(
+            validate("USAGE", "30s"),
+            Err("report.destination: unknown file `USAGE`".into())
+        );
+        assert_eq!(
+            validate("API", "30s"),
+            Err("report.destination: `API` is a `listen` file, which can not be written to".into())
+        );
+        assert_eq!(
+            validate("stdin", "30s"),
+            Err("report.destination: `stdin` is not writable".into())
+        );
+    }
+}
@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Setting up the WASI context of the application outside of the Enarx runtime
+//!
+//! Requires the `runtime` feature.
+//!
+//! The crate does not depend on a particular WASI implementation. Embedders implement
+//! [`WasiBuilder`] for the context builder of their runtime, e.g. for
+//! `wasmtime_wasi::WasiCtxBuilder`, and call [`EnarxConfig::apply_to_wasi_builder`].
+
+use std::path::{Path, PathBuf};
+
+use crate::{EnarxConfig, File, WriteMode};
+
+/// What a standard stream of the application is connected to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stdio<'a> {
+    /// nothing, reads return end of file and writes are discarded
+    Null,
+
+    /// the stdin of the host process
+    Stdin,
+
+    /// the stdout of the host process
+    Stdout,
+
+    /// the stderr of the host process
+    Stderr,
+
+    /// a file of the host
+    Path {
+        /// path of the file
+        path: &'a Path,
+
+        /// keep the existing content of an output file
+        append: bool,
+    },
+
+    /// the inline content of a `kind = "stdin"` file
+    Content(&'a [u8]),
+}
+
+/// A builder of the WASI context of the application
+///
+/// # Examples
+///
+/// ```
+/// use enarx_config::runtime::{Stdio, WasiBuilder};
+/// use enarx_config::EnarxConfig;
+///
+/// #[derive(Default)]
+/// struct Builder {
+///     args: Vec<String>,
+///     stdout: Option<String>,
+/// }
+///
+/// impl WasiBuilder for Builder {
+///     type Error = std::convert::Infallible;
+///
+///     fn arg(&mut self, arg: &str) -> Result<(), Self::Error> {
+///         self.args.push(arg.into());
+///         Ok(())
+///     }
+///
+///     fn env(&mut self, _name: &str, _value: &str) -> Result<(), Self::Error> {
+///         Ok(())
+///     }
+///
+///     fn stdio(&mut self, fd: u32, stdio: Stdio<'_>) -> Result<(), Self::Error> {
+///         if fd == 1 {
+///             self.stdout = Some(format!("{:?}", stdio));
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let config = EnarxConfig {
+///     args: vec!["serve".into(), "--verbose".into()],
+///     ..Default::default()
+/// };
+/// let mut builder = Builder::default();
+/// config.apply_to_wasi_builder(&mut builder).unwrap();
+///
+/// assert_eq!(builder.args, ["serve", "--verbose"]);
+/// assert_eq!(builder.stdout.as_deref(), Some("Stdout"));
+/// ```
+pub trait WasiBuilder {
+    /// error of the builder
+    type Error;
+
+    /// append an argument after the program name
+    fn arg(&mut self, arg: &str) -> Result<(), Self::Error>;
+
+    /// set an environment variable
+    fn env(&mut self, name: &str, value: &str) -> Result<(), Self::Error>;
+
+    /// connect the standard stream `fd` (0, 1 or 2)
+    fn stdio(&mut self, fd: u32, stdio: Stdio<'_>) -> Result<(), Self::Error>;
+}
+
+/// connect an output to the host file at `path`, or else to `stream`
+fn output<'a>(path: &'a Option<PathBuf>, mode: &Option<WriteMode>, stream: Stdio<'a>) -> Stdio<'a> {
+    match path {
+        Some(path) => Stdio::Path {
+            path,
+            append: matches!(mode, Some(WriteMode::Append)),
+        },
+        None => stream,
+    }
+}
+
+impl File {
+    /// what the file is connected to as a standard stream of the application,
+    /// `None` for sockets and other kinds without a WASI standard stream counterpart
+    pub fn stdio(&self) -> Option<Stdio<'_>> {
+        match self {
+            Self::Null { .. } => Some(Stdio::Null),
+            Self::Stdin {
+                path: Some(path), ..
+            } => Some(Stdio::Path {
+                path,
+                append: false,
+            }),
+            Self::Stdin { .. } => Some(self.content().map_or(Stdio::Stdin, Stdio::Content)),
+            Self::Stdout { path, mode, .. } => Some(output(path, mode, Stdio::Stdout)),
+            Self::Stderr { path, mode, .. } => Some(output(path, mode, Stdio::Stderr)),
+            _ => None,
+        }
+    }
+}
+
+impl EnarxConfig {
+    /// set the arguments, the environment and the standard streams of the application
+    ///
+    /// The arguments are the `args`, the program name has to be set by the embedder
+    /// before. The environment is the [`runtime_env`](EnarxConfig::runtime_env).
+    /// The standard streams are the first three `files`, after adding the files of the
+    /// [`stdio`](crate::StdioPolicy) policy for the missing ones. Missing entries are connected
+    /// to [`Stdio::Null`], sockets in their place are left to the embedder.
+    ///
+    /// Requires the `runtime` feature.
+    pub fn apply_to_wasi_builder<B: WasiBuilder>(&self, builder: &mut B) -> Result<(), B::Error> {
+        for arg in &self.args {
+            builder.arg(arg)?;
+        }
+
+        for (name, value) in self.runtime_env() {
+            builder.env(&name, &value)?;
+        }
+
+        let mut files = self.files.clone();
+        crate::stdio::inject(&mut files, self.stdio);
+        for fd in 0..3 {
+            let stdio = match files.get(fd) {
+                Some(file) => file.stdio(),
+                None => Some(Stdio::Null),
+            };
+            if let Some(stdio) = stdio {
+                builder.stdio(fd as u32, stdio)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// records all calls, failing on `FAIL` arguments
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl WasiBuilder for Recorder {
+        type Error = String;
+
+        fn arg(&mut self, arg: &str) -> Result<(), Self::Error> {
+            if arg == "FAIL" {
+                return Err("unsupported argument".into());
+            }
+            self.0.push(format!("arg {}", arg));
+            Ok(())
+        }
+
+        fn env(&mut self, name: &str, value: &str) -> Result<(), Self::Error> {
+            self.0.push(format!("env {}={}", name, value));
+            Ok(())
+        }
+
+        fn stdio(&mut self, fd: u32, stdio: Stdio<'_>) -> Result<(), Self::Error> {
+            self.0.push(format!("stdio {} {:?}", fd, stdio));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn apply() {
+        let config: EnarxConfig = toml::from_str(
+            r#"
+            args = [ "serve" ]
+            env = { "B" = "b", "A" = "a" }
+
+            [[files]]
+            kind = "stdin"
+            content = "hi"
+
+            [[files]]
+            kind = "stdout"
+            path = "/var/log/app.log"
+            mode = "append"
+            "#,
+        )
+        .unwrap();
+
+        let mut builder = Recorder::default();
+        config.apply_to_wasi_builder(&mut builder).unwrap();
+        assert_eq!(
+            builder.0,
+            [
+                "arg serve",
+                "env A=a",
+                "env B=b",
+                "stdio 0 Content([104, 105])",
+                "stdio 1 Path { path: \"/var/log/app.log\", append: true }",
+                "stdio 2 Null",
+            ]
+        );
+    }
+
+    #[test]
+    fn sockets() {
+        let config: EnarxConfig = toml::from_str(
+            r#"
+            args = [ "FAIL" ]
+
+            [[files]]
+            kind = "listen"
+            name = "API"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.files[0].stdio(), None);
+
+        let mut builder = Recorder::default();
+        assert_eq!(
+            config.apply_to_wasi_builder(&mut builder),
+            Err("unsupported argument".into())
+        );
+    }
+}
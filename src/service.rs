@@ -0,0 +1,225 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shared settings of the `[[services]]` array
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{EnarxConfig, File, Protocol, TlsOptions, ValidationError};
+
+/// Settings shared by all `files` with the same `service`
+///
+/// Settings of a file take precedence over the settings of its service.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Service {
+    /// name the files refer to the service by
+    pub name: String,
+
+    /// TLS settings of the `prot = "tls"` files of the service
+    ///
+    /// `client_auth`, `rotate_after` and `ticket_lifetime` only apply to `kind = "listen"` files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsOptions>,
+
+    /// timeout of a single connection attempt of the `kind = "connect"` files of the service
+    #[serde(
+        default,
+        with = "crate::duration::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout: Option<Duration>,
+
+    /// free-form labels of the service, e.g. for telemetry
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl EnarxConfig {
+    /// get the service named `name`
+    pub fn service(&self, name: &str) -> Option<&Service> {
+        self.services.iter().find(|service| service.name == name)
+    }
+
+    /// get the service `file` belongs to
+    pub fn service_of(&self, file: &File) -> Option<&Service> {
+        let name = match file {
+            File::Listen { service, .. } | File::Connect { service, .. } => service.as_deref()?,
+            _ => return None,
+        };
+        self.service(name)
+    }
+
+    /// copy the settings of the `services` into the `files` belonging to them
+    ///
+    /// Afterwards, the typed views of the files show the effective settings.
+    /// The `service` of the files is kept, so their labels can still be looked up
+    /// with [`service_of`](Self::service_of).
+    pub fn resolve_services(&mut self) {
+        let services = std::mem::take(&mut self.services);
+
+        for file in &mut self.files {
+            let service = match file {
+                File::Listen {
+                    service: Some(name),
+                    ..
+                }
+                | File::Connect {
+                    service: Some(name),
+                    ..
+                } => match services.iter().find(|service| service.name == *name) {
+                    Some(service) => service,
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            match file {
+                File::Listen { prot, tls, .. } if *prot == Protocol::Tls && tls.is_none() => {
+                    *tls = service.tls.clone();
+                }
+                File::Connect {
+                    prot, tls, timeout, ..
+                } => {
+                    if *prot == Protocol::Tls && tls.is_none() {
+                        *tls = service.tls.clone().map(|mut tls| {
+                            tls.client_auth = None;
+                            tls.rotate_after = None;
+                            tls.ticket_lifetime = None;
+                            tls
+                        });
+                    }
+                    if timeout.is_none() {
+                        *timeout = service.timeout;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.services = services;
+    }
+
+    pub(crate) fn validate_services(&self) -> Result<(), ValidationError> {
+        let mut names = HashSet::new();
+        for (i, service) in self.services.iter().enumerate() {
+            let path = |field: &str| format!("services[{}].{}", i, field);
+
+            if !names.insert(service.name.as_str()) {
+                return Err(ValidationError::new(
+                    path("name"),
+                    format!("duplicate service name `{}`", service.name),
+                ));
+            }
+            if matches!(service.timeout, Some(timeout) if timeout.is_zero()) {
+                return Err(ValidationError::new(path("timeout"), "must not be zero"));
+            }
+            if let Some(tls) = &service.tls {
+                tls.validate().map_err(|(field, message)| {
+                    ValidationError::new(path(&format!("tls.{}", field)), message)
+                })?;
+            }
+        }
+
+        for (i, file) in self.files.iter().enumerate() {
+            let name = match file {
+                File::Listen {
+                    service: Some(name),
+                    ..
+                }
+                | File::Connect {
+                    service: Some(name),
+                    ..
+                } => name,
+                _ => continue,
+            };
+            if !names.contains(name.as_str()) {
+                return Err(ValidationError::new(
+                    format!("files[{}].service", i),
+                    format!("unknown service `{}`", name),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+    [[services]]
+    name = "backend"
+    tls = { ocsp = "hard", rotate_after = "1d" }
+    timeout = "5s"
+    labels = { team = "payments" }
+
+    [[files]]
+    name = "DB"
+    kind = "connect"
+    host = "db.example.com"
+    service = "backend"
+
+    [[files]]
+    name = "CACHE"
+    kind = "connect"
+    host = "cache.example.com"
+    timeout = "1s"
+    service = "backend"
+
+    [[files]]
+    name = "API"
+    kind = "listen"
+    service = "backend"
+    "#;
+
+    #[test]
+    fn resolve() {
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.files[0].as_connect().unwrap().tls, None);
+
+        cfg.resolve_services();
+
+        let db = cfg.files[0].as_connect().unwrap();
+        assert_eq!(db.service, Some("backend"));
+        assert_eq!(db.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(db.tls.unwrap().ocsp, Some(crate::OcspMode::Hard));
+        assert_eq!(db.tls.unwrap().rotate_after, None);
+
+        let cache = cfg.files[1].as_connect().unwrap();
+        assert_eq!(cache.timeout, Some(Duration::from_secs(1)));
+
+        let api = cfg.files[2].as_listen().unwrap();
+        assert_eq!(
+            api.tls.unwrap().rotate_after,
+            Some(Duration::from_secs(86400))
+        );
+
+        let labels = &cfg.service_of(&cfg.files[2]).unwrap().labels;
+        assert_eq!(labels["team"], "payments");
+    }
+
+    #[test]
+    fn unknown() {
+        let cfg: EnarxConfig =
+            toml::from_str(&CONFIG.replace("service = \"backend\"", "service = \"frontend\""))
+                .unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "files[0].service: unknown service `frontend`"
+        );
+
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        cfg.services.push(cfg.services[0].clone());
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "services[1].name: duplicate service name `backend`"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detached signature verification over the canonical form of an [`EnarxConfig`]
+//!
+//! Requires the `signature` feature, ECDSA P-256 keys additionally require the `ecdsa` feature.
+
+use std::fmt;
+
+use crate::EnarxConfig;
+
+/// Public key to verify a detached config signature with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PublicKey {
+    /// raw 32 byte Ed25519 public key
+    Ed25519([u8; 32]),
+
+    /// SEC1 encoded ECDSA P-256 public key
+    #[cfg(feature = "ecdsa")]
+    P256(Vec<u8>),
+}
+
+/// Error verifying a detached config signature
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureError {
+    /// the public key is not a valid key for its algorithm
+    MalformedKey,
+
+    /// the signature has an invalid encoding for the key algorithm
+    MalformedSignature,
+
+    /// the signature does not match the config and public key
+    Mismatch,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedKey => f.write_str("malformed public key"),
+            Self::MalformedSignature => f.write_str("malformed signature"),
+            Self::Mismatch => f.write_str("signature does not match the config"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+impl PublicKey {
+    /// verify a detached `signature` over `message`
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), SignatureError> {
+        match self {
+            Self::Ed25519(key) => {
+                use ed25519_dalek::{Signature, VerifyingKey};
+
+                let key =
+                    VerifyingKey::from_bytes(key).map_err(|_| SignatureError::MalformedKey)?;
+                let signature = Signature::from_slice(signature)
+                    .map_err(|_| SignatureError::MalformedSignature)?;
+
+                key.verify_strict(message, &signature)
+                    .map_err(|_| SignatureError::Mismatch)
+            }
+
+            #[cfg(feature = "ecdsa")]
+            Self::P256(key) => {
+                use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+
+                let key =
+                    VerifyingKey::from_sec1_bytes(key).map_err(|_| SignatureError::MalformedKey)?;
+                let signature = Signature::from_slice(signature)
+                    .or_else(|_| Signature::from_der(signature))
+                    .map_err(|_| SignatureError::MalformedSignature)?;
+
+                key.verify(message, &signature)
+                    .map_err(|_| SignatureError::Mismatch)
+            }
+        }
+    }
+}
+
+impl EnarxConfig {
+    /// verify a detached signature over the [canonical form](EnarxConfig::to_canonical_bytes)
+    pub fn verify_signature(
+        &self,
+        key: &PublicKey,
+        signature: &[u8],
+    ) -> Result<(), SignatureError> {
+        key.verify(&self.to_canonical_bytes(), signature)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        args = [ "--verbose" ]
+
+        [[files]]
+        kind = "stdin"
+    "#;
+
+    #[test]
+    fn ed25519() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let signing = SigningKey::from_bytes(&[7; 32]);
+        let key = PublicKey::Ed25519(signing.verifying_key().to_bytes());
+        let signature = signing.sign(&cfg.to_canonical_bytes()).to_bytes();
+
+        assert_eq!(cfg.verify_signature(&key, &signature), Ok(()));
+
+        let mut tampered = cfg.clone();
+        tampered.args.push("--debug".into());
+        assert_eq!(
+            tampered.verify_signature(&key, &signature),
+            Err(SignatureError::Mismatch)
+        );
+
+        assert_eq!(
+            cfg.verify_signature(&key, &signature[1..]),
+            Err(SignatureError::MalformedSignature)
+        );
+    }
+
+    #[cfg(feature = "ecdsa")]
+    #[test]
+    fn p256() {
+        use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let signing = SigningKey::from_slice(&[7; 32]).unwrap();
+        let key = PublicKey::P256(
+            signing
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec(),
+        );
+        let signature: Signature = signing.sign(&cfg.to_canonical_bytes());
+
+        assert_eq!(cfg.verify_signature(&key, &signature.to_bytes()), Ok(()));
+        assert_eq!(
+            cfg.verify_signature(&key, signature.to_der().as_bytes()),
+            Ok(())
+        );
+        assert_eq!(
+            cfg.verify_signature(&PublicKey::P256(vec![4; 65]), &signature.to_bytes()),
+            Err(SignatureError::MalformedKey)
+        );
+    }
+}
@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Trusted signer declarations of the `[signature]` section
+
+use std::fmt;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// DER prefix of an Ed25519 `SubjectPublicKeyInfo`
+const ED25519_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// DER prefix of an uncompressed ECDSA P-256 `SubjectPublicKeyInfo`
+const P256_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
+    0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+];
+
+/// The `[signature]` section declaring the signers trusted for referenced artifacts
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TrustedSigners {
+    /// the signers trusted to sign artifacts
+    #[serde(default)]
+    pub signers: Vec<Signer>,
+}
+
+impl TrustedSigners {
+    /// get all signers trusted for an artifact
+    pub fn for_artifact(&self, artifact: SignedArtifact) -> impl Iterator<Item = &Signer> {
+        self.signers
+            .iter()
+            .filter(move |s| s.artifacts.contains(&artifact))
+    }
+}
+
+/// Artifact kinds a [`Signer`] can be trusted for
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SignedArtifact {
+    /// the WASM workload
+    #[serde(rename = "workload")]
+    Workload,
+
+    /// the configuration itself
+    #[serde(rename = "config")]
+    Config,
+
+    /// files included into the configuration
+    #[serde(rename = "include")]
+    Include,
+}
+
+fn all_artifacts() -> Vec<SignedArtifact> {
+    vec![
+        SignedArtifact::Workload,
+        SignedArtifact::Config,
+        SignedArtifact::Include,
+    ]
+}
+
+/// Signature algorithm of a [`Signer`] key
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum KeyAlgorithm {
+    /// Ed25519
+    #[serde(rename = "ed25519")]
+    Ed25519,
+
+    /// ECDSA with the NIST P-256 curve
+    #[serde(rename = "p256")]
+    P256,
+}
+
+impl fmt::Display for KeyAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ed25519 => f.write_str("ed25519"),
+            Self::P256 => f.write_str("p256"),
+        }
+    }
+}
+
+/// A trusted signer
+///
+/// At least one of `id` and `key` has to be specified. The `key` can be written
+/// as base64 encoded raw public key or as PEM encoded `SubjectPublicKeyInfo`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "RawSigner")]
+pub struct Signer {
+    /// identifier of the key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// algorithm of the key
+    pub algorithm: KeyAlgorithm,
+
+    /// the public key material
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<KeyMaterial>,
+
+    /// the artifacts this signer is trusted for
+    pub artifacts: Vec<SignedArtifact>,
+}
+
+#[derive(Deserialize)]
+struct RawSigner {
+    #[serde(default)]
+    id: Option<String>,
+    algorithm: KeyAlgorithm,
+    #[serde(default)]
+    key: Option<String>,
+    #[serde(default = "all_artifacts")]
+    artifacts: Vec<SignedArtifact>,
+}
+
+impl TryFrom<RawSigner> for Signer {
+    type Error = String;
+
+    fn try_from(raw: RawSigner) -> Result<Self, Self::Error> {
+        if raw.id.is_none() && raw.key.is_none() {
+            return Err("a signer needs at least one of `id` or `key`".into());
+        }
+
+        let key = raw
+            .key
+            .map(|key| KeyMaterial::parse(raw.algorithm, &key))
+            .transpose()?;
+
+        Ok(Self {
+            id: raw.id,
+            algorithm: raw.algorithm,
+            key,
+            artifacts: raw.artifacts,
+        })
+    }
+}
+
+#[cfg(feature = "signature")]
+impl Signer {
+    /// get the key of the signer for signature verification
+    pub fn public_key(&self) -> Option<crate::signature::PublicKey> {
+        use crate::signature::PublicKey;
+
+        let key = self.key.as_ref()?;
+        match self.algorithm {
+            // the length was checked while parsing
+            KeyAlgorithm::Ed25519 => key.0.as_slice().try_into().ok().map(PublicKey::Ed25519),
+            #[cfg(feature = "ecdsa")]
+            KeyAlgorithm::P256 => Some(PublicKey::P256(key.0.clone())),
+            #[cfg(not(feature = "ecdsa"))]
+            KeyAlgorithm::P256 => None,
+        }
+    }
+}
+
+/// Raw public key bytes of a [`Signer`]
+///
+/// Ed25519 keys are 32 bytes, P-256 keys are SEC1 encoded with 33 or 65 bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyMaterial(Vec<u8>);
+
+impl KeyMaterial {
+    /// parse and check a base64 or PEM encoded public key for `algorithm`
+    pub fn parse(algorithm: KeyAlgorithm, encoded: &str) -> Result<Self, String> {
+        let encoded = encoded.trim();
+
+        let key = if encoded.starts_with("-----BEGIN PUBLIC KEY-----") {
+            let body = encoded
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.starts_with("-----"))
+                .collect::<String>();
+            let der = decode(&body)?;
+
+            let prefix = match algorithm {
+                KeyAlgorithm::Ed25519 => ED25519_SPKI_PREFIX,
+                KeyAlgorithm::P256 => P256_SPKI_PREFIX,
+            };
+            if !der.starts_with(prefix) {
+                return Err(format!("PEM key is not a {} public key", algorithm));
+            }
+            der[prefix.len()..].to_vec()
+        } else {
+            decode(encoded)?
+        };
+
+        let valid = match algorithm {
+            KeyAlgorithm::Ed25519 => key.len() == 32,
+            KeyAlgorithm::P256 => match key.first() {
+                Some(0x02) | Some(0x03) => key.len() == 33,
+                Some(0x04) => key.len() == 65,
+                _ => false,
+            },
+        };
+        if !valid {
+            return Err(format!(
+                "invalid {} public key length of {} bytes",
+                algorithm,
+                key.len()
+            ));
+        }
+
+        Ok(Self(key))
+    }
+
+    /// get the raw key bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+fn decode(encoded: &str) -> Result<Vec<u8>, String> {
+    STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("invalid base64 key: {}", e))
+}
+
+impl Serialize for KeyMaterial {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        STANDARD.encode(&self.0).serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    #[test]
+    fn signers() {
+        const CONFIG: &str = r#"
+        [[signature.signers]]
+        id = "release"
+        algorithm = "ed25519"
+        key = "A6EHv/POEL4dcN0Y50vAmWfk1jCbpQ1fHdiGbDrzZ5c="
+        artifacts = [ "workload" ]
+
+        [[signature.signers]]
+        algorithm = "ed25519"
+        key = """
+        -----BEGIN PUBLIC KEY-----
+        MCowBQYDK2VwAyEAA6EHv/POEL4dcN0Y50vAmWfk1jCbpQ1fHdiGbDrzZ5c=
+        -----END PUBLIC KEY-----
+        """
+
+        [[signature.signers]]
+        id = "hsm-key-1"
+        algorithm = "p256"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let signers = cfg.signature.unwrap();
+
+        assert_eq!(signers.signers[0].key, signers.signers[1].key);
+        assert_eq!(signers.signers[1].artifacts, all_artifacts());
+        assert_eq!(signers.signers[2].key, None);
+        assert_eq!(signers.for_artifact(SignedArtifact::Include).count(), 2);
+    }
+
+    #[test]
+    fn invalid() {
+        let err = toml::from_str::<EnarxConfig>(
+            r#"
+            [[signature.signers]]
+            algorithm = "p256"
+            key = "A6EHv/POEL4dcN0Y50vAmWfk1jCbpQ1fHdiGbDrzZ5c="
+            "#,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("invalid p256 public key length of 32 bytes"));
+
+        let err = toml::from_str::<EnarxConfig>(
+            r#"
+            [[signature.signers]]
+            algorithm = "ed25519"
+            "#,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("a signer needs at least one of `id` or `key`"));
+    }
+}
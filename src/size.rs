@@ -0,0 +1,254 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Human-readable byte sizes like `"512KiB"`, `"10MB"` or `"1.5GiB"`
+
+use std::{fmt, num::NonZeroU64, ops::Deref, str::FromStr};
+
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
+
+/// binary units, largest first, used for formatting
+const BINARY_UNITS: &[(&str, u64)] = &[
+    ("TiB", 1 << 40),
+    ("GiB", 1 << 30),
+    ("MiB", 1 << 20),
+    ("KiB", 1 << 10),
+];
+
+/// decimal units, accepted when parsing
+const DECIMAL_UNITS: &[(&str, u64)] = &[
+    ("TB", 1_000_000_000_000),
+    ("GB", 1_000_000_000),
+    ("MB", 1_000_000),
+    ("KB", 1_000),
+    ("B", 1),
+];
+
+/// A number of bytes written human-readable, like `"512KiB"`, `"10MB"` or `"1.5GiB"`
+///
+/// Parsed from such a string, with binary (`KiB`, `MiB`, `GiB`, `TiB`) or decimal
+/// (`B`, `KB`, `MB`, `GB`, `TB`) units in any case, or from an integer number of bytes.
+/// Formatted with the largest binary unit representing it exactly, so `"1.5GiB"`
+/// is formatted as `"1536MiB"`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+/// The error of parsing a [`ByteSize`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseSizeError(String);
+
+impl fmt::Display for ParseSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseSizeError {}
+
+impl FromStr for ByteSize {
+    type Err = ParseSizeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            ParseSizeError(format!(
+                "invalid size `{}`, expected e.g. `4096`, `512KiB`, `10MB` or `1.5GiB`",
+                s
+            ))
+        };
+
+        let s = s.trim();
+        let number = s
+            .bytes()
+            .take_while(|b| b.is_ascii_digit() || *b == b'.')
+            .count();
+        let (number, unit) = s.split_at(number);
+        let (integer, fraction) = match number.split_once('.') {
+            Some((integer, fraction)) if !fraction.is_empty() => (integer, fraction),
+            Some(_) => return Err(invalid()),
+            None => (number, ""),
+        };
+        if integer.is_empty() || fraction.contains('.') {
+            return Err(invalid());
+        }
+
+        let unit = unit.trim_start();
+        let factor = match BINARY_UNITS
+            .iter()
+            .chain(DECIMAL_UNITS)
+            .find(|(name, _)| name.eq_ignore_ascii_case(unit))
+        {
+            Some((_, factor)) => *factor,
+            None if unit.is_empty() => 1,
+            None => return Err(invalid()),
+        };
+
+        let integer = integer.parse::<u64>().map_err(|_| invalid())?;
+        let mut bytes = integer.checked_mul(factor).ok_or_else(invalid)?;
+        if !fraction.is_empty() {
+            let scale = 10u128
+                .checked_pow(fraction.len() as u32)
+                .ok_or_else(invalid)?;
+            let fraction =
+                u128::from(fraction.parse::<u64>().map_err(|_| invalid())?) * u128::from(factor);
+            if fraction % scale != 0 {
+                return Err(ParseSizeError(format!(
+                    "invalid size `{}`, not a whole number of bytes",
+                    s
+                )));
+            }
+            let fraction = u64::try_from(fraction / scale).map_err(|_| invalid())?;
+            bytes = bytes.checked_add(fraction).ok_or_else(invalid)?;
+        }
+
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match BINARY_UNITS
+            .iter()
+            .find(|(_, factor)| self.0 != 0 && self.0.checked_rem(*factor) == Some(0))
+        {
+            Some((name, factor)) => write!(f, "{}{}", self.0 / factor, name),
+            None => write!(f, "{}B", self.0),
+        }
+    }
+}
+
+impl Deref for ByteSize {
+    type Target = u64;
+
+    fn deref(&self) -> &u64 {
+        &self.0
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+impl Serialize for ByteSize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+struct ByteSizeVisitor;
+
+impl<'de> Visitor<'de> for ByteSizeVisitor {
+    type Value = ByteSize;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a size string like `512KiB` or a number of bytes")
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(ByteSize(v))
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        u64::try_from(v)
+            .map(ByteSize)
+            .map_err(|_| E::custom("size must not be negative"))
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ByteSizeVisitor)
+    }
+}
+
+/// serde support for `Option<NonZeroU64>` fields of byte sizes
+pub(crate) mod option {
+    use super::*;
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<NonZeroU64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<ByteSize>::deserialize(deserializer)? {
+            Some(size) => NonZeroU64::new(size.0)
+                .map(Some)
+                .ok_or_else(|| serde::de::Error::custom("size must be greater than 0")),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn serialize<S>(size: &Option<NonZeroU64>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match size {
+            Some(size) => ByteSize(size.get()).serialize(serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_format() {
+        let parse = |s: &str| s.parse::<ByteSize>().map(|size| size.0);
+
+        assert_eq!(parse("4096"), Ok(4096));
+        assert_eq!(parse("512KiB"), Ok(512 * 1024));
+        assert_eq!(parse("10MB"), Ok(10_000_000));
+        assert_eq!(parse("1.5GiB"), Ok(1536 << 20));
+        assert_eq!(parse("2 mib"), Ok(2 << 20));
+        assert_eq!(parse("0B"), Ok(0));
+        assert!(parse("").is_err());
+        assert!(parse("KiB").is_err());
+        assert!(parse("1.KiB").is_err());
+        assert!(parse("1.2.3MB").is_err());
+        assert!(parse("10 bytes").is_err());
+        assert!(parse("-1").is_err());
+        assert!(parse("16777216TiB").is_err());
+        assert_eq!(
+            parse("1.5B").unwrap_err().to_string(),
+            "invalid size `1.5B`, not a whole number of bytes"
+        );
+
+        assert_eq!(ByteSize(1536 << 20).to_string(), "1536MiB");
+        assert_eq!(ByteSize(1 << 30).to_string(), "1GiB");
+        assert_eq!(ByteSize(10_000_000).to_string(), "10000000B");
+        assert_eq!(ByteSize(0).to_string(), "0B");
+    }
+
+    #[test]
+    fn serde() {
+        #[derive(Debug, PartialEq, Deserialize, Serialize)]
+        struct Limit {
+            #[serde(default, with = "option", skip_serializing_if = "Option::is_none")]
+            max_bytes: Option<NonZeroU64>,
+        }
+
+        let limit: Limit = toml::from_str("max_bytes = \"1MiB\"").unwrap();
+        assert_eq!(limit.max_bytes, NonZeroU64::new(1 << 20));
+        assert_eq!(toml::to_string(&limit).unwrap(), "max_bytes = \"1MiB\"\n");
+        let limit: Limit = toml::from_str("max_bytes = 4096").unwrap();
+        assert_eq!(limit.max_bytes, NonZeroU64::new(4096));
+        assert!(toml::from_str::<Limit>("max_bytes = 0").is_err());
+    }
+}
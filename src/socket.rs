@@ -0,0 +1,233 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Socket options of the `listen` and `connect` files
+
+use std::{fmt, time::Duration};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// TCP keepalive settings of the `[files.keepalive]` sub-table
+///
+/// Unset values use the defaults of the operating system.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KeepAlive {
+    /// idle time before the first keepalive probe is sent (`TCP_KEEPIDLE`)
+    #[serde(
+        default,
+        with = "crate::duration::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub idle: Option<Duration>,
+
+    /// time between keepalive probes (`TCP_KEEPINTVL`)
+    #[serde(
+        default,
+        with = "crate::duration::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub interval: Option<Duration>,
+
+    /// number of unanswered probes before the connection is dropped (`TCP_KEEPCNT`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+}
+
+impl KeepAlive {
+    pub(crate) fn validate(&self) -> Result<(), (&'static str, String)> {
+        let whole_seconds = |d: Option<Duration>| !matches!(d, Some(d) if d.as_secs() == 0 || d.subsec_nanos() != 0);
+
+        if !whole_seconds(self.idle) {
+            return Err(("keepalive.idle", "must be a whole number of seconds".into()));
+        }
+        if !whole_seconds(self.interval) {
+            return Err((
+                "keepalive.interval",
+                "must be a whole number of seconds".into(),
+            ));
+        }
+        if self.count == Some(0) {
+            return Err(("keepalive.count", "must be greater than 0".into()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Traffic priority of a socket, the `priority` of a `listen` or `connect` file
+///
+/// Either one of the classes `"low"`, `"normal"` and `"high"`, or an explicit DSCP value
+/// between 0 and 63 set on the outgoing packets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Priority {
+    /// bulk data transfers
+    Low,
+
+    /// the default class
+    Normal,
+
+    /// latency-critical traffic, e.g. control channels
+    High,
+
+    /// an explicit Differentiated Services Code Point
+    Dscp(u8),
+}
+
+impl Priority {
+    /// the DSCP value of the priority
+    ///
+    /// `low` maps to CS1 (8), `normal` to the default (0) and `high` to Expedited
+    /// Forwarding (46).
+    pub fn dscp(&self) -> u8 {
+        match self {
+            Self::Low => 8,
+            Self::Normal => 0,
+            Self::High => 46,
+            Self::Dscp(dscp) => *dscp,
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Low => f.write_str("low"),
+            Self::Normal => f.write_str("normal"),
+            Self::High => f.write_str("high"),
+            Self::Dscp(dscp) => write!(f, "{}", dscp),
+        }
+    }
+}
+
+impl Serialize for Priority {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Dscp(dscp) => serializer.serialize_u8(*dscp),
+            priority => serializer.collect_str(priority),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Priority {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = Priority;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(r#""low", "normal", "high" or a DSCP value between 0 and 63"#)
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                match v {
+                    "low" => Ok(Priority::Low),
+                    "normal" => Ok(Priority::Normal),
+                    "high" => Ok(Priority::High),
+                    _ => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+                }
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                match v {
+                    0..=63 => Ok(Priority::Dscp(v as u8)),
+                    _ => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+                }
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                match u64::try_from(v) {
+                    Ok(v) => self.visit_u64(v),
+                    Err(_) => Err(E::invalid_value(de::Unexpected::Signed(v), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    #[test]
+    fn keepalive() {
+        const CONFIG: &str = r#"
+        [[files]]
+        kind = "connect"
+        host = "example.com"
+
+        [files.keepalive]
+        idle = "5m"
+        interval = 30
+        count = 4
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(
+            cfg.files[0].as_connect().unwrap().keepalive,
+            Some(&KeepAlive {
+                idle: Some(Duration::from_secs(300)),
+                interval: Some(Duration::from_secs(30)),
+                count: Some(4),
+            })
+        );
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let invalid = KeepAlive {
+            idle: Some(Duration::from_millis(1500)),
+            ..Default::default()
+        };
+        assert_eq!(invalid.validate().unwrap_err().0, "keepalive.idle");
+    }
+
+    #[test]
+    fn priority() {
+        const CONFIG: &str = r#"
+        [[files]]
+        name = "CONTROL"
+        kind = "listen"
+        priority = "high"
+
+        [[files]]
+        kind = "connect"
+        host = "example.com"
+        priority = 10
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(
+            cfg.files[0].as_listen().unwrap().priority,
+            Some(Priority::High)
+        );
+        assert_eq!(
+            cfg.files[1].as_connect().unwrap().priority,
+            Some(Priority::Dscp(10))
+        );
+        assert_eq!(Priority::High.dscp(), 46);
+
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert!(json.contains(r#""priority":"high""#));
+        assert!(json.contains(r#""priority":10"#));
+        assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), cfg);
+
+        for priority in ["\"urgent\"", "64", "-1"] {
+            let config = format!(
+                "[[files]]\nkind = \"connect\"\nhost = \"example.com\"\npriority = {}",
+                priority
+            );
+            assert!(
+                toml::from_str::<EnarxConfig>(&config).is_err(),
+                "{}",
+                priority
+            );
+        }
+    }
+}
@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Locating the values of a TOML document by their path
+//!
+//! Requires the `toml` feature.
+
+use std::{collections::BTreeMap, fmt};
+
+use serde::{
+    de::{IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use toml::Spanned;
+
+use crate::{de::Key, timestamp::TOML_DATETIME, Location};
+
+/// the spans of the values of a TOML document
+enum Spans {
+    Table(BTreeMap<String, Spanned<Spans>>),
+    Array(Vec<Spanned<Spans>>),
+    Value,
+}
+
+impl<'de> Deserialize<'de> for Spans {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SpansVisitor)
+    }
+}
+
+struct SpansVisitor;
+
+impl<'de> Visitor<'de> for SpansVisitor {
+    type Value = Spans;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a TOML value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Spans, E> {
+        Ok(Spans::Value)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<Spans, E> {
+        Ok(Spans::Value)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<Spans, E> {
+        Ok(Spans::Value)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<Spans, E> {
+        Ok(Spans::Value)
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<Spans, E> {
+        Ok(Spans::Value)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Spans, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = vec![];
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Spans::Array(values))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Spans, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut table = BTreeMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            // the string of a datetime has no span of its own
+            if key == TOML_DATETIME {
+                map.next_value::<IgnoredAny>()?;
+                return Ok(Spans::Value);
+            }
+            table.insert(key, map.next_value()?);
+        }
+        Ok(Spans::Table(table))
+    }
+}
+
+/// locate the value at `path` in the TOML `source`
+///
+/// The tables of `[table]` and `[[array]]` headers have no span, a path ending in one of
+/// them is located at the innermost value with a span, or not at all.
+pub(crate) fn locate(source: &str, path: &[Key]) -> Option<Location> {
+    let root = toml::from_str::<Spans>(source).ok()?;
+
+    let mut node = &root;
+    let mut found = None;
+    for key in path {
+        let child = match (node, key) {
+            (Spans::Table(table), Key::Name(name)) => table.get(name),
+            (Spans::Array(values), Key::Index(i)) => values.get(*i),
+            _ => None,
+        };
+        let child = match child {
+            Some(child) => child,
+            None => break,
+        };
+        if child.end() > 0 {
+            found = Some(child.start());
+        }
+        node = child.get_ref();
+    }
+
+    let offset = found?;
+    let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    Some(Location {
+        line: source[..offset].matches('\n').count() + 1,
+        column: offset - line_start + 1,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn locate() {
+        const SOURCE: &str = r#"created = 2024-05-01T12:00:00Z
+
+[[files]]
+kind = "connect"
+fallback = [ { host = "b.example.com", port = -1 } ]
+
+[files.sub]
+a = 1
+"#;
+        let at = |path: &[Key]| super::locate(SOURCE, path);
+        let name = |name: &str| Key::Name(name.into());
+
+        assert_eq!(
+            at(&[name("created")]),
+            Some(Location {
+                line: 1,
+                column: 11
+            })
+        );
+        assert_eq!(
+            at(&[name("files"), Key::Index(0), name("kind")]),
+            Some(Location { line: 4, column: 8 })
+        );
+        assert_eq!(
+            at(&[
+                name("files"),
+                Key::Index(0),
+                name("fallback"),
+                Key::Index(0),
+                name("port")
+            ]),
+            Some(Location {
+                line: 5,
+                column: 47
+            })
+        );
+        // the missing field is located at the innermost value with a span
+        assert_eq!(
+            at(&[
+                name("files"),
+                Key::Index(0),
+                name("fallback"),
+                Key::Index(1)
+            ]),
+            Some(Location {
+                line: 5,
+                column: 12
+            })
+        );
+        assert_eq!(at(&[name("files"), Key::Index(0)]), None);
+        assert_eq!(at(&[name("missing")]), None);
+    }
+}
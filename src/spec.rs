@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compact spec strings of file entries, e.g. for `--file` arguments
+
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::{Error, File, Protocol};
+
+/// Parse a [`File`] from a compact spec
+///
+/// | Spec                                  | File                                    |
+/// |---------------------------------------|-----------------------------------------|
+/// | `null`, `stdin`, `stdout`, `stderr`   | the `kind` without a name               |
+/// | `stdout/LOG`                          | the `kind` named `LOG`                  |
+/// | `<prot>-listen://<addr>:<port>/<NAME>` | a `kind = "listen"` file named `NAME`  |
+/// | `<prot>-connect://<host>:<port>`      | a `kind = "connect"` file               |
+/// | `<prot>-connect://<host>:<port>/<NAME>` | a `kind = "connect"` file named `NAME` |
+///
+/// `<prot>` is `tcp` or `tls`, IPv6 addresses are written in brackets.
+/// All other fields have their default values.
+///
+/// # Examples
+///
+/// ```
+/// use enarx_config::File;
+///
+/// let file: File = "tls-listen://[::]:8443/API".parse().unwrap();
+/// assert_eq!(file.name(), "API");
+/// assert_eq!(file.to_spec_string().unwrap(), "tls-listen://[::]:8443/API");
+///
+/// let file: File = "tcp-connect://db.example.com:5432".parse().unwrap();
+/// assert_eq!(file.as_connect().unwrap().port.get(), 5432);
+/// ```
+impl FromStr for File {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| Error::parse(format!("invalid file spec `{}`: {}", s, reason));
+
+        let mut map = Map::new();
+        match s.split_once("://") {
+            None => {
+                let (kind, name) = match s.split_once('/') {
+                    Some((kind, name)) => (kind, Some(name)),
+                    None => (s, None),
+                };
+                if !matches!(kind, "null" | "stdin" | "stdout" | "stderr") {
+                    return Err(invalid(
+                        "expected `null`, `stdin`, `stdout`, `stderr`, \
+                         `<prot>-listen://` or `<prot>-connect://`",
+                    ));
+                }
+                map.insert("kind".into(), kind.into());
+                if let Some(name) = name.filter(|name| !name.is_empty()) {
+                    map.insert("name".into(), name.into());
+                }
+            }
+            Some((scheme, rest)) => {
+                let (prot, kind) = scheme
+                    .split_once('-')
+                    .ok_or_else(|| invalid("expected `<prot>-listen` or `<prot>-connect`"))?;
+                let prot = prot
+                    .parse::<Protocol>()
+                    .map_err(|e| invalid(&e.to_string()))?;
+
+                let (authority, name) = match rest.split_once('/') {
+                    Some((authority, name)) if !name.is_empty() => (authority, Some(name)),
+                    Some((authority, _)) => (authority, None),
+                    None => (rest, None),
+                };
+                let (host, port) = match authority.rsplit_once(':') {
+                    Some((host, port)) if host.ends_with(']') || !host.contains(':') => {
+                        (host, port)
+                    }
+                    _ => return Err(invalid("expected `<host>:<port>`")),
+                };
+                let port = port.parse::<u16>().map_err(|_| invalid("invalid port"))?;
+
+                match kind {
+                    "listen" => {
+                        let name = name.ok_or_else(|| invalid("`listen` requires a `/<NAME>`"))?;
+                        map.insert("addr".into(), host.into());
+                        map.insert("name".into(), name.into());
+                    }
+                    "connect" => {
+                        map.insert("host".into(), host.into());
+                        if let Some(name) = name {
+                            map.insert("name".into(), name.into());
+                        }
+                    }
+                    _ => return Err(invalid("expected `<prot>-listen` or `<prot>-connect`")),
+                }
+                map.insert("kind".into(), kind.into());
+                map.insert("prot".into(), prot.to_string().into());
+                map.insert("port".into(), port.into());
+            }
+        }
+
+        serde_json::from_value(Value::Object(map)).map_err(|e| invalid(&e.to_string()))
+    }
+}
+
+/// wrap IPv6 addresses in brackets
+fn bracketed(host: &str) -> String {
+    if host.contains(':') {
+        format!("[{}]", host)
+    } else {
+        host.into()
+    }
+}
+
+impl File {
+    /// the compact spec of the file, the inverse of its [`FromStr`] implementation
+    ///
+    /// Returns `None` for kinds without a spec, and for files with settings
+    /// a spec cannot express, like `tls` options or a `path`.
+    pub fn to_spec_string(&self) -> Option<String> {
+        let spec = match self {
+            Self::Null { name, .. }
+            | Self::Stdin { name, .. }
+            | Self::Stdout { name, .. }
+            | Self::Stderr { name, .. } => match name {
+                Some(name) => format!("{}/{}", self.kind(), &**name),
+                None => self.kind().into(),
+            },
+            Self::Listen {
+                name,
+                addr,
+                port,
+                prot,
+                ..
+            } => format!(
+                "{}-listen://{}:{}/{}",
+                prot,
+                bracketed(&addr.to_string()),
+                port,
+                &**name
+            ),
+            Self::Connect {
+                name,
+                host,
+                port,
+                prot,
+                ..
+            } => {
+                let mut spec = format!("{}-connect://{}:{}", prot, bracketed(host), port);
+                if let Some(name) = name {
+                    spec.push('/');
+                    spec.push_str(name);
+                }
+                spec
+            }
+            Self::Fifo { .. }
+            | Self::Pipe { .. }
+            | Self::Alias { .. }
+            | Self::Custom { .. }
+            | Self::Templated { .. }
+            | Self::Conditional { .. } => return None,
+        };
+
+        if spec.parse::<File>().ok()? == *self {
+            Some(spec)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ListenAddr;
+
+    #[test]
+    fn round_trip() {
+        for spec in [
+            "stdin",
+            "stdout/LOG",
+            "null",
+            "tls-listen://[::]:8443/API",
+            "tcp-listen://127.0.0.1:8080/METRICS",
+            "tcp-connect://db.example.com:5432",
+            "tls-connect://[2001:db8::1]:443/UPSTREAM",
+        ] {
+            let file = spec.parse::<File>().unwrap();
+            assert_eq!(file.to_spec_string().as_deref(), Some(spec));
+        }
+
+        let file: File = "tls-listen://*:8443/API".parse().unwrap();
+        let listen = file.as_listen().unwrap();
+        assert_eq!(listen.addr, &ListenAddr::Any);
+        assert_eq!(listen.prot, Protocol::Tls);
+    }
+
+    #[test]
+    fn inexpressible() {
+        let file: File = toml::from_str("kind = \"stdout\"\npath = \"/var/log/app.log\"").unwrap();
+        assert_eq!(file.to_spec_string(), None);
+    }
+
+    #[test]
+    fn invalid() {
+        for spec in [
+            "stdio",
+            "udp-listen://[::]:53/DNS",
+            "tls-bind://[::]:443/API",
+            "tls-listen://[::]:443",
+            "tls-listen://[::]/API",
+            "tcp-connect://db.example.com:x",
+            "tcp-connect://db.example.com:5432/A:B",
+        ] {
+            assert!(spec.parse::<File>().is_err(), "{}", spec);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The standard streams of the application, and the `stdio` policy for missing ones
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Access, EnarxConfig, File, ValidationError, IMPLICIT_FILES};
+
+/// What happens to the standard streams a non-empty `files` does not declare
+///
+/// A standard stream is declared by a file of its kind, or by a `kind = "null"` file
+/// with its name, like `{ kind = "null", name = "stdin" }`. An empty `files` has no
+/// file descriptors at all, the policy does not apply to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum StdioPolicy {
+    /// all standard streams have to be declared, checked by [`EnarxConfig::validate`]
+    #[serde(rename = "require")]
+    Require,
+
+    /// the missing standard streams are connected to the ones of the host
+    #[serde(rename = "inject-missing")]
+    InjectMissing,
+
+    /// the missing standard streams are connected to null
+    #[serde(rename = "null-missing")]
+    NullMissing,
+}
+
+impl fmt::Display for StdioPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Require => "require",
+            Self::InjectMissing => "inject-missing",
+            Self::NullMissing => "null-missing",
+        })
+    }
+}
+
+/// check if `file` declares the standard stream `stream`
+fn declares(file: &File, stream: &str) -> bool {
+    file.kind() == stream || (file.kind() == "null" && file.name() == stream)
+}
+
+/// the file injected for the missing standard stream `fd` by `policy`
+fn injected(policy: StdioPolicy, fd: usize) -> Option<File> {
+    let access = match fd {
+        0 => Access::ReadOnly,
+        _ => Access::WriteOnly,
+    };
+
+    match policy {
+        StdioPolicy::Require => None,
+        StdioPolicy::InjectMissing => EnarxConfig::default().files.into_iter().nth(fd),
+        StdioPolicy::NullMissing => Some(File::Null {
+            name: Some(IMPLICIT_FILES[fd].into()),
+            access: Some(access),
+            labels: Default::default(),
+        }),
+    }
+}
+
+/// the file descriptors of the standard streams a non-empty `files` does not declare
+fn missing(files: &[File]) -> impl Iterator<Item = usize> + '_ {
+    (0..IMPLICIT_FILES.len()).filter(move |fd| {
+        !files.is_empty() && !files.iter().any(|file| declares(file, IMPLICIT_FILES[*fd]))
+    })
+}
+
+/// add the files `policy` adds for the standard streams `files` does not declare
+///
+/// Each is inserted at the position of its file descriptor, or appended if `files`
+/// is shorter.
+pub(crate) fn inject(files: &mut Vec<File>, policy: Option<StdioPolicy>) {
+    let policy = match policy {
+        Some(policy) => policy,
+        None => return,
+    };
+
+    for fd in missing(files).collect::<Vec<_>>() {
+        if let Some(file) = injected(policy, fd) {
+            let index = fd.min(files.len());
+            files.insert(index, file);
+        }
+    }
+}
+
+impl EnarxConfig {
+    /// get the names of the standard streams, `"stdin"`, `"stdout"` and `"stderr"`,
+    /// a non-empty `files` does not declare
+    ///
+    /// Files with a `when` condition count as declaring their stream.
+    pub fn missing_stdio(&self) -> Vec<&'static str> {
+        missing(&self.files).map(|fd| IMPLICIT_FILES[fd]).collect()
+    }
+
+    /// get the files of the standard streams, stdin, stdout and stderr, which will exist
+    ///
+    /// These are the files declaring the streams, and the files the `stdio` policy adds
+    /// for the missing ones. Without a policy, or with `stdio = "require"`, missing
+    /// streams are `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::EnarxConfig;
+    ///
+    /// let config: EnarxConfig = toml::from_str(r#"
+    /// stdio = "null-missing"
+    ///
+    /// [[files]]
+    /// kind = "stdout"
+    /// "#).unwrap();
+    ///
+    /// let [stdin, stdout, stderr] = config.stdio_files();
+    /// assert_eq!(stdin.unwrap().kind(), "null");
+    /// assert_eq!(stdout.unwrap().kind(), "stdout");
+    /// assert_eq!(stderr.unwrap().name(), "stderr");
+    /// ```
+    pub fn stdio_files(&self) -> [Option<File>; 3] {
+        let file = |fd: usize| match self
+            .files
+            .iter()
+            .find(|file| declares(file, IMPLICIT_FILES[fd]))
+        {
+            Some(file) => Some(file.clone()),
+            None if self.files.is_empty() => None,
+            None => self.stdio.and_then(|policy| injected(policy, fd)),
+        };
+        [file(0), file(1), file(2)]
+    }
+
+    pub(crate) fn validate_stdio(&self) -> Result<(), ValidationError> {
+        if self.stdio != Some(StdioPolicy::Require) {
+            return Ok(());
+        }
+        match self.missing_stdio().first() {
+            Some(stream) => Err(ValidationError::new(
+                "files",
+                format!("missing `{}`, required by `stdio = \"require\"`", stream),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+    [[files]]
+    kind = "stdout"
+    path = "/var/log/app.log"
+
+    [[files]]
+    name = "API"
+    kind = "listen"
+    "#;
+
+    #[test]
+    fn policies() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.missing_stdio(), ["stdin", "stderr"]);
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.normalize().files.len(), 2);
+        let [stdin, stdout, stderr] = cfg.stdio_files();
+        assert!(stdin.is_none() && stderr.is_none());
+        assert!(stdout.unwrap().path().is_some());
+
+        let cfg: EnarxConfig = toml::from_str(&format!("stdio = \"require\"\n{}", CONFIG)).unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "files: missing `stdin`, required by `stdio = \"require\"`"
+        );
+
+        let cfg: EnarxConfig =
+            toml::from_str(&format!("stdio = \"inject-missing\"\n{}", CONFIG)).unwrap();
+        let normalized = cfg.normalize();
+        assert_eq!(
+            normalized.files.iter().map(File::kind).collect::<Vec<_>>(),
+            ["stdin", "stdout", "stderr", "listen"]
+        );
+        assert_eq!(normalized.validate(), Ok(()));
+        assert!(normalized.missing_stdio().is_empty());
+        assert_eq!(normalized.normalize(), normalized);
+        assert_eq!(cfg.fd_layout()[&0].kind(), "stdin");
+
+        let cfg: EnarxConfig =
+            toml::from_str(&format!("stdio = \"null-missing\"\n{}", CONFIG)).unwrap();
+        let normalized = cfg.normalize();
+        assert_eq!(
+            normalized
+                .files
+                .iter()
+                .map(|file| (file.kind(), file.name(), file.access()))
+                .collect::<Vec<_>>(),
+            [
+                ("null", "stdin", Access::ReadOnly),
+                ("stdout", "stdout", Access::WriteOnly),
+                ("null", "stderr", Access::WriteOnly),
+                ("listen", "API", Access::ReadWrite),
+            ]
+        );
+        assert_eq!(normalized.validate(), Ok(()));
+        assert!(normalized.missing_stdio().is_empty());
+        assert_eq!(
+            cfg.stdio_files(),
+            [
+                Some(normalized.files[0].clone()),
+                Some(cfg.files[0].clone()),
+                Some(normalized.files[2].clone()),
+            ]
+        );
+
+        // an empty `files` has no file descriptors
+        let cfg: EnarxConfig = toml::from_str("stdio = \"require\"\nfiles = []").unwrap();
+        assert!(cfg.missing_stdio().is_empty());
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.stdio_files(), [None, None, None]);
+    }
+}
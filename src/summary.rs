@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Human-readable rendering of an [`EnarxConfig`]
+
+use std::fmt;
+
+use crate::{EnarxConfig, File, Port};
+
+const REDACTED: &str = "***";
+
+/// A compact, human-readable summary of an [`EnarxConfig`]
+///
+/// Created by [`EnarxConfig::summary`]. The summary lists the steward, the number of
+/// arguments and environment variables and a table of all pre-opened file descriptors.
+///
+/// # Examples
+///
+/// ```
+/// extern crate toml;
+/// use enarx_config::EnarxConfig;
+/// const CONFIG: &str = r#"
+/// [[files]]
+/// name = "LISTEN"
+/// kind = "listen"
+/// port = 12345
+/// "#;
+///
+/// let config: EnarxConfig = toml::from_str(CONFIG).unwrap();
+/// println!("{}", config.summary().redact(true));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Summary<'a> {
+    config: &'a EnarxConfig,
+    redact: bool,
+}
+
+impl<'a> Summary<'a> {
+    pub(crate) fn new(config: &'a EnarxConfig) -> Self {
+        Self {
+            config,
+            redact: false,
+        }
+    }
+
+    /// replace potentially sensitive values (steward URL, hosts) with `***`
+    pub fn redact(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self
+    }
+
+    fn sensitive<'s>(&self, value: &'s str) -> &'s str {
+        if self.redact {
+            REDACTED
+        } else {
+            value
+        }
+    }
+
+    fn row(&self, fd: u32, file: &File) -> [String; 5] {
+        let (prot, address) = match file {
+            File::Listen {
+                addr, port, prot, ..
+            } => (prot.to_string(), join_host_port(&addr.to_string(), *port)),
+            File::Connect {
+                host, port, prot, ..
+            } => (
+                prot.to_string(),
+                join_host_port(self.sensitive(host), *port),
+            ),
+            _ => ("-".into(), "-".into()),
+        };
+
+        // the name of a normalized connect file defaults to its host
+        let name = match file {
+            File::Connect { host, .. } if file.name() == &**host => self.sensitive(file.name()),
+            _ => file.name(),
+        };
+
+        [
+            fd.to_string(),
+            name.into(),
+            file.kind().into(),
+            prot,
+            address,
+        ]
+    }
+}
+
+fn join_host_port(host: &str, port: Port) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let steward = match &self.config.steward {
+            Some(steward) => self.sensitive(steward.url.as_str()),
+            None => "none",
+        };
+
+        writeln!(f, "steward: {}", steward)?;
+        writeln!(f, "args:    {}", self.config.args.len())?;
+        writeln!(f, "env:     {}", self.config.env.len())?;
+
+        let header = [
+            "fd".to_string(),
+            "name".into(),
+            "kind".into(),
+            "prot".into(),
+            "address".into(),
+        ];
+        let rows = std::iter::once(header)
+            .chain(
+                self.config
+                    .fd_layout()
+                    .iter()
+                    .map(|(fd, file)| self.row(*fd, file)),
+            )
+            .collect::<Vec<_>>();
+
+        let mut widths = [0usize; 5];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+
+        for row in &rows {
+            let mut line = String::new();
+            for (i, (cell, width)) in row.iter().zip(widths).enumerate() {
+                if i > 0 {
+                    line.push_str("  ");
+                }
+                line.push_str(&format!("{:<width$}", cell, width = width));
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        steward = "https://steward.example.com/"
+        args = [ "--verbose" ]
+        env = { "FOO" = "foo", "BAR" = "bar" }
+
+        [[files]]
+        kind = "stdin"
+
+        [[files]]
+        name = "LISTEN"
+        kind = "listen"
+        port = 12345
+
+        [[files]]
+        kind = "connect"
+        prot = "tcp"
+        host = "db.example.com"
+        port = 5432
+    "#;
+
+    #[test]
+    fn summary() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+
+        assert_eq!(
+            cfg.summary().to_string(),
+            "\
+steward: https://steward.example.com/
+args:    1
+env:     2
+fd  name            kind     prot  address
+0   stdin           stdin    -     -
+1   LISTEN          listen   tls   [::]:12345
+2   db.example.com  connect  tcp   db.example.com:5432
+"
+        );
+    }
+
+    #[test]
+    fn redacted() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+
+        assert_eq!(
+            cfg.summary().redact(true).to_string(),
+            "\
+steward: ***
+args:    1
+env:     2
+fd  name    kind     prot  address
+0   stdin   stdin    -     -
+1   LISTEN  listen   tls   [::]:12345
+2   ***     connect  tcp   ***:5432
+"
+        );
+    }
+
+    #[test]
+    fn fd_numbers() {
+        let cfg: EnarxConfig = toml::from_str(
+            r#"
+            [[files]]
+            name = "API"
+            kind = "listen"
+            prot = "tcp"
+            port = 8080
+            mode = "http"
+            routes = [ { prefix = "/admin", name = "ADMIN" } ]
+
+            [[files]]
+            kind = "stdout"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            cfg.summary().to_string(),
+            "\
+steward: none
+args:    0
+env:     0
+fd  name    kind    prot  address
+0   API     listen  tcp   [::]:8080
+1   stdout  stdout  -     -
+2   ADMIN   listen  tcp   [::]:8080
+"
+        );
+    }
+}
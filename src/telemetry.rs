@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics export of the `[telemetry]` section
+
+use std::{fmt, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Host, Port, Protocol};
+
+/// Protocol the [`Telemetry`] metrics are exported with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Exporter {
+    /// OpenTelemetry protocol over gRPC
+    #[serde(rename = "otlp")]
+    Otlp,
+
+    /// statsd line protocol
+    #[serde(rename = "statsd")]
+    Statsd,
+}
+
+impl Exporter {
+    /// the port used if no `port` is specified
+    pub fn default_port(self) -> Port {
+        match self {
+            Self::Otlp => 4317.into(),
+            Self::Statsd => 8125.into(),
+        }
+    }
+}
+
+impl fmt::Display for Exporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Otlp => f.write_str("otlp"),
+            Self::Statsd => f.write_str("statsd"),
+        }
+    }
+}
+
+/// The `[telemetry]` section, declaring the endpoint the runtime exports metrics to
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Telemetry {
+    /// protocol of the metrics export
+    pub exporter: Exporter,
+
+    /// host of the metrics endpoint
+    pub host: Host,
+
+    /// port of the metrics endpoint, defaults to the [port of the exporter](Exporter::default_port)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<Port>,
+
+    /// transport protocol to the metrics endpoint
+    #[serde(default)]
+    pub prot: Protocol,
+
+    /// interval between two exports
+    #[serde(
+        default,
+        with = "crate::duration::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub interval: Option<Duration>,
+}
+
+impl Telemetry {
+    /// get the effective port of the metrics endpoint
+    pub fn port(&self) -> Port {
+        self.port.unwrap_or_else(|| self.exporter.default_port())
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), (&'static str, String)> {
+        if self.port().is_ephemeral() {
+            return Err(("port", "can not connect to port 0".into()));
+        }
+        if self.exporter == Exporter::Statsd && self.prot == Protocol::Tls {
+            return Err((
+                "prot",
+                "the `statsd` exporter does not support `prot = \"tls\"`".into(),
+            ));
+        }
+        if self.interval == Some(Duration::ZERO) {
+            return Err(("interval", "must not be zero".into()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    #[test]
+    fn telemetry() {
+        const CONFIG: &str = r#"
+        [telemetry]
+        exporter = "otlp"
+        host = "otel.example.com"
+        interval = "15s"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let telemetry = cfg.telemetry.as_ref().unwrap();
+
+        assert_eq!(telemetry.port(), 4317.into());
+        assert_eq!(telemetry.prot, Protocol::Tls);
+        assert_eq!(telemetry.interval, Some(Duration::from_secs(15)));
+        assert_eq!(cfg.validate(), Ok(()));
+    }
+
+    #[test]
+    fn statsd_tls() {
+        const CONFIG: &str = r#"
+        [telemetry]
+        exporter = "statsd"
+        host = "10.0.0.1"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "telemetry.prot: the `statsd` exporter does not support `prot = \"tls\"`"
+        );
+    }
+}
@@ -0,0 +1,276 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Self-documenting TOML rendering of an [`EnarxConfig`]
+
+use serde_json::{Map, Value};
+
+use crate::{reference, EnarxConfig};
+
+impl EnarxConfig {
+    /// render the configuration as `Enarx.toml` with a comment above every field
+    ///
+    /// All fields are written, including the ones set to their default, so
+    /// `EnarxConfig::default().to_commented_toml()` scaffolds a self-documenting
+    /// configuration for `init`-style commands.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::EnarxConfig;
+    ///
+    /// let template = EnarxConfig::default().to_commented_toml();
+    /// assert!(template.contains("# The arguments to provide to the application\nargs = []\n"));
+    ///
+    /// let parsed: EnarxConfig = toml::from_str(&template).unwrap();
+    /// assert_eq!(parsed, EnarxConfig::default());
+    /// ```
+    pub fn to_commented_toml(&self) -> String {
+        // serializing the config types into a `Value` cannot fail, all map keys are strings
+        let value = serde_json::to_value(self).expect("config is serializable");
+
+        let mut out = String::new();
+        if let Value::Object(map) = &value {
+            write_table(&mut out, &[], "", map);
+        }
+        out
+    }
+}
+
+fn is_table(value: &Value) -> bool {
+    value.is_object()
+}
+
+fn is_array_of_tables(value: &Value) -> bool {
+    matches!(value, Value::Array(values) if !values.is_empty() && values.iter().all(Value::is_object))
+}
+
+/// the keys of `map` in the order of the reference, unknown keys like `env` entries last
+fn ordered<'a>(path: &str, map: &'a Map<String, Value>) -> Vec<(&'a String, &'a Value)> {
+    let position = |key: &str| {
+        reference::FIELDS
+            .iter()
+            .position(|f| f.path == join(path, key))
+            .unwrap_or(usize::MAX)
+    };
+
+    let mut entries = map.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|(key, _)| position(key));
+    entries
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.into()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn write_doc(out: &mut String, path: &str) {
+    if let Some(doc) = reference::doc(path) {
+        out.push_str("# ");
+        out.push_str(doc);
+        out.push('\n');
+    }
+}
+
+/// write the entries of a table, whose header was already written
+///
+/// `header` are the keys of the table header, `path` is the reference path of the table.
+fn write_table(out: &mut String, header: &[&str], path: &str, map: &Map<String, Value>) {
+    let entries = ordered(path, map);
+
+    // tables of the schema get a header, entries of maps like `secrets` are written inline
+    let is_header = |key: &str, value: &Value| {
+        (is_table(value) || is_array_of_tables(value)) && reference::doc(&join(path, key)).is_some()
+    };
+
+    for (key, value) in &entries {
+        if is_header(key, value) {
+            continue;
+        }
+        write_doc(out, &join(path, key));
+        out.push_str(&format_key(key));
+        out.push_str(" = ");
+        write_value(out, value);
+        out.push('\n');
+    }
+
+    for (key, value) in &entries {
+        if !is_header(key, value) {
+            continue;
+        }
+        let path = join(path, key);
+        let mut header = header.to_vec();
+        header.push(key);
+
+        match value {
+            Value::Object(map) => {
+                write_header(out, &path, &header, false);
+                write_table(out, &header, &path, map);
+            }
+            Value::Array(values) if is_array_of_tables(value) => {
+                for value in values {
+                    if let Value::Object(map) = value {
+                        write_header(out, &path, &header, true);
+                        write_table(out, &header, &path, map);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn write_header(out: &mut String, path: &str, header: &[&str], array: bool) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    write_doc(out, path);
+
+    let header = header
+        .iter()
+        .map(|key| format_key(key))
+        .collect::<Vec<_>>()
+        .join(".");
+    if array {
+        out.push_str(&format!("[[{}]]\n", header));
+    } else {
+        out.push_str(&format!("[{}]\n", header));
+    }
+}
+
+fn format_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        key.into()
+    } else {
+        format_string(key)
+    }
+}
+
+fn format_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => {}
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&n.to_string()),
+        Value::String(s) => out.push_str(&format_string(s)),
+        Value::Array(values) if values.is_empty() => out.push_str("[]"),
+        Value::Array(values) => {
+            out.push_str("[ ");
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_value(out, value);
+            }
+            out.push_str(" ]");
+        }
+        Value::Object(map) => {
+            out.push_str("{ ");
+            for (i, (key, value)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                out.push_str(&format_key(key));
+                out.push_str(" = ");
+                write_value(out, value);
+            }
+            out.push_str(" }");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default() {
+        assert_eq!(
+            EnarxConfig::default().to_commented_toml(),
+            r#"# The arguments to provide to the application
+args = []
+
+# The environment variables to provide to the application
+[env]
+
+# A pre-opened file descriptor
+[[files]]
+# "null", "stdin", "stdout", "stderr", "fifo", "pipe", "alias", "listen", "connect" or "custom"
+kind = "stdin"
+
+# A pre-opened file descriptor
+[[files]]
+# "null", "stdin", "stdout", "stderr", "fifo", "pipe", "alias", "listen", "connect" or "custom"
+kind = "stdout"
+
+# A pre-opened file descriptor
+[[files]]
+# "null", "stdin", "stdout", "stderr", "fifo", "pipe", "alias", "listen", "connect" or "custom"
+kind = "stderr"
+"#
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        const CONFIG: &str = r#"
+        env = { "FOO" = "foo \"bar\"", "a.b" = "c" }
+        args = [ "--verbose" ]
+        steward = "https://steward.example.com/"
+
+        [secrets]
+        proxy-password = { env = "PROXY_PASSWORD" }
+
+        [proxy]
+        kind = "socks5"
+        host = "proxy.example.com"
+        port = 1080
+        username = "keep"
+        password = "proxy-password"
+
+        [network.egress]
+        ports = [ 443, "5432-5433" ]
+
+        [[files]]
+        name = "API"
+        kind = "listen"
+        port = 8080
+        keepalive = { idle = "1m", count = 3 }
+
+        [[files]]
+        kind = "connect"
+        hosts = [ "db1.example.com", { host = "db2.example.com", port = 5433 } ]
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let template = cfg.to_commented_toml();
+
+        assert!(template.contains("\n# port of the proxy\nport = 1080\n"));
+        assert!(template.contains("\n[files.keepalive]\n"));
+        assert_eq!(toml::from_str::<EnarxConfig>(&template).unwrap(), cfg);
+    }
+}
@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`proptest`] strategies generating valid configurations
+//!
+//! Requires the `testing` feature. All generated configurations pass
+//! [`EnarxConfig::validate`], so downstream crates can write property tests
+//! without re-deriving the invariants of the configuration.
+//!
+//! # Examples
+//!
+//! ```
+//! use enarx_config::testing::any_config;
+//! use proptest::prelude::*;
+//!
+//! proptest!(|(config in any_config())| {
+//!     prop_assert_eq!(config.validate(), Ok(()));
+//! });
+//! ```
+
+use std::collections::BTreeMap;
+
+use proptest::{collection, option, prelude::*};
+
+use crate::{
+    AddressFamily, EnarxConfig, File, FileName, Host, ListenAddr, ListenMode, Port, Protocol,
+};
+
+/// maximum number of generated `files`
+const MAX_FILES: usize = 8;
+
+/// generate a file descriptor name
+pub fn any_name() -> impl Strategy<Value = FileName> {
+    "[A-Z][A-Z0-9_]{0,11}".prop_map(FileName::from)
+}
+
+/// generate a non-ephemeral port
+pub fn any_port() -> impl Strategy<Value = Port> {
+    (1..=u16::MAX).prop_map(Port::from)
+}
+
+/// generate a transport protocol
+pub fn any_protocol() -> impl Strategy<Value = Protocol> {
+    prop_oneof![Just(Protocol::Tls), Just(Protocol::Tcp)]
+}
+
+/// generate a domain host name
+pub fn any_host() -> impl Strategy<Value = Host> {
+    "[a-z][a-z0-9]{0,9}(\\.[a-z][a-z0-9]{0,9}){0,2}\\.example\\.com"
+        .prop_map(|host| host.parse().expect("generated host is valid"))
+}
+
+/// generate a `kind = "stdin"`, `"stdout"`, `"stderr"` or `"null"` file
+pub fn any_stdio() -> impl Strategy<Value = File> {
+    let name = || option::of(any_name());
+    prop_oneof![
+        name().prop_map(|name| File::Null {
+            name,
+            access: None,
+            labels: BTreeMap::new(),
+        }),
+        name().prop_map(|name| File::Stdin {
+            name,
+            access: None,
+            path: None,
+            content: None,
+            content_base64: None,
+            labels: BTreeMap::new(),
+        }),
+        name().prop_map(|name| File::Stdout {
+            name,
+            access: None,
+            path: None,
+            mode: None,
+            max_bytes: None,
+            on_overflow: None,
+            labels: BTreeMap::new(),
+        }),
+        name().prop_map(|name| File::Stderr {
+            name,
+            access: None,
+            path: None,
+            mode: None,
+            max_bytes: None,
+            on_overflow: None,
+            labels: BTreeMap::new(),
+        }),
+    ]
+}
+
+/// generate a `kind = "listen"` file
+pub fn any_listen() -> impl Strategy<Value = File> {
+    (
+        any_name(),
+        any_port(),
+        1..=65535u32,
+        any::<(bool, bool, bool)>(),
+        any_protocol(),
+    )
+        .prop_map(
+            |(name, port, backlog, (reuseaddr, reuseport, nodelay), prot)| File::Listen {
+                name,
+                addr: ListenAddr::Any,
+                port,
+                ports: vec![],
+                ephemeral: false,
+                backlog,
+                reuseaddr,
+                reuseport,
+                keepalive: None,
+                nodelay,
+                priority: None,
+                idle_timeout: None,
+                allow: vec![],
+                max_connections_per_second: None,
+                max_concurrent_connections: None,
+                selftest: false,
+                prot,
+                tls: None,
+                cert: None,
+                key: None,
+                psk: None,
+                mode: ListenMode::Raw,
+                routes: vec![],
+                service: None,
+                labels: BTreeMap::new(),
+            },
+        )
+}
+
+/// generate a `kind = "connect"` file
+pub fn any_connect() -> impl Strategy<Value = File> {
+    (
+        option::of(any_name()),
+        any_host(),
+        any_port(),
+        any::<(bool, bool)>(),
+        any_protocol(),
+        0..5u32,
+    )
+        .prop_map(
+            |(name, host, port, (proxy, nodelay), prot, retries)| File::Connect {
+                name,
+                host,
+                port,
+                fallback: vec![],
+                family: AddressFamily::Any,
+                happy_eyeballs: true,
+                proxy,
+                prot,
+                tls: None,
+                psk: None,
+                advertise_identity: false,
+                keepalive: None,
+                nodelay,
+                priority: None,
+                idle_timeout: None,
+                timeout: None,
+                retries,
+                retry_backoff: None,
+                service: None,
+                labels: BTreeMap::new(),
+            },
+        )
+}
+
+/// generate a file of any kind
+pub fn any_file() -> impl Strategy<Value = File> {
+    prop_oneof![any_stdio(), any_listen(), any_connect()]
+}
+
+/// generate a configuration with `args`, `env` and uniquely named `files`
+pub fn any_config() -> impl Strategy<Value = EnarxConfig> {
+    (
+        collection::vec("[ -~]{0,16}", 0..4),
+        collection::btree_map("[A-Z][A-Z0-9_]{0,7}", "[ -~]{0,16}", 0..4),
+        collection::vec(any_file(), 0..MAX_FILES),
+    )
+        .prop_map(|(args, env, mut files)| {
+            for (i, file) in files.iter_mut().enumerate() {
+                let name = format!("{}_{}", file.name(), i).into();
+                match file {
+                    File::Null { name: n, .. }
+                    | File::Stdin { name: n, .. }
+                    | File::Stdout { name: n, .. }
+                    | File::Stderr { name: n, .. }
+                    | File::Connect { name: n, .. } => *n = Some(name),
+                    File::Listen { name: n, .. }
+                    | File::Fifo { name: n, .. }
+                    | File::Pipe { name: n, .. }
+                    | File::Alias { name: n, .. } => *n = name,
+                    // not generated by `any_file`
+                    File::Custom { .. } | File::Templated { .. } | File::Conditional { .. } => {}
+                }
+            }
+
+            EnarxConfig {
+                args,
+                env,
+                files,
+                ..Default::default()
+            }
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn valid(config in any_config()) {
+            prop_assert_eq!(config.validate(), Ok(()));
+        }
+
+        #[test]
+        fn round_trip(config in any_config()) {
+            let json = serde_json::to_string(&config).unwrap();
+            prop_assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), config);
+        }
+
+        #[test]
+        fn normalize_idempotent(config in any_config()) {
+            let normalized = config.normalize();
+            prop_assert_eq!(normalized.normalize(), normalized.clone());
+            prop_assert_eq!(normalized.to_canonical_bytes(), config.to_canonical_bytes());
+        }
+    }
+}
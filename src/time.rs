@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Clock policy of the `[time]` section
+
+use std::{fmt, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Base64, Host, Port};
+
+/// A WASI clock
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Clock {
+    /// wall clock time
+    #[serde(rename = "realtime")]
+    Realtime,
+
+    /// monotonic time since an unspecified point
+    #[serde(rename = "monotonic")]
+    Monotonic,
+
+    /// CPU time of the process
+    #[serde(rename = "process_cputime")]
+    ProcessCputime,
+
+    /// CPU time of the thread
+    #[serde(rename = "thread_cputime")]
+    ThreadCputime,
+}
+
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Realtime => f.write_str("realtime"),
+            Self::Monotonic => f.write_str("monotonic"),
+            Self::ProcessCputime => f.write_str("process_cputime"),
+            Self::ThreadCputime => f.write_str("thread_cputime"),
+        }
+    }
+}
+
+fn all_clocks() -> Vec<Clock> {
+    vec![
+        Clock::Realtime,
+        Clock::Monotonic,
+        Clock::ProcessCputime,
+        Clock::ThreadCputime,
+    ]
+}
+
+/// Protocol of a trusted [`TimeSource`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TimeSourceKind {
+    /// Network Time Protocol
+    #[serde(rename = "ntp")]
+    Ntp,
+
+    /// Roughtime, authenticating the time with the public key of the server
+    #[serde(rename = "roughtime")]
+    Roughtime,
+}
+
+impl TimeSourceKind {
+    /// the port used if no `port` is specified
+    pub fn default_port(self) -> Port {
+        match self {
+            Self::Ntp => 123.into(),
+            Self::Roughtime => 2002.into(),
+        }
+    }
+}
+
+/// The `[time.source]` the keep synchronizes its wall clock with
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TimeSource {
+    /// protocol of the time source
+    pub kind: TimeSourceKind,
+
+    /// host of the time source
+    pub host: Host,
+
+    /// port of the time source, defaults to the [port of the protocol](TimeSourceKind::default_port)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<Port>,
+
+    /// Ed25519 public key of a `roughtime` server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<Base64>,
+}
+
+impl TimeSource {
+    /// get the effective port of the time source
+    pub fn port(&self) -> Port {
+        self.port.unwrap_or_else(|| self.kind.default_port())
+    }
+}
+
+/// The `[time]` section
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Time {
+    /// the clocks exposed to the application, all by default
+    #[serde(default = "all_clocks")]
+    pub clocks: Vec<Clock>,
+
+    /// granularity the clock values are truncated to, mitigating timing side channels
+    #[serde(
+        default,
+        with = "crate::duration::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub resolution: Option<Duration>,
+
+    /// trusted source of the wall clock time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<TimeSource>,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self {
+            clocks: all_clocks(),
+            resolution: None,
+            source: None,
+        }
+    }
+}
+
+impl Time {
+    /// is `clock` exposed to the application
+    pub fn exposes(&self, clock: Clock) -> bool {
+        self.clocks.contains(&clock)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), (&'static str, String)> {
+        for (i, clock) in self.clocks.iter().enumerate() {
+            if self.clocks[..i].contains(clock) {
+                return Err(("clocks", format!("duplicate clock `{}`", clock)));
+            }
+        }
+        if self.resolution == Some(Duration::ZERO) {
+            return Err(("resolution", "must not be zero".into()));
+        }
+
+        if let Some(source) = &self.source {
+            if source.port().is_ephemeral() {
+                return Err(("source.port", "can not connect to port 0".into()));
+            }
+            match (source.kind, &source.key) {
+                (TimeSourceKind::Roughtime, None) => {
+                    return Err(("source.key", "`roughtime` requires a `key`".into()))
+                }
+                (TimeSourceKind::Roughtime, Some(key)) if key.len() != 32 => {
+                    return Err((
+                        "source.key",
+                        format!("invalid ed25519 public key length of {} bytes", key.len()),
+                    ))
+                }
+                (TimeSourceKind::Ntp, Some(_)) => {
+                    return Err(("source.key", "`ntp` does not support a `key`".into()))
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    #[test]
+    fn time() {
+        const CONFIG: &str = r#"
+        [time]
+        clocks = [ "realtime", "monotonic" ]
+        resolution = "1ms"
+
+        [time.source]
+        kind = "roughtime"
+        host = "roughtime.example.com"
+        key = "A6EHv/POEL4dcN0Y50vAmWfk1jCbpQ1fHdiGbDrzZ5c="
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        let time = cfg.time.as_ref().unwrap();
+
+        assert!(time.exposes(Clock::Monotonic));
+        assert!(!time.exposes(Clock::ThreadCputime));
+        assert_eq!(time.resolution, Some(Duration::from_millis(1)));
+        assert_eq!(time.source.as_ref().unwrap().port(), 2002.into());
+        assert_eq!(cfg.validate(), Ok(()));
+    }
+
+    #[test]
+    fn invalid() {
+        let cfg: EnarxConfig =
+            toml::from_str("[time.source]\nkind = \"roughtime\"\nhost = \"example.com\"").unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "time.source.key: `roughtime` requires a `key`"
+        );
+        assert!(cfg.time.unwrap().exposes(Clock::Realtime));
+    }
+}
@@ -0,0 +1,397 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! RFC 3339 timestamps like `"2024-05-01T12:00:00Z"`, and the validity window of a configuration
+
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{
+    de::{MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::EnarxConfig;
+
+/// the key TOML datetimes are deserialized with
+pub(crate) const TOML_DATETIME: &str = "$__toml_private_datetime";
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// the days since 1970-01-01 of a date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// the date of the proleptic Gregorian calendar of the days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// A point in time, written as an RFC 3339 timestamp like `"2024-05-01T12:00:00Z"`
+///
+/// Any offset is accepted, like `"2024-05-01T14:00:00+02:00"`, the timestamp is
+/// formatted in UTC. In TOML, it can also be written as an offset date-time without quotes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    secs: i64,
+    nanos: u32,
+}
+
+impl Timestamp {
+    /// the timestamp `secs` seconds after 1970-01-01T00:00:00Z, before it if negative
+    pub const fn from_unix(secs: i64) -> Self {
+        Self { secs, nanos: 0 }
+    }
+
+    /// the seconds since 1970-01-01T00:00:00Z, negative before it
+    pub const fn unix(self) -> i64 {
+        self.secs
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => Self {
+                secs: i64::try_from(since.as_secs()).unwrap_or(i64::MAX),
+                nanos: since.subsec_nanos(),
+            },
+            Err(before) => {
+                let before = before.duration();
+                let secs = i64::try_from(before.as_secs()).map_or(i64::MIN, |secs| -secs);
+                match before.subsec_nanos() {
+                    0 => Self { secs, nanos: 0 },
+                    nanos => Self {
+                        secs: secs.saturating_sub(1),
+                        nanos: 1_000_000_000 - nanos,
+                    },
+                }
+            }
+        }
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(timestamp: Timestamp) -> Self {
+        let nanos = Duration::from_nanos(timestamp.nanos.into());
+        match u64::try_from(timestamp.secs) {
+            Ok(secs) => UNIX_EPOCH + Duration::from_secs(secs) + nanos,
+            Err(_) => UNIX_EPOCH - Duration::from_secs(timestamp.secs.unsigned_abs()) + nanos,
+        }
+    }
+}
+
+/// The error of parsing a [`Timestamp`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseTimestampError(String);
+
+impl fmt::Display for ParseTimestampError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseTimestampError {}
+
+impl FromStr for Timestamp {
+    type Err = ParseTimestampError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            ParseTimestampError(format!(
+                "invalid timestamp `{}`: {}, expected e.g. `2024-05-01T12:00:00Z`",
+                s, reason
+            ))
+        };
+
+        let bytes = s.as_bytes();
+        let number = |range: std::ops::Range<usize>| -> Result<i64, ParseTimestampError> {
+            match bytes.get(range.clone()) {
+                Some(digits) if digits.iter().all(u8::is_ascii_digit) => {
+                    Ok(s[range].parse().expect("only digits"))
+                }
+                _ => Err(invalid("malformed date or time")),
+            }
+        };
+        let separator = |i: usize, allowed: &[u8]| match bytes.get(i) {
+            Some(b) if allowed.contains(b) => Ok(()),
+            _ => Err(invalid("malformed date or time")),
+        };
+
+        let year = number(0..4)?;
+        separator(4, b"-")?;
+        let month = number(5..7)?;
+        separator(7, b"-")?;
+        let day = number(8..10)?;
+        separator(10, b"Tt ")?;
+        let hour = number(11..13)?;
+        separator(13, b":")?;
+        let minute = number(14..16)?;
+        separator(16, b":")?;
+        let second = number(17..19)?;
+
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return Err(invalid("no such date"));
+        }
+        // a leap second is counted as the first second of the next minute
+        if hour > 23 || minute > 59 || second > 60 {
+            return Err(invalid("no such time"));
+        }
+
+        let mut rest = &s[19..];
+        let mut nanos = 0;
+        if let Some(fraction) = rest.strip_prefix('.') {
+            let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 {
+                return Err(invalid("malformed fraction of a second"));
+            }
+            // digits beyond nanoseconds are truncated
+            for (i, digit) in fraction[..digits.min(9)].bytes().enumerate() {
+                nanos += u32::from(digit - b'0') * 10u32.pow(8 - i as u32);
+            }
+            rest = &fraction[digits..];
+        }
+
+        let offset = match rest.as_bytes() {
+            [b'Z' | b'z'] => 0,
+            [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2]
+                if [h1, h2, m1, m2].iter().all(|b| b.is_ascii_digit()) =>
+            {
+                let hours = i64::from((h1 - b'0') * 10 + (h2 - b'0'));
+                let minutes = i64::from((m1 - b'0') * 10 + (m2 - b'0'));
+                if hours > 23 || minutes > 59 {
+                    return Err(invalid("no such offset"));
+                }
+                let offset = hours * 3600 + minutes * 60;
+                if *sign == b'-' {
+                    -offset
+                } else {
+                    offset
+                }
+            }
+            [] => return Err(invalid("an offset like `Z` or `+02:00` is required")),
+            _ => return Err(invalid("malformed offset")),
+        };
+
+        let days = days_from_civil(year, month, day);
+        let secs = days * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second - offset;
+        Ok(Self { secs, nanos })
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.secs.div_euclid(SECONDS_PER_DAY));
+        let secs = self.secs.rem_euclid(SECONDS_PER_DAY);
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )?;
+        if self.nanos != 0 {
+            let fraction = format!("{:09}", self.nanos);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        f.write_str("Z")
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+struct TimestampVisitor;
+
+impl<'de> Visitor<'de> for TimestampVisitor {
+    type Value = Timestamp;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an RFC 3339 timestamp like `2024-05-01T12:00:00Z`")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    /// an unquoted TOML date-time
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        match map.next_entry::<String, String>()? {
+            Some((key, value)) if key == TOML_DATETIME => {
+                value.parse().map_err(serde::de::Error::custom)
+            }
+            _ => Err(serde::de::Error::custom(
+                "expected an RFC 3339 timestamp like `2024-05-01T12:00:00Z`",
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(TimestampVisitor)
+    }
+}
+
+impl EnarxConfig {
+    /// is `time` within the validity window of `not_before` and `not_after`
+    ///
+    /// Both bounds are inclusive, a configuration without them is always valid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use std::time::{Duration, UNIX_EPOCH};
+    /// use enarx_config::EnarxConfig;
+    ///
+    /// let config: EnarxConfig = toml::from_str(r#"
+    /// not_before = "2024-05-01T00:00:00Z"
+    /// not_after = "2024-05-02T00:00:00Z"
+    /// "#).unwrap();
+    ///
+    /// let may_1 = UNIX_EPOCH + Duration::from_secs(1_714_521_600);
+    /// assert!(config.is_valid_at(may_1 + Duration::from_secs(3600)));
+    /// assert!(!config.is_valid_at(may_1 + Duration::from_secs(7 * 86_400)));
+    /// ```
+    pub fn is_valid_at(&self, time: SystemTime) -> bool {
+        let time = Timestamp::from(time);
+        !matches!(self.not_before, Some(not_before) if time < not_before)
+            && !matches!(self.not_after, Some(not_after) if time > not_after)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn timestamp() {
+        let parse = |s: &str| s.parse::<Timestamp>().map(|t| t.to_string());
+
+        assert_eq!(
+            parse("1970-01-01T00:00:00Z"),
+            Ok("1970-01-01T00:00:00Z".into())
+        );
+        assert_eq!(
+            "1970-01-01T00:00:00Z".parse::<Timestamp>().unwrap().unix(),
+            0
+        );
+        assert_eq!(
+            "2024-05-01T12:00:00Z".parse::<Timestamp>().unwrap().unix(),
+            1_714_564_800
+        );
+        assert_eq!(
+            parse("2024-05-01T14:30:00.250+02:00"),
+            Ok("2024-05-01T12:30:00.25Z".into())
+        );
+        assert_eq!(
+            parse("2024-02-29t23:00:00-01:30"),
+            Ok("2024-03-01T00:30:00Z".into())
+        );
+        assert_eq!(
+            parse("1969-12-31 23:59:59.000000001999z"),
+            Ok("1969-12-31T23:59:59.000000001Z".into())
+        );
+        assert_eq!(
+            parse("2016-12-31T23:59:60Z"),
+            Ok("2017-01-01T00:00:00Z".into())
+        );
+
+        for invalid in [
+            "",
+            "2024-05-01",
+            "2024-05-01T12:00:00",
+            "2024-05-01T12:00Z",
+            "2023-02-29T12:00:00Z",
+            "2024-13-01T12:00:00Z",
+            "2024-05-01T24:00:00Z",
+            "2024-05-01T12:00:00.Z",
+            "2024-05-01T12:00:00+2:00",
+            "2024-05-01T12:00:00+24:00",
+            "+2024-05-01T12:00:00Z",
+        ] {
+            assert!(parse(invalid).is_err(), "{}", invalid);
+        }
+
+        let time = UNIX_EPOCH - Duration::from_millis(1500);
+        let timestamp = Timestamp::from(time);
+        assert_eq!(timestamp.to_string(), "1969-12-31T23:59:58.5Z");
+        assert_eq!(SystemTime::from(timestamp), time);
+    }
+
+    #[test]
+    fn validity() {
+        const CONFIG: &str = r#"
+        not_before = 2024-05-01T00:00:00Z
+        not_after = "2024-05-01T06:00:00+02:00"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(cfg.not_after.unwrap().to_string(), "2024-05-01T04:00:00Z");
+
+        let at = |s: &str| SystemTime::from(s.parse::<Timestamp>().unwrap());
+        assert!(!cfg.is_valid_at(at("2024-04-30T23:59:59Z")));
+        assert!(cfg.is_valid_at(at("2024-05-01T00:00:00Z")));
+        assert!(cfg.is_valid_at(at("2024-05-01T04:00:00Z")));
+        assert!(!cfg.is_valid_at(at("2024-05-01T04:00:00.001Z")));
+        assert!(EnarxConfig::default().is_valid_at(SystemTime::now()));
+
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert!(json.contains(r#""not_before":"2024-05-01T00:00:00Z""#));
+        assert_eq!(serde_json::from_str::<EnarxConfig>(&json).unwrap(), cfg);
+
+        let reversed = EnarxConfig {
+            not_before: cfg.not_after,
+            not_after: cfg.not_before,
+            ..Default::default()
+        };
+        assert_eq!(
+            reversed.validate().unwrap_err().to_string(),
+            "not_after: must be after `not_before`"
+        );
+
+        assert!(toml::from_str::<EnarxConfig>("not_before = 2024-05-01T00:00:00").is_err());
+        assert!(toml::from_str::<EnarxConfig>("not_before = 2024-05-01").is_err());
+    }
+}
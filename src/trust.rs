@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Named trust anchors of the `[trust]` section
+
+use std::{fmt, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{EnarxConfig, File, ValidationError};
+
+/// A well-known root certificate shipped with the runtime
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BuiltinRoot {
+    /// the Intel SGX root CA
+    #[serde(rename = "sgx-root")]
+    SgxRoot,
+
+    /// the AMD root key (ARK) of the SEV-SNP Milan processors
+    #[serde(rename = "amd-milan")]
+    AmdMilan,
+
+    /// the AMD root key (ARK) of the SEV-SNP Genoa processors
+    #[serde(rename = "amd-genoa")]
+    AmdGenoa,
+}
+
+impl fmt::Display for BuiltinRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SgxRoot => f.write_str("sgx-root"),
+            Self::AmdMilan => f.write_str("amd-milan"),
+            Self::AmdGenoa => f.write_str("amd-genoa"),
+        }
+    }
+}
+
+/// Source of a named trust anchor
+///
+/// Other sections reference trust anchors by their name in the `[trust]` table,
+/// so the certificates don't have to be repeated.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TrustAnchor {
+    /// PEM encoded certificates
+    #[serde(rename = "pem")]
+    Pem(String),
+
+    /// a PEM bundle of certificates in a file of the host
+    #[serde(rename = "path")]
+    Path(PathBuf),
+
+    /// a root certificate shipped with the runtime
+    #[serde(rename = "builtin")]
+    Builtin(BuiltinRoot),
+}
+
+impl TrustAnchor {
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Pem(pem) if !pem.contains("-----BEGIN CERTIFICATE-----") => {
+                Err("`pem` contains no PEM encoded certificate".into())
+            }
+            Self::Path(path) if path.as_os_str().is_empty() => Err("empty path".into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl EnarxConfig {
+    /// get the trust anchor named `name` of the `[trust]` section
+    pub fn trust_anchor(&self, name: &str) -> Option<&TrustAnchor> {
+        self.trust.get(name)
+    }
+
+    /// get the references to trust anchors, by the path of the referencing array
+    pub(crate) fn trust_references(&self) -> Vec<(String, &[String])> {
+        let mut references: Vec<(String, &[String])> = vec![];
+        if let Some(steward) = &self.steward {
+            references.push(("steward.trust".into(), &steward.trust));
+        }
+        if let Some(attestation) = &self.attestation {
+            references.push(("attestation.trust".into(), &attestation.trust));
+        }
+        if let Some(trust) = self.tls.as_ref().and_then(|tls| tls.trust.as_ref()) {
+            references.push(("tls.trust".into(), trust));
+        }
+        for (i, service) in self.services.iter().enumerate() {
+            if let Some(trust) = service.tls.as_ref().and_then(|tls| tls.trust.as_ref()) {
+                references.push((format!("services[{}].tls.trust", i), trust));
+            }
+        }
+        for (i, file) in self.files.iter().enumerate() {
+            if let Some(trust) = file.tls().and_then(|tls| tls.trust.as_ref()) {
+                references.push((format!("files[{}].tls.trust", i), trust));
+            }
+        }
+
+        references
+    }
+
+    pub(crate) fn validate_trust(&self) -> Result<(), ValidationError> {
+        for (name, anchor) in &self.trust {
+            anchor
+                .validate()
+                .map_err(|message| ValidationError::new(format!("trust.{}", name), message))?;
+        }
+
+        for (path, names) in self.trust_references() {
+            for (i, name) in names.iter().enumerate() {
+                if !self.trust.contains_key(name) {
+                    return Err(ValidationError::new(
+                        format!("{}[{}]", path, i),
+                        format!("unknown trust anchor `{}`", name),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl File {
+    /// the names of the trust anchors peer certificates of the file are verified against,
+    /// empty for the trust store of the keep
+    pub fn trust_anchors(&self, config: &EnarxConfig) -> Vec<String> {
+        self.effective_tls(config)
+            .and_then(|tls| tls.trust)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CONFIG: &str = r#"
+    [trust]
+    corp = { path = "/etc/ssl/corp-ca.pem" }
+    amd = { builtin = "amd-milan" }
+    inline = { pem = "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n" }
+
+    [steward]
+    url = "https://steward.example.com"
+    trust = [ "corp" ]
+
+    [attestation]
+    trust = [ "amd" ]
+
+    [tls]
+    trust = [ "corp" ]
+
+    [[files]]
+    name = "API"
+    kind = "connect"
+    host = "api.example.com"
+    tls = { trust = [ "inline" ] }
+    "#;
+
+    #[test]
+    fn references() {
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(
+            cfg.trust_anchor("amd"),
+            Some(&TrustAnchor::Builtin(BuiltinRoot::AmdMilan))
+        );
+        assert_eq!(cfg.files[0].trust_anchors(&cfg), ["inline"]);
+
+        let cfg: EnarxConfig =
+            toml::from_str(&CONFIG.replace("trust = [ \"amd\" ]", "trust = [ \"intel\" ]"))
+                .unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "attestation.trust[0]: unknown trust anchor `intel`"
+        );
+
+        let cfg: EnarxConfig = toml::from_str(&CONFIG.replace("BEGIN", "BEGIN RSA")).unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "trust.inline: `pem` contains no PEM encoded certificate"
+        );
+    }
+}
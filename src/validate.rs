@@ -0,0 +1,882 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Semantic validation of an [`EnarxConfig`]
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    time::Duration,
+};
+
+use crate::{
+    Access, AttestationMode, Direction, EnarxConfig, File, KeepAlive, ListenMode, Overflow,
+    Protocol, TlsOptions,
+};
+
+/// upper bound of `backlog`, see `net.core.somaxconn` of Linux
+const MAX_BACKLOG: u32 = 65535;
+
+/// Error returned by [`EnarxConfig::validate`]
+///
+/// Contains the path of the offending value, e.g. `files[2].port`, and a description
+/// of the problem.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    path: String,
+    message: String,
+}
+
+impl ValidationError {
+    pub(crate) fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    /// the path of the offending value
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// the description of the problem
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl EnarxConfig {
+    /// check the semantic consistency of the configuration
+    ///
+    /// Deserialization only checks the syntax of the individual values,
+    /// this checks the relations between them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate toml;
+    /// use enarx_config::EnarxConfig;
+    /// const CONFIG: &str = r#"
+    /// [[files]]
+    /// name = "LISTEN"
+    /// kind = "listen"
+    /// port = 0
+    /// "#;
+    ///
+    /// let config: EnarxConfig = toml::from_str(CONFIG).unwrap();
+    /// let err = config.validate().unwrap_err();
+    /// assert_eq!(err.path(), "files[0].port");
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_templates()?;
+        self.validate_conditions()?;
+        if self.files.iter().any(|file| file.template().is_some()) {
+            return self.resolve_templates()?.validate();
+        }
+        if self.files.iter().any(|file| !file.ports().is_empty()) {
+            self.validate_ports()?;
+            let mut config = self.clone();
+            config.files = crate::ports::expand_ports(config.files);
+            return config.validate();
+        }
+
+        let mut names = HashSet::new();
+        let mut conditional = vec![];
+        for (i, file) in self.files.iter().enumerate() {
+            // only some of the conditional files are opened, `for_target` checks them
+            if file.when().is_none() && !names.insert(file.name()) {
+                return Err(ValidationError::new(
+                    format!("files[{}].name", i),
+                    format!("duplicate file name `{}`", file.name()),
+                ));
+            }
+            // but the normalization would merge equal ones, shifting the file descriptors
+            if file.when().is_some() {
+                let mut filled = file.clone();
+                crate::canonical::fill_implicit(&mut filled);
+                if let Some((j, _)) = conditional.iter().find(|(_, other)| *other == filled) {
+                    return Err(ValidationError::new(
+                        format!("files[{}]", i),
+                        format!("duplicate of `files[{}]`", j),
+                    ));
+                }
+                conditional.push((i, filled));
+            }
+
+            validate_file(file).map_err(|(field, message)| {
+                ValidationError::new(format!("files[{}].{}", i, field), message)
+            })?;
+
+            if let Some(tls) = file.tls() {
+                tls.validate().map_err(|(field, message)| {
+                    ValidationError::new(format!("files[{}].tls.{}", i, field), message)
+                })?;
+            }
+        }
+
+        for (i, file) in self.files.iter().enumerate() {
+            let routes = match file {
+                File::Listen { routes, .. } => routes,
+                _ => continue,
+            };
+            for (j, route) in routes.iter().enumerate() {
+                if !names.insert(&route.name) {
+                    return Err(ValidationError::new(
+                        format!("files[{}].routes[{}].name", i, j),
+                        format!("duplicate file name `{}`", &*route.name),
+                    ));
+                }
+            }
+        }
+
+        for (i, file) in self.files.iter().enumerate() {
+            let alias_of = match file {
+                File::Alias { alias_of, .. } => alias_of,
+                _ => continue,
+            };
+            let path = format!("files[{}].alias_of", i);
+            if !self.files.iter().any(|file| file.name() == &**alias_of) {
+                return Err(ValidationError::new(
+                    path,
+                    format!("unknown file `{}`", &**alias_of),
+                ));
+            }
+            if self.resolve(alias_of).is_none() {
+                return Err(ValidationError::new(
+                    path,
+                    format!("alias cycle through `{}`", &**alias_of),
+                ));
+            }
+        }
+
+        let mut pipes = BTreeMap::new();
+        for (i, file) in self.files.iter().enumerate() {
+            let (pipe, direction) = match file {
+                File::Pipe {
+                    pipe, direction, ..
+                } => (pipe, *direction),
+                _ => continue,
+            };
+            let ends = pipes.entry(&**pipe).or_insert((None, None));
+            let end = match direction {
+                Direction::Read => &mut ends.0,
+                Direction::Write => &mut ends.1,
+            };
+            if end.replace(i).is_some() {
+                return Err(ValidationError::new(
+                    format!("files[{}].direction", i),
+                    format!("duplicate `{}` end of pipe `{}`", direction, &**pipe),
+                ));
+            }
+        }
+        for (pipe, ends) in pipes {
+            let (i, missing) = match ends {
+                (Some(i), None) => (i, Direction::Write),
+                (None, Some(i)) => (i, Direction::Read),
+                _ => continue,
+            };
+            return Err(ValidationError::new(
+                format!("files[{}].pipe", i),
+                format!("pipe `{}` has no `{}` end", pipe, missing),
+            ));
+        }
+
+        self.validate_stdio()?;
+        self.validate_limits()?;
+
+        if let Some(dns) = self.network.as_ref().and_then(|n| n.dns.as_ref()) {
+            dns.validate().map_err(|(field, message)| {
+                ValidationError::new(format!("network.dns.{}", field), message)
+            })?;
+        }
+
+        if let Some(egress) = self.network.as_ref().and_then(|n| n.egress.as_ref()) {
+            for (i, connect) in self.files.iter().enumerate() {
+                let connect = match connect.as_connect() {
+                    Some(connect) => connect,
+                    None => continue,
+                };
+                for endpoint in connect.endpoints() {
+                    if !egress.allows(endpoint.host, endpoint.port) {
+                        return Err(ValidationError::new(
+                            format!("files[{}].host", i),
+                            format!(
+                                "`{}:{}` is not allowed by `network.egress`",
+                                endpoint.host, endpoint.port
+                            ),
+                        ));
+                    }
+                }
+            }
+
+            if let Some(source) = self.time.as_ref().and_then(|t| t.source.as_ref()) {
+                if !egress.allows(&source.host, source.port()) {
+                    return Err(ValidationError::new(
+                        "time.source.host",
+                        format!(
+                            "`{}:{}` is not allowed by `network.egress`",
+                            source.host,
+                            source.port()
+                        ),
+                    ));
+                }
+            }
+
+            if let Some(telemetry) = &self.telemetry {
+                if !egress.allows(&telemetry.host, telemetry.port()) {
+                    return Err(ValidationError::new(
+                        "telemetry.host",
+                        format!(
+                            "`{}:{}` is not allowed by `network.egress`",
+                            telemetry.host,
+                            telemetry.port()
+                        ),
+                    ));
+                }
+            }
+
+            if let Some(proxy) = &self.proxy {
+                if !egress.allows(&proxy.host, proxy.port) {
+                    return Err(ValidationError::new(
+                        "proxy.host",
+                        format!(
+                            "`{}:{}` is not allowed by `network.egress`",
+                            proxy.host, proxy.port
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if matches!(&self.random, Some(random) if random.seed.is_some())
+            && !matches!(&self.debug, Some(debug) if debug.allow_debug)
+        {
+            return Err(ValidationError::new(
+                "random.seed",
+                "a deterministic seed requires `debug.allow_debug = true`",
+            ));
+        }
+
+        if matches!(&self.steward, Some(steward) if steward.is_insecure()) && !self.steward_insecure
+        {
+            return Err(ValidationError::new(
+                "steward",
+                "plaintext `http://` requires `steward_insecure = true`",
+            ));
+        }
+
+        if let Some(attestation) = &self.attestation {
+            attestation.validate().map_err(|(field, message)| {
+                ValidationError::new(format!("attestation.{}", field), message)
+            })?;
+            if attestation.mode == AttestationMode::None && self.steward.is_some() {
+                return Err(ValidationError::new(
+                    "attestation.mode",
+                    "a `steward` requires attestation",
+                ));
+            }
+        }
+
+        if let Some(steward) = &self.steward {
+            steward.validate().map_err(|(field, message)| {
+                ValidationError::new(format!("steward.{}", field), message)
+            })?;
+        }
+
+        if let Some(csr) = self.steward.as_ref().and_then(|s| s.csr.as_ref()) {
+            csr.validate().map_err(|(field, message)| {
+                ValidationError::new(format!("steward.csr.{}", field), message)
+            })?;
+        }
+
+        if let Some(crypto) = &self.crypto {
+            self.validate_crypto(crypto)?;
+        }
+
+        if let Some(identity) = &self.identity {
+            self.validate_identity(identity)?;
+        }
+
+        self.validate_env_exports()?;
+
+        if let Some(runtime) = &self.runtime {
+            runtime.validate().map_err(|(field, message)| {
+                ValidationError::new(format!("runtime.{}", field), message)
+            })?;
+            if runtime.export_env {
+                self.validate_export_env()?;
+            }
+        }
+
+        if let Some(time) = &self.time {
+            time.validate().map_err(|(field, message)| {
+                ValidationError::new(format!("time.{}", field), message)
+            })?;
+        }
+
+        if let Some(health) = &self.health {
+            self.validate_health(health)?;
+        }
+
+        if let (Some(not_before), Some(not_after)) = (self.not_before, self.not_after) {
+            if not_after <= not_before {
+                return Err(ValidationError::new(
+                    "not_after",
+                    "must be after `not_before`",
+                ));
+            }
+        }
+
+        if let Some(report) = &self.report {
+            self.validate_report(report)?;
+        }
+
+        if let Some(lifecycle) = &self.lifecycle {
+            lifecycle.validate().map_err(|(field, message)| {
+                ValidationError::new(format!("lifecycle.{}", field), message)
+            })?;
+        }
+
+        if let Some(debug) = &self.debug {
+            debug.validate().map_err(|(field, message)| {
+                ValidationError::new(format!("debug.{}", field), message)
+            })?;
+        }
+
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.validate().map_err(|(field, message)| {
+                ValidationError::new(format!("telemetry.{}", field), message)
+            })?;
+        }
+
+        if let Some(wasm) = &self.wasm {
+            self.validate_wasm(wasm)?;
+        }
+
+        self.validate_kv()?;
+        self.validate_services()?;
+        self.validate_trust()?;
+        self.validate_certificates()?;
+        self.validate_psk()?;
+        self.validate_advertised_identity()?;
+        self.validate_labels()?;
+
+        if let Some(tls) = &self.tls {
+            tls.validate().map_err(|(field, message)| {
+                ValidationError::new(format!("tls.{}", field), message)
+            })?;
+        }
+        self.validate_key_exchange()?;
+
+        if let Some(proxy) = &self.proxy {
+            if proxy.port.is_ephemeral() {
+                return Err(ValidationError::new(
+                    "proxy.port",
+                    "can not connect to port 0",
+                ));
+            }
+            if let Some(password) = &proxy.password {
+                if proxy.username.is_none() {
+                    return Err(ValidationError::new(
+                        "proxy.password",
+                        "a password requires a `username`",
+                    ));
+                }
+                self.check_secret("proxy.password", password)?;
+            }
+        }
+
+        self.validate_apps()?;
+
+        Ok(())
+    }
+
+    /// check that `name` references a secret of the `[secrets]` section
+    pub(crate) fn check_secret(&self, path: &str, name: &str) -> Result<(), ValidationError> {
+        if self.secrets.contains_key(name) {
+            Ok(())
+        } else {
+            Err(ValidationError::new(
+                path,
+                format!("unknown secret `{}`", name),
+            ))
+        }
+    }
+}
+
+fn validate_file(file: &File) -> Result<(), (&'static str, String)> {
+    match file {
+        File::Conditional { file, .. } => validate_file(file),
+
+        File::Custom { r#type, .. } if r#type.is_empty() => {
+            Err(("type", "must not be empty".into()))
+        }
+
+        File::Stdin {
+            access: Some(access),
+            ..
+        } if *access != Access::ReadOnly => Err((
+            "access",
+            format!("`stdin` can only be read from, not `{}`", access),
+        )),
+
+        File::Stdout {
+            access: Some(access),
+            ..
+        }
+        | File::Stderr {
+            access: Some(access),
+            ..
+        } if *access != Access::WriteOnly => Err((
+            "access",
+            format!("`{}` can only be written to, not `{}`", file.kind(), access),
+        )),
+
+        File::Stdin {
+            content: Some(_),
+            content_base64: Some(_),
+            ..
+        } => Err((
+            "content_base64",
+            "only one of `content` and `content_base64` can be specified".into(),
+        )),
+
+        File::Stdin {
+            path: Some(_),
+            content: Some(_),
+            ..
+        }
+        | File::Stdin {
+            path: Some(_),
+            content_base64: Some(_),
+            ..
+        } => Err((
+            "path",
+            "`path` can not be combined with inline content".into(),
+        )),
+
+        File::Stdout {
+            path: None,
+            mode: Some(_),
+            ..
+        }
+        | File::Stderr {
+            path: None,
+            mode: Some(_),
+            ..
+        } => Err(("mode", "`mode` requires a `path`".into())),
+
+        File::Stdout {
+            max_bytes: None,
+            on_overflow: Some(_),
+            ..
+        }
+        | File::Stderr {
+            max_bytes: None,
+            on_overflow: Some(_),
+            ..
+        } => Err(("on_overflow", "`on_overflow` requires `max_bytes`".into())),
+
+        File::Stdout {
+            path: None,
+            on_overflow: Some(Overflow::Rotate),
+            ..
+        }
+        | File::Stderr {
+            path: None,
+            on_overflow: Some(Overflow::Rotate),
+            ..
+        } => Err((
+            "on_overflow",
+            "`on_overflow = \"rotate\"` requires a `path`".into(),
+        )),
+
+        File::Stdin {
+            path: Some(path), ..
+        }
+        | File::Stdout {
+            path: Some(path), ..
+        }
+        | File::Stderr {
+            path: Some(path), ..
+        }
+        | File::Fifo { path, .. }
+            if path.as_os_str().is_empty() =>
+        {
+            Err(("path", "empty path".into()))
+        }
+
+        File::Listen {
+            prot: Protocol::Tcp,
+            cert: Some(_),
+            ..
+        } => Err(("cert", "`cert` requires `prot = \"tls\"`".into())),
+
+        File::Listen {
+            cert: Some(_),
+            key: None,
+            ..
+        } => Err(("key", "`cert` requires a `key`".into())),
+
+        File::Listen {
+            cert: None,
+            key: Some(_),
+            ..
+        } => Err(("cert", "`key` requires a `cert`".into())),
+
+        File::Listen {
+            prot: Protocol::Tcp,
+            tls: Some(_),
+            ..
+        }
+        | File::Connect {
+            prot: Protocol::Tcp,
+            tls: Some(_),
+            ..
+        } => Err(("tls", "`tls` requires `prot = \"tls\"`".into())),
+
+        File::Connect {
+            tls:
+                Some(TlsOptions {
+                    rotate_after: Some(_),
+                    ..
+                }),
+            ..
+        } => Err((
+            "tls.rotate_after",
+            "`rotate_after` is only supported for `kind = \"listen\"`".into(),
+        )),
+
+        File::Connect {
+            tls:
+                Some(TlsOptions {
+                    ticket_lifetime: Some(_),
+                    ..
+                }),
+            ..
+        } => Err((
+            "tls.ticket_lifetime",
+            "`ticket_lifetime` is only supported for `kind = \"listen\"`".into(),
+        )),
+
+        File::Connect {
+            tls:
+                Some(TlsOptions {
+                    client_auth: Some(_),
+                    ..
+                }),
+            ..
+        } => Err((
+            "tls.client_auth",
+            "`client_auth` is only supported for `kind = \"listen\"`".into(),
+        )),
+
+        File::Connect {
+            tls:
+                Some(TlsOptions {
+                    server_names: Some(_),
+                    ..
+                }),
+            ..
+        } => Err((
+            "tls.server_names",
+            "`server_names` is only supported for `kind = \"listen\"`".into(),
+        )),
+
+        File::Listen {
+            tls:
+                Some(TlsOptions {
+                    rotate_after: Some(rotate_after),
+                    ..
+                }),
+            ..
+        } if rotate_after.is_zero() => Err(("tls.rotate_after", "must not be zero".into())),
+
+        File::Listen {
+            mode: ListenMode::Raw,
+            routes,
+            ..
+        } if !routes.is_empty() => Err((
+            "routes",
+            "`routes` require `mode = \"http\"` or `mode = \"h2\"`".into(),
+        )),
+
+        File::Listen {
+            port,
+            ephemeral,
+            backlog,
+            keepalive,
+            idle_timeout,
+            routes,
+            ..
+        } => {
+            let mut prefixes = HashSet::new();
+            for route in routes {
+                if !route.prefix.starts_with('/') {
+                    return Err((
+                        "routes",
+                        format!("route prefix `{}` must start with `/`", route.prefix),
+                    ));
+                }
+                if !prefixes.insert(route.prefix.trim_end_matches('/')) {
+                    return Err((
+                        "routes",
+                        format!("duplicate route prefix `{}`", route.prefix),
+                    ));
+                }
+            }
+
+            match (port.is_ephemeral(), ephemeral) {
+                (true, false) => return Err((
+                    "port",
+                    "port 0 selects a random port, set `ephemeral = true` to request this explicitly"
+                        .into(),
+                )),
+                (false, true) => return Err((
+                    "ephemeral",
+                    format!(
+                        "`ephemeral = true` requests a random port, but port {} is specified",
+                        port
+                    ),
+                )),
+                _ => {}
+            }
+
+            if *backlog == 0 || *backlog > MAX_BACKLOG {
+                return Err((
+                    "backlog",
+                    format!("backlog must be between 1 and {}", MAX_BACKLOG),
+                ));
+            }
+
+            if *idle_timeout == Some(Duration::ZERO) {
+                return Err(("idle_timeout", "must be greater than 0".into()));
+            }
+
+            keepalive.as_ref().map_or(Ok(()), KeepAlive::validate)
+        }
+
+        File::Connect {
+            host,
+            port,
+            fallback,
+            family,
+            keepalive,
+            idle_timeout,
+            timeout,
+            retries,
+            retry_backoff,
+            ..
+        } => {
+            if port.is_ephemeral() {
+                return Err(("port", "can not connect to port 0".into()));
+            }
+
+            if fallback.iter().any(|e| e.port.is_ephemeral()) {
+                return Err(("fallback", "can not connect to port 0".into()));
+            }
+
+            let hosts = std::iter::once(host).chain(fallback.iter().map(|e| &e.host));
+            for ip in hosts.filter_map(|h| h.ip()) {
+                if !family.matches(ip) {
+                    return Err((
+                        "family",
+                        format!("address `{}` is not in address family `{}`", ip, family),
+                    ));
+                }
+            }
+
+            if *timeout == Some(Duration::ZERO) {
+                return Err(("timeout", "must be greater than 0".into()));
+            }
+
+            if *idle_timeout == Some(Duration::ZERO) {
+                return Err(("idle_timeout", "must be greater than 0".into()));
+            }
+
+            if retry_backoff.is_some() && *retries == 0 {
+                return Err((
+                    "retry_backoff",
+                    "has no effect without `retries` greater than 0".into(),
+                ));
+            }
+
+            keepalive.as_ref().map_or(Ok(()), KeepAlive::validate)
+        }
+
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn validate(config: &str) -> Result<(), ValidationError> {
+        toml::from_str::<EnarxConfig>(config).unwrap().validate()
+    }
+
+    #[test]
+    fn ephemeral() {
+        assert_eq!(
+            validate(
+                r#"
+                [[files]]
+                name = "A"
+                kind = "listen"
+                ephemeral = true
+                "#
+            ),
+            Ok(())
+        );
+
+        let err = validate(
+            r#"
+            [[files]]
+            name = "A"
+            kind = "listen"
+            port = 8080
+            ephemeral = true
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(err.path(), "files[0].ephemeral");
+
+        let err = validate(
+            r#"
+            [[files]]
+            kind = "connect"
+            host = "example.com"
+            port = 0
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "files[0].port: can not connect to port 0");
+    }
+
+    #[test]
+    fn retries() {
+        let err = validate(
+            r#"
+            [[files]]
+            kind = "connect"
+            host = "example.com"
+            retry_backoff = "1s"
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(err.path(), "files[0].retry_backoff");
+    }
+
+    #[test]
+    fn idle_timeout() {
+        let err =
+            validate("[[files]]\nkind = \"listen\"\nname = \"A\"\nidle_timeout = 0").unwrap_err();
+        assert_eq!(err.path(), "files[0].idle_timeout");
+
+        let err = validate("[[files]]\nkind = \"connect\"\nhost = \"db\"\nidle_timeout = \"0s\"")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "files[0].idle_timeout: must be greater than 0"
+        );
+    }
+
+    #[test]
+    fn family() {
+        let err = validate(
+            r#"
+            [[files]]
+            kind = "connect"
+            host = "127.0.0.1"
+            family = "v6"
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "files[0].family: address `127.0.0.1` is not in address family `v6`"
+        );
+    }
+
+    #[test]
+    fn access() {
+        assert_eq!(
+            validate("[[files]]\nkind = \"null\"\naccess = \"ro\""),
+            Ok(())
+        );
+
+        let err = validate("[[files]]\nkind = \"stdout\"\naccess = \"ro\"").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "files[0].access: `stdout` can only be written to, not `ro`"
+        );
+
+        let err = validate("[[files]]\nkind = \"stdin\"\naccess = \"rw\"").unwrap_err();
+        assert_eq!(err.path(), "files[0].access");
+    }
+
+    #[test]
+    fn stdio_path() {
+        assert_eq!(
+            validate(
+                "[[files]]\nkind = \"stderr\"\npath = \"/var/log/app.log\"\nmode = \"append\""
+            ),
+            Ok(())
+        );
+
+        let err = validate("[[files]]\nkind = \"stdout\"\nmode = \"truncate\"").unwrap_err();
+        assert_eq!(err.to_string(), "files[0].mode: `mode` requires a `path`");
+
+        let err = validate("[[files]]\nkind = \"stdout\"\nmax_bytes = 1\non_overflow = \"rotate\"")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "files[0].on_overflow: `on_overflow = \"rotate\"` requires a `path`"
+        );
+    }
+
+    #[test]
+    fn duplicate_names() {
+        let err = validate(
+            r#"
+            [[files]]
+            kind = "stdin"
+
+            [[files]]
+            name = "stdin"
+            kind = "null"
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "files[1].name: duplicate file name `stdin`"
+        );
+    }
+
+    #[test]
+    fn backlog() {
+        let err = validate(
+            r#"
+            [[files]]
+            name = "A"
+            kind = "listen"
+            backlog = 100000
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "files[0].backlog: backlog must be between 1 and 65535"
+        );
+    }
+}
@@ -0,0 +1,362 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed views of the socket [`File`] variants
+
+use std::{collections::BTreeMap, net::IpAddr, num::NonZeroU32, time::Duration};
+
+use crate::{
+    AddressFamily, Cidr, Endpoint, File, Host, HttpRoute, KeepAlive, ListenAddr, ListenMode,
+    PemSource, Port, PortRange, Priority, Protocol, Psk, TlsOptions,
+};
+
+/// Typed view of a `kind = "listen"` [`File`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ListenView<'a> {
+    /// name of the file descriptor
+    pub name: &'a str,
+
+    /// address to listen on
+    pub addr: &'a ListenAddr,
+
+    /// port to listen on
+    pub port: Port,
+
+    /// all ports to listen on, if more than `port`
+    pub ports: &'a [PortRange],
+
+    /// whether the operating system chooses the port
+    pub ephemeral: bool,
+
+    /// maximum length of the queue of pending connections
+    pub backlog: u32,
+
+    /// set `SO_REUSEADDR` on the socket
+    pub reuseaddr: bool,
+
+    /// set `SO_REUSEPORT` on the socket
+    pub reuseport: bool,
+
+    /// TCP keepalive settings of accepted connections
+    pub keepalive: Option<&'a KeepAlive>,
+
+    /// disable Nagle's algorithm on accepted connections
+    pub nodelay: bool,
+
+    /// traffic priority of accepted connections
+    pub priority: Option<Priority>,
+
+    /// time after which accepted connections without any traffic are closed
+    pub idle_timeout: Option<Duration>,
+
+    /// networks clients are allowed to connect from, all if empty
+    pub allow: &'a [Cidr],
+
+    /// maximum number of connections accepted per second
+    pub max_connections_per_second: Option<NonZeroU32>,
+
+    /// maximum number of simultaneously open accepted connections
+    pub max_concurrent_connections: Option<NonZeroU32>,
+
+    /// whether the runtime verifies binding and the TLS handshake before starting the workload
+    pub selftest: bool,
+
+    /// protocol to use
+    pub prot: Protocol,
+
+    /// TLS settings overriding the `[tls]` section
+    pub tls: Option<&'a TlsOptions>,
+
+    /// certificate chain presented instead of the one issued by the Steward
+    pub cert: Option<&'a PemSource>,
+
+    /// private key of `cert`
+    pub key: Option<&'a PemSource>,
+
+    /// pre-shared key authenticating the clients instead of certificates
+    pub psk: Option<&'a Psk>,
+
+    /// how accepted connections are handed to the application
+    pub mode: ListenMode,
+
+    /// routes of requests to other file descriptors
+    pub routes: &'a [HttpRoute],
+
+    /// name of the `[[services]]` entry the file inherits settings from
+    pub service: Option<&'a str>,
+
+    /// free-form labels of the file
+    pub labels: &'a BTreeMap<String, String>,
+}
+
+/// Typed view of a `kind = "connect"` [`File`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConnectView<'a> {
+    /// name of the file descriptor
+    pub name: &'a str,
+
+    /// host to connect to
+    pub host: &'a Host,
+
+    /// port to connect to
+    pub port: Port,
+
+    /// endpoints to try in order, if `host` and `port` can not be connected to
+    pub fallback: &'a [Endpoint],
+
+    /// address family to resolve `host` with
+    pub family: AddressFamily,
+
+    /// race IPv6 and IPv4 connection attempts
+    pub happy_eyeballs: bool,
+
+    /// tunnel the connection through the `[proxy]`, if configured
+    pub proxy: bool,
+
+    /// protocol to use
+    pub prot: Protocol,
+
+    /// TCP keepalive settings
+    pub keepalive: Option<&'a KeepAlive>,
+
+    /// disable Nagle's algorithm
+    pub nodelay: bool,
+
+    /// traffic priority of the connection
+    pub priority: Option<Priority>,
+
+    /// time after which the connection is closed without any traffic
+    pub idle_timeout: Option<Duration>,
+
+    /// timeout of a single connection attempt
+    pub timeout: Option<Duration>,
+
+    /// number of additional connection attempts after a failure
+    pub retries: u32,
+
+    /// delay between connection attempts
+    pub retry_backoff: Option<Duration>,
+
+    /// TLS settings overriding the `[tls]` section
+    pub tls: Option<&'a TlsOptions>,
+
+    /// pre-shared key authenticating the connection instead of certificates
+    pub psk: Option<&'a Psk>,
+
+    /// present the attested identity of the keep as client certificate, even to servers
+    /// not requesting one
+    pub advertise_identity: bool,
+
+    /// name of the `[[services]]` entry the file inherits settings from
+    pub service: Option<&'a str>,
+
+    /// free-form labels of the file
+    pub labels: &'a BTreeMap<String, String>,
+}
+
+impl ListenView<'_> {
+    /// is a client with address `ip` allowed to connect
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        self.allow.is_empty() || self.allow.iter().any(|c| c.contains(ip))
+    }
+}
+
+impl<'a> ConnectView<'a> {
+    /// get all endpoints in the order they should be tried
+    pub fn endpoints(&self) -> impl Iterator<Item = EndpointRef<'a>> {
+        let first = EndpointRef {
+            host: self.host,
+            port: self.port,
+        };
+
+        std::iter::once(first).chain(self.fallback.iter().map(|e| EndpointRef {
+            host: &e.host,
+            port: e.port,
+        }))
+    }
+}
+
+/// Borrowed [`Endpoint`] returned by [`ConnectView::endpoints`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EndpointRef<'a> {
+    /// host to connect to
+    pub host: &'a Host,
+
+    /// port to connect to
+    pub port: Port,
+}
+
+impl File {
+    /// get the typed view of a `kind = "listen"` file
+    pub fn as_listen(&self) -> Option<ListenView<'_>> {
+        match self {
+            Self::Listen {
+                name,
+                addr,
+                port,
+                ports,
+                ephemeral,
+                backlog,
+                reuseaddr,
+                reuseport,
+                keepalive,
+                nodelay,
+                priority,
+                idle_timeout,
+                allow,
+                max_connections_per_second,
+                max_concurrent_connections,
+                selftest,
+                prot,
+                tls,
+                cert,
+                key,
+                psk,
+                mode,
+                routes,
+                service,
+                labels,
+            } => Some(ListenView {
+                name,
+                addr,
+                port: *port,
+                ports,
+                ephemeral: *ephemeral || port.is_ephemeral(),
+                backlog: *backlog,
+                reuseaddr: *reuseaddr,
+                reuseport: *reuseport,
+                keepalive: keepalive.as_ref(),
+                nodelay: *nodelay,
+                priority: *priority,
+                idle_timeout: *idle_timeout,
+                allow,
+                max_connections_per_second: *max_connections_per_second,
+                max_concurrent_connections: *max_concurrent_connections,
+                selftest: *selftest,
+                prot: *prot,
+                tls: tls.as_ref(),
+                cert: cert.as_ref(),
+                key: key.as_ref(),
+                psk: psk.as_ref(),
+                mode: *mode,
+                routes,
+                service: service.as_deref(),
+                labels,
+            }),
+            _ => None,
+        }
+    }
+
+    /// get the typed view of a `kind = "connect"` file
+    pub fn as_connect(&self) -> Option<ConnectView<'_>> {
+        match self {
+            Self::Connect {
+                host,
+                port,
+                fallback,
+                family,
+                happy_eyeballs,
+                proxy,
+                prot,
+                keepalive,
+                nodelay,
+                priority,
+                idle_timeout,
+                timeout,
+                retries,
+                retry_backoff,
+                tls,
+                psk,
+                advertise_identity,
+                service,
+                labels,
+                ..
+            } => Some(ConnectView {
+                name: self.name(),
+                host,
+                port: *port,
+                fallback,
+                family: *family,
+                happy_eyeballs: *happy_eyeballs && *family == AddressFamily::Any,
+                proxy: *proxy,
+                prot: *prot,
+                keepalive: keepalive.as_ref(),
+                nodelay: *nodelay,
+                priority: *priority,
+                idle_timeout: *idle_timeout,
+                timeout: *timeout,
+                retries: *retries,
+                retry_backoff: *retry_backoff,
+                tls: tls.as_ref(),
+                psk: psk.as_ref(),
+                advertise_identity: *advertise_identity,
+                service: service.as_deref(),
+                labels,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    #[test]
+    fn views() {
+        const CONFIG: &str = r#"
+        [[files]]
+        name = "A"
+        kind = "listen"
+        ephemeral = true
+        reuseport = true
+        selftest = true
+        allow = [ "10.0.0.0/8", "2001:db8::/32" ]
+        max_connections_per_second = 100
+        idle_timeout = "2m"
+
+        [[files]]
+        kind = "connect"
+        host = "example.com"
+        nodelay = true
+        timeout = "10s"
+        retries = 3
+        retry_backoff = "500ms"
+        family = "v6"
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+
+        let listen = cfg.files[0].as_listen().unwrap();
+        assert!(listen.ephemeral);
+        assert!(listen.port.is_ephemeral());
+        assert_eq!(listen.backlog, 128);
+        assert!(!listen.reuseaddr);
+        assert!(listen.reuseport);
+        assert!(listen.selftest);
+        assert!(listen.allows("10.1.2.3".parse().unwrap()));
+        assert!(listen.allows("2001:db8::1".parse().unwrap()));
+        assert!(!listen.allows("192.168.1.1".parse().unwrap()));
+        assert_eq!(listen.max_connections_per_second, NonZeroU32::new(100));
+        assert_eq!(listen.max_concurrent_connections, None);
+        assert_eq!(listen.idle_timeout, Some(Duration::from_secs(120)));
+
+        assert!(toml::from_str::<EnarxConfig>(
+            "[[files]]\nname = \"A\"\nkind = \"listen\"\nmax_concurrent_connections = 0"
+        )
+        .is_err());
+        assert!(cfg.files[0].as_connect().is_none());
+
+        let connect = cfg.files[1].as_connect().unwrap();
+        assert_eq!(connect.name, "example.com");
+        assert_eq!(connect.port.get(), 443);
+        assert!(connect.nodelay);
+        assert!(!listen.nodelay);
+        assert_eq!(connect.timeout, Some(Duration::from_secs(10)));
+        assert_eq!(connect.retries, 3);
+        assert_eq!(connect.family, AddressFamily::V6);
+        assert!(!connect.happy_eyeballs);
+        assert_eq!(connect.retry_backoff, Some(Duration::from_millis(500)));
+        assert_eq!(connect.idle_timeout, None);
+    }
+}
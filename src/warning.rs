@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Non-fatal findings about a configuration
+
+use std::{collections::BTreeSet, fmt};
+
+use crate::{EnarxConfig, File};
+
+/// A non-fatal finding about a configuration, like a deprecated spelling
+///
+/// Contains the path of the value, e.g. `files[2].protocol`, and a description.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    path: String,
+    message: String,
+}
+
+impl Warning {
+    pub(crate) fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    /// the path of the value
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// the description of the finding
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl EnarxConfig {
+    /// get the non-fatal findings about the configuration
+    ///
+    /// Complements [`EnarxConfig::validate`] with settings which are valid, but most likely
+    /// not intended, like `env_exports` patterns matching no environment variable, or
+    /// templates, trust anchors and secrets nothing refers to.
+    pub fn warnings(&self) -> Vec<Warning> {
+        let mut warnings = self.env_export_warnings();
+        warnings.extend(self.unused_warnings());
+        warnings
+    }
+
+    /// get the warnings about the templates, trust anchors and secrets nothing refers to,
+    /// neither the top-level settings nor the ones of any application
+    fn unused_warnings(&self) -> Vec<Warning> {
+        let mut configs = vec![self.clone()];
+        configs.extend(self.apps.keys().filter_map(|name| self.app(name)));
+
+        let mut templates = BTreeSet::new();
+        let mut trust = BTreeSet::new();
+        let mut secrets = BTreeSet::new();
+        for mut config in configs {
+            templates.extend(
+                config
+                    .files
+                    .iter()
+                    .filter_map(|file| file.template().map(String::from)),
+            );
+
+            // files with a `when` condition may be used, their references count
+            config.files = config
+                .resolve_templates_lossy()
+                .into_iter()
+                .map(|file| match file {
+                    File::Conditional { file, .. } => *file,
+                    file => file,
+                })
+                .collect();
+
+            for (_, names) in config.trust_references() {
+                trust.extend(names.iter().cloned());
+            }
+            for (_, name) in config.secret_references() {
+                secrets.insert(name.to_string());
+            }
+        }
+
+        let mut warnings = vec![];
+        for (section, what, defined, used) in [
+            (
+                "templates",
+                "template",
+                self.templates.keys().collect::<Vec<_>>(),
+                templates,
+            ),
+            ("trust", "trust anchor", self.trust.keys().collect(), trust),
+            ("secrets", "secret", self.secrets.keys().collect(), secrets),
+        ] {
+            for name in defined.into_iter().filter(|name| !used.contains(*name)) {
+                warnings.push(Warning::new(
+                    format!("{}.{}", section, name),
+                    format!("{} `{}` is not used", what, name),
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unused() {
+        const CONFIG: &str = r#"
+        [secrets]
+        api-key = { file = "/run/secrets/api.key" }
+        link-key = { env = "LINK_KEY" }
+        old-token = { env = "TOKEN" }
+
+        [trust]
+        corp = { path = "/etc/ssl/corp.pem" }
+        legacy = { path = "/etc/ssl/legacy.pem" }
+
+        [templates.web]
+        kind = "listen"
+        prot = "tls"
+        key = { secret = "api-key" }
+
+        [templates.unused]
+        kind = "null"
+
+        [[files]]
+        name = "API"
+        template = "web"
+
+        [apps.sidecar]
+        [[apps.sidecar.files]]
+        kind = "connect"
+        host = "peer.example.com"
+        psk = { identity = "sidecar", key = "link-key" }
+        tls = { trust = [ "corp" ] }
+        when = { profile = "dev" }
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(
+            cfg.warnings()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "templates.unused: template `unused` is not used",
+                "trust.legacy: trust anchor `legacy` is not used",
+                "secrets.old-token: secret `old-token` is not used",
+            ]
+        );
+
+        assert!(EnarxConfig::default().warnings().is_empty());
+    }
+}
@@ -0,0 +1,514 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! WASI proposals of the `[wasm]` section
+
+use std::{collections::HashSet, fmt, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{Digest, EnarxConfig, FileName, HttpMethod, UrlPattern, ValidationError};
+
+/// The `[wasm]` section, enabling WASI proposals beyond `wasi_snapshot_preview1`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Wasm {
+    /// the `wasi-crypto` proposal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasi_crypto: Option<WasiCrypto>,
+
+    /// the `wasi-nn` proposal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasi_nn: Option<WasiNn>,
+
+    /// outbound requests of the `wasi-http` proposal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<WasiHttp>,
+}
+
+impl EnarxConfig {
+    pub(crate) fn validate_wasm(&self, wasm: &Wasm) -> Result<(), ValidationError> {
+        if let Some(wasi_crypto) = &wasm.wasi_crypto {
+            wasi_crypto.validate()?;
+        }
+        if let Some(wasi_nn) = &wasm.wasi_nn {
+            wasi_nn.validate()?;
+        }
+        if let Some(http) = &wasm.http {
+            self.validate_wasi_http(http)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Algorithm of the `wasi-crypto` proposal, named like in the proposal
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum CryptoAlgorithm {
+    /// ECDSA signatures with the NIST P-256 curve and SHA-256
+    #[serde(rename = "ECDSA_P256_SHA256")]
+    EcdsaP256Sha256,
+
+    /// ECDSA signatures with the NIST P-384 curve and SHA-384
+    #[serde(rename = "ECDSA_P384_SHA384")]
+    EcdsaP384Sha384,
+
+    /// Ed25519 signatures
+    #[serde(rename = "Ed25519")]
+    Ed25519,
+
+    /// RSA PKCS#1 v1.5 signatures with 2048 bit keys and SHA-256
+    #[serde(rename = "RSA_PKCS1_2048_SHA256")]
+    RsaPkcs12048Sha256,
+
+    /// RSA PSS signatures with 2048 bit keys and SHA-256
+    #[serde(rename = "RSA_PSS_2048_SHA256")]
+    RsaPss2048Sha256,
+
+    /// X25519 key exchange
+    #[serde(rename = "X25519")]
+    X25519,
+
+    /// SHA-256 hash function
+    #[serde(rename = "SHA-256")]
+    Sha256,
+
+    /// SHA-512 hash function
+    #[serde(rename = "SHA-512")]
+    Sha512,
+
+    /// HMAC with SHA-256
+    #[serde(rename = "HMAC/SHA-256")]
+    HmacSha256,
+
+    /// HMAC with SHA-512
+    #[serde(rename = "HMAC/SHA-512")]
+    HmacSha512,
+
+    /// HKDF with SHA-256
+    #[serde(rename = "HKDF/SHA-256")]
+    HkdfSha256,
+
+    /// AES-128 in Galois/Counter Mode
+    #[serde(rename = "A128-GCM")]
+    Aes128Gcm,
+
+    /// AES-256 in Galois/Counter Mode
+    #[serde(rename = "A256-GCM")]
+    Aes256Gcm,
+
+    /// ChaCha20 with the Poly1305 authenticator
+    #[serde(rename = "CHACHA20-POLY1305")]
+    Chacha20Poly1305,
+
+    /// XChaCha20 with the Poly1305 authenticator
+    #[serde(rename = "XCHACHA20-POLY1305")]
+    Xchacha20Poly1305,
+}
+
+impl fmt::Display for CryptoAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::EcdsaP256Sha256 => "ECDSA_P256_SHA256",
+            Self::EcdsaP384Sha384 => "ECDSA_P384_SHA384",
+            Self::Ed25519 => "Ed25519",
+            Self::RsaPkcs12048Sha256 => "RSA_PKCS1_2048_SHA256",
+            Self::RsaPss2048Sha256 => "RSA_PSS_2048_SHA256",
+            Self::X25519 => "X25519",
+            Self::Sha256 => "SHA-256",
+            Self::Sha512 => "SHA-512",
+            Self::HmacSha256 => "HMAC/SHA-256",
+            Self::HmacSha512 => "HMAC/SHA-512",
+            Self::HkdfSha256 => "HKDF/SHA-256",
+            Self::Aes128Gcm => "A128-GCM",
+            Self::Aes256Gcm => "A256-GCM",
+            Self::Chacha20Poly1305 => "CHACHA20-POLY1305",
+            Self::Xchacha20Poly1305 => "XCHACHA20-POLY1305",
+        })
+    }
+}
+
+/// The `[wasm.wasi_crypto]` table, exposing the `wasi-crypto` proposal to the application
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WasiCrypto {
+    /// expose the proposal to the application
+    #[serde(default)]
+    pub enable: bool,
+
+    /// the algorithms the application may use, all algorithms of the runtime if not specified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithms: Option<Vec<CryptoAlgorithm>>,
+}
+
+impl WasiCrypto {
+    /// check if the application may use `algorithm`
+    pub fn allows(&self, algorithm: CryptoAlgorithm) -> bool {
+        match &self.algorithms {
+            Some(algorithms) => self.enable && algorithms.contains(&algorithm),
+            None => self.enable,
+        }
+    }
+
+    fn validate(&self) -> Result<(), ValidationError> {
+        if !self.enable && self.algorithms.is_some() {
+            return Err(ValidationError::new(
+                "wasm.wasi_crypto.algorithms",
+                "`algorithms` requires `enable = true`",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Backend of the `wasi-nn` proposal, the encoding of the models it executes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum NnBackend {
+    /// OpenVINO intermediate representation
+    #[serde(rename = "openvino")]
+    OpenVino,
+
+    /// ONNX
+    #[serde(rename = "onnx")]
+    Onnx,
+
+    /// TensorFlow
+    #[serde(rename = "tensorflow")]
+    TensorFlow,
+
+    /// TensorFlow Lite
+    #[serde(rename = "tensorflowlite")]
+    TensorFlowLite,
+
+    /// PyTorch
+    #[serde(rename = "pytorch")]
+    PyTorch,
+
+    /// GGML
+    #[serde(rename = "ggml")]
+    Ggml,
+}
+
+impl fmt::Display for NnBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::OpenVino => "openvino",
+            Self::Onnx => "onnx",
+            Self::TensorFlow => "tensorflow",
+            Self::TensorFlowLite => "tensorflowlite",
+            Self::PyTorch => "pytorch",
+            Self::Ggml => "ggml",
+        })
+    }
+}
+
+/// Execution provider of a `wasi-nn` model
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum NnTarget {
+    /// the CPU of the keep
+    #[serde(rename = "cpu")]
+    Cpu,
+
+    /// a GPU
+    #[serde(rename = "gpu")]
+    Gpu,
+
+    /// a TPU
+    #[serde(rename = "tpu")]
+    Tpu,
+}
+
+// `#[default]` on enum variants is not available on the MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for NnTarget {
+    fn default() -> Self {
+        NnTarget::Cpu
+    }
+}
+
+impl fmt::Display for NnTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cpu => f.write_str("cpu"),
+            Self::Gpu => f.write_str("gpu"),
+            Self::Tpu => f.write_str("tpu"),
+        }
+    }
+}
+
+/// A model the application can load by its name with `wasi-nn`
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NnModel {
+    /// name the application loads the model by
+    pub name: FileName,
+
+    /// backend executing the model
+    pub backend: NnBackend,
+
+    /// path of the model on the host
+    pub path: PathBuf,
+
+    /// SHA-256 digest the model file has to match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<Digest>,
+
+    /// execution provider of the model
+    #[serde(default)]
+    pub target: NnTarget,
+}
+
+/// The `[wasm.wasi_nn]` table, exposing the `wasi-nn` proposal to the application
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WasiNn {
+    /// expose the proposal to the application
+    #[serde(default)]
+    pub enable: bool,
+
+    /// the backends the application may use, all backends of the runtime if not specified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backends: Option<Vec<NnBackend>>,
+
+    /// the models the application can load by name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<NnModel>,
+}
+
+impl WasiNn {
+    /// check if the application may use `backend`
+    pub fn allows(&self, backend: NnBackend) -> bool {
+        match &self.backends {
+            Some(backends) => self.enable && backends.contains(&backend),
+            None => self.enable,
+        }
+    }
+
+    /// get the model the application loads by `name`
+    pub fn model(&self, name: &str) -> Option<&NnModel> {
+        self.models.iter().find(|model| &*model.name == name)
+    }
+
+    fn validate(&self) -> Result<(), ValidationError> {
+        if !self.enable {
+            for (field, set) in [
+                ("backends", self.backends.is_some()),
+                ("models", !self.models.is_empty()),
+            ] {
+                if set {
+                    return Err(ValidationError::new(
+                        format!("wasm.wasi_nn.{}", field),
+                        format!("`{}` requires `enable = true`", field),
+                    ));
+                }
+            }
+        }
+
+        let mut names = HashSet::new();
+        for (i, model) in self.models.iter().enumerate() {
+            let path = |field: &str| format!("wasm.wasi_nn.models[{}].{}", i, field);
+            if !names.insert(&*model.name) {
+                return Err(ValidationError::new(
+                    path("name"),
+                    format!("duplicate model name `{}`", &*model.name),
+                ));
+            }
+            if model.path.as_os_str().is_empty() {
+                return Err(ValidationError::new(path("path"), "empty path"));
+            }
+            if !self.allows(model.backend) {
+                return Err(ValidationError::new(
+                    path("backend"),
+                    format!("backend `{}` is not in `backends`", model.backend),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A rule of the `[wasm.http]` allowlist
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HttpRule {
+    /// the URLs the rule allows requests to
+    pub url: UrlPattern,
+
+    /// the allowed request methods, all methods if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<HttpMethod>,
+}
+
+impl HttpRule {
+    /// check if the rule allows a `method` request to `url`
+    pub fn allows(&self, method: HttpMethod, url: &Url) -> bool {
+        (self.methods.is_empty() || self.methods.contains(&method)) && self.url.matches(url)
+    }
+}
+
+/// The `[wasm.http]` table, the outbound requests of the `wasi-http` proposal
+///
+/// Only requests matching one of the `allow` rules are sent.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WasiHttp {
+    /// expose outbound requests to the application
+    #[serde(default)]
+    pub enable: bool,
+
+    /// the allowed requests
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<HttpRule>,
+}
+
+impl WasiHttp {
+    /// check if the application may send a `method` request to `url`
+    pub fn allows(&self, method: HttpMethod, url: &Url) -> bool {
+        self.enable && self.allow.iter().any(|rule| rule.allows(method, url))
+    }
+}
+
+impl EnarxConfig {
+    fn validate_wasi_http(&self, http: &WasiHttp) -> Result<(), ValidationError> {
+        if !http.enable && !http.allow.is_empty() {
+            return Err(ValidationError::new(
+                "wasm.http.allow",
+                "`allow` requires `enable = true`",
+            ));
+        }
+
+        let egress = match self.network.as_ref().and_then(|n| n.egress.as_ref()) {
+            Some(egress) => egress,
+            None => return Ok(()),
+        };
+        for (i, rule) in http.allow.iter().enumerate() {
+            // wildcard hosts can't be checked against the egress policy
+            let host = match rule.url.host() {
+                Some(host) => host,
+                None => continue,
+            };
+            if !egress.allows(host, rule.url.port()) {
+                return Err(ValidationError::new(
+                    format!("wasm.http.allow[{}].url", i),
+                    format!(
+                        "`{}:{}` is not allowed by `network.egress`",
+                        host,
+                        rule.url.port()
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EnarxConfig;
+
+    #[test]
+    fn wasi_crypto() {
+        const CONFIG: &str = r#"
+        [wasm.wasi_crypto]
+        enable = true
+        algorithms = [ "Ed25519", "A256-GCM", "HMAC/SHA-256" ]
+        "#;
+
+        let cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let wasi_crypto = cfg.wasm.as_ref().unwrap().wasi_crypto.as_ref().unwrap();
+        assert!(wasi_crypto.allows(CryptoAlgorithm::Ed25519));
+        assert!(wasi_crypto.allows(CryptoAlgorithm::HmacSha256));
+        assert!(!wasi_crypto.allows(CryptoAlgorithm::RsaPss2048Sha256));
+
+        assert!(toml::from_str::<EnarxConfig>(
+            "[wasm.wasi_crypto]\nenable = true\nalgorithms = [ \"MD5\" ]"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn wasi_nn() {
+        const CONFIG: &str = r#"
+        [wasm.wasi_nn]
+        enable = true
+        backends = [ "onnx" ]
+
+        [[wasm.wasi_nn.models]]
+        name = "mobilenet"
+        backend = "onnx"
+        path = "/models/mobilenet.onnx"
+        sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        "#;
+
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let wasi_nn = cfg.wasm.as_mut().unwrap().wasi_nn.as_mut().unwrap();
+        let model = wasi_nn.model("mobilenet").unwrap();
+        assert_eq!(model.target, NnTarget::Cpu);
+        assert_eq!(model.sha256, Some(Digest::of("")));
+        assert!(!wasi_nn.allows(NnBackend::PyTorch));
+
+        wasi_nn.models[0].backend = NnBackend::PyTorch;
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "wasm.wasi_nn.models[0].backend: backend `pytorch` is not in `backends`"
+        );
+    }
+
+    #[test]
+    fn wasi_http() {
+        const CONFIG: &str = r#"
+        [network.egress]
+        hosts = [ "api.example.com", "*.example.org" ]
+
+        [wasm.http]
+        enable = true
+
+        [[wasm.http.allow]]
+        url = "https://api.example.com/v1/*"
+        methods = [ "GET", "POST" ]
+
+        [[wasm.http.allow]]
+        url = "https://*.example.org/status"
+        "#;
+
+        let mut cfg: EnarxConfig = toml::from_str(CONFIG).unwrap();
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let http = cfg.wasm.as_ref().unwrap().http.as_ref().unwrap();
+        let url = |s: &str| s.parse::<Url>().unwrap();
+        assert!(http.allows(HttpMethod::Get, &url("https://api.example.com/v1/users")));
+        assert!(!http.allows(HttpMethod::Delete, &url("https://api.example.com/v1/users")));
+        assert!(!http.allows(HttpMethod::Get, &url("https://api.example.com/v2/users")));
+        assert!(http.allows(HttpMethod::Delete, &url("https://eu.example.org/status")));
+
+        cfg.network.as_mut().unwrap().egress.as_mut().unwrap().hosts =
+            vec!["*.example.org".parse().unwrap()];
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "wasm.http.allow[0].url: `api.example.com:443` is not allowed by `network.egress`"
+        );
+    }
+
+    #[test]
+    fn disabled() {
+        let cfg: EnarxConfig =
+            toml::from_str("[wasm.wasi_crypto]\nalgorithms = [ \"Ed25519\" ]").unwrap();
+        let wasi_crypto = cfg.wasm.as_ref().unwrap().wasi_crypto.as_ref().unwrap();
+        assert!(!wasi_crypto.allows(CryptoAlgorithm::Ed25519));
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "wasm.wasi_crypto.algorithms: `algorithms` requires `enable = true`"
+        );
+
+        let cfg: EnarxConfig = toml::from_str(
+            "[[wasm.wasi_nn.models]]\nname = \"m\"\nbackend = \"onnx\"\npath = \"/m.onnx\"",
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.validate().unwrap_err().to_string(),
+            "wasm.wasi_nn.models: `models` requires `enable = true`"
+        );
+    }
+}